@@ -1,132 +1,247 @@
 use crate::data_service::DataService;
+use crate::deep_link::{self, PendingDeepLink};
+use crate::error::AppError;
+use crate::logging;
 use crate::models::{
-    Chapter, ParsedStoryContent, SearchDebugResponse, SearchResult, StoryCategory, StoryEntry,
-    StoryIndexStatus,
+    Activity, AppStatus, ArchiveFormat, ArtistWork, AvgTagKind, Bookmark, BusyState, Chapter,
+    CharacterAttributes, CharacterCredits, CharacterFavor, CharacterForm, CharacterListFilter,
+    CharacterMatch, CharacterModule, CharacterObtainInfo, CharacterPage, CharacterProfile,
+    CharacterRanges, CharacterToken, CharacterUpgradeCosts, CharacterVoiceActors,
+    CharacterVoiceAssets, ChronologyYear, ContinueReading, DataCompatReport, DataSourceProfile,
+    DeepLinkNavigation, DiagnosticsExport, DiskUsage, FavoriteItem, FavoriteKind, GroupReadCount,
+    GroupStats, LocalizedName, MemoryGroup, NewStories, ParsedStoryContent, RandomStory,
+    RandomStoryFilter, RangeGrid, ReadingOrderItem, ReadingProgress, RecentStory, RelatedStory,
+    ResolvedStage, RosterStats, SearchDebugResponse, SearchResult, SkinInfo, StoryCacheStats,
+    StoryCategory, StoryCharacter, StoryDependency, StoryDetail, StoryEntry, StoryGroup,
+    StoryIndexStatus, StoryNeighbors, StoryStats, StoryTree, SubProfessionInfo, SyncReport,
+    TaskInfo, TeamPowerInfo, TerraTimeline, UpdateStatus,
 };
-use crate::parser::parse_story_text;
-use std::sync::{Arc, Mutex};
+use crate::settings::{AppSettings, SettingsPatch, SettingsService};
 use tauri::{AppHandle, State};
 
+/// DataService 内部的缓存与设置各自加锁，克隆共享同一份状态，因此命令之间无需再串行化
 pub struct AppState {
-    pub data_service: Arc<Mutex<DataService>>,
+    pub data_service: DataService,
+    /// 与 data_service 共享同一份设置
+    pub settings: SettingsService,
+    pub pending_deep_link: PendingDeepLink,
 }
 
-// 安全获取锁，即使 Mutex 被 panic 污染也能恢复
-fn lock_service(mutex: &Arc<Mutex<DataService>>) -> std::sync::MutexGuard<'_, DataService> {
-    mutex.lock().unwrap_or_else(|poisoned| {
-        eprintln!("[WARNING] Mutex was poisoned, recovering data");
-        poisoned.into_inner()
-    })
+/// 取一份服务克隆，供 spawn_blocking 中的耗时调用使用
+fn clone_service(state: &State<'_, AppState>) -> DataService {
+    state.data_service.clone()
 }
 
-fn clone_service(state: &State<'_, AppState>) -> DataService {
-    let guard = lock_service(&state.data_service);
-    let service = guard.clone();
-    drop(guard);
-    service
+/// exclude_hidden 为 true 时去掉被隐藏（防剧透）的剧情
+fn visible(groups: Vec<StoryGroup>, exclude_hidden: Option<bool>) -> Vec<StoryGroup> {
+    if exclude_hidden.unwrap_or(false) {
+        DataService::exclude_hidden_stories(groups)
+    } else {
+        groups
+    }
+}
+
+/// 分组接口的公共后处理：隐藏过滤、收藏标记，include_stats 为 true 时附带字数与阅读时长
+fn finish_groups(
+    service: &DataService,
+    groups: Vec<StoryGroup>,
+    exclude_hidden: Option<bool>,
+    include_stats: Option<bool>,
+) -> Vec<StoryGroup> {
+    let groups = service.annotate_favorites(visible(groups, exclude_hidden));
+    if include_stats.unwrap_or(false) {
+        service.attach_story_stats(groups)
+    } else {
+        groups
+    }
+}
+
+/// 旧版分组接口的返回格式：(分组名称, 剧情列表)
+fn into_pairs(groups: Vec<StoryGroup>) -> Vec<(String, Vec<StoryEntry>)> {
+    groups.into_iter().map(StoryGroup::into_pair).collect()
 }
 
 #[tauri::command]
-pub async fn sync_data(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn sync_data(app: AppHandle, state: State<'_, AppState>) -> Result<(), AppError> {
     let service = clone_service(&state);
     tauri::async_runtime::spawn_blocking(move || service.sync_data(app))
         .await
         .map_err(|err| format!("Failed to join sync task: {}", err))?
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn get_current_version(state: State<'_, AppState>) -> Result<String, String> {
-    let service = lock_service(&state.data_service);
-    service.get_current_version()
+pub async fn get_current_version(state: State<'_, AppState>) -> Result<String, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_current_version())
+        .await
+        .map_err(|err| format!("Failed to join current version task: {}", err))?
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn get_remote_version(state: State<'_, AppState>) -> Result<String, String> {
+pub async fn get_remote_version(state: State<'_, AppState>) -> Result<String, AppError> {
     let service = clone_service(&state);
     tauri::async_runtime::spawn_blocking(move || service.get_remote_version())
         .await
         .map_err(|err| format!("Failed to join remote version task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn check_update_detailed(state: State<'_, AppState>) -> Result<UpdateStatus, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.check_update_detailed())
+        .await
+        .map_err(|err| AppError::from(format!("Failed to join check update task: {}", err)))
+}
+
+/// 兼容旧前端：仅在确认有更新时返回 true，无法检查（如离线）时返回 false
+#[tauri::command]
+pub async fn check_update(state: State<'_, AppState>) -> Result<bool, AppError> {
+    match check_update_detailed(state).await? {
+        UpdateStatus::UpToDate => Ok(false),
+        UpdateStatus::UpdateAvailable { .. } => Ok(true),
+        UpdateStatus::Unknown { .. } => Ok(false),
+    }
 }
 
 #[tauri::command]
-pub async fn check_update(state: State<'_, AppState>) -> Result<bool, String> {
+pub async fn is_installed(state: State<'_, AppState>) -> Result<bool, AppError> {
     let service = clone_service(&state);
-    tauri::async_runtime::spawn_blocking(move || service.check_update())
+    tauri::async_runtime::spawn_blocking(move || service.is_installed())
         .await
-        .map_err(|err| format!("Failed to join check update task: {}", err))?
+        .map_err(|err| AppError::from(format!("Failed to join installed check task: {}", err)))
 }
 
 #[tauri::command]
-pub async fn is_installed(state: State<'_, AppState>) -> Result<bool, String> {
-    let service = lock_service(&state.data_service);
-    Ok(service.is_installed())
+pub async fn get_chapters(state: State<'_, AppState>) -> Result<Vec<Chapter>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_chapters())
+        .await
+        .map_err(|err| format!("Failed to join chapters task: {}", err))?
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn get_chapters(state: State<'_, AppState>) -> Result<Vec<Chapter>, String> {
-    let service = lock_service(&state.data_service);
-    service.get_chapters()
+pub async fn get_activities(state: State<'_, AppState>) -> Result<Vec<Activity>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_activities())
+        .await
+        .map_err(|err| format!("Failed to join activities task: {}", err))?
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_story_categories(
     state: State<'_, AppState>,
-) -> Result<Vec<StoryCategory>, String> {
-    let service = lock_service(&state.data_service);
-    service.get_story_categories()
+    include: Option<Vec<String>>,
+) -> Result<Vec<StoryCategory>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_story_categories(include.as_deref()))
+        .await
+        .map_err(|err| format!("Failed to join story categories task: {}", err))?
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_story_content(
     state: State<'_, AppState>,
     story_path: String,
-) -> Result<ParsedStoryContent, String> {
-    let service = lock_service(&state.data_service);
-    let content = service.read_story_text(&story_path)?;
-    Ok(parse_story_text(&content))
+) -> Result<ParsedStoryContent, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || {
+        let content = service.get_story_content(&story_path)?;
+        if let Err(err) = service.record_story_opened(&story_path) {
+            tracing::warn!("Failed to record history for {}: {}", story_path, err);
+        }
+        Ok(content)
+    })
+    .await
+    .map_err(|err| format!("Failed to join story content task: {}", err))?
+}
+
+#[tauri::command]
+pub async fn prefetch_story_contents(
+    state: State<'_, AppState>,
+    story_paths: Vec<String>,
+) -> Result<Vec<bool>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.prefetch_story_contents(&story_paths))
+        .await
+        .map_err(|err| format!("Failed to join prefetch task: {}", err))?
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_story_info(
     state: State<'_, AppState>,
     info_path: String,
-) -> Result<String, String> {
-    let service = lock_service(&state.data_service);
-    service.read_story_info(&info_path)
+) -> Result<String, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.read_story_info(&info_path))
+        .await
+        .map_err(|err| format!("Failed to join story info task: {}", err))?
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_story_entry(
     state: State<'_, AppState>,
     story_id: String,
-) -> Result<StoryEntry, String> {
-    let service = lock_service(&state.data_service);
-    service.get_story_entry(&story_id)
+) -> Result<StoryEntry, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_story_entry(&story_id))
+        .await
+        .map_err(|err| format!("Failed to join story entry task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_story_neighbors(
+    state: State<'_, AppState>,
+    story_id: String,
+    cross_groups: Option<bool>,
+) -> Result<StoryNeighbors, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || {
+        service.get_story_neighbors(&story_id, cross_groups.unwrap_or(false))
+    })
+    .await
+    .map_err(|err| format!("Failed to join story neighbors task: {}", err))?
+    .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_story_index_status(
     state: State<'_, AppState>,
-) -> Result<StoryIndexStatus, String> {
-    let service = lock_service(&state.data_service);
-    service.get_story_index_status()
+) -> Result<StoryIndexStatus, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_story_index_status())
+        .await
+        .map_err(|err| format!("Failed to join index status task: {}", err))?
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn build_story_index(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn build_story_index(state: State<'_, AppState>) -> Result<(), AppError> {
     let service = clone_service(&state);
     tauri::async_runtime::spawn_blocking(move || service.rebuild_story_index())
         .await
         .map_err(|err| format!("Failed to join build story index task: {}", err))?
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn search_stories(
     state: State<'_, AppState>,
     query: String,
-) -> Result<Vec<SearchResult>, String> {
-    let service = lock_service(&state.data_service);
-    service.search_stories(&query)
+) -> Result<Vec<SearchResult>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.search_stories(&query))
+        .await
+        .map_err(|err| format!("Failed to join search task: {}", err))?
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -134,20 +249,24 @@ pub async fn search_stories_with_progress(
     app: AppHandle,
     state: State<'_, AppState>,
     query: String,
-) -> Result<Vec<SearchResult>, String> {
+) -> Result<Vec<SearchResult>, AppError> {
     let service = clone_service(&state);
     tauri::async_runtime::spawn_blocking(move || service.search_stories_with_progress(&app, &query))
         .await
         .map_err(|err| format!("Failed to join search with progress task: {}", err))?
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn search_stories_debug(
     state: State<'_, AppState>,
     query: String,
-) -> Result<SearchDebugResponse, String> {
-    let service = lock_service(&state.data_service);
-    service.search_stories_with_debug(&query)
+) -> Result<SearchDebugResponse, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.search_stories_with_debug(&query))
+        .await
+        .map_err(|err| format!("Failed to join debug search task: {}", err))?
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -155,11 +274,12 @@ pub async fn import_from_zip(
     app: AppHandle,
     state: State<'_, AppState>,
     path: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let service = clone_service(&state);
     tauri::async_runtime::spawn_blocking(move || service.import_zip_from_path(path, app))
         .await
         .map_err(|err| format!("Failed to join import task: {}", err))?
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -167,57 +287,1169 @@ pub async fn import_from_zip_bytes(
     app: AppHandle,
     state: State<'_, AppState>,
     bytes: Vec<u8>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let service = clone_service(&state);
     tauri::async_runtime::spawn_blocking(move || service.import_zip_from_bytes(&bytes, app))
         .await
         .map_err(|err| format!("Failed to join import-bytes task: {}", err))?
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_main_stories_grouped(
     state: State<'_, AppState>,
-) -> Result<Vec<(String, Vec<StoryEntry>)>, String> {
+    tag_filter: Option<Vec<AvgTagKind>>,
+    exclude_hidden: Option<bool>,
+    include_stats: Option<bool>,
+) -> Result<Vec<(String, Vec<StoryEntry>)>, AppError> {
     let service = clone_service(&state);
-    tauri::async_runtime::spawn_blocking(move || service.get_main_stories_grouped())
-        .await
-        .map_err(|err| format!("Failed to join main stories grouped task: {}", err))?
+    tauri::async_runtime::spawn_blocking(move || {
+        service
+            .get_main_stories_grouped(tag_filter.as_deref())
+            .map(|groups| {
+                into_pairs(finish_groups(
+                    &service,
+                    groups,
+                    exclude_hidden,
+                    include_stats,
+                ))
+            })
+    })
+    .await
+    .map_err(|err| format!("Failed to join main stories grouped task: {}", err))?
+    .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_activity_stories_grouped(
     state: State<'_, AppState>,
-) -> Result<Vec<(String, Vec<StoryEntry>)>, String> {
+    tag_filter: Option<Vec<AvgTagKind>>,
+    exclude_hidden: Option<bool>,
+    include_stats: Option<bool>,
+) -> Result<Vec<(String, Vec<StoryEntry>)>, AppError> {
     let service = clone_service(&state);
-    tauri::async_runtime::spawn_blocking(move || service.get_activity_stories_grouped())
-        .await
-        .map_err(|err| format!("Failed to join activity stories grouped task: {}", err))?
+    tauri::async_runtime::spawn_blocking(move || {
+        service
+            .get_activity_stories_grouped(tag_filter.as_deref())
+            .map(|groups| {
+                into_pairs(finish_groups(
+                    &service,
+                    groups,
+                    exclude_hidden,
+                    include_stats,
+                ))
+            })
+    })
+    .await
+    .map_err(|err| format!("Failed to join activity stories grouped task: {}", err))?
+    .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_sidestory_stories_grouped(
     state: State<'_, AppState>,
-) -> Result<Vec<(String, Vec<StoryEntry>)>, String> {
+    exclude_hidden: Option<bool>,
+    include_stats: Option<bool>,
+) -> Result<Vec<(String, Vec<StoryEntry>)>, AppError> {
     let service = clone_service(&state);
-    tauri::async_runtime::spawn_blocking(move || service.get_sidestory_stories_grouped())
-        .await
-        .map_err(|err| format!("Failed to join sidestory stories grouped task: {}", err))?
+    tauri::async_runtime::spawn_blocking(move || {
+        service.get_sidestory_stories_grouped().map(|groups| {
+            into_pairs(finish_groups(
+                &service,
+                groups,
+                exclude_hidden,
+                include_stats,
+            ))
+        })
+    })
+    .await
+    .map_err(|err| format!("Failed to join sidestory stories grouped task: {}", err))?
+    .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_roguelike_stories_grouped(
     state: State<'_, AppState>,
-) -> Result<Vec<(String, Vec<StoryEntry>)>, String> {
+    exclude_hidden: Option<bool>,
+    include_stats: Option<bool>,
+) -> Result<Vec<(String, Vec<StoryEntry>)>, AppError> {
     let service = clone_service(&state);
-    tauri::async_runtime::spawn_blocking(move || service.get_roguelike_stories_grouped())
-        .await
-        .map_err(|err| format!("Failed to join roguelike stories grouped task: {}", err))?
+    tauri::async_runtime::spawn_blocking(move || {
+        service.get_roguelike_stories_grouped().map(|groups| {
+            into_pairs(finish_groups(
+                &service,
+                groups,
+                exclude_hidden,
+                include_stats,
+            ))
+        })
+    })
+    .await
+    .map_err(|err| format!("Failed to join roguelike stories grouped task: {}", err))?
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_rune_stories_grouped(
+    state: State<'_, AppState>,
+    exclude_hidden: Option<bool>,
+    include_stats: Option<bool>,
+) -> Result<Vec<(String, Vec<StoryEntry>)>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || {
+        service.get_rune_stories_grouped().map(|groups| {
+            into_pairs(finish_groups(
+                &service,
+                groups,
+                exclude_hidden,
+                include_stats,
+            ))
+        })
+    })
+    .await
+    .map_err(|err| format!("Failed to join rune stories grouped task: {}", err))?
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_record_stories_grouped(
+    state: State<'_, AppState>,
+    exclude_hidden: Option<bool>,
+    include_stats: Option<bool>,
+) -> Result<Vec<(String, Vec<StoryEntry>)>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || {
+        service.get_record_stories_grouped().map(|groups| {
+            into_pairs(finish_groups(
+                &service,
+                groups,
+                exclude_hidden,
+                include_stats,
+            ))
+        })
+    })
+    .await
+    .map_err(|err| format!("Failed to join record stories grouped task: {}", err))?
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_main_stories_grouped_v2(
+    state: State<'_, AppState>,
+    tag_filter: Option<Vec<AvgTagKind>>,
+    exclude_hidden: Option<bool>,
+    include_stats: Option<bool>,
+) -> Result<Vec<StoryGroup>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || {
+        service
+            .get_main_stories_grouped(tag_filter.as_deref())
+            .map(|groups| finish_groups(&service, groups, exclude_hidden, include_stats))
+    })
+    .await
+    .map_err(|err| format!("Failed to join main stories grouped task: {}", err))?
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_activity_stories_grouped_v2(
+    state: State<'_, AppState>,
+    tag_filter: Option<Vec<AvgTagKind>>,
+    exclude_hidden: Option<bool>,
+    include_stats: Option<bool>,
+) -> Result<Vec<StoryGroup>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || {
+        service
+            .get_activity_stories_grouped(tag_filter.as_deref())
+            .map(|groups| finish_groups(&service, groups, exclude_hidden, include_stats))
+    })
+    .await
+    .map_err(|err| format!("Failed to join activity stories grouped task: {}", err))?
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_sidestory_stories_grouped_v2(
+    state: State<'_, AppState>,
+    exclude_hidden: Option<bool>,
+    include_stats: Option<bool>,
+) -> Result<Vec<StoryGroup>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || {
+        service
+            .get_sidestory_stories_grouped()
+            .map(|groups| finish_groups(&service, groups, exclude_hidden, include_stats))
+    })
+    .await
+    .map_err(|err| format!("Failed to join sidestory stories grouped task: {}", err))?
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_roguelike_stories_grouped_v2(
+    state: State<'_, AppState>,
+    exclude_hidden: Option<bool>,
+    include_stats: Option<bool>,
+) -> Result<Vec<StoryGroup>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || {
+        service
+            .get_roguelike_stories_grouped()
+            .map(|groups| finish_groups(&service, groups, exclude_hidden, include_stats))
+    })
+    .await
+    .map_err(|err| format!("Failed to join roguelike stories grouped task: {}", err))?
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_rune_stories_grouped_v2(
+    state: State<'_, AppState>,
+    exclude_hidden: Option<bool>,
+    include_stats: Option<bool>,
+) -> Result<Vec<StoryGroup>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || {
+        service
+            .get_rune_stories_grouped()
+            .map(|groups| finish_groups(&service, groups, exclude_hidden, include_stats))
+    })
+    .await
+    .map_err(|err| format!("Failed to join rune stories grouped task: {}", err))?
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_record_stories_grouped_v2(
+    state: State<'_, AppState>,
+    exclude_hidden: Option<bool>,
+    include_stats: Option<bool>,
+) -> Result<Vec<StoryGroup>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || {
+        service
+            .get_record_stories_grouped()
+            .map(|groups| finish_groups(&service, groups, exclude_hidden, include_stats))
+    })
+    .await
+    .map_err(|err| format!("Failed to join record stories grouped task: {}", err))?
+    .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn get_memory_stories(state: State<'_, AppState>) -> Result<Vec<StoryEntry>, String> {
+pub async fn get_memory_stories(state: State<'_, AppState>) -> Result<Vec<StoryEntry>, AppError> {
     let service = clone_service(&state);
     tauri::async_runtime::spawn_blocking(move || service.get_memory_stories())
         .await
         .map_err(|err| format!("Failed to join memory stories task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_memory_stories_grouped(
+    state: State<'_, AppState>,
+) -> Result<Vec<MemoryGroup>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_memory_stories_grouped())
+        .await
+        .map_err(|err| format!("Failed to join memory stories grouped task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_story_tree(state: State<'_, AppState>) -> Result<StoryTree, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_story_tree())
+        .await
+        .map_err(|err| format!("Failed to join story tree task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_story_tree_version(state: State<'_, AppState>) -> Result<String, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_story_tree_version())
+        .await
+        .map_err(|err| AppError::from(format!("Failed to join tree version task: {}", err)))
+}
+
+#[tauri::command]
+pub async fn export_app_data(
+    state: State<'_, AppState>,
+    dest_path: String,
+    include_game_data: Option<bool>,
+) -> Result<(), AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || {
+        service.export_app_data(dest_path, include_game_data.unwrap_or(false))
+    })
+    .await
+    .map_err(|err| format!("Failed to join export app data task: {}", err))?
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn export_diagnostics(
+    state: State<'_, AppState>,
+    dest_path: String,
+    include_reading_progress: Option<bool>,
+) -> Result<DiagnosticsExport, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || {
+        service.export_diagnostics(dest_path, include_reading_progress.unwrap_or(false))
+    })
+    .await
+    .map_err(|err| format!("Failed to join export diagnostics task: {}", err))?
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_story_cache_stats(
+    state: State<'_, AppState>,
+) -> Result<StoryCacheStats, AppError> {
+    Ok(state.data_service.story_cache_stats())
+}
+
+#[tauri::command]
+pub async fn get_disk_usage(state: State<'_, AppState>) -> Result<DiskUsage, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_disk_usage())
+        .await
+        .map_err(|err| format!("Failed to join disk usage task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn import_app_data(state: State<'_, AppState>, src_path: String) -> Result<(), AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.import_app_data(src_path))
+        .await
+        .map_err(|err| format!("Failed to join import app data task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn list_data_sources(
+    state: State<'_, AppState>,
+) -> Result<Vec<DataSourceProfile>, AppError> {
+    Ok(state.data_service.list_data_sources())
+}
+
+#[tauri::command]
+pub async fn get_data_source(state: State<'_, AppState>) -> Result<DataSourceProfile, AppError> {
+    Ok(state.data_service.data_source())
+}
+
+#[tauri::command]
+pub async fn set_data_source(
+    state: State<'_, AppState>,
+    source_id: String,
+) -> Result<DataSourceProfile, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.set_data_source(&source_id))
+        .await
+        .map_err(|err| format!("Failed to join set data source task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_data_compat_report(
+    state: State<'_, AppState>,
+) -> Result<DataCompatReport, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_data_compat_report())
+        .await
+        .map_err(|err| format!("Failed to join data compat report task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_sync_report(state: State<'_, AppState>) -> Result<Option<SyncReport>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_sync_report())
+        .await
+        .map_err(|err| format!("Failed to join sync report task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_new_stories(state: State<'_, AppState>) -> Result<NewStories, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_new_stories())
+        .await
+        .map_err(|err| format!("Failed to join new stories task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_archive_format(state: State<'_, AppState>) -> Result<ArchiveFormat, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.archive_format())
+        .await
+        .map_err(|err| AppError::from(format!("Failed to join archive format task: {}", err)))
+}
+
+#[tauri::command]
+pub async fn set_archive_format(
+    state: State<'_, AppState>,
+    format: ArchiveFormat,
+) -> Result<(), AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.set_archive_format(format))
+        .await
+        .map_err(|err| format!("Failed to join archive format task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn export_data_bundle(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    dest_path: String,
+) -> Result<(), AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.export_data_bundle(dest_path, &app))
+        .await
+        .map_err(|err| format!("Failed to join export data bundle task: {}", err))?
+        .map_err(AppError::from)
+}
+
+/// 设置页一次性获取安装、版本、索引、磁盘占用与任务状态
+#[tauri::command]
+pub async fn get_app_status(state: State<'_, AppState>) -> Result<AppStatus, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_app_status())
+        .await
+        .map_err(|err| AppError::from(format!("Failed to join app status task: {}", err)))
+}
+
+/// 解析 arkstory:// 链接并确认目标存在，返回前端执行的跳转
+#[tauri::command]
+pub async fn resolve_deep_link(
+    state: State<'_, AppState>,
+    url: String,
+) -> Result<DeepLinkNavigation, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || deep_link::resolve_deep_link(&service, &url))
+        .await
+        .map_err(|err| AppError::from(format!("Failed to join deep link task: {}", err)))?
+}
+
+/// 取走冷启动时携带的链接，之后的链接通过 deep-link 事件送达
+#[tauri::command]
+pub async fn take_pending_deep_link(
+    state: State<'_, AppState>,
+) -> Result<Option<String>, AppError> {
+    Ok(state.pending_deep_link.take())
+}
+
+#[tauri::command]
+pub async fn get_busy_state(state: State<'_, AppState>) -> Result<BusyState, AppError> {
+    Ok(state.data_service.get_busy_state())
+}
+
+#[tauri::command]
+pub async fn set_reading_progress(
+    state: State<'_, AppState>,
+    story_id: String,
+    segment_index: i64,
+    percent: f64,
+    char_offset: Option<i64>,
+) -> Result<(), AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || {
+        service.set_reading_progress(&story_id, segment_index, char_offset.unwrap_or(0), percent)
+    })
+    .await
+    .map_err(|err| format!("Failed to join reading progress task: {}", err))?
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_reading_progress(
+    state: State<'_, AppState>,
+    story_id: String,
+) -> Result<Option<ReadingProgress>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_reading_progress(&story_id))
+        .await
+        .map_err(|err| format!("Failed to join reading progress task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_all_reading_progress(
+    state: State<'_, AppState>,
+) -> Result<Vec<ReadingProgress>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_all_reading_progress())
+        .await
+        .map_err(|err| format!("Failed to join reading progress task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn add_bookmark(
+    state: State<'_, AppState>,
+    story_id: String,
+    segment_index: i64,
+    excerpt: String,
+    note: Option<String>,
+) -> Result<Bookmark, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || {
+        service.add_bookmark(
+            &story_id,
+            segment_index,
+            &excerpt,
+            note.as_deref().unwrap_or(""),
+        )
+    })
+    .await
+    .map_err(|err| format!("Failed to join add bookmark task: {}", err))?
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn list_bookmarks(
+    state: State<'_, AppState>,
+    story_id: Option<String>,
+) -> Result<Vec<Bookmark>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.list_bookmarks(story_id.as_deref()))
+        .await
+        .map_err(|err| format!("Failed to join list bookmarks task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn update_bookmark_note(
+    state: State<'_, AppState>,
+    id: i64,
+    note: String,
+) -> Result<(), AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.update_bookmark_note(id, &note))
+        .await
+        .map_err(|err| format!("Failed to join bookmark task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_bookmark(state: State<'_, AppState>, id: i64) -> Result<(), AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.delete_bookmark(id))
+        .await
+        .map_err(|err| format!("Failed to join bookmark task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn toggle_favorite(
+    state: State<'_, AppState>,
+    kind: FavoriteKind,
+    key: String,
+) -> Result<bool, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.toggle_favorite(kind, &key))
+        .await
+        .map_err(|err| format!("Failed to join favorite task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn is_favorite(
+    state: State<'_, AppState>,
+    kind: FavoriteKind,
+    key: String,
+) -> Result<bool, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.is_favorite(kind, &key))
+        .await
+        .map_err(|err| format!("Failed to join favorite task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn list_favorites(state: State<'_, AppState>) -> Result<Vec<FavoriteItem>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.list_favorites())
+        .await
+        .map_err(|err| format!("Failed to join list favorites task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn report_read_session(
+    state: State<'_, AppState>,
+    story_id: String,
+    seconds: i64,
+) -> Result<(), AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.report_read_session(&story_id, seconds))
+        .await
+        .map_err(|err| format!("Failed to join read session task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_recent_stories(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+) -> Result<Vec<RecentStory>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_recent_stories(limit.unwrap_or(20)))
+        .await
+        .map_err(|err| format!("Failed to join recent stories task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_continue_reading(
+    state: State<'_, AppState>,
+) -> Result<Option<ContinueReading>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_continue_reading())
+        .await
+        .map_err(|err| format!("Failed to join continue reading task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn set_story_read(
+    state: State<'_, AppState>,
+    story_id: String,
+    read: bool,
+) -> Result<(), AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.set_story_read(&story_id, read))
+        .await
+        .map_err(|err| format!("Failed to join read state task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_group_read_counts(
+    state: State<'_, AppState>,
+) -> Result<Vec<GroupReadCount>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_group_read_counts())
+        .await
+        .map_err(|err| format!("Failed to join read counts task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn mark_group_read(
+    state: State<'_, AppState>,
+    group_key: String,
+) -> Result<usize, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.mark_group_read(&group_key))
+        .await
+        .map_err(|err| format!("Failed to join mark group read task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_story_stats(
+    state: State<'_, AppState>,
+    story_id: String,
+) -> Result<StoryStats, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_story_stats(&story_id))
+        .await
+        .map_err(|err| format!("Failed to join story stats task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_story_characters(
+    state: State<'_, AppState>,
+    story_path: String,
+) -> Result<Vec<StoryCharacter>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_story_characters(&story_path))
+        .await
+        .map_err(|err| format!("Failed to join story characters task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_stories_for_character(
+    state: State<'_, AppState>,
+    char_id: String,
+) -> Result<Vec<StoryGroup>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_stories_for_character(&char_id))
+        .await
+        .map_err(|err| format!("Failed to join character stories task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_stories_by_terra_date(
+    state: State<'_, AppState>,
+) -> Result<TerraTimeline, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_stories_by_terra_date())
+        .await
+        .map_err(|err| format!("Failed to join terra timeline task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_random_story(
+    state: State<'_, AppState>,
+    filter: Option<RandomStoryFilter>,
+) -> Result<RandomStory, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || {
+        service.get_random_story(&filter.unwrap_or_default())
+    })
+    .await
+    .map_err(|err| format!("Failed to join random story task: {}", err))?
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_related_stories(
+    state: State<'_, AppState>,
+    story_id: String,
+    limit: Option<usize>,
+) -> Result<Vec<RelatedStory>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_related_stories(&story_id, limit))
+        .await
+        .map_err(|err| format!("Failed to join related stories task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn resolve_required_stages(
+    state: State<'_, AppState>,
+    story_id: String,
+) -> Result<Vec<ResolvedStage>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.resolve_required_stages(&story_id))
+        .await
+        .map_err(|err| format!("Failed to join required stages task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_chapter_reading_order(
+    state: State<'_, AppState>,
+    group_key: String,
+) -> Result<Vec<ReadingOrderItem>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_chapter_reading_order(&group_key))
+        .await
+        .map_err(|err| format!("Failed to join reading order task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_story_dependencies(
+    state: State<'_, AppState>,
+    story_id: String,
+) -> Result<Vec<StoryDependency>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_story_dependencies(&story_id))
+        .await
+        .map_err(|err| format!("Failed to join story dependencies task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_month_chat_parts(
+    state: State<'_, AppState>,
+    story_id: String,
+) -> Result<Vec<StoryEntry>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_month_chat_parts(&story_id))
+        .await
+        .map_err(|err| format!("Failed to join month chat parts task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_characters_list(
+    state: State<'_, AppState>,
+    filter: Option<CharacterListFilter>,
+) -> Result<CharacterPage, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || {
+        service.get_characters_list(&filter.unwrap_or_default())
+    })
+    .await
+    .map_err(|err| format!("Failed to join characters list task: {}", err))?
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_character_voice_actors(
+    state: State<'_, AppState>,
+    char_id: String,
+) -> Result<CharacterVoiceActors, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_character_voice_actors(&char_id))
+        .await
+        .map_err(|err| format!("Failed to join voice actors task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_character_profile(
+    state: State<'_, AppState>,
+    char_id: String,
+) -> Result<CharacterProfile, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_character_profile(&char_id))
+        .await
+        .map_err(|err| format!("Failed to join character profile task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn search_characters(
+    state: State<'_, AppState>,
+    query: String,
+    include_nonplayable: Option<bool>,
+) -> Result<Vec<CharacterMatch>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || {
+        service.search_characters(&query, include_nonplayable.unwrap_or(false))
+    })
+    .await
+    .map_err(|err| format!("Failed to join character search task: {}", err))?
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_character_favor(
+    state: State<'_, AppState>,
+    char_id: String,
+) -> Result<CharacterFavor, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_character_favor(&char_id))
+        .await
+        .map_err(|err| format!("Failed to join character favor task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_character_attributes(
+    state: State<'_, AppState>,
+    char_id: String,
+) -> Result<CharacterAttributes, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_character_attributes(&char_id))
+        .await
+        .map_err(|err| format!("Failed to join character attributes task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_range(
+    state: State<'_, AppState>,
+    range_id: String,
+) -> Result<RangeGrid, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_range(&range_id))
+        .await
+        .map_err(|err| format!("Failed to join range task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_character_ranges(
+    state: State<'_, AppState>,
+    char_id: String,
+) -> Result<CharacterRanges, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_character_ranges(&char_id))
+        .await
+        .map_err(|err| format!("Failed to join character ranges task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_character_module_details(
+    state: State<'_, AppState>,
+    char_id: String,
+) -> Result<Vec<CharacterModule>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_character_module_details(&char_id))
+        .await
+        .map_err(|err| format!("Failed to join module details task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_character_upgrade_costs(
+    state: State<'_, AppState>,
+    char_id: String,
+) -> Result<CharacterUpgradeCosts, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_character_upgrade_costs(&char_id))
+        .await
+        .map_err(|err| format!("Failed to join upgrade costs task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_character_skins(
+    state: State<'_, AppState>,
+    char_id: String,
+) -> Result<Vec<SkinInfo>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_character_skins(&char_id))
+        .await
+        .map_err(|err| format!("Failed to join character skins task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_character_memories(
+    state: State<'_, AppState>,
+    char_id: String,
+) -> Result<Vec<StoryEntry>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_character_memories(&char_id))
+        .await
+        .map_err(|err| format!("Failed to join character memories task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_character_voice_assets(
+    state: State<'_, AppState>,
+    char_id: String,
+    lang: String,
+) -> Result<CharacterVoiceAssets, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || {
+        service.get_character_voice_assets(&char_id, &lang)
+    })
+    .await
+    .map_err(|err| format!("Failed to join voice assets task: {}", err))?
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_character_tokens(
+    state: State<'_, AppState>,
+    char_id: String,
+) -> Result<Vec<CharacterToken>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_character_tokens(&char_id))
+        .await
+        .map_err(|err| format!("Failed to join character tokens task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_character_forms(
+    state: State<'_, AppState>,
+    char_id: String,
+) -> Result<Vec<CharacterForm>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_character_forms(&char_id))
+        .await
+        .map_err(|err| format!("Failed to join character forms task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_all_factions(state: State<'_, AppState>) -> Result<Vec<TeamPowerInfo>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_all_factions())
+        .await
+        .map_err(|err| format!("Failed to join factions task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_all_sub_professions(
+    state: State<'_, AppState>,
+) -> Result<Vec<SubProfessionInfo>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_all_sub_professions())
+        .await
+        .map_err(|err| format!("Failed to join sub professions task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_roster_stats(state: State<'_, AppState>) -> Result<RosterStats, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_roster_stats())
+        .await
+        .map_err(|err| format!("Failed to join roster stats task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_character_by_name(
+    state: State<'_, AppState>,
+    name: String,
+) -> Result<CharacterMatch, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_character_by_name(&name))
+        .await
+        .map_err(|err| format!("Failed to join character lookup task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_character_credits(
+    state: State<'_, AppState>,
+    char_id: String,
+) -> Result<CharacterCredits, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_character_credits(&char_id))
+        .await
+        .map_err(|err| format!("Failed to join character credits task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_operators_by_artist(
+    state: State<'_, AppState>,
+    name: String,
+) -> Result<Vec<ArtistWork>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_operators_by_artist(&name))
+        .await
+        .map_err(|err| format!("Failed to join artist lookup task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_character_obtain_info(
+    state: State<'_, AppState>,
+    char_id: String,
+) -> Result<CharacterObtainInfo, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_character_obtain_info(&char_id))
+        .await
+        .map_err(|err| format!("Failed to join character obtain info task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_character_localized_names(
+    state: State<'_, AppState>,
+    char_id: String,
+) -> Result<Vec<LocalizedName>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_character_localized_names(&char_id))
+        .await
+        .map_err(|err| format!("Failed to join localized names task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn list_tasks(state: State<'_, AppState>) -> Result<Vec<TaskInfo>, AppError> {
+    Ok(state.data_service.list_tasks())
+}
+
+/// 返回 false 表示任务已结束或不存在
+#[tauri::command]
+pub async fn cancel_task(state: State<'_, AppState>, task_id: u64) -> Result<bool, AppError> {
+    Ok(state.data_service.cancel_task(task_id))
+}
+
+/// 最近的日志（默认 200 行），供粘贴到问题反馈中
+#[tauri::command]
+pub async fn get_recent_logs(lines: Option<usize>) -> Result<Vec<String>, AppError> {
+    let lines = lines.unwrap_or(200);
+    tauri::async_runtime::spawn_blocking(move || logging::recent_logs(lines))
+        .await
+        .map_err(|err| format!("Failed to join log task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn set_log_level(state: State<'_, AppState>, level: String) -> Result<(), AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.set_log_level(&level))
+        .await
+        .map_err(|err| format!("Failed to join log level task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_settings(state: State<'_, AppState>) -> Result<AppSettings, AppError> {
+    Ok(state.settings.get())
+}
+
+/// 部分更新设置，返回更新后的完整设置；变更通过 settings-changed 事件广播
+#[tauri::command]
+pub async fn update_settings(
+    state: State<'_, AppState>,
+    patch: SettingsPatch,
+) -> Result<AppSettings, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.update_settings(patch))
+        .await
+        .map_err(|err| format!("Failed to join settings task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_group_stats(
+    state: State<'_, AppState>,
+    group_key: String,
+) -> Result<GroupStats, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_group_stats(&group_key))
+        .await
+        .map_err(|err| format!("Failed to join group stats task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_stories_chronological(
+    state: State<'_, AppState>,
+) -> Result<Vec<ChronologyYear>, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_stories_chronological())
+        .await
+        .map_err(|err| format!("Failed to join chronological stories task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_story_detail(
+    state: State<'_, AppState>,
+    story_id: String,
+) -> Result<StoryDetail, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.get_story_detail(&story_id))
+        .await
+        .map_err(|err| format!("Failed to join story detail task: {}", err))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_include_hidden_in_search(state: State<'_, AppState>) -> Result<bool, AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.include_hidden_in_search())
+        .await
+        .map_err(|err| AppError::from(format!("Failed to join search settings task: {}", err)))
+}
+
+#[tauri::command]
+pub async fn set_include_hidden_in_search(
+    state: State<'_, AppState>,
+    include: bool,
+) -> Result<(), AppError> {
+    let service = clone_service(&state);
+    tauri::async_runtime::spawn_blocking(move || service.set_include_hidden_in_search(include))
+        .await
+        .map_err(|err| format!("Failed to join search settings task: {}", err))?
+        .map_err(AppError::from)
 }
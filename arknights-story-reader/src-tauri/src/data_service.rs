@@ -1,40 +1,140 @@
 use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::hash::{Hash, Hasher};
 use std::io::{ErrorKind, Read, Write};
-use std::path::{Path, PathBuf};
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use flate2::read::GzDecoder;
 use reqwest::blocking::Client;
 use rusqlite::{params, Connection, OptionalExtension};
-use serde_json::Value;
+use serde::Deserialize;
+use serde_json::{Map, Value};
 use tauri::{AppHandle, Emitter};
 use unicode_normalization::UnicodeNormalization;
 use zip::ZipArchive;
 
+use crate::logging;
 use crate::models::{
-    Activity, Chapter, SearchDebugResponse, SearchResult, StoryCategory, StoryEntry,
-    StoryIndexStatus, StorySegment,
+    Activity, AppStatus, ArchiveFormat, ArtistCredit, ArtistWork, AttributeBlock, AttributeBonus,
+    AvgTagKind, BlackboardValue, Bookmark, BusyState, Chapter, CharacterAttributes,
+    CharacterCredits, CharacterFavor, CharacterForm, CharacterListFilter, CharacterMatch,
+    CharacterMatchKind, CharacterModule, CharacterObtainInfo, CharacterPage, CharacterProfile,
+    CharacterRanges, CharacterSort, CharacterSummary, CharacterToken, CharacterUpgradeCosts,
+    CharacterVoiceActors, CharacterVoiceAssets, ChronologyYear, ContinueReading, CvCredit,
+    DataCompatReport, DataSourceProfile, DiagnosticsExport, DiskUsage, FavorKeyFrame, FavoriteItem,
+    FavoriteKind, GroupReadCount, GroupStats, HandbookSection, IndexSummary, InstalledVersion,
+    ItemCost, LevelCost, LocalizedName, MemoryGroup, ModuleMission, ModuleStage, ModuleTalent,
+    ModuleUnlock, NewStories, NewStoryGroup, ParsedStoryContent, PhaseAttributes, PhaseRange,
+    PrewarmReport, ProfileField, ProgressEvent, RandomStory, RandomStoryFilter, RangeGrid,
+    ReadingOrderItem, ReadingOrderKind, ReadingProgress, RecentStory, RelatedStory, RequiredStage,
+    ResolvedStage, RosterCount, RosterStats, SearchDebugResponse, SearchResult, SkillInfo,
+    SkillMasteryCost, SkillRange, SkinInfo, StoryCacheStats, StoryCategory, StoryCharacter,
+    StoryDependency, StoryDetail, StoryEntry, StoryGroup, StoryGroupExtra, StoryIndexStatus,
+    StoryNeighbors, StorySegment, StoryStats, StoryTree, StoryTreeGroup, StoryTreeNode,
+    StoryVisibility, SubProfessionInfo, SyncCategoryCount, SyncReport, TalentInfo, TaskInfo,
+    TeamPowerInfo, TerraDate, TerraDatedStory, TerraTimeline, UpdateCheckResult, UpdateStatus,
+    VoiceActorLang, VoiceAsset, VoiceSet,
 };
-use crate::parser::parse_story_text;
+use crate::parser::{parse_story_text, parse_story_text_with_nickname};
+use crate::settings::{builtin_data_sources, AppSettings, SettingsPatch, SettingsService};
+use crate::tasks::{CancelToken, ProgressSink, TaskHandle, TaskRegistry, TASK_PROGRESS_EVENT};
 
-const REPO_API_URL: &str = "https://api.github.com/repos/Kengxxiao/ArknightsGameData";
-const REPO_DOWNLOAD_URL: &str = "https://codeload.github.com/Kengxxiao/ArknightsGameData/zip";
-const DEFAULT_BRANCH: &str = "master";
+const REPO_API_BASE: &str = "https://api.github.com/repos";
+const REPO_DOWNLOAD_BASE: &str = "https://codeload.github.com";
 const VERSION_FILE: &str = "version.json";
-const SEARCH_RESULT_LIMIT: usize = 500;
-const INDEX_VERSION: i32 = 2; // bump when FTS schema changes
+const INDEX_DB_FILE: &str = "story_index.db";
+/// 阅读进度等用户数据，与可随时重建的剧情索引分开存放
+const USER_DB_FILE: &str = "user_data.db";
+/// 书签摘录保存的最大字符数
+const BOOKMARK_EXCERPT_LIMIT: usize = 200;
+/// 最近阅读保留的剧情数
+const RECENT_HISTORY_LIMIT: usize = 100;
+/// 估算阅读时长使用的每分钟阅读字数
+const READING_CHARS_PER_MINUTE: usize = 400;
+const SETTINGS_FILE: &str = "settings.json";
+const STORY_SNAPSHOT_FILE: &str = "story_snapshot.json";
+const SYNC_REPORT_FILE: &str = "sync_report.json";
+const SYNC_REPORT_NEW_NAMES_LIMIT: usize = 50;
+const APP_DATA_MANIFEST: &str = "manifest.json";
+/// 已解析剧情缓存的条目数
+const STORY_CONTENT_CACHE_CAPACITY: usize = 16;
+/// get_app_status 复用磁盘占用统计的时长
+const DISK_USAGE_CACHE_TTL: Duration = Duration::from_secs(60);
+/// 单次预取的剧情数上限
+const PREFETCH_MAX_STORIES: usize = 4;
+/// 诊断包包含的最近日志行数
+const DIAGNOSTICS_LOG_LINES: usize = 2000;
+const APP_DATA_FORMAT_VERSION: u32 = 1;
+/// 应用数据备份包含的文件（相对 app_data_dir）
+const APP_DATA_FILES: &[&str] = &[INDEX_DB_FILE, USER_DB_FILE, SETTINGS_FILE];
+/// 「出现过的剧情」最多返回的剧情数
+const CHARACTER_STORY_LIMIT: usize = 300;
+/// 「接下来读什么」默认返回的推荐数
+const RELATED_STORY_LIMIT: usize = 10;
+/// 解锁前置链的最大追溯深度
+const STORY_DEPENDENCY_DEPTH_LIMIT: usize = 32;
+/// 干员搜索最多返回的条数
+const CHARACTER_SEARCH_LIMIT: usize = 50;
+const INDEX_VERSION: i32 = 5; // bump when FTS or stats schema changes
+const SUB_PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+/// 列表页首次打开时需要的数据表
+const PREWARM_TABLES: &[&str] = &[
+    "story_review_table.json",
+    "story_review_meta_table.json",
+    "story_table.json",
+    "chapter_table.json",
+];
+const DOWNLOAD_STALLED_ERROR: &str = "下载停滞，已中断";
+const EXTRACT_ROOT_SEARCH_DEPTH: usize = 4;
+const SKIPPED_ENTRY_LIMIT: usize = 5;
+/// 调用方传入的剧情路径不合法（试图跳出剧情目录等）
+const INVALID_PATH_ERROR: &str = "INVALID_PATH";
+/// 扩展长度路径允许的最大长度（UTF-16 单元）
+const MAX_EXTENDED_PATH_LEN: usize = 32_767;
+/// 压缩包本身损坏（格式错误、CRC 校验失败等），同步时会自动重新下载一次
+const CORRUPT_ARCHIVE_ERROR: &str = "CORRUPT_ARCHIVE";
+const EXTRACTION_ABORTED: &str = "解压已中止";
+const MAX_EXTRACT_WORKERS: usize = 4;
+const PARALLEL_PROGRESS_POLL: Duration = Duration::from_millis(50);
+/// 剧情树依赖的数据表，其修改时间参与 tree_version 计算
+const STORY_TREE_TABLES: &[&str] = &[
+    "story_review_table.json",
+    "story_review_meta_table.json",
+    "story_table.json",
+    "handbook_info_table.json",
+    "character_table.json",
+    "item_table.json",
+    "roguelike_topic_table.json",
+    "zone_table.json",
+    "stage_table.json",
+    "crisis_table.json",
+    "crisis_v2_table.json",
+];
+/// 剧情树序列化后超过该大小时记录警告
+const STORY_TREE_WARN_BYTES: usize = 8 * 1024 * 1024;
 
 #[derive(Clone, serde::Serialize)]
 struct SyncProgress {
+    #[serde(rename = "taskId")]
+    task_id: u64,
     phase: String,
     current: usize,
     total: usize,
     message: String,
+    /// 仅在最终「完成」事件中携带同步摘要
+    #[serde(skip_serializing_if = "Option::is_none")]
+    report: Option<SyncReport>,
 }
 
 #[derive(Clone, serde::Serialize)]
 pub struct SearchProgress {
+    #[serde(rename = "taskId")]
+    task_id: u64,
     phase: String,
     current: usize,
     total: usize,
@@ -45,6 +145,57 @@ pub struct SearchProgress {
 struct VersionInfo {
     commit: String,
     fetched_at: i64,
+    /// 数据来源；旧版本写入的 version.json 中不存在，视为国服
+    #[serde(default)]
+    source: Option<DataSourceProfile>,
+}
+
+/// 同步前已安装剧情的快照
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct StorySnapshot {
+    version: Option<String>,
+    #[serde(default)]
+    stories: HashMap<String, SnapshotStory>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct SnapshotStory {
+    name: String,
+    category: String,
+}
+
+/// 诊断包中的清单
+#[derive(Debug, serde::Serialize)]
+struct DiagnosticsManifest {
+    #[serde(rename = "appVersion")]
+    app_version: String,
+    os: String,
+    arch: String,
+    #[serde(rename = "exportedAt")]
+    exported_at: i64,
+    #[serde(rename = "dataSource")]
+    data_source: String,
+    files: Vec<String>,
+    #[serde(rename = "includesReadingProgress")]
+    includes_reading_progress: bool,
+}
+
+/// 最近一次调试搜索，供诊断包说明「搜不到」时的检索过程
+#[derive(Clone)]
+struct LastSearchDebug {
+    query: String,
+    response: SearchDebugResponse,
+}
+
+/// 应用数据备份包中的清单
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct AppDataManifest {
+    format_version: u32,
+    app_version: String,
+    exported_at: i64,
+    files: Vec<String>,
+    #[serde(default)]
+    includes_game_data: bool,
 }
 
 #[derive(Clone)]
@@ -54,39 +205,213 @@ struct IndexedStory {
     story: StoryEntry,
 }
 
+/// 把任务的统一进度事件转发到前端
+fn progress_sink(app: &AppHandle) -> ProgressSink {
+    let app = app.clone();
+    Arc::new(move |event: &ProgressEvent| {
+        let _ = app.emit(TASK_PROGRESS_EVENT, event);
+    })
+}
+
+/// 旧的 sync-progress 事件，作为统一进度事件的适配层保留到前端迁移完成
 fn emit_progress(
     app: &AppHandle,
+    task: &TaskHandle,
     phase: impl Into<String>,
     current: usize,
     total: usize,
     message: impl Into<String>,
 ) {
+    let phase = phase.into();
+    let message = message.into();
+    if !task.progress(&phase, current, total, &message) {
+        return;
+    }
     let progress = SyncProgress {
-        phase: phase.into(),
+        task_id: task.id(),
+        phase,
         current,
         total,
-        message: message.into(),
+        message,
+        report: None,
+    };
+    let _ = app.emit("sync-progress", progress);
+}
+
+fn emit_sync_complete(
+    app: &AppHandle,
+    task: &TaskHandle,
+    message: impl Into<String>,
+    report: Option<SyncReport>,
+) {
+    let message = message.into();
+    task.finish("完成", 1, 1, &message);
+    let progress = SyncProgress {
+        task_id: task.id(),
+        phase: "完成".to_string(),
+        current: 1,
+        total: 1,
+        message,
+        report,
     };
     let _ = app.emit("sync-progress", progress);
 }
 
+/// 旧的 search-progress 事件，同样只是统一进度事件的适配层
 fn emit_search_progress(
     app: &AppHandle,
+    task: &TaskHandle,
+    phase: impl Into<String>,
+    current: usize,
+    total: usize,
+    message: impl Into<String>,
+) {
+    let phase = phase.into();
+    let message = message.into();
+    if !task.progress(&phase, current, total, &message) {
+        return;
+    }
+    let progress = SearchProgress {
+        task_id: task.id(),
+        phase,
+        current,
+        total,
+        message,
+    };
+    let _ = app.emit("search-progress", progress);
+}
+
+/// 搜索的最后一个进度：不经节流，同时作为统一进度的完成事件
+fn emit_search_complete(
+    app: &AppHandle,
+    task: &TaskHandle,
     phase: impl Into<String>,
     current: usize,
     total: usize,
     message: impl Into<String>,
 ) {
+    let phase = phase.into();
+    let message = message.into();
+    task.finish(&phase, current, total, &message);
     let progress = SearchProgress {
-        phase: phase.into(),
+        task_id: task.id(),
+        phase,
         current,
         total,
-        message: message.into(),
+        message,
     };
     let _ = app.emit("search-progress", progress);
 }
 
+/// Windows 下将绝对路径转换为扩展长度形式（`\\?\` 前缀），绕过 260 字符的 MAX_PATH 限制；
+/// 其他平台原样返回
+#[cfg(windows)]
+fn long_path(path: &Path) -> PathBuf {
+    if !path.is_absolute() || path.as_os_str().to_string_lossy().starts_with(r"\\?\") {
+        return path.to_path_buf();
+    }
+    // 扩展长度路径不再解析 `/`、`.` 与 `..`，需要先规范化
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    let normalized = normalized.to_string_lossy().replace('/', "\\");
+    match normalized.strip_prefix(r"\\") {
+        Some(unc) => PathBuf::from(format!(r"\\?\UNC\{}", unc)),
+        None => PathBuf::from(format!(r"\\?\{}", normalized)),
+    }
+}
+
+#[cfg(not(windows))]
+fn long_path(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+/// 创建文件失败时的提示；Windows 上路径过长会表现为“系统找不到指定的路径”，单独说明原因
+fn create_file_error(path: &Path, err: &std::io::Error) -> String {
+    let length = path.as_os_str().len();
+    let too_long = length > MAX_EXTENDED_PATH_LEN
+        || (cfg!(windows) && matches!(err.raw_os_error(), Some(3) | Some(206)) && length > 260);
+    if too_long {
+        format!(
+            "路径过长（{} 个字符），请将应用数据目录移动到更短的位置: {}",
+            length,
+            path.display()
+        )
+    } else {
+        format!("Failed to create file {:?}: {}", path, err)
+    }
+}
+
+fn invalid_path_error(path: &str) -> String {
+    format!("{}:{}", INVALID_PATH_ERROR, path)
+}
+
+/// 将调用方传入的相对路径解析到 base_dir 下：去掉开头的斜杠，拒绝 `..`、盘符等成分，
+/// 并在规范化后确认最终路径仍位于 base_dir 内（防止符号链接逃逸）
+///
+/// 数据表中 `Obt/Rogue` 与 `obt/rogue` 大小写混用，精确匹配不存在时按忽略大小写查找每一级
+fn resolve_within(base_dir: &Path, relative: &str) -> Result<PathBuf, String> {
+    let normalized = relative
+        .trim()
+        .trim_start_matches(['/', '\\'])
+        .replace('\\', "/");
+    let mut resolved = base_dir.to_path_buf();
+    let mut has_component = false;
+    for part in normalized.split('/') {
+        match part {
+            "" | "." => continue,
+            ".." => return Err(invalid_path_error(relative)),
+            _ if part.contains(':') || part.contains('\0') => {
+                return Err(invalid_path_error(relative))
+            }
+            _ => {
+                resolved = find_entry_ignore_case(&resolved, part);
+                has_component = true;
+            }
+        }
+    }
+    if !has_component {
+        return Err(invalid_path_error(relative));
+    }
+
+    if let (Ok(canonical), Ok(base)) = (resolved.canonicalize(), base_dir.canonicalize()) {
+        if !canonical.starts_with(&base) {
+            return Err(invalid_path_error(relative));
+        }
+    }
+    Ok(resolved)
+}
+
+fn find_entry_ignore_case(dir: &Path, name: &str) -> PathBuf {
+    let exact = dir.join(name);
+    if exact.exists() {
+        return exact;
+    }
+    fs::read_dir(dir)
+        .ok()
+        .and_then(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .find(|entry| {
+                    entry
+                        .file_name()
+                        .to_string_lossy()
+                        .eq_ignore_ascii_case(name)
+                })
+                .map(|entry| entry.path())
+        })
+        .unwrap_or(exact)
+}
+
 fn copy_dir_all(src: &Path, dst: &Path) -> Result<(), String> {
+    let (src, dst) = (&long_path(src), &long_path(dst));
     if !dst.exists() {
         fs::create_dir_all(dst)
             .map_err(|e| format!("Failed to create directory {:?}: {}", dst, e))?;
@@ -104,2063 +429,15534 @@ fn copy_dir_all(src: &Path, dst: &Path) -> Result<(), String> {
         if file_type.is_dir() {
             copy_dir_all(&entry.path(), &dest_path)?;
         } else {
-            fs::copy(entry.path(), &dest_path)
-                .map_err(|e| format!("Failed to copy file {:?}: {}", entry.path(), e))?;
+            fs::copy(entry.path(), &dest_path).map_err(|e| create_file_error(&dest_path, &e))?;
         }
     }
 
     Ok(())
 }
 
-fn is_common_punctuation(ch: char) -> bool {
-    if ch.is_ascii_punctuation() {
-        return true;
-    }
+/// 统计已读取字节数的 Reader，用于在大文件解压过程中汇报子进度
+struct CountingReader<'a, R: Read> {
+    inner: R,
+    copied: u64,
+    interval: Duration,
+    last_report: Instant,
+    on_progress: &'a mut dyn FnMut(u64),
+}
 
-    matches!(
-        ch,
-        '，' | '、'
-            | '。'
-            | '！'
-            | '？'
-            | '：'
-            | '；'
-            | '（'
-            | '）'
-            | '【'
-            | '】'
-            | '「'
-            | '」'
-            | '『'
-            | '』'
-            | '《'
-            | '》'
-            | '〈'
-            | '〉'
-            | '—'
-            | '～'
-            | '…'
-            | '·'
-            | '﹑'
-            | '﹔'
-            | '﹗'
-            | '﹖'
-            | '﹐'
-            | '﹒'
-            | '﹕'
-            | '︰'
-    )
+impl<'a, R: Read> CountingReader<'a, R> {
+    fn new(inner: R, interval: Duration, on_progress: &'a mut dyn FnMut(u64)) -> Self {
+        Self {
+            inner,
+            copied: 0,
+            interval,
+            last_report: Instant::now(),
+            on_progress,
+        }
+    }
 }
 
-fn is_cjk(ch: char) -> bool {
-    // Basic + Ext A/B ranges (not exhaustive but sufficient here)
-    (ch >= '\u{4E00}' && ch <= '\u{9FFF}') // CJK Unified Ideographs
-        || (ch >= '\u{3400}' && ch <= '\u{4DBF}') // Extension A
-        || (ch >= '\u{20000}' && ch <= '\u{2A6DF}') // Extension B
-        || (ch >= '\u{2A700}' && ch <= '\u{2B73F}')
-        || (ch >= '\u{2B740}' && ch <= '\u{2B81F}')
-        || (ch >= '\u{2B820}' && ch <= '\u{2CEAF}')
+impl<R: Read> Read for CountingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.copied += read as u64;
+        if read > 0 && self.last_report.elapsed() >= self.interval {
+            self.last_report = Instant::now();
+            (self.on_progress)(self.copied);
+        }
+        Ok(read)
+    }
 }
 
-fn normalize_nfkc_lower_strip_marks(text: &str) -> String {
-    // NFKC + lowercase + strip combining marks (e.g., café -> cafe)
-    text.nfkc()
-        .flat_map(|c| c.to_lowercase())
-        .filter(|c| unicode_normalization::char::canonical_combining_class(*c) == 0)
-        .collect()
+fn corrupt_archive_error(detail: impl std::fmt::Display) -> String {
+    format!("{}:{}", CORRUPT_ARCHIVE_ERROR, detail)
 }
 
-fn extract_numeric_parts(text: &str) -> Vec<i32> {
-    let mut parts = Vec::new();
-    let mut current = String::new();
+/// 解析 ZIP 条目名称：优先按 UTF-8，其次按 GBK（国内常见的重新打包工具），
+/// 都不合法时退回 zip 库按 CP437 解码的结果
+fn decode_entry_name(raw: &[u8], fallback: &str) -> String {
+    if let Ok(name) = std::str::from_utf8(raw) {
+        return name.to_string();
+    }
+    match encoding_rs::GBK.decode_without_bom_handling_and_without_replacement(raw) {
+        Some(name) => name.into_owned(),
+        None => fallback.to_string(),
+    }
+}
 
-    for ch in text.chars() {
-        if ch.is_ascii_digit() {
-            current.push(ch);
-        } else if !current.is_empty() {
-            if let Ok(num) = current.parse::<i32>() {
-                parts.push(num);
-            }
-            current.clear();
+/// 解析条目的相对路径；名称无法安全解析时返回解码后的名称
+fn resolve_entry_path(raw: &[u8], fallback: &str) -> Result<PathBuf, String> {
+    let entry_name = decode_entry_name(raw, fallback);
+    match enclosed_path(Path::new(&entry_name)) {
+        Some(path) if !entry_name.contains('\0') => Ok(path),
+        _ => {
+            tracing::warn!("跳过无法解析的条目: {:?}", entry_name);
+            Err(entry_name)
         }
     }
+}
 
-    if !current.is_empty() {
-        if let Ok(num) = current.parse::<i32>() {
-            parts.push(num);
+fn check_skipped_entries(skipped: &[String]) -> Result<usize, String> {
+    if skipped.len() > SKIPPED_ENTRY_LIMIT {
+        let examples: Vec<&str> = skipped.iter().take(3).map(|name| name.as_str()).collect();
+        return Err(format!(
+            "压缩包中有 {} 个条目的文件名无法解析（例如 {}），请重新打包后再导入",
+            skipped.len(),
+            examples.join("、")
+        ));
+    }
+    Ok(skipped.len())
+}
+
+/// 复制单个条目的内容；区分读取（解压/CRC 校验）失败与写入失败，前者说明压缩包损坏
+///
+/// on_chunk 返回 false 时中止复制
+fn copy_zip_entry(
+    reader: &mut dyn Read,
+    out_path: &Path,
+    entry_name: &str,
+    on_chunk: &mut dyn FnMut(u64) -> bool,
+) -> Result<(), String> {
+    let mut outfile = fs::File::create(out_path).map_err(|e| create_file_error(out_path, &e))?;
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .map_err(|e| corrupt_archive_error(format!("条目 {} 已损坏: {}", entry_name, e)))?;
+        if read == 0 {
+            return Ok(());
+        }
+        outfile
+            .write_all(&buffer[..read])
+            .map_err(|e| format!("Failed to write file: {}", e))?;
+        if !on_chunk(read as u64) {
+            return Err(EXTRACTION_ABORTED.to_string());
         }
     }
+}
 
-    parts
+fn open_zip_archive(zip_path: &Path) -> Result<ZipArchive<fs::File>, String> {
+    let zip_file =
+        fs::File::open(zip_path).map_err(|e| format!("Failed to open downloaded zip: {}", e))?;
+    ZipArchive::new(zip_file).map_err(|e| corrupt_archive_error(format!("无法读取压缩包: {}", e)))
 }
 
-fn compare_story_group_ids(a: &str, b: &str) -> Ordering {
-    let mut a_parts = extract_numeric_parts(a);
-    let mut b_parts = extract_numeric_parts(b);
+/// 按解压后字节数计算百分比；全部为空文件时退化为按条目数计算
+fn extraction_percent(
+    processed_bytes: u64,
+    total_bytes: u64,
+    processed_entries: usize,
+    entry_count: usize,
+) -> f64 {
+    let ratio = if total_bytes > 0 {
+        processed_bytes as f64 / total_bytes as f64
+    } else {
+        processed_entries as f64 / usize::max(entry_count, 1) as f64
+    };
+    (ratio * 100.0).min(100.0)
+}
 
-    if !a_parts.is_empty() || !b_parts.is_empty() {
-        let len = a_parts.len().max(b_parts.len());
-        a_parts.resize(len, 0);
-        b_parts.resize(len, 0);
+/// 解压 ZIP 中的所有条目到 extract_root，按已处理的解压后字节数汇报进度（百分比）
+///
+/// 返回因名称无法安全解析而跳过的条目数；跳过过多时视为压缩包损坏
+fn extract_zip_entries(
+    zip_path: &Path,
+    extract_root: &Path,
+    sub_progress_interval: Duration,
+    cancel: &CancelToken,
+    on_progress: &mut dyn FnMut(f64, String),
+) -> Result<usize, String> {
+    let extract_root = &long_path(extract_root);
+    let mut archive = open_zip_archive(zip_path)?;
+
+    let entry_count = archive.len();
+    let mut total_bytes: u64 = 0;
+    for i in 0..entry_count {
+        let file = archive
+            .by_index_raw(i)
+            .map_err(|e| corrupt_archive_error(format!("无法读取第 {} 个条目: {}", i + 1, e)))?;
+        total_bytes += file.size();
+    }
+    let percent_of = |processed_bytes: u64, processed_entries: usize| -> f64 {
+        extraction_percent(processed_bytes, total_bytes, processed_entries, entry_count)
+    };
 
-        for (a_part, b_part) in a_parts.iter().zip(b_parts.iter()) {
-            match a_part.cmp(b_part) {
-                Ordering::Equal => continue,
-                non_eq => return non_eq,
+    let mut processed_bytes: u64 = 0;
+    let mut skipped: Vec<String> = Vec::new();
+    for i in 0..entry_count {
+        if cancel.is_cancelled() {
+            return Err(EXTRACTION_ABORTED.to_string());
+        }
+        let mut file = archive
+            .by_index(i)
+            .map_err(|e| corrupt_archive_error(format!("无法读取第 {} 个条目: {}", i + 1, e)))?;
+        let relative_path = match resolve_entry_path(file.name_raw(), file.name()) {
+            Ok(path) => path,
+            Err(entry_name) => {
+                skipped.push(entry_name);
+                processed_bytes += file.size();
+                continue;
+            }
+        };
+        let out_path = extract_root.join(&relative_path);
+        tracing::trace!("解压条目 {}", relative_path.display());
+
+        if file.is_dir() {
+            fs::create_dir_all(&out_path)
+                .map_err(|e| format!("Failed to create directory: {}", e))?;
+        } else {
+            if let Some(parent) = out_path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create parent directory: {}", e))?;
             }
+
+            let entry_size = file.size();
+            let entry_name = relative_path.display().to_string();
+            let mut report_copied = |copied: u64| {
+                let percent = percent_of(processed_bytes + copied.min(entry_size), i);
+                let entry_percent = if entry_size > 0 {
+                    copied as f64 / entry_size as f64 * 100.0
+                } else {
+                    100.0
+                };
+                on_progress(
+                    percent,
+                    format!("解压 {} ({:.0}%)", entry_name, entry_percent.min(100.0)),
+                );
+            };
+            let mut reader =
+                CountingReader::new(&mut file, sub_progress_interval, &mut report_copied);
+            copy_zip_entry(&mut reader, &out_path, &entry_name, &mut |_| {
+                !cancel.is_cancelled()
+            })?;
         }
+
+        processed_bytes += file.size();
+        let percent = percent_of(processed_bytes, i + 1);
+        on_progress(
+            percent,
+            format!("解压 {}/{} ({:.1}%)", i + 1, entry_count, percent),
+        );
     }
 
-    a.cmp(b)
+    check_skipped_entries(&skipped)
 }
 
-#[derive(Clone)]
-pub struct DataService {
-    data_dir: PathBuf,
-    index_db_path: PathBuf,
-}
+/// 多线程解压：读取一次中央目录后按大小把文件条目分配给各个工作线程，
+/// 每个线程各自打开一个 ZipArchive 句柄；目录在分配前统一创建。
+/// 任一线程出错时其余线程会在当前数据块写完后停止
+fn extract_zip_entries_parallel(
+    zip_path: &Path,
+    extract_root: &Path,
+    workers: usize,
+    cancel: &CancelToken,
+    on_progress: &mut dyn FnMut(f64, String),
+) -> Result<usize, String> {
+    let extract_root = &long_path(extract_root);
+    let mut archive = open_zip_archive(zip_path)?;
+    let entry_count = archive.len();
+
+    let mut files: Vec<(usize, u64, PathBuf)> = Vec::new();
+    let mut directories: HashSet<PathBuf> = HashSet::new();
+    let mut skipped: Vec<String> = Vec::new();
+    let mut total_bytes: u64 = 0;
+    for i in 0..entry_count {
+        let file = archive
+            .by_index_raw(i)
+            .map_err(|e| corrupt_archive_error(format!("无法读取第 {} 个条目: {}", i + 1, e)))?;
+        total_bytes += file.size();
+        let relative_path = match resolve_entry_path(file.name_raw(), file.name()) {
+            Ok(path) => path,
+            Err(entry_name) => {
+                skipped.push(entry_name);
+                continue;
+            }
+        };
+        if file.is_dir() {
+            directories.insert(relative_path);
+        } else {
+            if let Some(parent) = relative_path.parent() {
+                directories.insert(parent.to_path_buf());
+            }
+            files.push((i, file.size(), relative_path));
+        }
+    }
+    drop(archive);
+    check_skipped_entries(&skipped)?;
 
-impl DataService {
-    pub fn is_installed(&self) -> bool {
-        self.data_dir
-            .join("zh_CN/gamedata/excel/story_review_table.json")
-            .exists()
+    for dir in &directories {
+        fs::create_dir_all(extract_root.join(dir))
+            .map_err(|e| format!("Failed to create directory: {}", e))?;
     }
-    pub fn new(app_data_dir: PathBuf) -> Self {
-        Self {
-            data_dir: app_data_dir.join("ArknightsGameData"),
-            index_db_path: app_data_dir.join("story_index.db"),
-        }
+
+    // 大文件优先，依次分配给当前负载最小的线程
+    let workers = workers.clamp(1, files.len().max(1));
+    files.sort_by_key(|(_, size, _)| std::cmp::Reverse(*size));
+    let mut partitions: Vec<(u64, Vec<(usize, PathBuf)>)> = vec![(0, Vec::new()); workers];
+    for (index, size, path) in files {
+        let lightest = partitions
+            .iter_mut()
+            .min_by_key(|(bytes, _)| *bytes)
+            .expect("at least one worker");
+        lightest.0 += size;
+        lightest.1.push((index, path));
     }
 
-    fn open_index_connection(&self) -> Result<Connection, String> {
-        if let Some(parent) = self.index_db_path.parent() {
-            fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create index directory: {}", e))?;
+    let processed_bytes = AtomicU64::new(0);
+    let processed_entries = AtomicUsize::new(0);
+    let aborted = AtomicBool::new(false);
+    let file_count = partitions.iter().map(|(_, p)| p.len()).sum::<usize>();
+
+    let results: Vec<Result<(), String>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = partitions
+            .into_iter()
+            .map(|(_, partition)| {
+                let (processed_bytes, processed_entries, aborted) =
+                    (&processed_bytes, &processed_entries, &aborted);
+                scope.spawn(move || -> Result<(), String> {
+                    let result = (|| {
+                        let mut archive = open_zip_archive(zip_path)?;
+                        for (index, relative_path) in partition {
+                            if aborted.load(AtomicOrdering::Relaxed) || cancel.is_cancelled() {
+                                return Err(EXTRACTION_ABORTED.to_string());
+                            }
+                            let mut file = archive.by_index(index).map_err(|e| {
+                                corrupt_archive_error(format!(
+                                    "无法读取第 {} 个条目: {}",
+                                    index + 1,
+                                    e
+                                ))
+                            })?;
+                            let entry_name = relative_path.display().to_string();
+                            tracing::trace!("解压条目 {}", entry_name);
+                            copy_zip_entry(
+                                &mut file,
+                                &extract_root.join(&relative_path),
+                                &entry_name,
+                                &mut |read| {
+                                    processed_bytes.fetch_add(read, AtomicOrdering::Relaxed);
+                                    !aborted.load(AtomicOrdering::Relaxed) && !cancel.is_cancelled()
+                                },
+                            )?;
+                            processed_entries.fetch_add(1, AtomicOrdering::Relaxed);
+                        }
+                        Ok(())
+                    })();
+                    if result.is_err() {
+                        aborted.store(true, AtomicOrdering::Relaxed);
+                    }
+                    result
+                })
+            })
+            .collect();
+
+        // 进度回调不要求 Send，只在当前线程上汇总调用
+        while !handles.iter().all(|handle| handle.is_finished()) {
+            std::thread::sleep(PARALLEL_PROGRESS_POLL);
+            let done = processed_entries.load(AtomicOrdering::Relaxed);
+            let percent = extraction_percent(
+                processed_bytes.load(AtomicOrdering::Relaxed),
+                total_bytes,
+                done,
+                file_count,
+            );
+            on_progress(
+                percent,
+                format!("解压 {}/{} ({:.1}%)", done, file_count, percent),
+            );
         }
-        let conn = Connection::open(&self.index_db_path)
-            .map_err(|e| format!("Failed to open story index database: {}", e))?;
-        conn.execute_batch(
-            "
-            PRAGMA journal_mode = WAL;
-            PRAGMA synchronous = NORMAL;
-            ",
-        )
-        .map_err(|e| format!("Failed to configure index database: {}", e))?;
-        Ok(conn)
+
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err("Extraction worker panicked".to_string()))
+            })
+            .collect()
+    });
+
+    // 优先返回真正的错误，而不是因其他线程出错而中止的提示
+    let mut first_abort = None;
+    for result in results {
+        match result {
+            Err(err) if err == EXTRACTION_ABORTED => first_abort = Some(err),
+            Err(err) => return Err(err),
+            Ok(()) => {}
+        }
+    }
+    if let Some(err) = first_abort {
+        return Err(err);
     }
 
-    fn try_open_index_connection(&self) -> Result<Option<Connection>, String> {
-        if !self.index_db_path.exists() {
-            return Ok(None);
+    on_progress(
+        100.0,
+        format!("解压 {}/{} (100.0%)", file_count, file_count),
+    );
+    Ok(skipped.len())
+}
+
+/// 下载停滞看门狗：在辅助线程上读取响应，超过 stall_timeout 没有任何数据到达时返回 TimedOut
+///
+/// 阻塞客户端只能设置整个请求的超时，无法区分“慢”与“卡死”，因此在这里单独检测
+struct StallWatchdogReader {
+    receiver: mpsc::Receiver<std::io::Result<Vec<u8>>>,
+    chunk: Vec<u8>,
+    offset: usize,
+    stall_timeout: Duration,
+    finished: bool,
+}
+
+impl StallWatchdogReader {
+    fn new<R: Read + Send + 'static>(mut inner: R, stall_timeout: Duration) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(4);
+        std::thread::spawn(move || {
+            let mut buffer = vec![0u8; 64 * 1024];
+            loop {
+                let message = match inner.read(&mut buffer) {
+                    Ok(read) => Ok(buffer[..read].to_vec()),
+                    Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                    Err(err) => Err(err),
+                };
+                let done = !matches!(&message, Ok(chunk) if !chunk.is_empty());
+                // 读取方已放弃（停滞中断或出错）时退出，连同 inner 一起释放连接
+                if sender.send(message).is_err() || done {
+                    break;
+                }
+            }
+        });
+        Self {
+            receiver,
+            chunk: Vec::new(),
+            offset: 0,
+            stall_timeout,
+            finished: false,
         }
-        match self.open_index_connection() {
-            Ok(conn) => Ok(Some(conn)),
-            Err(err) => {
-                eprintln!("[INDEX] Failed to open story index: {}", err);
-                Ok(None)
+    }
+}
+
+impl Read for StallWatchdogReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.offset >= self.chunk.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            match self.receiver.recv_timeout(self.stall_timeout) {
+                Ok(Ok(chunk)) if chunk.is_empty() => {
+                    self.finished = true;
+                    return Ok(0);
+                }
+                Ok(Ok(chunk)) => {
+                    self.chunk = chunk;
+                    self.offset = 0;
+                }
+                Ok(Err(err)) => return Err(err),
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    return Err(std::io::Error::new(
+                        ErrorKind::TimedOut,
+                        DOWNLOAD_STALLED_ERROR,
+                    ))
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    return Err(std::io::Error::new(
+                        ErrorKind::BrokenPipe,
+                        "download reader thread exited unexpectedly",
+                    ))
+                }
             }
         }
+
+        let available = &self.chunk[self.offset..];
+        let read = available.len().min(buf.len());
+        buf[..read].copy_from_slice(&available[..read]);
+        self.offset += read;
+        Ok(read)
     }
+}
 
-    fn init_index_tables(conn: &Connection) -> Result<(), String> {
-        // meta table
-        conn.execute_batch(
-            "
-            CREATE TABLE IF NOT EXISTS story_index_meta (
-                key TEXT PRIMARY KEY,
-                value TEXT
-            );
-            ",
-        )
-        .map_err(|e| format!("Failed to init story index meta: {}", e))?;
-
-        // read current version
-        let current_version: i32 = conn
-            .query_row(
-                "SELECT value FROM story_index_meta WHERE key = 'index_version'",
-                [],
-                |row| row.get::<_, String>(0),
-            )
-            .ok()
-            .and_then(|s| s.parse::<i32>().ok())
-            .unwrap_or(0);
+/// 读取下载流出错时的提示；停滞超时统一返回 DOWNLOAD_STALLED_ERROR 供上层识别
+fn download_read_error(context: &str, err: &std::io::Error) -> String {
+    if err.kind() == ErrorKind::TimedOut {
+        DOWNLOAD_STALLED_ERROR.to_string()
+    } else {
+        format!("{}: {}", context, err)
+    }
+}
 
-        let should_recreate = current_version < INDEX_VERSION;
+/// 与 ZipFile::enclosed_name 相同的过滤规则：拒绝绝对路径与 `..`，避免写出解压目录
+fn enclosed_path(path: &Path) -> Option<PathBuf> {
+    let mut enclosed = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => enclosed.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    if enclosed.as_os_str().is_empty() {
+        None
+    } else {
+        Some(enclosed)
+    }
+}
 
-        if should_recreate {
-            // Drop and recreate virtual table with new schema
-            conn.execute_batch(
-                "
-                DROP TABLE IF EXISTS story_index;
-                CREATE VIRTUAL TABLE story_index USING fts5(
-                    story_id UNINDEXED,
-                    story_name,
-                    category UNINDEXED,
-                    tokenized_content,
-                    story_code,
-                    raw_content UNINDEXED,
-                    tokenize = 'unicode61 remove_diacritics 2',
-                    prefix='2 3 4'
-                );
-                ",
-            )
-            .map_err(|e| format!("Failed to (re)create story index: {}", e))?;
+/// 边读取边解压 tar.gz 流到 extract_root，返回写出的条目数
+fn extract_tar_gz_entries<R: Read>(
+    reader: R,
+    extract_root: &Path,
+    cancel: &CancelToken,
+) -> Result<usize, String> {
+    let extract_root = &long_path(extract_root);
+    let mut archive = tar::Archive::new(GzDecoder::new(reader));
+    let entries = archive
+        .entries()
+        .map_err(|e| download_read_error("Failed to read tar archive", &e))?;
+
+    let mut extracted = 0usize;
+    for entry in entries {
+        cancel.check()?;
+        let mut entry = entry.map_err(|e| download_read_error("Failed to read tar entry", &e))?;
+        let relative_path = match entry.path().ok().and_then(|path| enclosed_path(&path)) {
+            Some(path) => path,
+            None => continue,
+        };
+        let out_path = extract_root.join(&relative_path);
+        tracing::trace!("解压条目 {}", relative_path.display());
 
-            conn.execute(
-                "INSERT INTO story_index_meta (key, value) VALUES ('index_version', ?1)
-                 ON CONFLICT(key) DO UPDATE SET value = excluded.value",
-                params![INDEX_VERSION.to_string()],
-            )
-            .map_err(|e| format!("Failed to update index version: {}", e))?;
-        } else {
-            // Ensure table exists (fresh install)
-            conn.execute_batch(
-                "
-                CREATE VIRTUAL TABLE IF NOT EXISTS story_index USING fts5(
-                    story_id UNINDEXED,
-                    story_name,
-                    category UNINDEXED,
-                    tokenized_content,
-                    story_code,
-                    raw_content UNINDEXED,
-                    tokenize = 'unicode61 remove_diacritics 2',
-                    prefix='2 3 4'
-                );
-                ",
-            )
-            .map_err(|e| format!("Failed to ensure story index table: {}", e))?;
+        match entry.header().entry_type() {
+            tar::EntryType::Directory => {
+                fs::create_dir_all(&out_path)
+                    .map_err(|e| format!("Failed to create directory: {}", e))?;
+            }
+            tar::EntryType::Regular | tar::EntryType::Continuous => {
+                if let Some(parent) = out_path.parent() {
+                    fs::create_dir_all(parent)
+                        .map_err(|e| format!("Failed to create parent directory: {}", e))?;
+                }
+                let mut outfile =
+                    fs::File::create(&out_path).map_err(|e| create_file_error(&out_path, &e))?;
+                std::io::copy(&mut entry, &mut outfile)
+                    .map_err(|e| download_read_error("Failed to write file", &e))?;
+            }
+            // pax 全局头、符号链接等与数据无关的条目
+            _ => continue,
         }
+        extracted += 1;
+    }
 
-        Ok(())
+    Ok(extracted)
+}
+
+/// 在解压目录中查找数据根目录：包含 {language}/gamedata/excel/story_review_table.json 的最浅一层目录
+///
+/// 兼容 codeload 归档（ArknightsGameData-master/）、手工打包时直接以语言目录为顶层、
+/// 多套一层目录以及带有 __MACOSX 等元数据目录的压缩包
+fn find_extracted_root(extract_root: &Path, language: &str) -> Result<PathBuf, String> {
+    let marker = Path::new(language)
+        .join("gamedata")
+        .join("excel")
+        .join("story_review_table.json");
+
+    let found = walkdir::WalkDir::new(extract_root)
+        .max_depth(EXTRACT_ROOT_SEARCH_DEPTH)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !is_archive_metadata(entry.file_name()))
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_dir() && entry.path().join(&marker).is_file())
+        .min_by_key(|entry| entry.depth());
+    if let Some(entry) = found {
+        return Ok(entry.into_path());
     }
 
-    fn clear_story_index(&self) -> Result<(), String> {
-        if self.index_db_path.exists() {
-            fs::remove_file(&self.index_db_path)
-                .map_err(|e| format!("Failed to remove story index: {}", e))?;
+    let mut top_level: Vec<String> = fs::read_dir(extract_root)
+        .map_err(|e| format!("Failed to read extracted directory: {}", e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    top_level.sort();
+    let listing = if top_level.is_empty() {
+        "（空）".to_string()
+    } else {
+        top_level.join(", ")
+    };
+    Err(format!(
+        "解压后的文件结构不正确：未找到 {}，压缩包顶层内容为 {}",
+        marker.to_string_lossy().replace('\\', "/"),
+        listing
+    ))
+}
+
+/// macOS 打包时附带的 __MACOSX、.DS_Store 等与数据无关的条目
+fn is_archive_metadata(name: &std::ffi::OsStr) -> bool {
+    let name = name.to_string_lossy();
+    name == "__MACOSX" || name.starts_with('.')
+}
+
+fn download_progress(downloaded: u64, total_bytes: u64) -> (usize, String) {
+    let percent = if total_bytes > 0 {
+        (downloaded as f64 / total_bytes as f64 * 100.0).min(100.0)
+    } else {
+        0.0
+    };
+    let downloaded_mb = downloaded as f64 / 1_048_576.0;
+    let total_mb = total_bytes as f64 / 1_048_576.0;
+    let message = if total_bytes > 0 {
+        format!("已下载 {:.1}/{:.1} MB", downloaded_mb, total_mb.max(0.1))
+    } else {
+        format!("已下载 {:.1} MB", downloaded_mb)
+    };
+    (percent.round() as usize, message)
+}
+
+/// 键名像是凭据（token、password、secret 等）的值整体替换，其余字符串中的令牌就地遮蔽
+fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, entry) in map.iter_mut() {
+                let key = key.to_ascii_lowercase();
+                let sensitive = ["token", "password", "secret", "apikey", "api_key"]
+                    .iter()
+                    .any(|marker| key.contains(marker));
+                if sensitive && !entry.is_null() {
+                    *entry = Value::String("[REDACTED]".to_string());
+                } else {
+                    redact_json(entry);
+                }
+            }
         }
-        Ok(())
+        Value::Array(items) => items.iter_mut().for_each(redact_json),
+        Value::String(text) => {
+            if let std::borrow::Cow::Owned(redacted) = logging::redact_secrets(text) {
+                *text = redacted;
+            }
+        }
+        _ => {}
     }
+}
 
-    fn entry_type_display(entry_type: &str) -> String {
-        match entry_type {
-            "MAINLINE" => "主线".to_string(),
-            "ACTIVITY" | "MINI_ACTIVITY" => "活动".to_string(),
-            "ROGUELIKE" => "肉鸽".to_string(),
-            "SIDESTORY" => "支线".to_string(),
-            "NONE" => "干员密录".to_string(),
-            _ => entry_type.to_string(),
-        }
+fn write_zip_entry<W: Write + std::io::Seek>(
+    writer: &mut zip::ZipWriter<W>,
+    name: &str,
+    bytes: &[u8],
+) -> Result<(), String> {
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    writer
+        .start_file(name, options)
+        .map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+    writer
+        .write_all(bytes)
+        .map_err(|e| format!("写入 {} 失败: {}", name, e))
+}
+
+/// infoUnlockDatas 条目的解析结果
+enum StoryEntryParse {
+    Strict(StoryEntry),
+    /// 严格反序列化失败，仅从关键字段构造
+    Lenient(StoryEntry, String),
+    Failed(String),
+}
+
+fn classify_story_entry(value: &Value) -> StoryEntryParse {
+    match serde_json::from_value::<StoryEntry>(value.clone()) {
+        Ok(story) => StoryEntryParse::Strict(story),
+        Err(err) => match lenient_story_entry(value) {
+            Some(story) => StoryEntryParse::Lenient(story, err.to_string()),
+            None => StoryEntryParse::Failed(err.to_string()),
+        },
     }
+}
 
-    fn resolve_category_name(entry_type: &str, entry_id: &str, value: &Value) -> String {
-        if let Some(name) = value
-            .get("name")
+/// 宽松构造：只要求 storyId、storyName、storyTxt 三个字段，其余字段尽力提取
+fn lenient_story_entry(value: &Value) -> Option<StoryEntry> {
+    let str_field = |key: &str| {
+        value
+            .get(key)
             .and_then(|v| v.as_str())
-            .map(str::trim)
-            .filter(|s| !s.is_empty())
-        {
-            return name.to_string();
+            .map(|s| s.to_string())
+    };
+    let int_field = |key: &str| {
+        value.get(key).and_then(|v| {
+            v.as_i64()
+                .or_else(|| v.as_str().and_then(|s| s.trim().parse::<i64>().ok()))
+                .map(|n| n as i32)
+        })
+    };
+
+    Some(StoryEntry {
+        story_id: str_field("storyId")?,
+        story_name: str_field("storyName")?,
+        story_code: str_field("storyCode"),
+        story_group: str_field("storyGroup").unwrap_or_default(),
+        story_sort: int_field("storySort").unwrap_or(0),
+        avg_tag: str_field("avgTag"),
+        avg_tag_kind: None,
+        story_txt: str_field("storyTxt")?,
+        story_info: str_field("storyInfo"),
+        story_review_type: str_field("storyReviewType").unwrap_or_default(),
+        unlock_type: str_field("unLockType").unwrap_or_default(),
+        story_dependence: str_field("storyDependence"),
+        story_can_show: int_field("storyCanShow"),
+        story_can_enter: int_field("storyCanEnter"),
+        visibility: None,
+        stage_count: int_field("stageCount"),
+        required_stages: value
+            .get("requiredStages")
+            .and_then(|v| serde_json::from_value(v.clone()).ok()),
+        cost_item_type: str_field("costItemType"),
+        cost_item_id: str_field("costItemId"),
+        cost_item_count: int_field("costItemCount"),
+        unlock_desc: None,
+        favorite: None,
+        group_favorite: None,
+        word_count: None,
+        estimated_minutes: None,
+        source_act_name: None,
+        source_stage_code: None,
+    })
+}
+
+/// 解析 story_review_table 中的单个剧情条目，失败时记录日志并跳过
+fn parse_story_entry(entry_id: &str, value: &Value) -> Option<StoryEntry> {
+    let mut story = match classify_story_entry(value) {
+        StoryEntryParse::Strict(story) => story,
+        StoryEntryParse::Lenient(story, err) => {
+            tracing::debug!(
+                "Story {} in {} parsed leniently: {}",
+                story.story_id,
+                entry_id,
+                err
+            );
+            story
+        }
+        StoryEntryParse::Failed(err) => {
+            let story_id = value
+                .get("storyId")
+                .and_then(|v| v.as_str())
+                .unwrap_or("<unknown>");
+            tracing::warn!("Skip story {} in {}: {}", story_id, entry_id, err);
+            return None;
         }
+    };
+    story.avg_tag_kind = Some(classify_avg_tag(story.avg_tag.as_deref()));
+    story.visibility = Some(story_visibility(&story));
+    Some(story)
+}
 
-        let display = Self::entry_type_display(entry_type);
-        if display == entry_type {
-            format!("{} ({})", entry_type, entry_id)
-        } else {
-            format!("{} ({})", display, entry_id)
+/// 逐条解析 chapter_table，无法解析的章节记录日志后跳过；返回解析成功的章节与被跳过的 id
+fn parse_chapters(table: &Value) -> Result<(Vec<Chapter>, Vec<String>), String> {
+    let data = table_entries(table, "chapter_table.json")?;
+    let mut chapters = Vec::with_capacity(data.len());
+    let mut skipped = Vec::new();
+    for (chapter_id, value) in data.iter() {
+        match Chapter::deserialize(value) {
+            Ok(chapter) => chapters.push(chapter),
+            Err(err) => {
+                tracing::warn!("Skip chapter {}: {}", chapter_id, err);
+                skipped.push(chapter_id.clone());
+            }
         }
     }
+    Ok((chapters, skipped))
+}
 
-    fn format_category_label(entry_type: &str, category_name: &str) -> String {
-        let prefix = Self::entry_type_display(entry_type);
-        let name = category_name.trim();
-        if name.is_empty() || name == prefix {
-            prefix
-        } else {
-            format!("{} | {}", prefix, name)
-        }
+fn story_visibility(story: &StoryEntry) -> StoryVisibility {
+    if story.story_can_show == Some(0) {
+        StoryVisibility::Hidden
+    } else if story.story_can_enter == Some(0) {
+        StoryVisibility::Locked
+    } else {
+        StoryVisibility::Normal
     }
+}
 
-    fn collect_stories_for_index(&self) -> Result<Vec<IndexedStory>, String> {
-        if !self.is_installed() {
-            return Err("NOT_INSTALLED".to_string());
-        }
+/// 归一化 avgTag，兼容各语言数据源的写法；缺失或无法识别的归为 Other
+fn classify_avg_tag(tag: Option<&str>) -> AvgTagKind {
+    let tag = tag.unwrap_or("").trim().to_lowercase();
+    match tag.as_str() {
+        "行动前" | "戦闘前" | "작전 전" | "before operation" => AvgTagKind::Before,
+        "行动后" | "戦闘後" | "작전 후" | "after operation" => AvgTagKind::After,
+        "幕间" | "幕間" | "막간" | "interlude" => AvgTagKind::Interlude,
+        _ => AvgTagKind::Other,
+    }
+}
 
-        let story_review_file = self
-            .data_dir
-            .join("zh_CN/gamedata/excel/story_review_table.json");
+/// 行动前 < 行动后 < 幕间 < 其他
+fn avg_tag_rank(story: &StoryEntry) -> u8 {
+    match story
+        .avg_tag_kind
+        .unwrap_or_else(|| classify_avg_tag(story.avg_tag.as_deref()))
+    {
+        AvgTagKind::Before => 0,
+        AvgTagKind::After => 1,
+        AvgTagKind::Interlude => 2,
+        AvgTagKind::Other => 3,
+    }
+}
 
-        let content = fs::read_to_string(&story_review_file)
-            .map_err(|e| format!("Failed to read story review file: {}", e))?;
+/// 组内剧情排序：story_sort 相同时依次比较关卡编号（自然数顺序）、avgTag 与 story_id，
+/// 避免「行动前/行动后」顺序随 HashMap 遍历顺序变化
+fn sort_story_entries(stories: &mut [StoryEntry]) {
+    stories.sort_by(|a, b| {
+        a.story_sort
+            .cmp(&b.story_sort)
+            .then_with(|| match (&a.story_code, &b.story_code) {
+                (Some(a_code), Some(b_code)) => compare_story_group_ids(a_code, b_code),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            })
+            .then_with(|| avg_tag_rank(a).cmp(&avg_tag_rank(b)))
+            .then_with(|| a.story_id.cmp(&b.story_id))
+    });
+}
 
-        let data: HashMap<String, Value> = serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse story review data: {}", e))?;
+fn matches_tag_filter(story: &StoryEntry, tag_filter: Option<&[AvgTagKind]>) -> bool {
+    tag_filter.is_none_or(|kinds| kinds.contains(&story.avg_tag_kind.unwrap_or(AvgTagKind::Other)))
+}
 
-        let mut seen_ids = HashSet::new();
-        let mut stories = Vec::new();
+/// 按标签类型过滤分组内的剧情，过滤后为空的分组整体去掉
+fn filter_groups_by_tag(
+    groups: Vec<StoryGroup>,
+    tag_filter: Option<&[AvgTagKind]>,
+) -> Vec<StoryGroup> {
+    if tag_filter.is_none() {
+        return groups;
+    }
+    groups
+        .into_iter()
+        .filter_map(|mut group| {
+            group
+                .stories
+                .retain(|story| matches_tag_filter(story, tag_filter));
+            (!group.stories.is_empty()).then_some(group)
+        })
+        .collect()
+}
 
-        for (entry_id, value) in data.iter() {
-            let entry_type = value
-                .get("entryType")
-                .and_then(|v| v.as_str())
-                .unwrap_or("UNKNOWN");
+/// 条目的开放时间；0 或负数视为缺失
+fn group_start_time(value: &Value) -> Option<i64> {
+    value
+        .get("startTime")
+        .and_then(|v| v.as_i64())
+        .filter(|time| *time > 0)
+}
 
-            let Some(unlock_datas) = value.get("infoUnlockDatas").and_then(|v| v.as_array()) else {
-                continue;
-            };
+/// 解析 story_review_table 中的活动条目；id 优先取条目自身字段，缺失时回退到表键
+fn parse_activity(key: &str, value: &Value) -> Activity {
+    let text = |field: &str| value.get(field).and_then(|v| v.as_str()).unwrap_or("");
+    let id = match text("id") {
+        "" => key.to_string(),
+        id => id.to_string(),
+    };
+    let name = text("name").to_string();
+    let act_type = text("actType").to_string();
+    let is_rerun = is_rerun_activity(&name, &act_type);
+    let info_unlock_datas = value
+        .get("infoUnlockDatas")
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| parse_story_entry(&id, item))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Activity {
+        entry_type: text("entryType").to_string(),
+        start_time: value.get("startTime").and_then(|v| v.as_i64()).unwrap_or(0),
+        end_time: value
+            .get("endTime")
+            .and_then(|v| v.as_i64())
+            .filter(|time| *time > 0),
+        is_rerun,
+        info_unlock_datas,
+        id,
+        name,
+        act_type,
+    }
+}
 
-            let category_name = Self::resolve_category_name(entry_type, entry_id, value);
+/// 开放时间（Unix 秒）对应的年份，按服务器所在的 UTC+8 计算
+fn timestamp_year(timestamp: i64) -> i32 {
+    // 公历换算参考 Howard Hinnant 的 civil_from_days
+    let days = (timestamp + 8 * 3600).div_euclid(86_400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let year = year_of_era + era * 400;
+    (if month_index >= 10 { year + 1 } else { year }) as i32
+}
 
-            for unlock_data in unlock_datas {
-                if let Ok(story) = serde_json::from_value::<StoryEntry>(unlock_data.clone()) {
-                    if story.story_txt.trim().is_empty() {
-                        continue;
-                    }
-                    if seen_ids.insert(story.story_id.clone()) {
-                        stories.push(IndexedStory {
-                            category_name: category_name.clone(),
-                            entry_type: entry_type.to_string(),
-                            story,
-                        });
-                    }
+/// 去掉活动名称末尾的「·复刻」/「复刻」
+fn strip_rerun_suffix(name: &str) -> &str {
+    let trimmed = name.trim_end();
+    match trimmed.strip_suffix("复刻") {
+        Some(base) => base.trim_end().trim_end_matches('·').trim_end(),
+        None => trimmed,
+    }
+}
+
+/// story_review_table 中活动类条目的归类，支线与活动列表各取其一、互不重叠
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ActivityKind {
+    /// 支线：大型活动（actType = ACTIVITY_STORY）
+    SideStory,
+    /// 故事集（actType = MINI_STORY）
+    StoryCollection,
+    /// 其余小型活动
+    MiniActivity,
+}
+
+impl ActivityKind {
+    /// 写入搜索索引的分类类型，对应 entry_type_display 的标签
+    fn index_entry_type(self) -> &'static str {
+        match self {
+            ActivityKind::SideStory => "SIDESTORY",
+            ActivityKind::StoryCollection => "STORY_COLLECTION",
+            ActivityKind::MiniActivity => "ACTIVITY",
+        }
+    }
+}
+
+/// 按 entryType/actType 归类活动条目；非活动条目返回 None
+fn classify_activity_entry(entry_type: &str, act_type: &str) -> Option<ActivityKind> {
+    if !matches!(entry_type, "ACTIVITY" | "MINI_ACTIVITY") {
+        return None;
+    }
+    Some(match act_type {
+        "ACTIVITY_STORY" => ActivityKind::SideStory,
+        "MINI_STORY" => ActivityKind::StoryCollection,
+        _ => ActivityKind::MiniActivity,
+    })
+}
+
+/// 复刻活动在名称上带「·复刻」后缀，部分数据源会在 actType 中标注 RERUN
+fn is_rerun_activity(name: &str, act_type: &str) -> bool {
+    name.trim_end().ends_with("复刻") || act_type.to_ascii_uppercase().contains("RERUN")
+}
+
+/// 从 handbook_info_table 的 handbookAvgList 建立 storySetId/storyId -> charId 的映射
+fn memory_owner_map(handbook: &Value) -> HashMap<String, String> {
+    let mut owners = HashMap::new();
+    let Some(dict) = handbook.get("handbookDict").and_then(|v| v.as_object()) else {
+        return owners;
+    };
+    for (key, info) in dict {
+        let char_id = info
+            .get("charID")
+            .and_then(|v| v.as_str())
+            .unwrap_or(key.as_str());
+        let Some(avg_sets) = info.get("handbookAvgList").and_then(|v| v.as_array()) else {
+            continue;
+        };
+        for avg_set in avg_sets {
+            if let Some(set_id) = avg_set.get("storySetId").and_then(|v| v.as_str()) {
+                owners.insert(set_id.to_string(), char_id.to_string());
+            }
+            let avgs = avg_set.get("avgList").and_then(|v| v.as_array());
+            for avg in avgs.into_iter().flatten() {
+                if let Some(story_id) = avg.get("storyId").and_then(|v| v.as_str()) {
+                    owners.insert(story_id.to_string(), char_id.to_string());
                 }
             }
         }
+    }
+    owners
+}
 
-        stories.sort_by(|a, b| a.story.story_id.cmp(&b.story.story_id));
-        Ok(stories)
+/// roguelike_topic_table 的 topics：主题 id（小写）-> 主题名称
+fn roguelike_topic_names(topic_table: &Value) -> HashMap<String, String> {
+    let Some(topics) = topic_table.get("topics").and_then(|v| v.as_object()) else {
+        return HashMap::new();
+    };
+    topics
+        .iter()
+        .filter_map(|(key, topic)| {
+            let id = topic.get("id").and_then(|v| v.as_str()).unwrap_or(key);
+            let name = topic.get("name").and_then(|v| v.as_str())?.trim();
+            (!name.is_empty()).then(|| (id.to_ascii_lowercase(), name.to_string()))
+        })
+        .collect()
+}
+
+/// 文本路径对应的分组键：obt/roguelike/ro1/... -> RO1，月度小队记闻 -> MONTH_CHAT_ROGUE_1
+fn roguelike_group_key(story_txt: &str) -> Option<String> {
+    let lower = story_txt.to_ascii_lowercase();
+    let rest = lower
+        .strip_prefix("obt/roguelike/")
+        .or_else(|| lower.strip_prefix("obt/rogue/"))?;
+    let mut segments = rest.split('/');
+    let topic = segments.next().filter(|s| !s.is_empty())?;
+    if segments.any(|segment| segment.starts_with("month_chat_")) {
+        Some(format!("MONTH_CHAT_{}", topic.to_uppercase()))
+    } else {
+        Some(topic.to_uppercase())
     }
+}
 
-    fn flatten_segments(segments: &[StorySegment]) -> String {
-        let mut parts = Vec::with_capacity(segments.len());
-        for segment in segments {
-            match segment {
-                StorySegment::Dialogue {
-                    character_name,
-                    text,
-                    ..
-                } => {
-                    parts.push(format!("{}：{}", character_name, text));
-                }
-                StorySegment::Narration { text }
-                | StorySegment::System { text, .. }
-                | StorySegment::Subtitle { text, .. }
-                | StorySegment::Sticker { text, .. } => {
-                    parts.push(text.clone());
-                }
-                StorySegment::Decision { options, .. } => {
-                    parts.push(options.join(" / "));
+/// roguelike_topic_table 中 endbookGroupData 声明的结局回响章节
+#[derive(Debug)]
+struct EndbookChapter {
+    ending_id: String,
+    ending_name: String,
+    sequence: i32,
+    title: Option<String>,
+}
+
+/// 递归查找 endbookGroupData，按文本路径（小写）收集结局回响章节
+fn collect_endbook_chapters(chapters: &mut HashMap<String, EndbookChapter>, value: &Value) {
+    match value {
+        Value::Object(obj) => {
+            match obj.get("endbookGroupData") {
+                Some(Value::Object(groups)) => {
+                    for (key, group) in groups {
+                        parse_endbook_group(chapters, key, group);
+                    }
                 }
-                StorySegment::Header { title } => {
-                    parts.push(title.clone());
+                Some(Value::Array(groups)) => {
+                    for group in groups {
+                        parse_endbook_group(chapters, "", group);
+                    }
                 }
+                _ => {}
+            }
+            for v in obj.values() {
+                collect_endbook_chapters(chapters, v);
             }
         }
-        parts.join("\n")
+        Value::Array(items) => {
+            for v in items {
+                collect_endbook_chapters(chapters, v);
+            }
+        }
+        _ => {}
     }
+}
 
-    fn tokenize_for_fts(text: &str) -> Vec<String> {
-        let text = normalize_nfkc_lower_strip_marks(text);
-        let mut tokens = Vec::new();
-        let mut ascii_buffer = String::new();
+fn parse_endbook_group(chapters: &mut HashMap<String, EndbookChapter>, key: &str, group: &Value) {
+    let text = |value: &Value, fields: &[&str]| {
+        fields.iter().find_map(|field| {
+            value
+                .get(*field)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        })
+    };
+    let group_ending = text(group, &["endingId", "groupId"]).unwrap_or_else(|| key.to_string());
+    let group_name = text(group, &["endbookName", "name"]);
 
-        for ch in text.chars() {
-            if ch.is_ascii_alphanumeric() {
-                ascii_buffer.push(ch.to_ascii_lowercase());
-                continue;
-            }
+    // 章节可能直接写在分组对象上，也可能位于分组内的数组中
+    let mut entries = vec![group];
+    if let Some(obj) = group.as_object() {
+        entries.extend(obj.values().filter_map(|v| v.as_array()).flatten());
+    }
+    for (index, entry) in entries.into_iter().enumerate() {
+        let Some(path) = text(entry, &["textId", "avgId", "contentPath", "storyTxt"]) else {
+            continue;
+        };
+        let ending_id = text(entry, &["endingId"]).unwrap_or_else(|| group_ending.clone());
+        let ending_name = group_name
+            .clone()
+            .or_else(|| text(entry, &["endbookName"]))
+            .unwrap_or_else(|| ending_id.clone());
+        let sequence = ["sortId", "sequence", "sort"]
+            .iter()
+            .find_map(|field| entry.get(*field).and_then(|v| v.as_i64()))
+            .unwrap_or(index as i64) as i32;
+        chapters.insert(
+            path.to_ascii_lowercase(),
+            EndbookChapter {
+                ending_id,
+                ending_name,
+                sequence,
+                title: text(entry, &["title", "chapterName"]),
+            },
+        );
+    }
+}
 
-            if !ascii_buffer.is_empty() {
-                let token = std::mem::take(&mut ascii_buffer);
-                tokens.push(token);
+/// 递归收集 roguelike_topic_table 中带 chatDesc 的月度聊天条目：文件名（小写）-> 标题
+fn collect_month_chat_titles(titles: &mut HashMap<String, String>, value: &Value) {
+    match value {
+        Value::Object(obj) => {
+            let desc = obj
+                .get("chatDesc")
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty());
+            let path = ["chatStoryId", "storyId", "avgId", "textId", "contentPath"]
+                .iter()
+                .find_map(|field| obj.get(*field).and_then(|v| v.as_str()));
+            if let (Some(desc), Some(path)) = (desc, path) {
+                let stem = path.rsplit('/').next().unwrap_or(path).to_ascii_lowercase();
+                titles.entry(stem).or_insert_with(|| desc.to_string());
             }
-
-            if ch.is_whitespace() {
-                continue;
+            for v in obj.values() {
+                collect_month_chat_titles(titles, v);
             }
-
-            if is_common_punctuation(ch) {
-                continue;
+        }
+        Value::Array(items) => {
+            for v in items {
+                collect_month_chat_titles(titles, v);
             }
+        }
+        _ => {}
+    }
+}
 
-            if ch.is_alphanumeric() {
-                let token: String = ch.to_lowercase().collect();
-                if !token.is_empty() {
-                    tokens.push(token);
+/// 分组键映射为主题名：RO1 / ROGUE_1 对应 rogue_1，MONTH_CHAT_ROGUE_1 额外标注为月度小队记闻；
+/// 无法对应主题时保留原分组键
+fn roguelike_group_label(group_key: &str, topics: &HashMap<String, String>) -> String {
+    let lower = group_key.to_ascii_lowercase();
+    let (base, month_chat) = match lower.strip_prefix("month_chat_") {
+        Some(rest) => (rest, true),
+        None => (lower.as_str(), false),
+    };
+    let topic_name = base
+        .strip_prefix("ro")
+        .map(|rest| rest.trim_start_matches(|c: char| !c.is_ascii_digit()))
+        .and_then(|rest| rest.split(|c: char| !c.is_ascii_digit()).next())
+        .filter(|n| !n.is_empty())
+        .and_then(|n| topics.get(&format!("rogue_{}", n)));
+
+    match (topic_name, month_chat) {
+        (Some(name), true) => format!("{} · 月度小队记闻", name),
+        (Some(name), false) => name.clone(),
+        (None, _) => group_key.to_string(),
+    }
+}
+
+/// 危机合约表中的赛季名称（seasonId -> name）与剧情描述（文本路径 -> desc）
+#[derive(Debug, Default)]
+struct RuneMetadata {
+    seasons: HashMap<String, String>,
+    titles: HashMap<String, String>,
+}
+
+/// 递归扫描危机合约表：收集 seasonId -> 赛季名称，以及 obt/rune 文本路径 -> 剧情描述
+fn collect_rune_metadata(
+    seasons: &mut HashMap<String, String>,
+    titles: &mut HashMap<String, String>,
+    value: &Value,
+) {
+    match value {
+        Value::Object(obj) => {
+            let text = |key: &str| {
+                obj.get(key)
+                    .and_then(|v| v.as_str())
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+            };
+            if let (Some(season_id), Some(name)) = (text("seasonId"), text("name")) {
+                seasons.insert(season_id.to_ascii_lowercase(), name.to_string());
+            }
+            let path = text("contentPath").or_else(|| text("storyTxt"));
+            if let Some(path) = path.map(str::to_ascii_lowercase) {
+                let title = text("desc")
+                    .or_else(|| text("storyName"))
+                    .or_else(|| text("name"));
+                if let (true, Some(title)) = (path.starts_with("obt/rune/"), title) {
+                    titles.insert(path, title.to_string());
                 }
-                continue;
             }
-
-            tokens.push(ch.to_string());
+            for v in obj.values() {
+                collect_rune_metadata(seasons, titles, v);
+            }
         }
-
-        if !ascii_buffer.is_empty() {
-            tokens.push(ascii_buffer);
+        Value::Array(items) => {
+            for v in items {
+                collect_rune_metadata(seasons, titles, v);
+            }
         }
+        _ => {}
+    }
+}
 
-        tokens
+/// obt/rune/<season>/... 中的赛季目录名（小写）
+fn rune_season_key(story_txt: &str) -> Option<String> {
+    let lower = story_txt.to_ascii_lowercase();
+    let rest = lower.strip_prefix("obt/rune/")?;
+    let (season, _) = rest.split_once('/')?;
+    (!season.is_empty()).then(|| season.to_string())
+}
+
+/// 赛季目录名对应的展示名称：优先精确匹配 seasonId，其次取互相包含的最长 seasonId
+fn rune_season_label(season_key: &str, seasons: &HashMap<String, String>) -> String {
+    if let Some(name) = seasons.get(season_key) {
+        return name.clone();
     }
+    seasons
+        .iter()
+        .filter(|(id, _)| id.contains(season_key) || season_key.contains(id.as_str()))
+        .max_by(|a, b| a.0.len().cmp(&b.0.len()).then_with(|| b.0.cmp(a.0)))
+        .map(|(_, name)| name.clone())
+        .unwrap_or_else(|| format!("危机合约 ({})", season_key))
+}
 
-    fn build_tokenized_content(text: &str) -> String {
-        Self::tokenize_for_fts(text).join(" ")
+fn current_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// stage_table 中关卡的显示编号（如 10-17），缺失时回退为关卡 id
+fn stage_code(stages: Option<&Value>, stage_id: &str) -> String {
+    stages
+        .and_then(|table| table.get("stages"))
+        .and_then(|table| table.get(stage_id))
+        .and_then(|stage| stage.get("code"))
+        .and_then(|v| v.as_str())
+        .filter(|code| !code.trim().is_empty())
+        .unwrap_or(stage_id)
+        .to_string()
+}
+
+/// 关卡的显示编号与名称；stage_table 缺失或没有该关卡时编号回退为关卡 id
+fn resolve_stage(stages: Option<&Value>, stage: &RequiredStage) -> ResolvedStage {
+    let name = stages
+        .and_then(|table| table.get("stages"))
+        .and_then(|table| table.get(&stage.stage_id))
+        .and_then(|entry| entry.get("name"))
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string);
+    ResolvedStage {
+        stage_id: stage.stage_id.clone(),
+        code: stage_code(stages, &stage.stage_id),
+        name,
+        min_state: stage.min_state.clone(),
+        max_state: stage.max_state.clone(),
     }
+}
 
-    // Build a more expressive FTS query:
-    // - Normalize (NFKC + lowercase + strip marks)
-    // - Chinese contiguous sequences (len>=2) -> quoted phrase of spaced characters: "凯 尔 希"
-    // - ASCII terms -> add * suffix for prefix match
-    // - Support simple NOT via leading '-' and OR keyword, default AND
-    fn build_fts_query_advanced(raw_query: &str) -> Option<String> {
-        let q = normalize_nfkc_lower_strip_marks(raw_query.trim());
-        if q.is_empty() {
-            return None;
-        }
+/// zone_table 中区域的展示名称（zoneNameSecond 优先）
+fn zone_display_name(zones: Option<&Value>, zone_id: &str) -> String {
+    let zone = zones
+        .and_then(|table| table.get("zones"))
+        .and_then(|t| t.get(zone_id));
+    ["zoneNameSecond", "zoneNameFirst"]
+        .iter()
+        .find_map(|field| {
+            zone.and_then(|z| z.get(*field))
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+        })
+        .unwrap_or(zone_id)
+        .to_string()
+}
 
-        // Simple tokenizer that respects quoted phrases
-        let mut terms: Vec<(String, bool, bool)> = Vec::new(); // (term, is_not, is_or_before)
-        let mut buf = String::new();
-        let mut in_quotes = false;
-        let mut prev_was_or = false;
-        let mut chars = q.chars().peekable();
-        while let Some(ch) = chars.next() {
-            match ch {
-                '"' => {
-                    if in_quotes {
-                        in_quotes = false;
-                        let t = std::mem::take(&mut buf);
-                        if !t.is_empty() {
-                            terms.push((t, false, prev_was_or));
-                            prev_was_or = false;
-                        }
-                    } else {
-                        if !buf.trim().is_empty() {
-                            let t = std::mem::take(&mut buf);
-                            if t == "or" {
-                                prev_was_or = true;
-                            } else {
-                                let is_not = t.starts_with('-');
-                                let content = if is_not {
-                                    t.trim_start_matches('-').to_string()
-                                } else {
-                                    t
-                                };
-                                if !content.is_empty() {
-                                    terms.push((content, is_not, prev_was_or));
-                                    prev_was_or = false;
-                                }
-                            }
-                        }
-                        in_quotes = true;
-                    }
-                }
-                c if c.is_whitespace() && !in_quotes => {
-                    if !buf.is_empty() {
-                        let t = std::mem::take(&mut buf);
-                        if t == "or" {
-                            prev_was_or = true;
-                        } else {
-                            let is_not = t.starts_with('-');
-                            let content = if is_not {
-                                t.trim_start_matches('-').to_string()
-                            } else {
-                                t
-                            };
-                            if !content.is_empty() {
-                                terms.push((content, is_not, prev_was_or));
-                                prev_was_or = false;
-                            }
-                        }
-                    }
+/// handbookAvgList 中单个密录章节的解锁条件
+#[derive(Debug, Default)]
+struct MemoryUnlock {
+    unlock_type: Option<String>,
+    conditions: Vec<String>,
+    /// (costItemType, costItemId, costItemCount)
+    cost: Option<(String, String, i32)>,
+}
+
+/// 从 handbook_info_table 的 unlockParam 解析解锁条件，按 storySetId 与各 storyId 建立索引，
+/// 同一章节下的多个 avg 条目共享同一条件
+fn memory_unlock_map(handbook: &Value) -> HashMap<String, Arc<MemoryUnlock>> {
+    let mut unlocks = HashMap::new();
+    let Some(dict) = handbook.get("handbookDict").and_then(|v| v.as_object()) else {
+        return unlocks;
+    };
+    let avg_sets = dict
+        .values()
+        .filter_map(|info| info.get("handbookAvgList").and_then(|v| v.as_array()))
+        .flatten();
+    for avg_set in avg_sets {
+        let params = avg_set.get("unlockParam").and_then(|v| v.as_array());
+        let mut unlock = MemoryUnlock::default();
+        for param in params.into_iter().flatten() {
+            let text = |key: &str| match param.get(key) {
+                Some(Value::String(s)) => s.trim().to_string(),
+                Some(Value::Number(n)) => n.to_string(),
+                _ => String::new(),
+            };
+            let unlock_type = text("unlockType");
+            let (param1, param2) = (text("unlockParam1"), text("unlockParam2"));
+            match unlock_type.as_str() {
+                "FAVOR" => unlock.conditions.push(format!("信赖值{}", param1)),
+                "PROMOTE" | "ELITE" => unlock.conditions.push(format!("精英阶段{}", param1)),
+                "LEVEL" => unlock.conditions.push(format!("等级{}", param1)),
+                "ITEM" | "ITEM_COST" if !param1.is_empty() => {
+                    let count = param2.parse::<i32>().unwrap_or(1);
+                    unlock.cost = Some(("MATERIAL".to_string(), param1, count));
                 }
-                _ => buf.push(ch),
+                _ => {}
             }
-        }
-        if !buf.is_empty() {
-            let t = std::mem::take(&mut buf);
-            if t == "or" {
-                // dangling OR, ignore
-            } else {
-                let is_not = t.starts_with('-');
-                let content = if is_not {
-                    t.trim_start_matches('-').to_string()
-                } else {
-                    t
-                };
-                if !content.is_empty() {
-                    terms.push((content, is_not, prev_was_or));
-                }
+            if unlock.unlock_type.is_none() && !unlock_type.is_empty() {
+                unlock.unlock_type = Some(unlock_type);
             }
         }
-
-        if terms.is_empty() {
-            return None;
+        if unlock.unlock_type.is_none() {
+            continue;
         }
 
-        fn to_phrase_if_cjk(s: &str) -> String {
-            let mut has_cjk = false;
-            let mut all_cjk = true;
-            for ch in s.chars() {
-                if is_cjk(ch) {
-                    has_cjk = true;
-                } else if !ch.is_whitespace() {
-                    all_cjk = false;
-                }
-            }
-            if has_cjk && all_cjk {
-                let parts: Vec<String> = s
-                    .chars()
-                    .filter(|c| !c.is_whitespace())
-                    .map(|c| if c == '"' { ' ' } else { c })
-                    .map(|c| c.to_string())
-                    .collect();
-                let spaced = parts.join(" ");
-                format!("\"{}\"", spaced)
-            } else if s.chars().all(|c| c.is_ascii_alphanumeric()) {
-                format!("{}*", s)
-            } else {
-                // mixed or others: quote as is
-                let escaped = s.replace('"', "");
-                format!("\"{}\"", escaped)
+        let unlock = Arc::new(unlock);
+        if let Some(set_id) = avg_set.get("storySetId").and_then(|v| v.as_str()) {
+            unlocks.insert(set_id.to_string(), unlock.clone());
+        }
+        let avgs = avg_set.get("avgList").and_then(|v| v.as_array());
+        for avg in avgs.into_iter().flatten() {
+            if let Some(story_id) = avg.get("storyId").and_then(|v| v.as_str()) {
+                unlocks.insert(story_id.to_string(), unlock.clone());
             }
         }
+    }
+    unlocks
+}
 
-        let mut parts: Vec<String> = Vec::new();
-        for (i, (raw, is_not, is_or)) in terms.into_iter().enumerate() {
-            if raw.is_empty() {
-                continue;
-            }
-            let mut piece = to_phrase_if_cjk(&raw);
-            if is_not {
-                piece = format!("NOT {}", piece);
+/// actArchiveData 中收录密录的活动档案：活动 id 与关联关卡 id
+#[derive(Debug, Clone)]
+struct MemoryArchive {
+    act_id: String,
+    stage_id: Option<String>,
+}
+
+/// 从 story_review_meta_table.actArchiveData 建立 密录 storyId / storySetId -> 活动档案 的映射；
+/// 档案组件按活动 id 为键，组件内任意层级带剧情 id 的对象都视为收录条目
+fn memory_archive_map(meta: &Value) -> HashMap<String, MemoryArchive> {
+    fn collect(archives: &mut HashMap<String, MemoryArchive>, act_id: &str, value: &Value) {
+        match value {
+            Value::Object(obj) => {
+                let stage_id = obj
+                    .get("stageId")
+                    .and_then(|v| v.as_str())
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty());
+                for field in ["storyId", "storySetId", "avgId"] {
+                    let Some(id) = obj.get(field).and_then(|v| v.as_str()).map(str::trim) else {
+                        continue;
+                    };
+                    if id.is_empty() {
+                        continue;
+                    }
+                    archives
+                        .entry(id.to_string())
+                        .or_insert_with(|| MemoryArchive {
+                            act_id: act_id.to_string(),
+                            stage_id: stage_id.map(str::to_string),
+                        });
+                }
+                for v in obj.values() {
+                    collect(archives, act_id, v);
+                }
             }
-            if i > 0 {
-                if is_or {
-                    parts.push("OR".to_string());
-                } else {
-                    parts.push("AND".to_string());
+            Value::Array(items) => {
+                for v in items {
+                    collect(archives, act_id, v);
                 }
             }
-            parts.push(piece);
-        }
-
-        if parts.is_empty() {
-            None
-        } else {
-            Some(parts.join(" "))
+            _ => {}
         }
     }
 
-    fn extract_meta_value(conn: &Connection, key: &str) -> Result<Option<String>, String> {
-        conn.query_row(
-            "SELECT value FROM story_index_meta WHERE key = ?1",
-            params![key],
-            |row| row.get::<_, String>(0),
-        )
-        .optional()
-        .map_err(|e| format!("Failed to read story index meta {}: {}", key, e))
+    let mut archives = HashMap::new();
+    let archive_data = meta.get("actArchiveData");
+    let components = archive_data
+        .and_then(|data| data.get("components"))
+        .or(archive_data)
+        .and_then(|v| v.as_object());
+    for (key, component) in components.into_iter().flatten() {
+        let act_id = component
+            .get("actId")
+            .and_then(|v| v.as_str())
+            .unwrap_or(key);
+        collect(&mut archives, act_id, component);
     }
+    archives
+}
 
-    /// 下载并解压最新数据包
-    pub fn sync_data(&self, app: AppHandle) -> Result<(), String> {
-        eprintln!("[SYNC] === 开始同步数据 ===");
-        emit_progress(&app, "准备", 0, 1, "正在初始化同步环境");
-
-        eprintln!("[SYNC] 创建 HTTP 客户端");
-        let client = Self::create_http_client()?;
-
-        eprintln!("[SYNC] 获取最新 commit");
-        let remote_commit = match self.fetch_latest_commit(&client) {
-            Ok(commit) => {
-                eprintln!("[SYNC] 成功获取 commit: {}", &commit);
-                let short = commit.get(..7).unwrap_or(commit.as_str());
-                emit_progress(&app, "准备", 1, 1, format!("最新版本 {}", short));
-                Some(commit)
-            }
-            Err(err) => {
-                eprintln!("[SYNC] 获取 commit 失败: {}", err);
-                emit_progress(
-                    &app,
-                    "准备",
-                    0,
-                    1,
-                    format!("获取版本信息失败，回退到 {}: {}", DEFAULT_BRANCH, err),
-                );
-                None
-            }
-        };
+/// 密录来源的展示信息：活动名称与关卡编号
+#[derive(Debug, Default)]
+struct MemorySource {
+    act_name: Option<String>,
+    stage_code: Option<String>,
+}
 
-        let reference = remote_commit
-            .clone()
-            .unwrap_or_else(|| DEFAULT_BRANCH.to_string());
-        eprintln!("[SYNC] 使用引用: {}", reference);
+/// item_table 中的物品名称，缺失时为物品 id
+fn item_name<'a>(items: Option<&'a Value>, item_id: &'a str) -> &'a str {
+    items
+        .and_then(|table| table.get("items"))
+        .and_then(|table| table.get(item_id))
+        .and_then(|item| item.get("name"))
+        .and_then(|v| v.as_str())
+        .unwrap_or(item_id)
+}
 
-        eprintln!("[SYNC] 开始下载和解压");
-        self.download_and_extract(&client, &app, &reference)?;
-        eprintln!("[SYNC] 下载和解压完成");
+/// 解析 [{ id, count, type }] 形式的材料列表，null 视为无消耗
+fn parse_item_costs(costs: Option<&Value>, items: Option<&Value>) -> Vec<ItemCost> {
+    costs
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|cost| {
+            let item_id = cost.get("id")?.as_str()?;
+            Some(ItemCost {
+                item_id: item_id.to_string(),
+                item_name: item_name(items, item_id).to_string(),
+                count: cost.get("count").and_then(|v| v.as_i64()).unwrap_or(0) as i32,
+            })
+        })
+        .collect()
+}
 
-        if let Err(err) = self.clear_story_index() {
-            eprintln!("[SYNC] Failed to reset story index: {}", err);
+/// 将解锁条件写入密录条目，并根据 item_table 生成可读描述
+fn apply_memory_unlock(
+    story: &mut StoryEntry,
+    unlock: Option<&MemoryUnlock>,
+    items: Option<&Value>,
+) {
+    let mut conditions: &[String] = &[];
+    if let Some(unlock) = unlock {
+        if let Some(unlock_type) = &unlock.unlock_type {
+            story.unlock_type = unlock_type.clone();
         }
+        if let Some((item_type, item_id, count)) = &unlock.cost {
+            story.cost_item_type = Some(item_type.clone());
+            story.cost_item_id = Some(item_id.clone());
+            story.cost_item_count = Some(*count);
+        }
+        conditions = &unlock.conditions;
+    }
 
-        // 写入版本信息
-        eprintln!("[SYNC] 写入版本信息");
-        let commit_to_store = remote_commit.unwrap_or_else(|| "unknown".to_string());
-        let fetched_at = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map(|d| d.as_secs() as i64)
-            .unwrap_or(0);
-        let info = VersionInfo {
-            commit: commit_to_store,
-            fetched_at,
-        };
-        self.write_version(&info)?;
-
-        eprintln!("[SYNC] === 同步完成 ===");
-        emit_progress(&app, "完成", 1, 1, "同步完成");
-        Ok(())
+    let mut parts = Vec::new();
+    if !conditions.is_empty() {
+        parts.push(format!("解锁需要{}", conditions.join("、")));
+    }
+    if let Some(item_id) = story.cost_item_id.as_deref().filter(|id| !id.is_empty()) {
+        let item_name = item_name(items, item_id);
+        parts.push(format!(
+            "消耗{}×{}",
+            item_name,
+            story.cost_item_count.unwrap_or(1)
+        ));
+    }
+    if !parts.is_empty() {
+        story.unlock_desc = Some(parts.join("，"));
     }
+}
 
-    pub fn get_current_version(&self) -> Result<String, String> {
-        if let Some(info) = self.read_version() {
-            let commit_short = if info.commit.len() >= 7 {
-                &info.commit[..7]
-            } else {
-                info.commit.as_str()
-            };
-            Ok(format!(
-                "{} ({})",
-                commit_short,
-                format_timestamp(info.fetched_at)
-            ))
-        } else {
-            Ok("未安装".to_string())
-        }
+/// character_table 中的 rarity 旧版为 0 起的数字，新版为 "TIER_N" 字符串
+fn parse_rarity(value: Option<&Value>) -> u8 {
+    match value {
+        Some(Value::Number(num)) => num.as_u64().map_or(0, |r| (r + 1).min(6) as u8),
+        Some(Value::String(tier)) => tier
+            .strip_prefix("TIER_")
+            .and_then(|n| n.parse::<u8>().ok())
+            .unwrap_or(0),
+        _ => 0,
     }
+}
 
-    pub fn get_remote_version(&self) -> Result<String, String> {
-        let client = Self::create_http_client()?;
-        match self.fetch_latest_commit(&client) {
-            Ok(commit) => {
-                let short = if commit.len() >= 7 {
-                    &commit[..7]
-                } else {
-                    commit.as_str()
-                };
-                Ok(short.to_string())
+/// uniequip_table.subProfDict 中职业分支 id 到名称的映射
+fn sub_profession_names(uniequip: &Value) -> HashMap<&str, &str> {
+    uniequip
+        .get("subProfDict")
+        .and_then(|dict| dict.as_object())
+        .into_iter()
+        .flatten()
+        .filter_map(|(id, sub_prof)| {
+            Some((id.as_str(), sub_prof.get("subProfessionName")?.as_str()?))
+        })
+        .collect()
+}
+
+/// 配音语言的展示顺序，未列出的语言按类型名排在最后
+const VOICE_LANG_ORDER: &[&str] = &["JP", "CN_MANDARIN", "CN_TOPOLECT", "EN", "KR"];
+
+/// 从 charword_table.voiceLangDict 解析干员的各套语音；旧数据的 cvName 可能是字符串，
+/// 部分语言或 voiceLangTypeDict 缺失时按已有字段返回
+fn parse_voice_actors(table: &Value, char_id: &str) -> Vec<VoiceSet> {
+    let Some(voice_langs) = table.get("voiceLangDict").and_then(|v| v.as_object()) else {
+        return Vec::new();
+    };
+    let lang_types = table.get("voiceLangTypeDict");
+    let text = |value: &Value, key: &str| {
+        value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+
+    let mut sets: Vec<VoiceSet> = voice_langs
+        .iter()
+        .filter_map(|(key, entry)| {
+            let owner = text(entry, "charId").unwrap_or_else(|| key.clone());
+            if owner != char_id {
+                return None;
             }
-            Err(_) => Ok("未知".to_string()),
-        }
-    }
+            let word_key = text(entry, "wordkey").unwrap_or_else(|| key.clone());
+            let mut languages: Vec<VoiceActorLang> = entry
+                .get("dict")
+                .and_then(|v| v.as_object())
+                .into_iter()
+                .flatten()
+                .map(|(lang_key, lang)| {
+                    let lang_type = text(lang, "voiceLangType").unwrap_or_else(|| lang_key.clone());
+                    let cv_names = match lang.get("cvName") {
+                        Some(Value::Array(names)) => names
+                            .iter()
+                            .filter_map(|v| v.as_str())
+                            .map(str::trim)
+                            .filter(|s| !s.is_empty())
+                            .map(str::to_string)
+                            .collect(),
+                        Some(Value::String(name)) if !name.trim().is_empty() => {
+                            vec![name.trim().to_string()]
+                        }
+                        _ => Vec::new(),
+                    };
+                    let lang_name = lang_types
+                        .and_then(|types| types.get(&lang_type))
+                        .and_then(|t| text(t, "name"))
+                        .unwrap_or_else(|| lang_type.clone());
+                    VoiceActorLang {
+                        voice_path: text(lang, "voicePath"),
+                        lang_type,
+                        lang_name,
+                        cv_names,
+                    }
+                })
+                .collect();
+            languages.sort_by_key(|lang| {
+                let rank = VOICE_LANG_ORDER
+                    .iter()
+                    .position(|t| *t == lang.lang_type)
+                    .unwrap_or(VOICE_LANG_ORDER.len());
+                (rank, lang.lang_type.clone())
+            });
+            Some(VoiceSet {
+                is_alternate: word_key != char_id,
+                has_topolect: languages.iter().any(|l| l.lang_type == "CN_TOPOLECT"),
+                word_key,
+                languages,
+            })
+        })
+        .collect();
+    sets.sort_by(|a, b| {
+        a.is_alternate
+            .cmp(&b.is_alternate)
+            .then_with(|| a.word_key.cmp(&b.word_key))
+    });
+    sets
+}
 
-    pub fn check_update(&self) -> Result<bool, String> {
-        let current = self.read_version();
-        if current.is_none() {
-            return Ok(true);
+/// 解析基础档案中的【键】值：值可与键同行，也可写在后续行；没有键的行续接到上一个值。
+/// 名称含「报告」的键（体检医师报告）是叙述段落，单独返回而不计入键值
+fn parse_handbook_profile(text: &str) -> (Vec<ProfileField>, Option<String>) {
+    let mut fields: Vec<ProfileField> = Vec::new();
+    let mut report: Option<String> = None;
+    let mut in_report = false;
+    let append = |target: &mut String, line: &str| {
+        if !target.is_empty() {
+            target.push('\n');
         }
+        target.push_str(line);
+    };
 
-        let client = Self::create_http_client()?;
-        match self.fetch_latest_commit(&client) {
-            Ok(remote) => {
-                if let Some(cur) = current {
-                    Ok(cur.commit != remote)
-                } else {
-                    Ok(true)
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let bracketed = line
+            .strip_prefix('【')
+            .and_then(|rest| rest.split_once('】'))
+            .map(|(key, value)| (key.trim(), value.trim()));
+        match bracketed {
+            Some((key, value)) if key.contains("报告") => {
+                in_report = true;
+                let report = report.get_or_insert_with(String::new);
+                if !value.is_empty() {
+                    append(report, value);
+                }
+            }
+            Some((key, value)) if !key.is_empty() => {
+                in_report = false;
+                fields.push(ProfileField {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                });
+            }
+            _ if in_report => append(report.get_or_insert_with(String::new), line),
+            _ => {
+                if let Some(field) = fields.last_mut() {
+                    append(&mut field.value, line);
                 }
             }
-            Err(_) => Ok(true),
         }
     }
+    (fields, report.filter(|r| !r.is_empty()))
+}
 
-    fn create_http_client() -> Result<Client, String> {
-        Client::builder()
-            .user_agent("arknights-story-reader")
-            .build()
-            .map_err(|e| format!("Failed to create http client: {}", e))
+/// 信赖加成中常见属性的中文名称，按展示顺序排列
+const ATTRIBUTE_LABELS: &[(&str, &str)] = &[
+    ("maxHp", "生命"),
+    ("atk", "攻击"),
+    ("def", "防御"),
+    ("magicResistance", "法术抗性"),
+    ("cost", "部署费用"),
+    ("blockCnt", "阻挡数"),
+    ("attackSpeed", "攻击速度"),
+    ("respawnTime", "再部署时间"),
+];
+
+/// 属性加成的展示形式：整数不带小数位
+fn format_bonus_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{:+}", value as i64)
+    } else {
+        format!("{:+}", value)
     }
+}
 
-    fn fetch_latest_commit(&self, client: &Client) -> Result<String, String> {
-        let url = format!("{}/commits/{}", REPO_API_URL, DEFAULT_BRANCH);
-        let response = client
-            .get(&url)
-            .send()
-            .map_err(|e| format!("Failed to request latest commit: {}", e))?;
+/// favor_table.favorFrames：信赖等级 → 游戏内显示的信赖百分比
+fn favor_percents(favor_table: Option<&Value>) -> HashMap<i64, i64> {
+    favor_table
+        .and_then(|table| table.get("favorFrames"))
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|frame| {
+            let level = frame.get("level")?.as_i64()?;
+            let percent = frame.get("data")?.get("percent")?.as_i64()?;
+            Some((level, percent))
+        })
+        .collect()
+}
 
-        if !response.status().is_success() {
-            return Err(format!("GitHub API returned status {}", response.status()));
+/// 档案章节的解锁说明；FAVOR 的参数是信赖等级，经 favor_table 换算为百分比，
+/// 表中没有对应等级时按原值显示。未知类型优先使用表中的 unLockString
+fn describe_handbook_unlock(
+    unlock_type: Option<&str>,
+    unlock_param: Option<&str>,
+    unlock_string: Option<&str>,
+    favor_percents: &HashMap<i64, i64>,
+) -> String {
+    let param = unlock_param.map(str::trim).filter(|p| !p.is_empty());
+    match (unlock_type.map(str::to_ascii_uppercase).as_deref(), param) {
+        (None | Some("DIRECT"), _) => "直接解锁".to_string(),
+        (Some("FAVOR"), Some(param)) => {
+            let percent = param
+                .parse::<i64>()
+                .ok()
+                .and_then(|level| favor_percents.get(&level).copied());
+            match percent {
+                Some(percent) => format!("信赖达到{}%解锁", percent),
+                None => format!("信赖达到{}解锁", param),
+            }
         }
-
-        let value: serde_json::Value = response
-            .json()
-            .map_err(|e| format!("Failed to parse commit response: {}", e))?;
-
-        value
-            .get("sha")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .ok_or_else(|| "Failed to read commit sha".to_string())
+        (Some("AWAKE"), Some(param)) => format!("晋升至精英阶段{}解锁", param),
+        (Some(other), _) => unlock_string
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| match param {
+                Some(param) => format!("{} {}", other, param),
+                None => other.to_string(),
+            }),
     }
+}
 
-    fn download_and_extract(
-        &self,
-        client: &Client,
-        app: &AppHandle,
-        reference: &str,
-    ) -> Result<(), String> {
-        eprintln!("[SYNC] download_and_extract 开始");
-        let parent_dir = self
-            .data_dir
-            .parent()
-            .ok_or_else(|| "Invalid data directory".to_string())?;
-        eprintln!("[SYNC] parent_dir: {:?}", parent_dir);
-
-        let download_url = format!("{}/{}", REPO_DOWNLOAD_URL, reference);
-        eprintln!("[SYNC] download_url: {}", download_url);
-        emit_progress(app, "下载", 0, 100, format!("从 {} 下载", reference));
-
-        eprintln!("[SYNC] 发起 HTTP GET 请求");
-        let mut response = client.get(&download_url).send().map_err(|e| {
-            eprintln!("[SYNC ERROR] HTTP 请求失败: {}", e);
-            format!("Download failed: {}", e)
-        })?;
-
-        eprintln!("[SYNC] HTTP 状态码: {}", response.status());
-        if !response.status().is_success() {
-            return Err(format!("Download returned status {}", response.status()));
-        }
-
-        let total_bytes = response.content_length().unwrap_or(0) as usize;
-        let zip_path = parent_dir.join("ArknightsGameData.zip");
-        let mut zip_file = fs::File::create(&zip_path)
-            .map_err(|e| format!("Failed to create temp zip file: {}", e))?;
-
-        let mut downloaded: usize = 0;
-        let mut buffer = [0u8; 8192];
-        loop {
-            let bytes_read = response
-                .read(&mut buffer)
-                .map_err(|e| format!("Failed to read download stream: {}", e))?;
-            if bytes_read == 0 {
-                break;
+/// 解析 favorKeyFrames；旧干员可能缺少 level 或 data 中的部分属性，缺失按 0 处理。
+/// 信赖百分比取 favor_table.favorFrames 中对应 level 的 percent
+fn parse_favor_key_frames(character: &Value, favor_table: Option<&Value>) -> Vec<FavorKeyFrame> {
+    let percents = favor_percents(favor_table);
+
+    let mut frames: Vec<FavorKeyFrame> = character
+        .get("favorKeyFrames")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .map(|(index, frame)| {
+            let level = frame
+                .get("level")
+                .and_then(|v| v.as_i64())
+                .unwrap_or(index as i64);
+            let mut bonuses: Vec<AttributeBonus> = frame
+                .get("data")
+                .and_then(|v| v.as_object())
+                .into_iter()
+                .flatten()
+                .filter_map(|(attribute, value)| {
+                    let value = value.as_f64().filter(|v| *v != 0.0)?;
+                    let label = ATTRIBUTE_LABELS
+                        .iter()
+                        .find(|(key, _)| key == attribute)
+                        .map_or(attribute.as_str(), |(_, label)| label);
+                    Some(AttributeBonus {
+                        label: label.to_string(),
+                        attribute: attribute.clone(),
+                        value,
+                    })
+                })
+                .collect();
+            bonuses.sort_by_key(|bonus| {
+                let rank = ATTRIBUTE_LABELS
+                    .iter()
+                    .position(|(key, _)| *key == bonus.attribute)
+                    .unwrap_or(ATTRIBUTE_LABELS.len());
+                (rank, bonus.attribute.clone())
+            });
+            FavorKeyFrame {
+                level: level as i32,
+                percent: percents.get(&level).copied().unwrap_or(level * 2) as i32,
+                bonuses,
             }
-            zip_file
-                .write_all(&buffer[..bytes_read])
-                .map_err(|e| format!("Failed to write zip data: {}", e))?;
-            downloaded += bytes_read;
+        })
+        .collect();
+    frames.sort_by_key(|frame| frame.level);
+    frames
+}
 
-            let percent = if total_bytes > 0 {
-                (downloaded as f64 / total_bytes as f64 * 100.0).min(100.0)
-            } else {
-                0.0
-            };
-            let downloaded_mb = downloaded as f64 / 1_048_576.0;
-            let total_mb = total_bytes as f64 / 1_048_576.0;
-            let message = if total_bytes > 0 {
-                format!("已下载 {:.1}/{:.1} MB", downloaded_mb, total_mb.max(0.1))
-            } else {
-                format!("已下载 {:.1} MB", downloaded_mb)
-            };
-            emit_progress(app, "下载", percent.round() as usize, 100, message);
-        }
-        zip_file
-            .flush()
-            .map_err(|e| format!("Failed to flush zip file: {}", e))?;
+/// 解析 phases[].attributesKeyFrames；没有关键帧的阶段跳过，召唤物等阶段较少的角色照常返回
+fn parse_phase_attributes(character: &Value) -> Vec<PhaseAttributes> {
+    let block = |frame: &Value| {
+        frame
+            .get("data")
+            .cloned()
+            .and_then(|data| serde_json::from_value::<AttributeBlock>(data).ok())
+            .unwrap_or_default()
+    };
+    character
+        .get("phases")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .filter_map(|(phase, entry)| {
+            let frames = entry.get("attributesKeyFrames")?.as_array()?;
+            let (first, last) = (frames.first()?, frames.last()?);
+            let level = |frame: &Value| frame.get("level").and_then(|v| v.as_i64());
+            let min_level = level(first).unwrap_or(1) as i32;
+            let max_level = entry
+                .get("maxLevel")
+                .and_then(|v| v.as_i64())
+                .or_else(|| level(last))
+                .unwrap_or(min_level as i64) as i32;
+            Some(PhaseAttributes {
+                phase,
+                min_level,
+                max_level,
+                start: block(first),
+                end: block(last),
+            })
+        })
+        .collect()
+}
 
-        emit_progress(app, "下载", 100, 100, "下载完成");
-        self.extract_zip_at(&zip_path, parent_dir, app)?;
-        fs::remove_file(&zip_path).ok();
+/// 从 range_table 解析攻击范围；grids 为空的范围（如仅自身）同样有效
+fn parse_range_grid(ranges: &Value, range_id: &str) -> Option<RangeGrid> {
+    let range = ranges.get(range_id)?;
+    let cells = range
+        .get("grids")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|cell| {
+            let row = cell.get("row")?.as_i64()?;
+            let col = cell.get("col")?.as_i64()?;
+            Some((row as i32, col as i32))
+        })
+        .collect();
+    Some(RangeGrid {
+        id: range_id.to_string(),
+        cells,
+    })
+}
 
-        Ok(())
+/// 解析 uniequip_table 中模组的解锁条件：精英阶段可为 "PHASE_2" 或数字，
+/// itemCost 旧版为单个数组、新版按模组等级分组；任务描述取自同表的 missions
+fn parse_module_unlock(uniequip: &Value, equip: &Value, items: Option<&Value>) -> ModuleUnlock {
+    let int = |key: &str| {
+        equip.get(key).and_then(|v| {
+            v.as_i64().or_else(|| {
+                v.as_str()
+                    .and_then(|s| s.trim().trim_start_matches("PHASE_").parse().ok())
+            })
+        })
+    };
+    let evolve_phase = int("unlockEvolvePhase").unwrap_or(0) as i32;
+    let level = int("unlockLevel").unwrap_or(1) as i32;
+    let favor = ["unlockFavorPercent", "unlockFavor", "unlockFavorPoint"]
+        .iter()
+        .find_map(|key| int(key))
+        .filter(|favor| *favor > 0)
+        .map(|favor| favor as i32);
+
+    let missions: Vec<ModuleMission> = equip
+        .get("missionList")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|id| id.as_str())
+        .map(|mission_id| ModuleMission {
+            mission_id: mission_id.to_string(),
+            description: uniequip
+                .get("missions")
+                .and_then(|m| m.get(mission_id))
+                .and_then(|m| m.get("desc"))
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+        })
+        .collect();
+
+    let mut item_costs: Vec<LevelCost> = match equip.get("itemCost") {
+        Some(Value::Object(levels)) => levels
+            .iter()
+            .map(|(level, costs)| LevelCost {
+                level: level.parse().unwrap_or(1),
+                costs: parse_item_costs(Some(costs), items),
+            })
+            .collect(),
+        Some(costs @ Value::Array(_)) => vec![LevelCost {
+            level: 1,
+            costs: parse_item_costs(Some(costs), items),
+        }],
+        _ => Vec::new(),
+    };
+    item_costs.sort_by_key(|cost| cost.level);
+
+    let phase_name = ["精零", "精一", "精二"]
+        .get(evolve_phase as usize)
+        .copied()
+        .unwrap_or("精二");
+    let mut parts = vec![format!("{}{}级", phase_name, level)];
+    if let Some(favor) = favor {
+        parts.push(format!("信赖{}", favor));
+    }
+    if !missions.is_empty() {
+        parts.push(format!("完成{}个任务", missions.len()));
     }
 
-    fn extract_zip_at(
-        &self,
-        zip_path: &Path,
-        parent_dir: &Path,
-        app: &AppHandle,
-    ) -> Result<(), String> {
-        emit_progress(app, "解压", 0, 100, "正在解压数据");
-        let extract_root = parent_dir.join("ArknightsGameData_extract");
-        if extract_root.exists() {
-            fs::remove_dir_all(&extract_root)
-                .map_err(|e| format!("Failed to clean extract dir: {}", e))?;
-        }
-        fs::create_dir_all(&extract_root)
-            .map_err(|e| format!("Failed to create extract dir: {}", e))?;
+    ModuleUnlock {
+        evolve_phase,
+        level,
+        favor,
+        missions,
+        item_costs,
+        summary: parts.join("、"),
+    }
+}
 
-        let zip_file = fs::File::open(zip_path)
-            .map_err(|e| format!("Failed to open downloaded zip: {}", e))?;
-        let mut archive =
-            ZipArchive::new(zip_file).map_err(|e| format!("Failed to read zip archive: {}", e))?;
-
-        let total_entries = usize::max(archive.len(), 1);
-        for i in 0..archive.len() {
-            let mut file = archive
-                .by_index(i)
-                .map_err(|e| format!("Failed to access zip entry: {}", e))?;
-            let relative_path = match file.enclosed_name() {
-                Some(path) => path.to_owned(),
-                None => continue,
-            };
-            let out_path = extract_root.join(&relative_path);
+/// 解析 blackboard 数组；value 缺失时为 0，仅有 valueStr 的项同样保留
+fn parse_blackboard(value: Option<&Value>) -> Vec<BlackboardValue> {
+    value
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            Some(BlackboardValue {
+                key: item.get("key")?.as_str()?.to_string(),
+                value: item.get("value").and_then(|v| v.as_f64()).unwrap_or(0.0),
+                value_str: item
+                    .get("valueStr")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string),
+            })
+        })
+        .collect()
+}
 
-            if file.is_dir() {
-                fs::create_dir_all(&out_path)
-                    .map_err(|e| format!("Failed to create directory: {}", e))?;
-            } else {
-                if let Some(parent) = out_path.parent() {
-                    fs::create_dir_all(parent)
-                        .map_err(|e| format!("Failed to create parent directory: {}", e))?;
+/// 解析 battle_equip_table 中一个模组的各等级：属性加成、特性描述与天赋升级。
+/// 同一天赋按潜能有多个候选时只取第一个（最低潜能）
+fn parse_module_stages(battle: &Value) -> Vec<ModuleStage> {
+    let text = |value: &Value, key: &str| {
+        value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let candidates = |part: &Value, bundle: &str| -> Vec<Value> {
+        part.get(bundle)
+            .and_then(|b| b.get("candidates"))
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    battle
+        .get("phases")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .map(|(index, phase)| {
+            let mut stage = ModuleStage {
+                level: phase
+                    .get("equipLevel")
+                    .and_then(|v| v.as_i64())
+                    .unwrap_or(index as i64 + 1) as i32,
+                attributes: parse_blackboard(phase.get("attributeBlackboard")),
+                trait_descriptions: Vec::new(),
+                talents: Vec::new(),
+            };
+            let parts = phase.get("parts").and_then(|v| v.as_array());
+            for part in parts.into_iter().flatten() {
+                if let Some(candidate) = candidates(part, "overrideTraitDataBundle").first() {
+                    if let Some(description) = text(candidate, "additionalDescription")
+                        .or_else(|| text(candidate, "overrideDescripton"))
+                    {
+                        stage.trait_descriptions.push(description);
+                    }
+                }
+                for candidate in candidates(part, "addOrOverrideTalentDataBundle") {
+                    let talent_index = candidate
+                        .get("talentIndex")
+                        .and_then(|v| v.as_i64())
+                        .unwrap_or(-1) as i32;
+                    if stage.talents.iter().any(|t| t.talent_index == talent_index) {
+                        continue;
+                    }
+                    stage.talents.push(ModuleTalent {
+                        talent_index,
+                        name: text(&candidate, "name"),
+                        description: text(&candidate, "upgradeDescription")
+                            .or_else(|| text(&candidate, "description")),
+                        required_potential_rank: candidate
+                            .get("requiredPotentialRank")
+                            .and_then(|v| v.as_i64())
+                            .unwrap_or(0) as i32,
+                        blackboard: parse_blackboard(candidate.get("blackboard")),
+                    });
                 }
-                let mut outfile = fs::File::create(&out_path)
-                    .map_err(|e| format!("Failed to create file: {}", e))?;
-                std::io::copy(&mut file, &mut outfile)
-                    .map_err(|e| format!("Failed to write file: {}", e))?;
             }
+            stage
+        })
+        .collect()
+}
 
-            let percent = ((i + 1) as f64 / total_entries as f64 * 100.0).min(100.0);
-            emit_progress(
-                app,
-                "解压",
-                percent.round() as usize,
-                100,
-                format!("解压 {}/{} ({:.1}%)", i + 1, total_entries, percent),
-            );
-        }
+/// 语言类型对应的音频目录，约定见 VoiceAsset 的文档
+fn voice_lang_dir(lang: &str) -> String {
+    match lang {
+        "JP" => "voice".to_string(),
+        "CN_MANDARIN" => "voice_cn".to_string(),
+        "CN_TOPOLECT" => "voice_custom".to_string(),
+        other => format!("voice_{}", other.to_ascii_lowercase()),
+    }
+}
 
-        emit_progress(app, "解压", 100, 100, "解压完成");
+/// 由 voiceAsset（wordKey 目录/语音名）与语言计算语音的相对资源路径
+fn voice_asset_path(lang: &str, voice_asset: &str, voice_path: Option<&str>) -> String {
+    let (word_dir, name) = voice_asset.rsplit_once('/').unwrap_or(("", voice_asset));
+    let word_dir = voice_path.unwrap_or(word_dir);
+    let mut path = voice_lang_dir(lang);
+    if !word_dir.is_empty() {
+        path.push('/');
+        path.push_str(word_dir);
+    }
+    path.push('/');
+    path.push_str(name);
+    path.push_str(".mp3");
+    path.to_ascii_lowercase()
+}
 
-        let extracted_root = fs::read_dir(&extract_root)
-            .map_err(|e| format!("Failed to read extracted directory: {}", e))?
-            .filter_map(|entry| entry.ok())
-            .map(|entry| entry.path())
-            .find(|path| path.is_dir())
-            .ok_or_else(|| "解压后的文件结构不正确".to_string())?;
+/// character_table 中的召唤物与装置（profession 为 TOKEN / TRAP），部分召唤物也使用 char_ 前缀
+fn is_token_character(character: &Value) -> bool {
+    matches!(
+        character.get("profession").and_then(|v| v.as_str()),
+        Some("TOKEN" | "TRAP")
+    )
+}
 
-        if self.data_dir.exists() {
-            fs::remove_dir_all(&self.data_dir)
-                .map_err(|e| format!("Failed to remove old data: {}", e))?;
+/// 干员引用的召唤物 id：displayTokenDict 的键、tokenKey 与技能的 overrideTokenKey，按出现顺序去重
+fn character_token_ids(character: &Value) -> Vec<String> {
+    let mut ids: Vec<String> = Vec::new();
+    let mut push = |id: &str| {
+        if !id.is_empty() && !ids.iter().any(|existing| existing == id) {
+            ids.push(id.to_string());
         }
-
-        match fs::rename(&extracted_root, &self.data_dir) {
-            Ok(_) => {}
-            Err(_) => {
-                copy_dir_all(&extracted_root, &self.data_dir)?;
-                fs::remove_dir_all(&extracted_root).ok();
-            }
+    };
+    if let Some(dict) = character
+        .get("displayTokenDict")
+        .and_then(|v| v.as_object())
+    {
+        dict.keys().for_each(|id| push(id));
+    }
+    if let Some(id) = character.get("tokenKey").and_then(|v| v.as_str()) {
+        push(id);
+    }
+    let skills = character.get("skills").and_then(|v| v.as_array());
+    for skill in skills.into_iter().flatten() {
+        if let Some(id) = skill.get("overrideTokenKey").and_then(|v| v.as_str()) {
+            push(id);
         }
+    }
+    ids
+}
 
-        fs::remove_dir_all(&extract_root).ok();
-        Ok(())
+/// 召唤物条目的基础信息、特性、天赋与属性关键帧
+fn parse_character_token(token_id: &str, token: &Value) -> CharacterToken {
+    let text = |value: &Value, key: &str| {
+        value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let last_candidate = |value: Option<&Value>| {
+        value
+            .and_then(|v| v.get("candidates"))
+            .and_then(|v| v.as_array())
+            .and_then(|candidates| candidates.last())
+            .cloned()
+    };
+    let description = text(token, "description");
+    let trait_description = last_candidate(token.get("trait"))
+        .and_then(|candidate| text(&candidate, "overrideDescripton"))
+        .or_else(|| description.clone());
+    CharacterToken {
+        token_id: token_id.to_string(),
+        name: text(token, "name").unwrap_or_else(|| token_id.to_string()),
+        description,
+        trait_description,
+        talents: parse_talents(token),
+        phases: parse_phase_attributes(token),
     }
+}
 
-    fn finalize_manual_import(&self, temp_path: &Path, app: &AppHandle) -> Result<(), String> {
-        let parent_dir = self
-            .data_dir
-            .parent()
-            .ok_or_else(|| "Invalid data directory".to_string())?;
+/// 干员/召唤物的天赋，每个天赋取最后一个候选（最高潜能/精英阶段）
+fn parse_talents(character: &Value) -> Vec<TalentInfo> {
+    let text = |value: &Value, key: &str| {
+        value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    character
+        .get("talents")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|talent| {
+            talent
+                .get("candidates")
+                .and_then(|v| v.as_array())
+                .and_then(|candidates| candidates.last())
+        })
+        .map(|candidate| TalentInfo {
+            name: text(candidate, "name"),
+            description: text(candidate, "description"),
+        })
+        .collect()
+}
 
-        emit_progress(app, "导入", 40, 100, "正在解压 ZIP 文件");
-        self.extract_zip_at(temp_path, parent_dir, app)?;
-        fs::remove_file(temp_path).ok();
-
-        if let Err(err) = self.clear_story_index() {
-            eprintln!("[IMPORT] Failed to reset story index: {}", err);
-        }
+/// character_table（或 patchChars）中的干员条目：按职业排除召唤物与装置，
+/// include_nonplayable 为 true 时全部保留。缺少名称的条目照常返回，只记录日志
+fn roster_characters(
+    table: &Value,
+    include_nonplayable: bool,
+) -> impl Iterator<Item = (&String, &Value)> {
+    table
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(move |(_, character)| include_nonplayable || !is_token_character(character))
+        .inspect(|(id, character)| {
+            let named = character
+                .get("name")
+                .and_then(|v| v.as_str())
+                .is_some_and(|name| !name.trim().is_empty());
+            if !named {
+                tracing::debug!("Character {} has no name", id);
+            }
+        })
+}
 
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map(|d| d.as_secs() as i64)
-            .unwrap_or(0);
-        let info = VersionInfo {
-            commit: format!("manual-{}", timestamp),
-            fetched_at: timestamp,
-        };
-        self.write_version(&info)?;
+/// gacha_table.gachaPoolClient 中以「名称」形式在简介/详情里列出该干员的寻访：
+/// 返回 (开放时间, 是否为限定或联动寻访)
+fn featured_gacha_pools(gacha_table: &Value, name: &str) -> Vec<(i64, bool)> {
+    let marker = format!("「{}」", name);
+    gacha_table
+        .get("gachaPoolClient")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|pool| {
+            ["gachaPoolSummary", "gachaPoolDetail"].iter().any(|key| {
+                pool.get(*key)
+                    .and_then(|v| v.as_str())
+                    .is_some_and(|text| text.contains(&marker))
+            })
+        })
+        .filter_map(|pool| {
+            let open_time = pool.get("openTime")?.as_i64().filter(|t| *t > 0)?;
+            let limited = matches!(
+                pool.get("gachaRuleType").and_then(|v| v.as_str()),
+                Some("LIMITED" | "LINKAGE")
+            );
+            Some((open_time, limited))
+        })
+        .collect()
+}
 
-        emit_progress(app, "完成", 100, 100, "导入完成");
-        Ok(())
+/// 皮肤的画师列表（displaySkin.drawerList），去除空白与重复项
+fn skin_drawers(skin: &Value) -> Vec<String> {
+    let mut drawers: Vec<String> = Vec::new();
+    let names = skin
+        .get("displaySkin")
+        .and_then(|display| display.get("drawerList"))
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+    for name in names {
+        if !drawers.iter().any(|drawer| drawer == name) {
+            drawers.push(name.to_string());
+        }
     }
+    drawers
+}
 
-    pub fn import_zip_from_path<P: AsRef<Path>>(
-        &self,
-        source: P,
-        app: AppHandle,
-    ) -> Result<(), String> {
-        let source_path = source.as_ref();
-        if !source_path.exists() {
-            return Err("ZIP 文件不存在".to_string());
+/// 将 patchChars 中其他形态的条目覆盖到原版条目上：非 null 的字段整体替换，
+/// talents 按下标逐项替换，未覆盖的天赋沿用原版
+fn merge_patch_character(mut base: Value, patch: &Value) -> Value {
+    let (Some(base_fields), Some(patch_fields)) = (base.as_object_mut(), patch.as_object()) else {
+        return patch.clone();
+    };
+    for (key, value) in patch_fields {
+        if value.is_null() {
+            continue;
         }
+        match (key.as_str(), base_fields.get_mut(key), value.as_array()) {
+            ("talents", Some(Value::Array(base_talents)), Some(patch_talents)) => {
+                for (index, talent) in patch_talents.iter().enumerate() {
+                    if talent.is_null() {
+                        continue;
+                    }
+                    match base_talents.get_mut(index) {
+                        Some(slot) => *slot = talent.clone(),
+                        None => base_talents.push(talent.clone()),
+                    }
+                }
+            }
+            _ => {
+                base_fields.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    base
+}
 
-        let parent_dir = self
-            .data_dir
-            .parent()
-            .ok_or_else(|| "Invalid data directory".to_string())?;
+/// char_patch_table.infos：原版干员 id → 其他形态 id（按 tmplIds 顺序，不含原版自身）
+fn character_form_groups(patch: &Value) -> HashMap<String, Vec<String>> {
+    let Some(infos) = patch.get("infos").and_then(|v| v.as_object()) else {
+        return HashMap::new();
+    };
+    infos
+        .iter()
+        .filter_map(|(base_id, info)| {
+            let alternates: Vec<String> = info
+                .get("tmplIds")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|id| id.as_str())
+                .filter(|id| *id != base_id)
+                .map(str::to_string)
+                .collect();
+            (!alternates.is_empty()).then(|| (base_id.clone(), alternates))
+        })
+        .collect()
+}
 
-        let temp_path = parent_dir.join("ArknightsGameData_import.zip");
-        emit_progress(&app, "导入", 0, 100, "正在复制 ZIP 文件");
-        fs::copy(source_path, &temp_path).map_err(|e| format!("复制 ZIP 文件失败: {}", e))?;
+/// character_table 的名称索引：name 优先于 appellation，同名时取 id 较小者（如阿米娅取原版）
+fn character_name_lookup(table: &Value) -> HashMap<&str, (&str, u8)> {
+    let Some(entries) = table.as_object() else {
+        return HashMap::new();
+    };
+    let mut ids: Vec<&String> = entries.keys().collect();
+    ids.sort();
+
+    let mut by_name = HashMap::new();
+    let mut by_appellation = HashMap::new();
+    for id in ids {
+        let character = &entries[id.as_str()];
+        let rarity = parse_rarity(character.get("rarity"));
+        let field = |key: &str| {
+            character
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+        };
+        if let Some(name) = field("name") {
+            by_name.entry(name).or_insert((id.as_str(), rarity));
+        }
+        if let Some(appellation) = field("appellation") {
+            by_appellation
+                .entry(appellation)
+                .or_insert((id.as_str(), rarity));
+        }
+    }
+    for (appellation, value) in by_appellation {
+        by_name.entry(appellation).or_insert(value);
+    }
+    by_name
+}
 
-        emit_progress(&app, "导入", 30, 100, "正在校验 ZIP 文件");
-        self.finalize_manual_import(&temp_path, &app)
+fn is_common_punctuation(ch: char) -> bool {
+    if ch.is_ascii_punctuation() {
+        return true;
     }
 
-    pub fn import_zip_from_bytes(&self, data: &[u8], app: AppHandle) -> Result<(), String> {
-        let parent_dir = self
-            .data_dir
-            .parent()
-            .ok_or_else(|| "Invalid data directory".to_string())?;
+    matches!(
+        ch,
+        '，' | '、'
+            | '。'
+            | '！'
+            | '？'
+            | '：'
+            | '；'
+            | '（'
+            | '）'
+            | '【'
+            | '】'
+            | '「'
+            | '」'
+            | '『'
+            | '』'
+            | '《'
+            | '》'
+            | '〈'
+            | '〉'
+            | '—'
+            | '～'
+            | '…'
+            | '·'
+            | '﹑'
+            | '﹔'
+            | '﹗'
+            | '﹖'
+            | '﹐'
+            | '﹒'
+            | '﹕'
+            | '︰'
+    )
+}
 
-        fs::create_dir_all(parent_dir).map_err(|e| format!("无法创建数据目录: {}", e))?;
+fn is_cjk(ch: char) -> bool {
+    // Basic + Ext A/B ranges (not exhaustive but sufficient here)
+    (ch >= '\u{4E00}' && ch <= '\u{9FFF}') // CJK Unified Ideographs
+        || (ch >= '\u{3400}' && ch <= '\u{4DBF}') // Extension A
+        || (ch >= '\u{20000}' && ch <= '\u{2A6DF}') // Extension B
+        || (ch >= '\u{2A700}' && ch <= '\u{2B73F}')
+        || (ch >= '\u{2B740}' && ch <= '\u{2B81F}')
+        || (ch >= '\u{2B820}' && ch <= '\u{2CEAF}')
+}
 
-        let temp_path = parent_dir.join("ArknightsGameData_import.zip");
-        emit_progress(&app, "导入", 0, 100, "正在写入 ZIP 数据");
-        fs::write(&temp_path, data).map_err(|e| format!("写入 ZIP 数据失败: {}", e))?;
+fn normalize_nfkc_lower_strip_marks(text: &str) -> String {
+    // NFKC + lowercase + strip combining marks (e.g., café -> cafe)
+    text.nfkc()
+        .flat_map(|c| c.to_lowercase())
+        .filter(|c| unicode_normalization::char::canonical_combining_class(*c) == 0)
+        .collect()
+}
 
-        emit_progress(&app, "导入", 30, 100, "正在校验 ZIP 文件");
-        self.finalize_manual_import(&temp_path, &app)
-    }
+/// 干员名称与说话人名称的统一规范化，说话人索引与干员搜索共用
+fn normalize_character_name(name: &str) -> String {
+    normalize_nfkc_lower_strip_marks(name.trim())
+}
 
-    fn version_file_path(&self) -> PathBuf {
-        self.data_dir.join(VERSION_FILE)
-    }
+/// 识别文本中的泰拉历日期（如「1099年1月27日 11:38 A.M.」「1098年7月」「1097年」），
+/// 年份需为 3-4 位数字，月/日超出范围时只保留前面合法的部分
+fn parse_terra_date(text: &str) -> Option<TerraDate> {
+    let chars: Vec<char> = text.nfkc().collect();
+    // 读取 start 处的数字（允许前导空白），返回数值、位数与结束位置
+    let number_at = |start: usize| -> Option<(u32, usize, usize)> {
+        let mut pos = start;
+        while pos < chars.len() && chars[pos].is_whitespace() {
+            pos += 1;
+        }
+        let digits_start = pos;
+        while pos < chars.len() && chars[pos].is_ascii_digit() {
+            pos += 1;
+        }
+        let digits: String = chars[digits_start..pos].iter().collect();
+        let value = digits.parse::<u32>().ok()?;
+        Some((value, pos - digits_start, pos))
+    };
 
-    fn read_version(&self) -> Option<VersionInfo> {
-        let path = self.version_file_path();
-        if !path.exists() {
-            return None;
+    for (index, ch) in chars.iter().enumerate() {
+        if *ch != '年' {
+            continue;
         }
-        let content = fs::read_to_string(&path).ok()?;
-        serde_json::from_str(&content).ok()
-    }
+        let mut start = index;
+        while start > 0 && chars[start - 1].is_ascii_digit() {
+            start -= 1;
+        }
+        if !(3..=4).contains(&(index - start))
+            || (start > 0 && chars[start - 1].is_ascii_alphanumeric())
+        {
+            continue;
+        }
+        let year: String = chars[start..index].iter().collect();
+        let Ok(year) = year.parse::<i32>() else {
+            continue;
+        };
 
-    fn write_version(&self, info: &VersionInfo) -> Result<(), String> {
-        if !self.data_dir.exists() {
-            fs::create_dir_all(&self.data_dir)
-                .map_err(|e| format!("Failed to create data directory: {}", e))?;
+        let mut date = TerraDate {
+            year,
+            month: None,
+            day: None,
+        };
+        if let Some((month, len, end)) = number_at(index + 1) {
+            if len <= 2 && (1..=12).contains(&month) && chars.get(end) == Some(&'月') {
+                date.month = Some(month as u8);
+                if let Some((day, len, end)) = number_at(end + 1) {
+                    if len <= 2
+                        && (1..=31).contains(&day)
+                        && matches!(chars.get(end), Some('日' | '号'))
+                    {
+                        date.day = Some(day as u8);
+                    }
+                }
+            }
         }
-        let path = self.version_file_path();
-        let content = serde_json::to_string_pretty(info)
-            .map_err(|e| format!("Failed to serialize version info: {}", e))?;
-        fs::write(&path, content).map_err(|e| format!("Failed to write version info: {}", e))
+        return Some(date);
     }
+    None
 }
 
-/// 格式化时间戳
-fn format_timestamp(timestamp: i64) -> String {
-    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+/// 轻量的可设种子伪随机数发生器（SplitMix64），用于随机选篇
+struct SplitMix64(u64);
 
-    let duration = Duration::from_secs(timestamp as u64);
-    let datetime = UNIX_EPOCH + duration;
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
 
-    if let Ok(elapsed) = SystemTime::now().duration_since(datetime) {
-        let days = elapsed.as_secs() / 86400;
-        if days == 0 {
-            let hours = elapsed.as_secs() / 3600;
-            if hours == 0 {
-                let mins = elapsed.as_secs() / 60;
-                return format!("{}分钟前", mins.max(1));
-            }
-            return format!("{}小时前", hours);
-        } else if days < 30 {
-            return format!("{}天前", days);
-        }
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
     }
 
-    "较早前".to_string()
+    /// [0, bound) 内的随机下标，bound 须大于 0
+    fn below(&mut self, bound: usize) -> usize {
+        ((self.next_u64() as u128 * bound as u128) >> 64) as usize
+    }
 }
 
-impl DataService {
-    /// 获取所有章节
-    pub fn get_chapters(&self) -> Result<Vec<Chapter>, String> {
-        if !self.is_installed() {
-            return Err("NOT_INSTALLED".to_string());
-        }
-        let chapter_file = self
-            .data_dir
-            .join("zh_CN/gamedata/excel/chapter_table.json");
+/// 活动名称的比较键：统一全半角与大小写，去掉复刻后缀、空白与分隔符
+fn activity_name_key(name: &str) -> String {
+    normalize_nfkc_lower_strip_marks(strip_rerun_suffix(name))
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '·' | '・' | '-' | '_' | ':' | '：'))
+        .collect()
+}
 
-        let content = fs::read_to_string(&chapter_file)
-            .map_err(|e| format!("Failed to read chapter file: {}", e))?;
+/// 在 activity_table.basicInfo 中查找 story_review 条目对应的活动：先按 id 精确匹配，
+/// 再按规范化后的名称匹配；名称对应多个活动时优先非复刻版本，仍有歧义则放弃，以免配错横幅
+fn match_activity<'a>(
+    table: &'a Value,
+    entry_id: &str,
+    name: &str,
+) -> Option<(&'a str, &'a Value)> {
+    let basic_info = table.get("basicInfo")?.as_object()?;
+    if let Some((id, basic)) = basic_info.get_key_value(entry_id) {
+        return Some((id.as_str(), basic));
+    }
 
-        let data: HashMap<String, Chapter> = serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse chapter data: {}", e))?;
+    let key = activity_name_key(name);
+    if key.is_empty() {
+        return None;
+    }
+    let candidates: Vec<(&String, &Value, bool)> = basic_info
+        .iter()
+        .filter_map(|(id, basic)| {
+            let act_name = basic.get("name")?.as_str()?;
+            (activity_name_key(act_name) == key).then(|| {
+                let act_type = basic.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                (id, basic, is_rerun_activity(act_name, act_type))
+            })
+        })
+        .collect();
+    let originals: Vec<_> = candidates.iter().filter(|(_, _, rerun)| !rerun).collect();
+    match (originals.as_slice(), candidates.as_slice()) {
+        ([(id, basic, _)], _) | ([], [(id, basic, _)]) => Some((id.as_str(), *basic)),
+        _ => None,
+    }
+}
 
-        let mut chapters: Vec<Chapter> = data.into_values().collect();
-        chapters.sort_by_key(|c| c.chapter_index);
+/// 活动代号：首个带「-」的剧情编号的前缀，如 CB-1 -> CB
+fn activity_code(stories: &[StoryEntry]) -> Option<String> {
+    stories
+        .iter()
+        .filter_map(|story| story.story_code.as_deref())
+        .find_map(|code| {
+            let (prefix, _) = code.trim().split_once('-')?;
+            (!prefix.is_empty()).then(|| prefix.to_string())
+        })
+}
 
-        Ok(chapters)
+/// 关卡编号的配对键：去掉「10-17 (磨难)」之类的括号后缀
+fn stage_pair_key(code: &str) -> &str {
+    code.split(['(', '（']).next().unwrap_or(code).trim()
+}
+
+/// 剧情中所有说话人的规范化名称，用于「出现过的剧情」反查
+fn story_speakers(segments: &[StorySegment]) -> HashSet<String> {
+    segments
+        .iter()
+        .filter_map(|segment| match segment {
+            StorySegment::Dialogue { character_name, .. } => {
+                Some(normalize_character_name(character_name))
+            }
+            _ => None,
+        })
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+fn extract_numeric_parts(text: &str) -> Vec<i32> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+
+    for ch in text.chars() {
+        if ch.is_ascii_digit() {
+            current.push(ch);
+        } else if !current.is_empty() {
+            if let Ok(num) = current.parse::<i32>() {
+                parts.push(num);
+            }
+            current.clear();
+        }
     }
 
-    /// 获取所有活动
-    pub fn get_activities(&self) -> Result<Vec<Activity>, String> {
-        if !self.is_installed() {
-            return Err("NOT_INSTALLED".to_string());
+    if !current.is_empty() {
+        if let Ok(num) = current.parse::<i32>() {
+            parts.push(num);
         }
-        let story_review_file = self
-            .data_dir
-            .join("zh_CN/gamedata/excel/story_review_table.json");
+    }
 
-        let content = fs::read_to_string(&story_review_file)
-            .map_err(|e| format!("Failed to read story review file: {}", e))?;
+    parts
+}
 
-        let data: HashMap<String, Value> = serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse story review data: {}", e))?;
+fn compare_story_group_ids(a: &str, b: &str) -> Ordering {
+    let mut a_parts = extract_numeric_parts(a);
+    let mut b_parts = extract_numeric_parts(b);
 
-        let mut activities = Vec::new();
+    if !a_parts.is_empty() || !b_parts.is_empty() {
+        let len = a_parts.len().max(b_parts.len());
+        a_parts.resize(len, 0);
+        b_parts.resize(len, 0);
 
-        for (id, value) in data.iter() {
-            if let Some(entry_type) = value.get("entryType").and_then(|v| v.as_str()) {
-                if entry_type == "ACTIVITY" {
-                    let activity: Activity = serde_json::from_value(value.clone())
-                        .map_err(|e| format!("Failed to parse activity: {}", e))?;
-                    activities.push(Activity {
-                        id: id.clone(),
-                        ..activity
-                    });
-                }
+        for (a_part, b_part) in a_parts.iter().zip(b_parts.iter()) {
+            match a_part.cmp(b_part) {
+                Ordering::Equal => continue,
+                non_eq => return non_eq,
             }
         }
-
-        Ok(activities)
     }
 
-    /// 获取分类的剧情列表（仅返回分类，不含故事列表）
-    pub fn get_story_categories(&self) -> Result<Vec<StoryCategory>, String> {
-        if !self.is_installed() {
-            return Err("NOT_INSTALLED".to_string());
-        }
+    a.cmp(b)
+}
 
-        let story_review_file = self
-            .data_dir
-            .join("zh_CN/gamedata/excel/story_review_table.json");
+struct CachedTable {
+    modified: Option<SystemTime>,
+    value: Arc<Value>,
+}
+
+/// 上一篇/下一篇导航使用的分组；category 用于限制跨组时不跨越分类
+struct NavigationGroup {
+    category: &'static str,
+    name: String,
+    stories: Vec<StoryEntry>,
+}
 
-        let content = fs::read_to_string(&story_review_file)
-            .map_err(|e| format!("Failed to read story review file: {}", e))?;
+/// 以 story_review_table 的路径与修改时间为键缓存导航分组
+struct CachedNavigation {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    groups: Arc<Vec<NavigationGroup>>,
+}
 
-        let data: HashMap<String, Value> = serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse story review data: {}", e))?;
+/// story_id -> StoryEntry 查找表，同样以 story_review_table 的修改时间失效
+struct CachedEntries {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    entries: Arc<HashMap<String, IndexedStory>>,
+}
 
-        let mut categories = Vec::new();
+/// 已解析剧情的缓存键：修改时间变化（重新同步、手动替换文件）即视为新内容
+#[derive(Clone, PartialEq, Eq)]
+struct StoryContentKey {
+    story_path: String,
+    modified: SystemTime,
+}
 
-        // 主线剧情
-        let main_stories = self.parse_stories_by_entry_type(&data, "MAINLINE")?;
-        if !main_stories.is_empty() {
-            categories.push(StoryCategory {
-                id: "mainline".to_string(),
-                name: "主线剧情".to_string(),
-                category_type: "chapter".to_string(),
-                stories: main_stories,
-            });
-        }
+/// 最近阅读的已解析剧情，按最近使用顺序排列（末尾最新）；容量很小，线性查找即可
+#[derive(Default)]
+struct StoryContentCache {
+    entries: Vec<(StoryContentKey, Arc<ParsedStoryContent>)>,
+}
 
-        Ok(categories)
+impl StoryContentCache {
+    fn get(&mut self, key: &StoryContentKey) -> Option<Arc<ParsedStoryContent>> {
+        let index = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(index);
+        let content = entry.1.clone();
+        self.entries.push(entry);
+        Some(content)
     }
 
-    /// 根据 entryType 解析剧情
-    fn parse_stories_by_entry_type(
-        &self,
-        data: &HashMap<String, Value>,
-        entry_type: &str,
-    ) -> Result<Vec<StoryEntry>, String> {
-        let mut stories = Vec::new();
-
-        for (_id, value) in data.iter() {
-            if let Some(et) = value.get("entryType").and_then(|v| v.as_str()) {
-                if et == entry_type {
-                    if let Some(unlock_datas) =
-                        value.get("infoUnlockDatas").and_then(|v| v.as_array())
-                    {
-                        for unlock_data in unlock_datas {
-                            if let Ok(story) =
-                                serde_json::from_value::<StoryEntry>(unlock_data.clone())
-                            {
-                                stories.push(story);
-                            }
-                        }
-                    }
-                }
-            }
+    fn insert(&mut self, key: StoryContentKey, content: Arc<ParsedStoryContent>) {
+        // 同一剧情的旧版本不会再命中，直接替换
+        self.entries.retain(|(k, _)| k.story_path != key.story_path);
+        if self.entries.len() >= STORY_CONTENT_CACHE_CAPACITY {
+            self.entries.remove(0);
         }
-
-        stories.sort_by_key(|s| s.story_sort);
-        Ok(stories)
+        self.entries.push((key, content));
     }
+}
 
-    /// 获取主线剧情
-    fn get_main_stories(&self) -> Result<Vec<StoryEntry>, String> {
-        let story_review_file = self
-            .data_dir
-            .join("zh_CN/gamedata/excel/story_review_table.json");
-
-        let content = fs::read_to_string(&story_review_file)
-            .map_err(|e| format!("Failed to read story review file: {}", e))?;
-
-        let data: HashMap<String, Value> = serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse story review data: {}", e))?;
+/// get_app_status 使用的缓存，在 DataService 的所有克隆间共享
+#[derive(Default)]
+struct StatusCache {
+    /// 统计磁盘占用需要遍历整个数据目录，短时间内重复打开设置页时复用
+    disk_usage: Mutex<Option<(Instant, DiskUsage)>>,
+    last_update_check: Mutex<Option<UpdateCheckResult>>,
+}
 
-        let mut stories = Vec::new();
+/// 已解析的 excel 表缓存，在 DataService 的所有克隆间共享
+#[derive(Default)]
+struct TableCache {
+    tables: RwLock<HashMap<PathBuf, CachedTable>>,
+    navigation: RwLock<Option<CachedNavigation>>,
+    entries: RwLock<Option<CachedEntries>>,
+    /// 出场人物列表，按剧情文本哈希缓存
+    story_characters: RwLock<HashMap<String, Arc<Vec<StoryCharacter>>>>,
+    story_contents: Mutex<StoryContentCache>,
+    story_content_hits: AtomicUsize,
+    story_content_misses: AtomicUsize,
+    /// 实际读取磁盘的次数，用于测试缓存是否命中
+    disk_reads: AtomicUsize,
+    /// 按表文件名统计的磁盘读取次数，用于测试某张表是否只解析了一次
+    table_reads: RwLock<HashMap<String, usize>>,
+}
 
-        for (_id, value) in data.iter() {
-            if let Some(entry_type) = value.get("entryType").and_then(|v| v.as_str()) {
-                if entry_type == "MAINLINE" {
-                    if let Some(unlock_datas) =
-                        value.get("infoUnlockDatas").and_then(|v| v.as_array())
-                    {
-                        for unlock_data in unlock_datas {
-                            if let Ok(story) =
-                                serde_json::from_value::<StoryEntry>(unlock_data.clone())
-                            {
-                                stories.push(story);
-                            }
-                        }
-                    }
-                }
-            }
-        }
+fn estimate_reading_minutes(word_count: usize) -> usize {
+    word_count.div_ceil(READING_CHARS_PER_MINUTE)
+}
 
-        stories.sort_by_key(|s| s.story_sort);
-        Ok(stories)
-    }
+fn table_entries<'a>(table: &'a Value, table_file: &str) -> Result<&'a Map<String, Value>, String> {
+    table
+        .as_object()
+        .ok_or_else(|| format!("Failed to parse {}: expected an object", table_file))
+}
 
-    /// 读取剧情文本
-    pub fn read_story_text(&self, story_path: &str) -> Result<String, String> {
-        let full_path = self
-            .data_dir
-            .join("zh_CN/gamedata/story")
-            .join(format!("{}.txt", story_path));
+#[derive(Clone)]
+pub struct DataService {
+    data_dir: PathBuf,
+    index_db_path: PathBuf,
+    user_db_path: PathBuf,
+    settings: SettingsService,
+    tasks: Arc<TaskRegistry>,
+    table_cache: Arc<TableCache>,
+    last_search_debug: Arc<Mutex<Option<LastSearchDebug>>>,
+    status_cache: Arc<StatusCache>,
+}
 
-        fs::read_to_string(&full_path).map_err(|e| format!("Failed to read story file: {}", e))
+impl DataService {
+    pub fn is_installed(&self) -> bool {
+        self.excel_path("story_review_table.json").exists()
     }
-
-    /// 读取剧情简介
-    pub fn read_story_info(&self, info_path: &str) -> Result<String, String> {
-        let base_dir = self.data_dir.join("zh_CN/gamedata/story");
-
-        let trimmed = info_path.trim();
-        if trimmed.is_empty() {
-            return Err("Failed to read info file: empty info path".to_string());
+    pub fn new(app_data_dir: PathBuf) -> Self {
+        Self {
+            data_dir: app_data_dir.join("ArknightsGameData"),
+            index_db_path: app_data_dir.join(INDEX_DB_FILE),
+            user_db_path: app_data_dir.join(USER_DB_FILE),
+            settings: SettingsService::load(app_data_dir.join(SETTINGS_FILE)),
+            tasks: Arc::new(TaskRegistry::default()),
+            table_cache: Arc::new(TableCache::default()),
+            last_search_debug: Arc::new(Mutex::new(None)),
+            status_cache: Arc::new(StatusCache::default()),
         }
+    }
 
-        let normalized = trimmed
-            .trim_matches(|c| c == '/' || c == '\\')
-            .replace('\\', "/");
+    /// 当前使用的数据源
+    pub fn data_source(&self) -> DataSourceProfile {
+        self.settings.get().data_source
+    }
 
-        let mut candidates = Vec::new();
-        candidates.push(base_dir.join(format!("{}.txt", normalized)));
+    /// 设置服务，其他模块通过它同步读取当前设置
+    pub fn settings(&self) -> &SettingsService {
+        &self.settings
+    }
 
-        if normalized.starts_with("info/") {
-            let replaced = normalized.replacen("info/", "[uc]info/", 1);
-            candidates.push(base_dir.join(format!("{}.txt", replaced)));
+    /// 部分更新设置；日志级别变更立即生效，称呼变更后丢弃已解析的剧情缓存
+    pub fn update_settings(&self, patch: SettingsPatch) -> Result<AppSettings, String> {
+        let log_level_changed = patch.log_level.is_some();
+        let nickname_changed = patch.nickname.is_some();
+        let settings = self.settings.apply_patch(patch)?;
+        if log_level_changed {
+            logging::set_level(&settings.log_level)?;
         }
-
-        for candidate in &candidates {
-            match fs::read_to_string(candidate) {
-                Ok(content) => return Ok(content),
-                Err(err) if err.kind() == ErrorKind::NotFound => continue,
-                Err(err) => {
-                    return Err(format!("Failed to read info file: {}", err));
-                }
-            }
+        if nickname_changed {
+            self.table_cache
+                .story_contents
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .entries
+                .clear();
         }
+        Ok(settings)
+    }
 
-        Err(format!(
-            "Failed to read info file: {} (candidates: {})",
-            info_path,
-            candidates
-                .iter()
-                .map(|p| p.display().to_string())
-                .collect::<Vec<_>>()
-                .join(", ")
-        ))
+    pub fn list_data_sources(&self) -> Vec<DataSourceProfile> {
+        builtin_data_sources()
     }
 
-    /// 重建剧情全文索引
-    pub fn rebuild_story_index(&self) -> Result<(), String> {
-        if !self.is_installed() {
-            return Err("NOT_INSTALLED".to_string());
-        }
+    /// 切换数据源；切换后需要重新同步对应语言的数据
+    pub fn set_data_source(&self, source_id: &str) -> Result<DataSourceProfile, String> {
+        let profile = builtin_data_sources()
+            .into_iter()
+            .find(|p| p.id == source_id)
+            .ok_or_else(|| format!("未知的数据源: {}", source_id))?;
+        let _task = self.begin_task("data-source")?;
 
-        let mut conn = self.open_index_connection()?;
-        Self::init_index_tables(&conn)?;
+        self.settings.update(|settings| {
+            settings.data_source = profile.clone();
+            Ok(())
+        })?;
+        Ok(profile)
+    }
 
-        let tx = conn
-            .transaction()
-            .map_err(|e| format!("Failed to start index transaction: {}", e))?;
+    /// 同步时使用的归档格式
+    pub fn archive_format(&self) -> ArchiveFormat {
+        self.settings.get().archive_format
+    }
 
-        tx.execute("DELETE FROM story_index", [])
-            .map_err(|e| format!("Failed to clear story index: {}", e))?;
+    pub fn set_archive_format(&self, format: ArchiveFormat) -> Result<(), String> {
+        self.settings.update(|settings| {
+            settings.archive_format = format;
+            Ok(())
+        })?;
+        Ok(())
+    }
 
-        let indexed_stories = self.collect_stories_for_index()?;
-        let mut insert_stmt = tx
-            .prepare(
-                "
-            INSERT INTO story_index (
-                story_id,
-                story_name,
-                category,
-                tokenized_content,
-                story_code,
-                raw_content
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
-        ",
-            )
-            .map_err(|e| format!("Failed to prepare story index insert: {}", e))?;
+    /// 全文索引是否包含被隐藏的剧情
+    pub fn include_hidden_in_search(&self) -> bool {
+        self.settings.get().include_hidden_in_search
+    }
 
-        let mut total = 0usize;
+    /// 下载数据包的地址前缀：设置了镜像时使用镜像，否则直连 codeload.github.com
+    fn download_base(&self) -> String {
+        self.settings
+            .get()
+            .download_mirror
+            .map(|mirror| mirror.trim_end_matches('/').to_string())
+            .unwrap_or_else(|| REPO_DOWNLOAD_BASE.to_string())
+    }
 
-        for indexed in &indexed_stories {
-            let story_id = &indexed.story.story_id;
-            let story_name = &indexed.story.story_name;
-            let story_path = &indexed.story.story_txt;
+    /// 单次剧情搜索最多返回的结果数
+    pub fn search_result_limit(&self) -> usize {
+        self.settings.get().search_result_limit.max(1)
+    }
 
-            let raw_text = match self.read_story_text(story_path) {
-                Ok(text) => text,
-                Err(err) => {
-                    eprintln!(
-                        "[INDEX] Skip story {}: failed to read text ({})",
-                        story_id, err
-                    );
-                    continue;
-                }
-            };
+    pub fn log_level(&self) -> String {
+        self.settings.get().log_level
+    }
 
-            let parsed = parse_story_text(&raw_text);
-            let flattened = Self::flatten_segments(&parsed.segments);
+    /// 立即调整日志级别并写入设置，下次启动沿用
+    pub fn set_log_level(&self, level: &str) -> Result<(), String> {
+        let level = level.trim().to_ascii_lowercase();
+        logging::set_level(&level)?;
+        self.settings.update(|settings| {
+            settings.log_level = level;
+            Ok(())
+        })?;
+        Ok(())
+    }
 
-            let combined_raw = if flattened.trim().is_empty() {
-                story_name.clone()
-            } else {
-                format!("{}\n{}", story_name, flattened)
-            };
+    pub fn set_include_hidden_in_search(&self, include: bool) -> Result<(), String> {
+        self.settings.update(|settings| {
+            settings.include_hidden_in_search = include;
+            Ok(())
+        })?;
+        Ok(())
+    }
 
-            let tokenized = Self::build_tokenized_content(&combined_raw);
-            if tokenized.trim().is_empty() {
-                continue;
-            }
+    /// 去掉被隐藏的剧情，去掉后为空的分组整体移除
+    pub fn exclude_hidden_stories(groups: Vec<StoryGroup>) -> Vec<StoryGroup> {
+        groups
+            .into_iter()
+            .filter_map(|mut group| {
+                group
+                    .stories
+                    .retain(|story| story.visibility != Some(StoryVisibility::Hidden));
+                (!group.stories.is_empty()).then_some(group)
+            })
+            .collect()
+    }
 
-            let category_label =
-                Self::format_category_label(&indexed.entry_type, &indexed.category_name);
+    /// 当前语言的 gamedata 目录，例如 ArknightsGameData/zh_CN/gamedata
+    fn gamedata_dir(&self) -> PathBuf {
+        self.data_dir
+            .join(self.data_source().language)
+            .join("gamedata")
+    }
 
-            insert_stmt
-                .execute(params![
-                    story_id,
-                    story_name,
-                    &category_label,
-                    tokenized,
-                    indexed
-                        .story
-                        .story_code
-                        .as_ref()
-                        .map(|s| normalize_nfkc_lower_strip_marks(s))
-                        .unwrap_or_default(),
-                    combined_raw
-                ])
-                .map_err(|e| format!("Failed to insert story into index: {}", e))?;
-            total += 1;
-        }
+    fn excel_path(&self, table_file: &str) -> PathBuf {
+        self.excel_path_for(&self.data_source().language, table_file)
+    }
 
-        drop(insert_stmt);
+    /// 指定语言的 excel 表路径，用于读取同时安装的其他语言数据
+    fn excel_path_for(&self, language: &str, table_file: &str) -> PathBuf {
+        self.data_dir
+            .join(language)
+            .join("gamedata")
+            .join("excel")
+            .join(table_file)
+    }
 
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map(|d| d.as_secs() as i64)
-            .unwrap_or(0);
+    /// 数据目录中带有 excel 表的语言目录，按名称排序
+    fn installed_languages(&self) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(&self.data_dir) else {
+            return Vec::new();
+        };
+        let mut languages: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().join("gamedata").join("excel").is_dir())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect();
+        languages.sort();
+        languages
+    }
 
-        tx.execute(
-            "
-            INSERT INTO story_index_meta (key, value)
-            VALUES ('last_built_at', ?1)
-            ON CONFLICT(key) DO UPDATE SET value = excluded.value
-        ",
-            params![timestamp.to_string()],
-        )
-        .map_err(|e| format!("Failed to update index metadata: {}", e))?;
+    fn story_dir(&self) -> PathBuf {
+        self.gamedata_dir().join("story")
+    }
 
-        tx.execute(
-            "
-            INSERT INTO story_index_meta (key, value)
-            VALUES ('total_count', ?1)
-            ON CONFLICT(key) DO UPDATE SET value = excluded.value
-        ",
-            params![total.to_string()],
-        )
-        .map_err(|e| format!("Failed to update index total: {}", e))?;
+    /// 读取并解析 excel 表，结果按路径缓存；文件修改时间变化时重新读取
+    fn load_table(&self, table_file: &str) -> Result<Arc<Value>, String> {
+        self.read_optional_table(table_file)?
+            .ok_or_else(|| format!("Failed to read {}: file not found", table_file))
+    }
 
-        tx.commit()
-            .map_err(|e| format!("Failed to commit story index rebuild: {}", e))?;
+    /// 读取可选的 excel 表：不同服务器的数据包可能缺少部分表，缺失时返回 None
+    fn read_optional_table(&self, table_file: &str) -> Result<Option<Arc<Value>>, String> {
+        self.read_optional_table_at(self.excel_path(table_file), table_file)
+    }
 
-        Ok(())
+    /// 与 read_optional_table 相同，但读取指定语言的数据
+    fn read_optional_language_table(
+        &self,
+        language: &str,
+        table_file: &str,
+    ) -> Result<Option<Arc<Value>>, String> {
+        self.read_optional_table_at(self.excel_path_for(language, table_file), table_file)
     }
 
-    /// 获取索引状态
-    pub fn get_story_index_status(&self) -> Result<StoryIndexStatus, String> {
-        let Some(conn) = self.try_open_index_connection()? else {
-            return Ok(StoryIndexStatus {
-                ready: false,
-                total: 0,
-                last_built_at: None,
-            });
+    fn read_optional_table_at(
+        &self,
+        path: PathBuf,
+        table_file: &str,
+    ) -> Result<Option<Arc<Value>>, String> {
+        let modified = match fs::metadata(&path) {
+            Ok(meta) => meta.modified().ok(),
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                tracing::debug!("Optional table {} is missing, skipping", table_file);
+                return Ok(None);
+            }
+            Err(err) => return Err(format!("Failed to read {}: {}", table_file, err)),
         };
 
-        Self::init_index_tables(&conn)?;
-
-        let total: i64 = conn
-            .query_row("SELECT COUNT(*) FROM story_index", [], |row| row.get(0))
-            .unwrap_or(0);
+        if let Some(cached) = self
+            .table_cache
+            .tables
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&path)
+        {
+            if modified.is_some() && cached.modified == modified {
+                return Ok(Some(cached.value.clone()));
+            }
+        }
 
-        let last_built_at = Self::extract_meta_value(&conn, "last_built_at")?
-            .and_then(|value| value.parse::<i64>().ok());
+        self.table_cache
+            .disk_reads
+            .fetch_add(1, AtomicOrdering::Relaxed);
+        *self
+            .table_cache
+            .table_reads
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(table_file.to_string())
+            .or_insert(0) += 1;
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", table_file, e))?;
+        let value: Arc<Value> = Arc::new(
+            serde_json::from_str(&content)
+                .map_err(|e| format!("Failed to parse {}: {}", table_file, e))?,
+        );
+        self.table_cache
+            .tables
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(
+                path,
+                CachedTable {
+                    modified,
+                    value: value.clone(),
+                },
+            );
+        Ok(Some(value))
+    }
 
-        Ok(StoryIndexStatus {
-            ready: total > 0,
-            total: total.max(0) as usize,
-            last_built_at,
-        })
+    /// 启动后预热：加载常用数据表并遍历一次剧情列表，完成后发送 prewarm-complete 事件
+    pub fn prewarm(&self, app: &AppHandle) {
+        match self.prewarm_tables() {
+            Ok(Some(report)) => {
+                tracing::info!(
+                    "预热完成: {} 张表, {} 条剧情, 耗时 {}ms",
+                    report.tables,
+                    report.stories,
+                    report.duration_ms
+                );
+                let _ = app.emit("prewarm-complete", report);
+            }
+            Ok(None) => {}
+            Err(err) => tracing::warn!("预热失败: {}", err),
+        }
     }
 
-    fn search_stories_with_index(&self, query: &str) -> Result<Option<Vec<SearchResult>>, String> {
-        let Some(conn) = self.try_open_index_connection()? else {
+    /// 未安装数据、设置中关闭、有独占任务在执行或被取消时跳过（返回 None）；
+    /// 预热登记为非独占任务，同步/导入替换数据前会将其取消，每读完一张表检查一次
+    fn prewarm_tables(&self) -> Result<Option<PrewarmReport>, String> {
+        if !self.settings.get().prewarm_cache || !self.is_installed() {
             return Ok(None);
-        };
+        }
+        let task = self.tasks.start("prewarm", false)?;
+        self.prewarm_with_task(&task)
+    }
 
-        Self::init_index_tables(&conn)?;
+    fn prewarm_with_task(&self, task: &TaskHandle) -> Result<Option<PrewarmReport>, String> {
+        let should_stop = || task.token().is_cancelled() || self.tasks.exclusive_task().is_some();
+        let started = Instant::now();
+        let mut tables = 0;
+        for table_file in PREWARM_TABLES {
+            if should_stop() {
+                return Ok(None);
+            }
+            if self.read_optional_table(table_file)?.is_some() {
+                tables += 1;
+            }
+        }
 
-        let total: i64 = conn
-            .query_row("SELECT COUNT(*) FROM story_index", [], |row| row.get(0))
-            .unwrap_or(0);
-        if total == 0 {
+        if should_stop() {
+            return Ok(None);
+        }
+        let stories = self.collect_stories_for_index()?.len();
+        if should_stop() {
+            // 读取期间数据已被替换，丢弃可能混入的旧表
+            self.invalidate_caches();
             return Ok(None);
         }
 
-        let Some(fts_query) = Self::build_fts_query_advanced(query) else {
-            return Ok(Some(Vec::new()));
-        };
+        Ok(Some(PrewarmReport {
+            duration_ms: started.elapsed().as_millis() as u64,
+            tables,
+            stories,
+        }))
+    }
 
-        let query_sql = format!(
-            "
-            SELECT story_id, story_name, category, raw_content,
-                   snippet(story_index, -1, '', '', '...', 24) as snip
-            FROM story_index
-            WHERE story_index MATCH ?1
-            ORDER BY bm25(story_index)
-            LIMIT {}
-        ",
-            SEARCH_RESULT_LIMIT
-        );
+    /// 清空表缓存；数据目录被整体替换（同步、导入）后调用
+    pub fn invalidate_caches(&self) {
+        self.table_cache
+            .tables
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clear();
+        *self
+            .table_cache
+            .navigation
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+        *self
+            .table_cache
+            .entries
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+        self.table_cache
+            .story_characters
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clear();
+        self.table_cache
+            .story_contents
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entries
+            .clear();
+        *self
+            .status_cache
+            .disk_usage
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+    }
+
+    /// 记录阅读进度（单条 upsert，前端会在滚动防抖后频繁调用）
+    pub fn set_reading_progress(
+        &self,
+        story_id: &str,
+        segment_index: i64,
+        char_offset: i64,
+        percent: f64,
+    ) -> Result<(), String> {
+        let updated_at = current_millis();
+        self.open_user_connection()?
+            .execute(
+                "INSERT INTO reading_progress (story_id, segment_index, char_offset, percent, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT(story_id) DO UPDATE SET
+                    segment_index = excluded.segment_index,
+                    char_offset = excluded.char_offset,
+                    percent = excluded.percent,
+                    updated_at = excluded.updated_at",
+                params![
+                    story_id,
+                    segment_index.max(0),
+                    char_offset.max(0),
+                    percent.clamp(0.0, 100.0),
+                    updated_at
+                ],
+            )
+            .map_err(|e| format!("Failed to save reading progress: {}", e))?;
+        Ok(())
+    }
 
+    pub fn get_reading_progress(&self, story_id: &str) -> Result<Option<ReadingProgress>, String> {
+        self.open_user_connection()?
+            .query_row(
+                "SELECT story_id, segment_index, char_offset, percent, updated_at
+                 FROM reading_progress WHERE story_id = ?1",
+                params![story_id],
+                Self::map_reading_progress,
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read reading progress: {}", e))
+    }
+
+    /// 全部阅读进度，最近阅读的在前
+    pub fn get_all_reading_progress(&self) -> Result<Vec<ReadingProgress>, String> {
+        let conn = self.open_user_connection()?;
         let mut stmt = conn
-            .prepare(&query_sql)
-            .map_err(|e| format!("Failed to prepare story index query: {}", e))?;
+            .prepare(
+                "SELECT story_id, segment_index, char_offset, percent, updated_at
+                 FROM reading_progress ORDER BY updated_at DESC, story_id",
+            )
+            .map_err(|e| format!("Failed to read reading progress: {}", e))?;
+        let rows = stmt
+            .query_map([], Self::map_reading_progress)
+            .map_err(|e| format!("Failed to read reading progress: {}", e))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read reading progress: {}", e))
+    }
+
+    fn map_reading_progress(row: &rusqlite::Row<'_>) -> rusqlite::Result<ReadingProgress> {
+        Ok(ReadingProgress {
+            story_id: row.get(0)?,
+            segment_index: row.get(1)?,
+            char_offset: row.get(2)?,
+            percent: row.get(3)?,
+            updated_at: row.get(4)?,
+        })
+    }
+
+    pub fn add_bookmark(
+        &self,
+        story_id: &str,
+        segment_index: i64,
+        excerpt: &str,
+        note: &str,
+    ) -> Result<Bookmark, String> {
+        let excerpt: String = excerpt
+            .trim()
+            .chars()
+            .take(BOOKMARK_EXCERPT_LIMIT)
+            .collect();
+        let now = current_millis();
+        let conn = self.open_user_connection()?;
+        conn.execute(
+            "INSERT INTO bookmarks (story_id, segment_index, excerpt, note, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+            params![story_id, segment_index.max(0), excerpt, note, now],
+        )
+        .map_err(|e| format!("Failed to add bookmark: {}", e))?;
+        let id = conn.last_insert_rowid();
+        drop(conn);
+
+        self.list_bookmarks(Some(story_id))?
+            .into_iter()
+            .find(|bookmark| bookmark.id == id)
+            .ok_or_else(|| "书签不存在".to_string())
+    }
 
+    /// 书签列表（最新的在前），附带剧情名称与分类；数据未安装时名称为空
+    pub fn list_bookmarks(&self, story_id: Option<&str>) -> Result<Vec<Bookmark>, String> {
+        let conn = self.open_user_connection()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, story_id, segment_index, excerpt, note, created_at, updated_at
+                 FROM bookmarks
+                 WHERE ?1 IS NULL OR story_id = ?1
+                 ORDER BY created_at DESC, id DESC",
+            )
+            .map_err(|e| format!("Failed to read bookmarks: {}", e))?;
         let rows = stmt
-            .query_map(params![fts_query], |row| {
-                let story_id: String = row.get(0)?;
-                let story_name: String = row.get(1)?;
-                let category: String = row.get(2)?;
-                let raw_content: String = row.get(3)?;
-                let snip: String = row.get(4).unwrap_or_else(|_| String::new());
-                Ok((story_id, story_name, category, raw_content, snip))
+            .query_map(params![story_id], |row| {
+                Ok(Bookmark {
+                    id: row.get(0)?,
+                    story_id: row.get(1)?,
+                    segment_index: row.get(2)?,
+                    excerpt: row.get(3)?,
+                    note: row.get(4)?,
+                    created_at: row.get(5)?,
+                    updated_at: row.get(6)?,
+                    story_name: None,
+                    category: None,
+                })
             })
-            .map_err(|e| format!("Failed to execute story index query: {}", e))?;
+            .map_err(|e| format!("Failed to read bookmarks: {}", e))?;
+        let mut bookmarks = rows
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read bookmarks: {}", e))?;
+        if bookmarks.is_empty() || !self.is_installed() {
+            return Ok(bookmarks);
+        }
 
-        let query_lower = query.to_lowercase();
-        let mut results = Vec::new();
-        for row in rows {
-            if let Ok((story_id, story_name, category, raw_content, snip)) = row {
-                // 优先使用原始内容提取上下文，避免 tokenized_content 导致的空格断字
-                let mut matched_text = self.extract_context(&raw_content, &query_lower);
-                if matched_text.trim().is_empty() && !snip.trim().is_empty() {
-                    // 兜底：少数情况下 extract_context 未命中，回退 snippet 再做一次去空格优化
-                    let cleaned = snip
-                        .replace('\n', " ")
-                        .replace('\r', " ")
-                        .replace("  ", " ");
-                    matched_text = cleaned;
-                }
-                if matched_text.is_empty() {
-                    let preview: String = raw_content.chars().take(120).collect();
-                    matched_text = if preview.len() < raw_content.len() {
-                        format!("{}...", preview)
-                    } else {
-                        preview
-                    };
-                }
-                results.push(SearchResult {
-                    story_id,
-                    story_name,
-                    matched_text,
-                    category,
-                });
+        let stories: HashMap<String, IndexedStory> = self
+            .collect_stories_for_index()?
+            .into_iter()
+            .map(|indexed| (indexed.story.story_id.clone(), indexed))
+            .collect();
+        for bookmark in &mut bookmarks {
+            if let Some(indexed) = stories.get(&bookmark.story_id) {
+                bookmark.story_name = Some(indexed.story.story_name.clone());
+                bookmark.category = Some(Self::format_category_label(
+                    &indexed.entry_type,
+                    &indexed.category_name,
+                ));
             }
         }
+        Ok(bookmarks)
+    }
 
-        Ok(Some(results))
+    pub fn update_bookmark_note(&self, id: i64, note: &str) -> Result<(), String> {
+        let updated = self
+            .open_user_connection()?
+            .execute(
+                "UPDATE bookmarks SET note = ?2, updated_at = ?3 WHERE id = ?1",
+                params![id, note, current_millis()],
+            )
+            .map_err(|e| format!("Failed to update bookmark: {}", e))?;
+        if updated == 0 {
+            return Err("书签不存在".to_string());
+        }
+        Ok(())
     }
 
-    fn search_stories_fallback(&self, query: &str) -> Result<Vec<SearchResult>, String> {
-        let mut results = Vec::new();
-        let query_norm = normalize_nfkc_lower_strip_marks(query);
+    pub fn delete_bookmark(&self, id: i64) -> Result<(), String> {
+        self.open_user_connection()?
+            .execute("DELETE FROM bookmarks WHERE id = ?1", params![id])
+            .map_err(|e| format!("Failed to delete bookmark: {}", e))?;
+        Ok(())
+    }
 
-        let stories = self.collect_stories_for_index()?;
+    /// 切换收藏状态，返回切换后的状态
+    pub fn toggle_favorite(&self, kind: FavoriteKind, key: &str) -> Result<bool, String> {
+        let conn = self.open_user_connection()?;
+        let removed = conn
+            .execute(
+                "DELETE FROM favorites WHERE kind = ?1 AND key = ?2",
+                params![kind.as_str(), key],
+            )
+            .map_err(|e| format!("Failed to update favorite: {}", e))?;
+        if removed > 0 {
+            return Ok(false);
+        }
+        conn.execute(
+            "INSERT INTO favorites (kind, key, created_at) VALUES (?1, ?2, ?3)",
+            params![kind.as_str(), key, current_millis()],
+        )
+        .map_err(|e| format!("Failed to update favorite: {}", e))?;
+        Ok(true)
+    }
 
-        for indexed in &stories {
-            let story = &indexed.story;
-            let category_label =
-                Self::format_category_label(&indexed.entry_type, &indexed.category_name);
+    pub fn is_favorite(&self, kind: FavoriteKind, key: &str) -> Result<bool, String> {
+        self.open_user_connection()?
+            .query_row(
+                "SELECT 1 FROM favorites WHERE kind = ?1 AND key = ?2",
+                params![kind.as_str(), key],
+                |_| Ok(()),
+            )
+            .optional()
+            .map(|found| found.is_some())
+            .map_err(|e| format!("Failed to read favorite: {}", e))
+    }
 
-            let story_name_norm = normalize_nfkc_lower_strip_marks(&story.story_name);
-            if story_name_norm.contains(&query_norm) {
-                results.push(SearchResult {
-                    story_id: story.story_id.clone(),
-                    story_name: story.story_name.clone(),
-                    matched_text: story.story_name.clone(),
-                    category: category_label,
-                });
-                if results.len() >= SEARCH_RESULT_LIMIT {
-                    return Ok(results);
-                }
-                continue;
-            }
+    fn favorite_keys(&self) -> Result<Vec<(FavoriteKind, String, i64)>, String> {
+        let conn = self.open_user_connection()?;
+        let mut stmt = conn
+            .prepare("SELECT kind, key, created_at FROM favorites ORDER BY created_at DESC, key")
+            .map_err(|e| format!("Failed to read favorites: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })
+            .map_err(|e| format!("Failed to read favorites: {}", e))?;
+        let mut keys = Vec::new();
+        for row in rows {
+            let (kind, key, created_at) =
+                row.map_err(|e| format!("Failed to read favorites: {}", e))?;
+            let kind = match kind.as_str() {
+                "story" => FavoriteKind::Story,
+                "group" => FavoriteKind::Group,
+                _ => continue,
+            };
+            keys.push((kind, key, created_at));
+        }
+        Ok(keys)
+    }
 
-            if let Ok(content) = self.read_story_text(&story.story_txt) {
-                let content_norm = normalize_nfkc_lower_strip_marks(&content);
-                if content_norm.contains(&query_norm) {
-                    // Use original content for extracting visible context
-                    let matched_text = self.extract_context(&content, &query_norm);
-                    results.push(SearchResult {
-                        story_id: story.story_id.clone(),
-                        story_name: story.story_name.clone(),
-                        matched_text,
-                        category: category_label,
-                    });
-                    if results.len() >= SEARCH_RESULT_LIMIT {
-                        return Ok(results);
+    /// 收藏列表（最新的在前）；剧情与分组从已收集的剧情中解析，数据缺失时仅返回键
+    pub fn list_favorites(&self) -> Result<Vec<FavoriteItem>, String> {
+        let keys = self.favorite_keys()?;
+        let stories = if keys.is_empty() || !self.is_installed() {
+            Vec::new()
+        } else {
+            self.collect_stories_for_index()?
+        };
+
+        Ok(keys
+            .into_iter()
+            .map(|(kind, key, created_at)| {
+                let mut item = FavoriteItem {
+                    kind,
+                    key,
+                    created_at,
+                    story: None,
+                    group_name: None,
+                    story_count: None,
+                };
+                match kind {
+                    FavoriteKind::Story => {
+                        item.story = stories
+                            .iter()
+                            .find(|indexed| indexed.story.story_id == item.key)
+                            .map(|indexed| indexed.story.clone());
+                    }
+                    FavoriteKind::Group => {
+                        let members: Vec<&IndexedStory> = stories
+                            .iter()
+                            .filter(|indexed| indexed.story.story_group == item.key)
+                            .collect();
+                        if let Some(first) = members.first() {
+                            item.group_name = Some(first.category_name.clone());
+                            item.story_count = Some(members.len());
+                        }
                     }
                 }
-            }
-        }
-
-        Ok(results)
+                item
+            })
+            .collect())
     }
 
-    /// 搜索剧情（混合：索引优先 + 线性扫描补全，防止遗漏）
-    pub fn search_stories(&self, query: &str) -> Result<Vec<SearchResult>, String> {
-        let trimmed = query.trim();
-        if trimmed.is_empty() {
-            return Ok(Vec::new());
+    /// 为分组列表填充收藏标记；读取失败时记录日志并原样返回
+    pub fn annotate_favorites(&self, mut groups: Vec<StoryGroup>) -> Vec<StoryGroup> {
+        let favorites: HashSet<(FavoriteKind, String)> = match self.favorite_keys() {
+            Ok(keys) => keys.into_iter().map(|(kind, key, _)| (kind, key)).collect(),
+            Err(err) => {
+                tracing::warn!("Failed to read favorites: {}", err);
+                return groups;
+            }
+        };
+        for story in groups.iter_mut().flat_map(|group| group.stories.iter_mut()) {
+            story.favorite =
+                Some(favorites.contains(&(FavoriteKind::Story, story.story_id.clone())));
+            story.group_favorite =
+                Some(favorites.contains(&(FavoriteKind::Group, story.story_group.clone())));
         }
+        groups
+    }
 
-        // 先走索引
-        let mut combined: Vec<SearchResult> = match self.search_stories_with_index(trimmed) {
-            Ok(Some(results)) => results,
-            Ok(None) => Vec::new(),
+    /// 为分组列表附加索引中缓存的字数与阅读时长，不改变顺序；
+    /// 索引未建立或读取失败时原样返回，前端据此隐藏时长标记
+    pub fn attach_story_stats(&self, mut groups: Vec<StoryGroup>) -> Vec<StoryGroup> {
+        let stats = match self.cached_story_stats() {
+            Ok(stats) => stats,
             Err(err) => {
-                eprintln!(
-                    "[INDEX] Failed to search using index ({}), fallback to linear scan",
-                    err
-                );
-                Vec::new()
+                tracing::warn!("Failed to read cached stats: {}", err);
+                return groups;
+            }
+        };
+        for story in groups.iter_mut().flat_map(|group| group.stories.iter_mut()) {
+            if let Some(&(word_count, estimated_minutes)) = stats.get(&story.story_id) {
+                story.word_count = Some(word_count);
+                story.estimated_minutes = Some(estimated_minutes);
             }
+        }
+        groups
+    }
+
+    /// 索引中所有剧情的 (字数, 预计阅读分钟)；不校验内容哈希，仅用于列表展示
+    fn cached_story_stats(&self) -> Result<HashMap<String, (usize, usize)>, String> {
+        let Some(conn) = self.open_stats_connection()? else {
+            return Ok(HashMap::new());
         };
+        let mut stmt = conn
+            .prepare("SELECT story_id, word_count, estimated_minutes FROM story_stats")
+            .map_err(|e| format!("Failed to prepare story stats query: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    (
+                        row.get::<_, i64>(1)? as usize,
+                        row.get::<_, i64>(2)? as usize,
+                    ),
+                ))
+            })
+            .map_err(|e| format!("Failed to query story stats: {}", e))?;
+        rows.collect::<Result<HashMap<_, _>, _>>()
+            .map_err(|e| format!("Failed to read story stats: {}", e))
+    }
 
-        // 线性扫描补全（去重 by story_id）
-        let mut seen = std::collections::HashSet::new();
-        for r in &combined {
-            seen.insert(r.story_id.clone());
+    /// 阅读剧情后记录到最近阅读；无法对应到剧情条目时跳过
+    pub fn record_story_opened(&self, story_path: &str) -> Result<(), String> {
+        let normalize = |path: &str| {
+            let path = path.trim().replace('\\', "/").to_ascii_lowercase();
+            path.trim_end_matches(".txt").to_string()
+        };
+        let normalized = normalize(story_path);
+        let story_id = self
+            .listed_stories()?
+            .into_iter()
+            .find(|story| normalize(&story.story_txt) == normalized)
+            .map(|story| story.story_id);
+        match story_id {
+            Some(story_id) => self.touch_history(&story_id, current_millis()),
+            None => Ok(()),
         }
+    }
 
-        let fallback_results = self.search_stories_fallback(trimmed)?;
-        for r in fallback_results {
-            if seen.insert(r.story_id.clone()) {
-                combined.push(r);
-                if combined.len() >= SEARCH_RESULT_LIMIT {
-                    break;
-                }
-            }
+    fn touch_history(&self, story_id: &str, opened_at: i64) -> Result<(), String> {
+        let conn = self.open_user_connection()?;
+        conn.execute(
+            "INSERT INTO reading_history (story_id, opened_at) VALUES (?1, ?2)
+             ON CONFLICT(story_id) DO UPDATE SET opened_at = excluded.opened_at",
+            params![story_id, opened_at],
+        )
+        .map_err(|e| format!("Failed to record reading history: {}", e))?;
+        conn.execute(
+            "DELETE FROM reading_history WHERE story_id NOT IN (
+                SELECT story_id FROM reading_history ORDER BY opened_at DESC LIMIT ?1
+             )",
+            params![RECENT_HISTORY_LIMIT as i64],
+        )
+        .map_err(|e| format!("Failed to prune reading history: {}", e))?;
+        Ok(())
+    }
+
+    /// 前端上报的阅读时长，累加到最近一次打开的记录上
+    pub fn report_read_session(&self, story_id: &str, seconds: i64) -> Result<(), String> {
+        self.open_user_connection()?
+            .execute(
+                "UPDATE reading_history SET read_seconds = read_seconds + ?2 WHERE story_id = ?1",
+                params![story_id, seconds.max(0)],
+            )
+            .map_err(|e| format!("Failed to record read session: {}", e))?;
+        Ok(())
+    }
+
+    /// 最近阅读（最新的在前）；已不在当前数据中的剧情会被跳过
+    pub fn get_recent_stories(&self, limit: usize) -> Result<Vec<RecentStory>, String> {
+        let conn = self.open_user_connection()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT story_id, opened_at, read_seconds FROM reading_history
+                 ORDER BY opened_at DESC, story_id",
+            )
+            .map_err(|e| format!("Failed to read reading history: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })
+            .map_err(|e| format!("Failed to read reading history: {}", e))?;
+        let history = rows
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read reading history: {}", e))?;
+        if history.is_empty() || !self.is_installed() {
+            return Ok(Vec::new());
         }
 
-        Ok(combined)
+        let mut stories: HashMap<String, StoryEntry> = self
+            .listed_stories()?
+            .into_iter()
+            .map(|story| (story.story_id.clone(), story))
+            .collect();
+        Ok(history
+            .into_iter()
+            .filter_map(|(story_id, last_opened_at, read_seconds)| {
+                stories.remove(&story_id).map(|story| RecentStory {
+                    story,
+                    last_opened_at,
+                    read_seconds,
+                })
+            })
+            .take(limit)
+            .collect())
     }
 
-    pub fn search_stories_with_debug(&self, query: &str) -> Result<SearchDebugResponse, String> {
-        let mut logs = Vec::new();
-        let trimmed = query.trim();
-        if trimmed.is_empty() {
-            logs.push("查询为空，直接返回".to_string());
-            return Ok(SearchDebugResponse {
-                results: Vec::new(),
-                logs,
+    /// 手动标记剧情已读/未读；标记之后再次读到 100% 时以阅读进度为准
+    pub fn set_story_read(&self, story_id: &str, read: bool) -> Result<(), String> {
+        self.open_user_connection()?
+            .execute(
+                "INSERT INTO read_state (story_id, read, updated_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(story_id) DO UPDATE SET
+                    read = excluded.read,
+                    updated_at = excluded.updated_at",
+                params![story_id, read, current_millis()],
+            )
+            .map_err(|e| format!("Failed to save read state: {}", e))?;
+        Ok(())
+    }
+
+    /// 已读剧情集合：手动标记为已读，或阅读进度达到 100% 且晚于最近一次手动标记为未读
+    fn read_story_ids(&self) -> Result<HashSet<String>, String> {
+        let conn = self.open_user_connection()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT story_id FROM read_state WHERE read = 1
+                 UNION
+                 SELECT p.story_id FROM reading_progress p
+                 LEFT JOIN read_state r ON r.story_id = p.story_id
+                 WHERE p.percent >= 100 AND (r.story_id IS NULL OR p.updated_at > r.updated_at)",
+            )
+            .map_err(|e| format!("Failed to read read state: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| format!("Failed to read read state: {}", e))?;
+        rows.collect::<Result<HashSet<_>, _>>()
+            .map_err(|e| format!("Failed to read read state: {}", e))
+    }
+
+    /// 各分组的已读/总数：剧情表分组按出现顺序在前，集成战略、危机合约与主线笔记分组在后
+    pub fn get_group_read_counts(&self) -> Result<Vec<GroupReadCount>, String> {
+        let read_ids = self.read_story_ids()?;
+        let mut counts: Vec<GroupReadCount> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for (group_key, story_id) in self.listed_group_members()? {
+            let index = *positions.entry(group_key.clone()).or_insert_with(|| {
+                counts.push(GroupReadCount {
+                    group_key,
+                    read: 0,
+                    total: 0,
+                });
+                counts.len() - 1
             });
+            let count = &mut counts[index];
+            count.total += 1;
+            if read_ids.contains(&story_id) {
+                count.read += 1;
+            }
         }
+        Ok(counts)
+    }
 
-        let start_time = Instant::now();
-        logs.push(format!("开始搜索: \"{}\"", trimmed));
-
-        // Show normalized and FTS query preview
-        let normalized = normalize_nfkc_lower_strip_marks(trimmed);
-        logs.push(format!("规范化后的查询: \"{}\"", normalized));
-        if let Some(fts_query_preview) = Self::build_fts_query_advanced(trimmed) {
-            logs.push(format!("FTS 查询: {}", fts_query_preview));
-        } else {
-            logs.push("FTS 查询为空（可能仅包含标点或无效字符）".to_string());
+    /// 将整个分组标记为已读，返回分组内的剧情数
+    pub fn mark_group_read(&self, group_key: &str) -> Result<usize, String> {
+        let story_ids: Vec<String> = self
+            .listed_group_members()?
+            .into_iter()
+            .filter(|(key, _)| key == group_key)
+            .map(|(_, story_id)| story_id)
+            .collect();
+        if story_ids.is_empty() {
+            return Err(format!("Unknown story group: {}", group_key));
         }
 
-        let index_attempt_start = Instant::now();
-        let mut index_results: Vec<SearchResult> = Vec::new();
-        match self.search_stories_with_index(trimmed) {
-            Ok(Some(results)) => {
-                let index_elapsed = index_attempt_start.elapsed();
-                logs.push(format!(
-                    "全文索引查询完成，耗时 {} ms，结果 {} 条",
-                    index_elapsed.as_millis(),
-                    results.len()
-                ));
-                index_results = results;
+        let mut conn = self.open_user_connection()?;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start read state transaction: {}", e))?;
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO read_state (story_id, read, updated_at) VALUES (?1, 1, ?2)
+                     ON CONFLICT(story_id) DO UPDATE SET
+                        read = 1,
+                        updated_at = excluded.updated_at",
+                )
+                .map_err(|e| format!("Failed to save read state: {}", e))?;
+            let updated_at = current_millis();
+            for story_id in &story_ids {
+                stmt.execute(params![story_id, updated_at])
+                    .map_err(|e| format!("Failed to save read state: {}", e))?;
             }
-            Ok(None) => {
-                logs.push(format!(
-                    "全文索引不可用或未建立，耗时 {} ms",
-                    index_attempt_start.elapsed().as_millis()
-                ));
+        }
+        tx.commit()
+            .map_err(|e| format!("Failed to commit read state: {}", e))?;
+        Ok(story_ids.len())
+    }
+
+    /// 读取剧情统计；索引中的统计缺失或内容哈希不一致时现场解析
+    fn story_stats_for(
+        &self,
+        conn: Option<&Connection>,
+        story: &StoryEntry,
+    ) -> Result<StoryStats, String> {
+        let raw_text = self.read_story_text(&story.story_txt)?;
+        let hash = Self::content_hash(&raw_text);
+        if let Some(conn) = conn {
+            let cached = conn
+                .query_row(
+                    "SELECT word_count, dialogue_count, narration_count, decision_count, speaker_count,
+                            terra_year, terra_month, terra_day
+                     FROM story_stats WHERE story_id = ?1 AND content_hash = ?2",
+                    params![story.story_id, hash],
+                    |row| {
+                        let month = row.get::<_, Option<u8>>(6)?;
+                        let day = row.get::<_, Option<u8>>(7)?;
+                        Ok(StoryStats {
+                            story_id: story.story_id.clone(),
+                            word_count: row.get::<_, i64>(0)? as usize,
+                            dialogue_count: row.get::<_, i64>(1)? as usize,
+                            narration_count: row.get::<_, i64>(2)? as usize,
+                            decision_count: row.get::<_, i64>(3)? as usize,
+                            speaker_count: row.get::<_, i64>(4)? as usize,
+                            estimated_minutes: 0,
+                            terra_date: row
+                                .get::<_, Option<i32>>(5)?
+                                .map(|year| TerraDate { year, month, day }),
+                        })
+                    },
+                )
+                .optional()
+                .map_err(|e| format!("Failed to read story stats: {}", e))?;
+            if let Some(mut stats) = cached {
+                stats.estimated_minutes = estimate_reading_minutes(stats.word_count);
+                return Ok(stats);
             }
-            Err(err) => {
-                logs.push(format!(
-                    "全文索引查询失败: {} (耗时 {} ms)，将回退线性扫描",
-                    err,
-                    index_attempt_start.elapsed().as_millis()
-                ));
+        }
+        let parsed = parse_story_text(&raw_text);
+        Ok(Self::compute_story_stats(&story.story_id, &parsed.segments))
+    }
+
+    /// 打开索引库读取统计表；索引尚未建立时返回 None
+    fn open_stats_connection(&self) -> Result<Option<Connection>, String> {
+        self.open_index_with_table("story_stats")
+    }
+
+    /// 打开索引库，仅当其中已有指定的表时返回连接（旧版索引可能缺少新增的表）
+    fn open_index_with_table(&self, table: &str) -> Result<Option<Connection>, String> {
+        let Some(conn) = self.try_open_index_connection()? else {
+            return Ok(None);
+        };
+        let has_table = conn
+            .query_row(
+                "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                params![table],
+                |_| Ok(()),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to inspect story index: {}", e))?
+            .is_some();
+        Ok(has_table.then_some(conn))
+    }
+
+    /// 单篇剧情的字数、对话数等统计
+    pub fn get_story_stats(&self, story_id: &str) -> Result<StoryStats, String> {
+        let story = self
+            .collect_stories_for_index()?
+            .into_iter()
+            .map(|indexed| indexed.story)
+            .find(|story| story.story_id == story_id)
+            .ok_or_else(|| format!("Story not found: {}", story_id))?;
+        let conn = self.open_stats_connection()?;
+        self.story_stats_for(conn.as_ref(), &story)
+    }
+
+    /// 干员列表：按筛选条件过滤、排序后分页，召唤物与装置仅在 include_nonplayable 时列出；
+    /// 参数全部缺省时按干员 id 返回完整列表
+    pub fn get_characters_list(
+        &self,
+        filter: &CharacterListFilter,
+    ) -> Result<CharacterPage, String> {
+        let mut roster: Vec<CharacterSummary> = self
+            .character_roster(filter.include_nonplayable)?
+            .into_iter()
+            .filter(|character| !character.is_patch)
+            .filter(|character| {
+                filter.profession.as_ref().is_none_or(|professions| {
+                    character
+                        .profession
+                        .as_ref()
+                        .is_some_and(|profession| professions.contains(profession))
+                })
+            })
+            .filter(|character| {
+                filter
+                    .rarity
+                    .as_ref()
+                    .is_none_or(|rarities| rarities.contains(&character.rarity))
+            })
+            .filter(|character| {
+                filter
+                    .nation
+                    .as_ref()
+                    .is_none_or(|nation| character.nation_id.as_ref() == Some(nation))
+            })
+            .filter(|character| {
+                filter.sub_profession.as_ref().is_none_or(|sub_profession| {
+                    character.sub_profession_id.as_ref() == Some(sub_profession)
+                })
+            })
+            .collect();
+        match filter.sort {
+            Some(CharacterSort::Rarity) => roster.sort_by(|a, b| {
+                b.rarity
+                    .cmp(&a.rarity)
+                    .then_with(|| a.char_id.cmp(&b.char_id))
+            }),
+            Some(CharacterSort::Name) => {
+                roster.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.char_id.cmp(&b.char_id)))
             }
+            None => {}
         }
 
-        let fallback_start = Instant::now();
-        let fallback_results = self.search_stories_fallback(trimmed)?;
-        logs.push(format!(
-            "线性扫描完成，耗时 {} ms，结果 {} 条",
-            fallback_start.elapsed().as_millis(),
-            fallback_results.len()
-        ));
-        if fallback_results.len() >= SEARCH_RESULT_LIMIT {
-            logs.push(format!(
-                "结果数量达到上限 {} 条，建议缩小检索范围",
-                SEARCH_RESULT_LIMIT
-            ));
+        let total = roster.len();
+        let items = roster
+            .into_iter()
+            .skip(filter.offset.unwrap_or(0))
+            .take(filter.limit.unwrap_or(usize::MAX))
+            .collect();
+        Ok(CharacterPage { total, items })
+    }
+
+    /// 干员名册（按干员 id 排序，经 roster_characters 按职业排除召唤物与装置），
+    /// 含 char_patch_table.patchChars 中合并在原版条目之上的其他形态（is_patch）；
+    /// 职业分支名称取自 uniequip_table.subProfDict；
+    /// 各表都经表缓存读取，重复筛选不会重新解析 JSON
+    fn character_roster(&self, include_nonplayable: bool) -> Result<Vec<CharacterSummary>, String> {
+        let Some(characters) = self.read_optional_table("character_table.json")? else {
+            return Ok(Vec::new());
+        };
+        let patch = self.read_optional_table("char_patch_table.json")?;
+        let form_groups = patch
+            .as_deref()
+            .map(character_form_groups)
+            .unwrap_or_default();
+        let base_of: HashMap<&str, &str> = form_groups
+            .iter()
+            .flat_map(|(base, alternates)| {
+                alternates
+                    .iter()
+                    .map(move |alternate| (alternate.as_str(), base.as_str()))
+            })
+            .collect();
+        let patch_entries: Map<String, Value> = patch
+            .as_deref()
+            .and_then(|patch| patch.get("patchChars"))
+            .and_then(|chars| chars.as_object())
+            .into_iter()
+            .flatten()
+            .map(|(id, entry)| {
+                let merged = base_of
+                    .get(id.as_str())
+                    .and_then(|base| characters.get(*base))
+                    .map(|base| merge_patch_character(base.clone(), entry))
+                    .unwrap_or_else(|| entry.clone());
+                (id.clone(), merged)
+            })
+            .collect();
+        let patch_entries = Value::Object(patch_entries);
+        let uniequip = self.read_optional_table("uniequip_table.json")?;
+        let sub_profession_names = uniequip
+            .as_deref()
+            .map(sub_profession_names)
+            .unwrap_or_default();
+
+        let text = |value: &Value, key: &str| {
+            value
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        let mut roster: Vec<CharacterSummary> = roster_characters(&characters, include_nonplayable)
+            .chain(roster_characters(&patch_entries, include_nonplayable))
+            .map(|(id, character)| {
+                let sub_profession_id = text(character, "subProfessionId");
+                CharacterSummary {
+                    char_id: id.clone(),
+                    name: text(character, "name").unwrap_or_else(|| id.clone()),
+                    appellation: text(character, "appellation"),
+                    rarity: parse_rarity(character.get("rarity")),
+                    profession: text(character, "profession"),
+                    sub_profession_name: sub_profession_id
+                        .as_deref()
+                        .and_then(|sub_id| sub_profession_names.get(sub_id))
+                        .map(|name| name.to_string()),
+                    sub_profession_id,
+                    nation_id: text(character, "nationId"),
+                    group_id: text(character, "groupId"),
+                    team_id: text(character, "teamId"),
+                    tags: character
+                        .get("tagList")
+                        .and_then(|v| v.as_array())
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|tag| tag.as_str())
+                        .map(str::to_string)
+                        .collect(),
+                    has_alternate_forms: form_groups.contains_key(id.as_str())
+                        || base_of.contains_key(id.as_str()),
+                    base_char_id: base_of.get(id.as_str()).map(|base| base.to_string()),
+                    is_not_obtainable: character
+                        .get("isNotObtainable")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false),
+                    is_patch: patch_entries.get(id.as_str()).is_some(),
+                }
+            })
+            .collect();
+        roster.sort_by(|a, b| a.char_id.cmp(&b.char_id));
+        Ok(roster)
+    }
+
+    /// 干员的配音演员与语音套组（charword_table 缺失或没有该干员时为空）
+    pub fn get_character_voice_actors(
+        &self,
+        char_id: &str,
+    ) -> Result<CharacterVoiceActors, String> {
+        let voice_sets = self
+            .read_optional_table("charword_table.json")?
+            .map(|table| parse_voice_actors(&table, char_id))
+            .unwrap_or_default();
+        Ok(CharacterVoiceActors {
+            char_id: char_id.to_string(),
+            voice_sets,
+        })
+    }
+
+    /// 干员档案：各章节原文，以及从首个章节（基础档案）解析出的键值与体检医师报告
+    pub fn get_character_profile(&self, char_id: &str) -> Result<CharacterProfile, String> {
+        let handbook = self.read_optional_table("handbook_info_table.json")?;
+        let info = handbook
+            .as_deref()
+            .and_then(|table| table.get("handbookDict"))
+            .and_then(|dict| dict.get(char_id))
+            .ok_or_else(|| format!("Character {} 没有档案", char_id))?;
+        let favor_table = self.read_optional_table("favor_table.json")?;
+        let percents = favor_percents(favor_table.as_deref());
+
+        let sections: Vec<HandbookSection> = info
+            .get("storyTextAudio")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .map(|section| {
+                let title = section
+                    .get("storyTitle")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string();
+                let text = section
+                    .get("stories")
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|story| story.get("storyText").and_then(|v| v.as_str()))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                // 同一章节的多段文本解锁条件相同，取第一段
+                let first = section
+                    .get("stories")
+                    .and_then(|v| v.as_array())
+                    .and_then(|stories| stories.first());
+                let field = |key: &str| {
+                    first
+                        .and_then(|story| story.get(key))
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string)
+                };
+                let unlock_type = field("unLockType");
+                let unlock_param = field("unLockParam");
+                let unlock_desc = describe_handbook_unlock(
+                    unlock_type.as_deref(),
+                    unlock_param.as_deref(),
+                    field("unLockString").as_deref(),
+                    &percents,
+                );
+                HandbookSection {
+                    title,
+                    text,
+                    unlock_type,
+                    unlock_param,
+                    unlock_desc,
+                }
+            })
+            .collect();
+        let (profile, medical_report) = sections
+            .first()
+            .map(|section| parse_handbook_profile(&section.text))
+            .unwrap_or_default();
+
+        Ok(CharacterProfile {
+            char_id: char_id.to_string(),
+            profile,
+            medical_report,
+            sections,
+        })
+    }
+
+    /// 干员在指定语言下各条语音的资源路径（仅按 charword_table 推算，不读取音频文件）；
+    /// 默认语音在前，其后为皮肤等语音套组，同一套组按 voiceIndex 排序
+    pub fn get_character_voice_assets(
+        &self,
+        char_id: &str,
+        lang: &str,
+    ) -> Result<CharacterVoiceAssets, String> {
+        let lang = lang.trim().to_ascii_uppercase();
+        if lang.is_empty() || !lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("Invalid voice language: {}", lang));
         }
-        // 合并结果（索引优先顺序），去重并截断
-        let mut seen = std::collections::HashSet::new();
-        let mut merged = Vec::new();
-        for r in index_results {
-            if seen.insert(r.story_id.clone()) {
-                merged.push(r);
-                if merged.len() >= SEARCH_RESULT_LIMIT {
-                    break;
+        let Some(table) = self.read_optional_table("charword_table.json")? else {
+            return Ok(CharacterVoiceAssets {
+                char_id: char_id.to_string(),
+                lang,
+                has_alternate_sets: false,
+                lines: Vec::new(),
+            });
+        };
+
+        let voice_sets = parse_voice_actors(&table, char_id);
+        let voice_path = |word_key: &str| {
+            voice_sets
+                .iter()
+                .find(|set| set.word_key == word_key)
+                .and_then(|set| set.languages.iter().find(|l| l.lang_type == lang))
+                .and_then(|l| l.voice_path.clone())
+        };
+        let text = |value: &Value, key: &str| {
+            value
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+
+        let mut lines: Vec<(i64, VoiceAsset)> = table
+            .get("charWords")
+            .and_then(|v| v.as_object())
+            .into_iter()
+            .flatten()
+            .filter(|(_, word)| word.get("charId").and_then(|v| v.as_str()) == Some(char_id))
+            .filter_map(|(char_word_id, word)| {
+                let word_key = text(word, "wordKey").unwrap_or_else(|| char_id.to_string());
+                let voice_id = text(word, "voiceId")?;
+                let voice_asset = text(word, "voiceAsset")
+                    .unwrap_or_else(|| format!("{}/{}", word_key, voice_id));
+                let asset_path =
+                    voice_asset_path(&lang, &voice_asset, voice_path(&word_key).as_deref());
+                let index = word.get("voiceIndex").and_then(|v| v.as_i64()).unwrap_or(0);
+                Some((
+                    index,
+                    VoiceAsset {
+                        char_word_id: char_word_id.clone(),
+                        voice_title: text(word, "voiceTitle"),
+                        voice_text: text(word, "voiceText"),
+                        word_key,
+                        voice_id,
+                        asset_path,
+                    },
+                ))
+            })
+            .collect();
+        lines.sort_by(|(a_index, a), (b_index, b)| {
+            (a.word_key != char_id)
+                .cmp(&(b.word_key != char_id))
+                .then_with(|| a.word_key.cmp(&b.word_key))
+                .then_with(|| a_index.cmp(b_index))
+        });
+
+        Ok(CharacterVoiceAssets {
+            char_id: char_id.to_string(),
+            lang,
+            has_alternate_sets: voice_sets.iter().any(|set| set.is_alternate),
+            lines: lines.into_iter().map(|(_, line)| line).collect(),
+        })
+    }
+
+    /// 剧情的出场人物（按首次发言顺序），说话人名称先精确匹配 character_table 的 name，
+    /// 再回退到 appellation；匹配不到的视为 NPC
+    pub fn get_story_characters(&self, story_path: &str) -> Result<Vec<StoryCharacter>, String> {
+        let content = self.read_story_text(story_path)?;
+        let hash = Self::content_hash(&content);
+        if let Some(cached) = self
+            .table_cache
+            .story_characters
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&hash)
+        {
+            return Ok(cached.as_ref().clone());
+        }
+
+        let mut characters: Vec<StoryCharacter> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for segment in parse_story_text(&content).segments {
+            let StorySegment::Dialogue { character_name, .. } = segment else {
+                continue;
+            };
+            let name = character_name.trim();
+            if name.is_empty() {
+                continue;
+            }
+            match positions.get(name) {
+                Some(&index) => characters[index].line_count += 1,
+                None => {
+                    positions.insert(name.to_string(), characters.len());
+                    characters.push(StoryCharacter {
+                        name: name.to_string(),
+                        char_id: None,
+                        rarity: None,
+                        avatar_id: None,
+                        line_count: 1,
+                    });
                 }
             }
         }
-        let mut added = 0usize;
-        if merged.len() < SEARCH_RESULT_LIMIT {
-            for r in fallback_results {
-                if seen.insert(r.story_id.clone()) {
-                    merged.push(r);
-                    added += 1;
-                    if merged.len() >= SEARCH_RESULT_LIMIT {
-                        break;
+
+        if !characters.is_empty() {
+            if let Some(table) = self.read_optional_table("character_table.json")? {
+                let lookup = character_name_lookup(&table);
+                for character in &mut characters {
+                    if let Some((char_id, rarity)) = lookup.get(character.name.as_str()) {
+                        character.char_id = Some(char_id.to_string());
+                        character.avatar_id = Some(char_id.to_string());
+                        character.rarity = (*rarity > 0).then_some(*rarity);
                     }
                 }
-            }
-        }
-        if added > 0 {
-            logs.push(format!("线性扫描补全 {} 条结果", added));
-        }
-        logs.push(format!(
-            "搜索总耗时 {} ms",
-            start_time.elapsed().as_millis()
-        ));
+            }
+        }
+
+        let characters = Arc::new(characters);
+        self.table_cache
+            .story_characters
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(hash, characters.clone());
+        Ok(characters.as_ref().clone())
+    }
+
+    /// 分组内所有剧情的统计汇总，无法读取文本的剧情跳过
+    pub fn get_group_stats(&self, group_key: &str) -> Result<GroupStats, String> {
+        let stories: Vec<StoryEntry> = self
+            .collect_stories_for_index()?
+            .into_iter()
+            .map(|indexed| indexed.story)
+            .filter(|story| story.story_group == group_key)
+            .collect();
+        if stories.is_empty() {
+            return Err(format!("Unknown story group: {}", group_key));
+        }
+
+        let conn = self.open_stats_connection()?;
+        let mut group = GroupStats {
+            group_key: group_key.to_string(),
+            story_count: stories.len(),
+            word_count: 0,
+            dialogue_count: 0,
+            narration_count: 0,
+            decision_count: 0,
+            estimated_minutes: 0,
+        };
+        for story in &stories {
+            let stats = match self.story_stats_for(conn.as_ref(), story) {
+                Ok(stats) => stats,
+                Err(err) => {
+                    tracing::debug!("Skip story {}: {}", story.story_id, err);
+                    continue;
+                }
+            };
+            group.word_count += stats.word_count;
+            group.dialogue_count += stats.dialogue_count;
+            group.narration_count += stats.narration_count;
+            group.decision_count += stats.decision_count;
+        }
+        group.estimated_minutes = estimate_reading_minutes(group.word_count);
+        Ok(group)
+    }
+
+    /// 与指定剧情共享说话人的其他剧情，稀有角色权重更高（IDF）；同组剧情由前后篇导航覆盖，
+    /// 不参与推荐。索引中没有 story_speakers 表时返回空列表
+    pub fn get_related_stories(
+        &self,
+        story_id: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<RelatedStory>, String> {
+        let entries = self.story_entries()?;
+        let current = entries
+            .get(story_id)
+            .ok_or_else(|| format!("Story {} 不存在", story_id))?;
+        let Some(conn) = self.open_index_with_table("story_speakers")? else {
+            return Ok(Vec::new());
+        };
+
+        let total_stories: i64 = conn
+            .query_row(
+                "SELECT COUNT(DISTINCT story_id) FROM story_speakers",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to count story speakers: {}", e))?;
+        let mut speakers_stmt = conn
+            .prepare(
+                "SELECT s.speaker_norm, COUNT(DISTINCT o.story_id)
+                 FROM story_speakers s JOIN story_speakers o ON o.speaker_norm = s.speaker_norm
+                 WHERE s.story_id = ?1
+                 GROUP BY s.speaker_norm",
+            )
+            .map_err(|e| format!("Failed to prepare speaker query: {}", e))?;
+        let weights: HashMap<String, f64> = speakers_stmt
+            .query_map(params![story_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })
+            .map_err(|e| format!("Failed to query story speakers: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read story speakers: {}", e))?
+            .into_iter()
+            .map(|(speaker, count)| {
+                let idf = (1.0 + total_stories as f64 / count.max(1) as f64).ln();
+                (speaker, idf)
+            })
+            .collect();
+        if weights.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut shared_stmt = conn
+            .prepare(
+                "SELECT story_id FROM story_speakers WHERE speaker_norm = ?1 AND story_id != ?2",
+            )
+            .map_err(|e| format!("Failed to prepare related story query: {}", e))?;
+        let mut shared: HashMap<String, Vec<&str>> = HashMap::new();
+        for speaker in weights.keys() {
+            let rows = shared_stmt
+                .query_map(params![speaker, story_id], |row| row.get::<_, String>(0))
+                .map_err(|e| format!("Failed to query related stories: {}", e))?;
+            for row in rows {
+                let other = row.map_err(|e| format!("Failed to read related story: {}", e))?;
+                shared.entry(other).or_default().push(speaker.as_str());
+            }
+        }
+
+        let mut related: Vec<RelatedStory> = shared
+            .into_iter()
+            .filter_map(|(other_id, mut speakers)| {
+                let indexed = entries.get(&other_id)?;
+                if indexed.story.story_group == current.story.story_group {
+                    return None;
+                }
+                speakers.sort_by(|a, b| weights[*b].total_cmp(&weights[*a]).then_with(|| a.cmp(b)));
+                Some(RelatedStory {
+                    story: indexed.story.clone(),
+                    score: speakers.iter().map(|speaker| weights[*speaker]).sum(),
+                    shared_speakers: speakers.into_iter().map(str::to_string).collect(),
+                })
+            })
+            .collect();
+        related.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.story.story_id.cmp(&b.story.story_id))
+        });
+        related.truncate(limit.unwrap_or(RELATED_STORY_LIMIT));
+        Ok(related)
+    }
+
+    /// 主线章节的阅读顺序指引：行动前之后、行动后之前插入打关提示，
+    /// 只有行动前或只有行动后的关卡同样只插入一次提示
+    pub fn get_chapter_reading_order(
+        &self,
+        group_key: &str,
+    ) -> Result<Vec<ReadingOrderItem>, String> {
+        let group = self
+            .get_main_stories_grouped(None)?
+            .into_iter()
+            .find(|group| group.id == group_key)
+            .ok_or_else(|| format!("Unknown mainline group: {}", group_key))?;
+
+        let marker = |code: &str| ReadingOrderItem {
+            kind: ReadingOrderKind::StageMarker,
+            story: None,
+            stage_code: Some(code.to_string()),
+        };
+        let mut items = Vec::with_capacity(group.stories.len() * 2);
+        // 最近一次插入提示的关卡，避免同一关卡的行动后再插一次
+        let mut marked_stage: Option<String> = None;
+        for story in group.stories {
+            let code = story
+                .story_code
+                .as_deref()
+                .map(str::trim)
+                .filter(|code| !code.is_empty())
+                .map(str::to_string);
+            let key = code.as_deref().map(stage_pair_key);
+            let kind = story.avg_tag_kind;
+            if let (Some(AvgTagKind::After), Some(code), Some(key)) = (kind, &code, key) {
+                if marked_stage.as_deref() != Some(key) {
+                    items.push(marker(code));
+                    marked_stage = Some(key.to_string());
+                }
+            }
+            items.push(ReadingOrderItem {
+                kind: ReadingOrderKind::Story,
+                story: Some(story),
+                stage_code: code.clone(),
+            });
+            if let (Some(AvgTagKind::Before), Some(code), Some(key)) = (kind, &code, key) {
+                items.push(marker(code));
+                marked_stage = Some(key.to_string());
+            }
+        }
+        Ok(items)
+    }
+
+    /// 按泰拉历日期排列的剧情时间线，日期相同时按所属分组与顺序；无法读取文本的剧情跳过
+    pub fn get_stories_by_terra_date(&self) -> Result<TerraTimeline, String> {
+        let include_hidden = self.include_hidden_in_search();
+        let stories: Vec<StoryEntry> = self
+            .collect_stories_for_index()?
+            .into_iter()
+            .map(|indexed| indexed.story)
+            .filter(|story| include_hidden || story.visibility != Some(StoryVisibility::Hidden))
+            .collect();
+
+        let conn = self.open_stats_connection()?;
+        let mut timeline = TerraTimeline {
+            dated: Vec::new(),
+            undated: Vec::new(),
+        };
+        for story in stories {
+            match self.story_stats_for(conn.as_ref(), &story) {
+                Ok(stats) => match stats.terra_date {
+                    Some(date) => timeline.dated.push(TerraDatedStory { story, date }),
+                    None => timeline.undated.push(story),
+                },
+                Err(err) => tracing::debug!("Skip story {}: {}", story.story_id, err),
+            }
+        }
+        timeline.dated.sort_by(|a, b| {
+            a.date
+                .cmp(&b.date)
+                .then_with(|| compare_story_group_ids(&a.story.story_group, &b.story.story_group))
+                .then_with(|| a.story.story_sort.cmp(&b.story.story_sort))
+        });
+        Ok(timeline)
+    }
+
+    /// 「继续阅读」：最近更新且未读完的剧情；任何分组列表中都已不存在的剧情会被跳过并清理
+    pub fn get_continue_reading(&self) -> Result<Option<ContinueReading>, String> {
+        if !self.is_installed() {
+            return Ok(None);
+        }
+
+        let conn = self.open_user_connection()?;
+        let candidates: Vec<ReadingProgress> = {
+            // 最近打开时间也参与排序：只打开未滚动的剧情同样算作最近阅读
+            let mut stmt = conn
+                .prepare(
+                    "SELECT p.story_id, p.segment_index, p.char_offset, p.percent, p.updated_at
+                     FROM reading_progress p
+                     LEFT JOIN reading_history h ON h.story_id = p.story_id
+                     WHERE p.percent < 100
+                     ORDER BY MAX(p.updated_at, COALESCE(h.opened_at, 0)) DESC, p.story_id",
+                )
+                .map_err(|e| format!("Failed to read reading progress: {}", e))?;
+            let rows = stmt
+                .query_map([], Self::map_reading_progress)
+                .map_err(|e| format!("Failed to read reading progress: {}", e))?;
+            rows.collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Failed to read reading progress: {}", e))?
+        };
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+
+        let stories: HashMap<String, StoryEntry> = self
+            .listed_stories()?
+            .into_iter()
+            .map(|story| (story.story_id.clone(), story))
+            .collect();
+        for progress in candidates {
+            let Some(story) = stories.get(&progress.story_id) else {
+                conn.execute(
+                    "DELETE FROM reading_progress WHERE story_id = ?1",
+                    params![progress.story_id],
+                )
+                .map_err(|e| format!("Failed to prune reading progress: {}", e))?;
+                conn.execute(
+                    "DELETE FROM reading_history WHERE story_id = ?1",
+                    params![progress.story_id],
+                )
+                .map_err(|e| format!("Failed to prune reading history: {}", e))?;
+                continue;
+            };
+            // 密录等不在导航分组中的剧情没有分组上下文
+            let neighbors = self.get_story_neighbors(&story.story_id, false).ok();
+            return Ok(Some(ContinueReading {
+                story: story.clone(),
+                progress,
+                neighbors,
+            }));
+        }
+        Ok(None)
+    }
+
+    /// 当前正在执行的独占任务，供前端在同步、导入期间禁用相关按钮
+    pub fn get_busy_state(&self) -> BusyState {
+        let task = self.tasks.exclusive_task();
+        BusyState {
+            busy: task.is_some(),
+            task,
+        }
+    }
+
+    /// 设置页所需的全部状态，只读取本地数据与缓存，不发起网络请求
+    pub fn get_app_status(&self) -> AppStatus {
+        let log_failure = |what: &str, err: String| {
+            tracing::warn!("Failed to read {} for app status: {}", what, err);
+        };
+        let index = self
+            .get_story_index_status()
+            .map_err(|err| log_failure("index status", err))
+            .ok()
+            .map(|status| IndexSummary {
+                ready: status.ready,
+                total: status.total,
+                last_built_at: status.last_built_at,
+                building: self.tasks.list().iter().any(|task| task.kind == "index"),
+            });
+        AppStatus {
+            installed: Some(self.is_installed()),
+            version: self.read_version().map(|info| InstalledVersion {
+                commit_short: short_commit(&info.commit).to_string(),
+                commit: info.commit,
+                fetched_at: info.fetched_at,
+                source_id: info.source.map(|source| source.id),
+            }),
+            language: Some(self.data_source().language),
+            index,
+            disk_usage: self
+                .cached_disk_usage()
+                .map_err(|err| log_failure("disk usage", err))
+                .ok(),
+            tasks: Some(self.tasks.list()),
+            last_update_check: self
+                .status_cache
+                .last_update_check
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .clone(),
+            story_cache: Some(self.story_cache_stats()),
+        }
+    }
+
+    fn cached_disk_usage(&self) -> Result<DiskUsage, String> {
+        let mut cached = self
+            .status_cache
+            .disk_usage
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some((measured_at, usage)) = cached.as_ref() {
+            if measured_at.elapsed() < DISK_USAGE_CACHE_TTL {
+                return Ok(usage.clone());
+            }
+        }
+        let usage = self.get_disk_usage()?;
+        *cached = Some((Instant::now(), usage.clone()));
+        Ok(usage)
+    }
+
+    /// 正在执行的长耗时任务及其最新进度
+    pub fn list_tasks(&self) -> Vec<TaskInfo> {
+        self.tasks.list()
+    }
+
+    /// 请求取消任务，任务会在下一个检查点以 CANCELLED 结束；任务已结束时返回 false
+    pub fn cancel_task(&self, task_id: u64) -> bool {
+        self.tasks.cancel(task_id)
+    }
+
+    /// 登记独占任务，已有独占任务在执行时返回 BUSY
+    fn begin_task(&self, kind: &str) -> Result<TaskHandle, String> {
+        self.tasks.start(kind, true)
+    }
+
+    fn open_index_connection(&self) -> Result<Connection, String> {
+        if let Some(parent) = self.index_db_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create index directory: {}", e))?;
+        }
+        let conn = Connection::open(&self.index_db_path)
+            .map_err(|e| format!("Failed to open story index database: {}", e))?;
+        conn.execute_batch(
+            "
+            PRAGMA journal_mode = WAL;
+            PRAGMA synchronous = NORMAL;
+            ",
+        )
+        .map_err(|e| format!("Failed to configure index database: {}", e))?;
+        Ok(conn)
+    }
+
+    fn try_open_index_connection(&self) -> Result<Option<Connection>, String> {
+        if !self.index_db_path.exists() {
+            return Ok(None);
+        }
+        match self.open_index_connection() {
+            Ok(conn) => Ok(Some(conn)),
+            Err(err) => {
+                tracing::warn!("Failed to open story index: {}", err);
+                Ok(None)
+            }
+        }
+    }
+
+    fn init_index_tables(conn: &Connection) -> Result<(), String> {
+        // meta table
+        conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS story_index_meta (
+                key TEXT PRIMARY KEY,
+                value TEXT
+            );
+            ",
+        )
+        .map_err(|e| format!("Failed to init story index meta: {}", e))?;
+
+        // read current version
+        let current_version: i32 = conn
+            .query_row(
+                "SELECT value FROM story_index_meta WHERE key = 'index_version'",
+                [],
+                |row| row.get::<_, String>(0),
+            )
+            .ok()
+            .and_then(|s| s.parse::<i32>().ok())
+            .unwrap_or(0);
+
+        let should_recreate = current_version < INDEX_VERSION;
+
+        if should_recreate {
+            // Drop and recreate virtual table with new schema
+            conn.execute_batch(
+                "
+                DROP TABLE IF EXISTS story_index;
+                DROP TABLE IF EXISTS story_stats;
+                CREATE VIRTUAL TABLE story_index USING fts5(
+                    story_id UNINDEXED,
+                    story_name,
+                    category UNINDEXED,
+                    tokenized_content,
+                    story_code,
+                    raw_content UNINDEXED,
+                    tokenize = 'unicode61 remove_diacritics 2',
+                    prefix='2 3 4'
+                );
+                ",
+            )
+            .map_err(|e| format!("Failed to (re)create story index: {}", e))?;
+
+            conn.execute(
+                "INSERT INTO story_index_meta (key, value) VALUES ('index_version', ?1)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                params![INDEX_VERSION.to_string()],
+            )
+            .map_err(|e| format!("Failed to update index version: {}", e))?;
+        } else {
+            // Ensure table exists (fresh install)
+            conn.execute_batch(
+                "
+                CREATE VIRTUAL TABLE IF NOT EXISTS story_index USING fts5(
+                    story_id UNINDEXED,
+                    story_name,
+                    category UNINDEXED,
+                    tokenized_content,
+                    story_code,
+                    raw_content UNINDEXED,
+                    tokenize = 'unicode61 remove_diacritics 2',
+                    prefix='2 3 4'
+                );
+                ",
+            )
+            .map_err(|e| format!("Failed to ensure story index table: {}", e))?;
+        }
+
+        conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS story_stats (
+                story_id TEXT PRIMARY KEY,
+                content_hash TEXT NOT NULL,
+                word_count INTEGER NOT NULL,
+                dialogue_count INTEGER NOT NULL,
+                narration_count INTEGER NOT NULL,
+                decision_count INTEGER NOT NULL,
+                speaker_count INTEGER NOT NULL,
+                estimated_minutes INTEGER NOT NULL DEFAULT 0,
+                terra_year INTEGER,
+                terra_month INTEGER,
+                terra_day INTEGER
+            );
+            CREATE TABLE IF NOT EXISTS story_speakers (
+                story_id TEXT NOT NULL,
+                speaker_norm TEXT NOT NULL,
+                PRIMARY KEY (story_id, speaker_norm)
+            );
+            CREATE INDEX IF NOT EXISTS idx_story_speakers_speaker
+                ON story_speakers(speaker_norm);
+            ",
+        )
+        .map_err(|e| format!("Failed to init story stats table: {}", e))?;
+
+        Ok(())
+    }
+
+    fn open_user_connection(&self) -> Result<Connection, String> {
+        if let Some(parent) = self.user_db_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create user data directory: {}", e))?;
+        }
+        let conn = Connection::open(&self.user_db_path)
+            .map_err(|e| format!("Failed to open user database: {}", e))?;
+        conn.execute_batch(
+            "
+            PRAGMA journal_mode = WAL;
+            PRAGMA synchronous = NORMAL;
+            CREATE TABLE IF NOT EXISTS reading_progress (
+                story_id TEXT PRIMARY KEY,
+                segment_index INTEGER NOT NULL,
+                char_offset INTEGER NOT NULL DEFAULT 0,
+                percent REAL NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS bookmarks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                story_id TEXT NOT NULL,
+                segment_index INTEGER NOT NULL,
+                excerpt TEXT NOT NULL,
+                note TEXT NOT NULL DEFAULT '',
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_bookmarks_story ON bookmarks(story_id);
+            CREATE TABLE IF NOT EXISTS favorites (
+                kind TEXT NOT NULL,
+                key TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (kind, key)
+            );
+            CREATE TABLE IF NOT EXISTS reading_history (
+                story_id TEXT PRIMARY KEY,
+                opened_at INTEGER NOT NULL,
+                read_seconds INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE TABLE IF NOT EXISTS read_state (
+                story_id TEXT PRIMARY KEY,
+                read INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            ",
+        )
+        .map_err(|e| format!("Failed to init user database: {}", e))?;
+        Ok(conn)
+    }
+
+    fn clear_story_index(&self) -> Result<(), String> {
+        if self.index_db_path.exists() {
+            fs::remove_file(&self.index_db_path)
+                .map_err(|e| format!("Failed to remove story index: {}", e))?;
+        }
+        Ok(())
+    }
+
+    fn entry_type_display(entry_type: &str) -> String {
+        match entry_type {
+            "MAINLINE" => "主线".to_string(),
+            "ACTIVITY" | "MINI_ACTIVITY" => "活动".to_string(),
+            "STORY_COLLECTION" => "故事集".to_string(),
+            "ROGUELIKE" => "肉鸽".to_string(),
+            "SIDESTORY" => "支线".to_string(),
+            "NONE" => "干员密录".to_string(),
+            "RUNE" => "危机合约".to_string(),
+            _ => entry_type.to_string(),
+        }
+    }
+
+    fn resolve_category_name(entry_type: &str, entry_id: &str, value: &Value) -> String {
+        if let Some(name) = value
+            .get("name")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+        {
+            return name.to_string();
+        }
+
+        let display = Self::entry_type_display(entry_type);
+        if display == entry_type {
+            format!("{} ({})", entry_type, entry_id)
+        } else {
+            format!("{} ({})", display, entry_id)
+        }
+    }
+
+    fn format_category_label(entry_type: &str, category_name: &str) -> String {
+        let prefix = Self::entry_type_display(entry_type);
+        let name = category_name.trim();
+        if name.is_empty() || name == prefix {
+            prefix
+        } else {
+            format!("{} | {}", prefix, name)
+        }
+    }
+
+    fn collect_stories_for_index(&self) -> Result<Vec<IndexedStory>, String> {
+        if !self.is_installed() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+
+        let table = self.load_table("story_review_table.json")?;
+        let data = table_entries(&table, "story_review_table.json")?;
+
+        let rune_seasons = self.rune_metadata()?.seasons;
+        let roguelike_topics = self.roguelike_topics()?;
+        let memory_sources = self.memory_sources()?;
+        let mut seen_ids = HashSet::new();
+        let mut seen_activity_txts = HashSet::new();
+        let mut stories = Vec::new();
+
+        for (entry_id, value) in data.iter() {
+            let entry_type = value
+                .get("entryType")
+                .and_then(|v| v.as_str())
+                .unwrap_or("UNKNOWN");
+
+            let Some(unlock_datas) = value.get("infoUnlockDatas").and_then(|v| v.as_array()) else {
+                continue;
+            };
+
+            let mut category_name = Self::resolve_category_name(entry_type, entry_id, value);
+            let act_type = value.get("actType").and_then(|v| v.as_str()).unwrap_or("");
+            let activity_kind = classify_activity_entry(entry_type, act_type);
+            let is_activity = activity_kind.is_some();
+            if is_activity {
+                // 复刻与原版归入同一分类
+                category_name = strip_rerun_suffix(&category_name).to_string();
+            }
+
+            for unlock_data in unlock_datas {
+                if let Some(story) = parse_story_entry(entry_id, unlock_data) {
+                    if story.story_txt.trim().is_empty() {
+                        continue;
+                    }
+                    if is_activity
+                        && !seen_activity_txts.insert(story.story_txt.to_ascii_lowercase())
+                    {
+                        continue;
+                    }
+                    // 危机合约按赛季、集成战略按主题归类，与对应分组接口的标签一致
+                    let category_name = match entry_type {
+                        "RUNE" => rune_season_key(&story.story_txt)
+                            .map(|season| rune_season_label(&season, &rune_seasons)),
+                        "ROGUELIKE" => roguelike_group_key(&story.story_txt)
+                            .map(|key| roguelike_group_label(&key, &roguelike_topics)),
+                        // 密录标签附上来源活动，搜索活动名时也能命中对应密录
+                        "NONE" => memory_sources
+                            .get(&story.story_id)
+                            .or_else(|| memory_sources.get(&story.story_group))
+                            .and_then(|source| source.act_name.as_deref())
+                            .map(|act_name| format!("{} · 来自「{}」", category_name, act_name)),
+                        _ => None,
+                    }
+                    .unwrap_or_else(|| category_name.clone());
+                    if seen_ids.insert(story.story_id.clone()) {
+                        stories.push(IndexedStory {
+                            category_name,
+                            entry_type: activity_kind
+                                .map_or(entry_type, |kind| kind.index_entry_type())
+                                .to_string(),
+                            story,
+                        });
+                    }
+                }
+            }
+        }
+
+        stories.sort_by(|a, b| a.story.story_id.cmp(&b.story.story_id));
+        Ok(stories)
+    }
+
+    fn content_hash(text: &str) -> String {
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
+    fn compute_story_stats(story_id: &str, segments: &[StorySegment]) -> StoryStats {
+        let count_chars = |text: &str| text.chars().filter(|c| !c.is_whitespace()).count();
+        let mut stats = StoryStats {
+            story_id: story_id.to_string(),
+            word_count: 0,
+            dialogue_count: 0,
+            narration_count: 0,
+            decision_count: 0,
+            speaker_count: 0,
+            estimated_minutes: 0,
+            terra_date: None,
+        };
+        let mut speakers = HashSet::new();
+        for segment in segments {
+            match segment {
+                StorySegment::Dialogue {
+                    character_name,
+                    text,
+                    ..
+                } => {
+                    stats.dialogue_count += 1;
+                    stats.word_count += count_chars(text);
+                    if !character_name.trim().is_empty() {
+                        speakers.insert(character_name.trim());
+                    }
+                }
+                StorySegment::Narration { text } => {
+                    stats.narration_count += 1;
+                    stats.word_count += count_chars(text);
+                }
+                StorySegment::Decision { options, .. } => {
+                    stats.decision_count += 1;
+                    stats.word_count += options.iter().map(|o| count_chars(o)).sum::<usize>();
+                }
+                StorySegment::System { text, .. }
+                | StorySegment::Subtitle { text, .. }
+                | StorySegment::Sticker { text, .. } => {
+                    stats.word_count += count_chars(text);
+                }
+                StorySegment::Header { .. } => {}
+            }
+        }
+        stats.speaker_count = speakers.len();
+        stats.estimated_minutes = estimate_reading_minutes(stats.word_count);
+        stats.terra_date = segments.iter().find_map(|segment| match segment {
+            StorySegment::Subtitle { text, .. } | StorySegment::Sticker { text, .. } => {
+                parse_terra_date(text)
+            }
+            _ => None,
+        });
+        stats
+    }
+
+    fn flatten_segments(segments: &[StorySegment]) -> String {
+        let mut parts = Vec::with_capacity(segments.len());
+        for segment in segments {
+            match segment {
+                StorySegment::Dialogue {
+                    character_name,
+                    text,
+                    ..
+                } => {
+                    parts.push(format!("{}：{}", character_name, text));
+                }
+                StorySegment::Narration { text }
+                | StorySegment::System { text, .. }
+                | StorySegment::Subtitle { text, .. }
+                | StorySegment::Sticker { text, .. } => {
+                    parts.push(text.clone());
+                }
+                StorySegment::Decision { options, .. } => {
+                    parts.push(options.join(" / "));
+                }
+                StorySegment::Header { title } => {
+                    parts.push(title.clone());
+                }
+            }
+        }
+        parts.join("\n")
+    }
+
+    fn tokenize_for_fts(text: &str) -> Vec<String> {
+        let text = normalize_nfkc_lower_strip_marks(text);
+        let mut tokens = Vec::new();
+        let mut ascii_buffer = String::new();
+
+        for ch in text.chars() {
+            if ch.is_ascii_alphanumeric() {
+                ascii_buffer.push(ch.to_ascii_lowercase());
+                continue;
+            }
+
+            if !ascii_buffer.is_empty() {
+                let token = std::mem::take(&mut ascii_buffer);
+                tokens.push(token);
+            }
+
+            if ch.is_whitespace() {
+                continue;
+            }
+
+            if is_common_punctuation(ch) {
+                continue;
+            }
+
+            if ch.is_alphanumeric() {
+                let token: String = ch.to_lowercase().collect();
+                if !token.is_empty() {
+                    tokens.push(token);
+                }
+                continue;
+            }
+
+            tokens.push(ch.to_string());
+        }
+
+        if !ascii_buffer.is_empty() {
+            tokens.push(ascii_buffer);
+        }
+
+        tokens
+    }
+
+    fn build_tokenized_content(text: &str) -> String {
+        Self::tokenize_for_fts(text).join(" ")
+    }
+
+    // Build a more expressive FTS query:
+    // - Normalize (NFKC + lowercase + strip marks)
+    // - Chinese contiguous sequences (len>=2) -> quoted phrase of spaced characters: "凯 尔 希"
+    // - ASCII terms -> add * suffix for prefix match
+    // - Support simple NOT via leading '-' and OR keyword, default AND
+    fn build_fts_query_advanced(raw_query: &str) -> Option<String> {
+        let q = normalize_nfkc_lower_strip_marks(raw_query.trim());
+        if q.is_empty() {
+            return None;
+        }
+
+        // Simple tokenizer that respects quoted phrases
+        let mut terms: Vec<(String, bool, bool)> = Vec::new(); // (term, is_not, is_or_before)
+        let mut buf = String::new();
+        let mut in_quotes = false;
+        let mut prev_was_or = false;
+        let mut chars = q.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '"' => {
+                    if in_quotes {
+                        in_quotes = false;
+                        let t = std::mem::take(&mut buf);
+                        if !t.is_empty() {
+                            terms.push((t, false, prev_was_or));
+                            prev_was_or = false;
+                        }
+                    } else {
+                        if !buf.trim().is_empty() {
+                            let t = std::mem::take(&mut buf);
+                            if t == "or" {
+                                prev_was_or = true;
+                            } else {
+                                let is_not = t.starts_with('-');
+                                let content = if is_not {
+                                    t.trim_start_matches('-').to_string()
+                                } else {
+                                    t
+                                };
+                                if !content.is_empty() {
+                                    terms.push((content, is_not, prev_was_or));
+                                    prev_was_or = false;
+                                }
+                            }
+                        }
+                        in_quotes = true;
+                    }
+                }
+                c if c.is_whitespace() && !in_quotes => {
+                    if !buf.is_empty() {
+                        let t = std::mem::take(&mut buf);
+                        if t == "or" {
+                            prev_was_or = true;
+                        } else {
+                            let is_not = t.starts_with('-');
+                            let content = if is_not {
+                                t.trim_start_matches('-').to_string()
+                            } else {
+                                t
+                            };
+                            if !content.is_empty() {
+                                terms.push((content, is_not, prev_was_or));
+                                prev_was_or = false;
+                            }
+                        }
+                    }
+                }
+                _ => buf.push(ch),
+            }
+        }
+        if !buf.is_empty() {
+            let t = std::mem::take(&mut buf);
+            if t == "or" {
+                // dangling OR, ignore
+            } else {
+                let is_not = t.starts_with('-');
+                let content = if is_not {
+                    t.trim_start_matches('-').to_string()
+                } else {
+                    t
+                };
+                if !content.is_empty() {
+                    terms.push((content, is_not, prev_was_or));
+                }
+            }
+        }
+
+        if terms.is_empty() {
+            return None;
+        }
+
+        fn to_phrase_if_cjk(s: &str) -> String {
+            let mut has_cjk = false;
+            let mut all_cjk = true;
+            for ch in s.chars() {
+                if is_cjk(ch) {
+                    has_cjk = true;
+                } else if !ch.is_whitespace() {
+                    all_cjk = false;
+                }
+            }
+            if has_cjk && all_cjk {
+                let parts: Vec<String> = s
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .map(|c| if c == '"' { ' ' } else { c })
+                    .map(|c| c.to_string())
+                    .collect();
+                let spaced = parts.join(" ");
+                format!("\"{}\"", spaced)
+            } else if s.chars().all(|c| c.is_ascii_alphanumeric()) {
+                format!("{}*", s)
+            } else {
+                // mixed or others: quote as is
+                let escaped = s.replace('"', "");
+                format!("\"{}\"", escaped)
+            }
+        }
+
+        let mut parts: Vec<String> = Vec::new();
+        for (i, (raw, is_not, is_or)) in terms.into_iter().enumerate() {
+            if raw.is_empty() {
+                continue;
+            }
+            let mut piece = to_phrase_if_cjk(&raw);
+            if is_not {
+                piece = format!("NOT {}", piece);
+            }
+            if i > 0 {
+                if is_or {
+                    parts.push("OR".to_string());
+                } else {
+                    parts.push("AND".to_string());
+                }
+            }
+            parts.push(piece);
+        }
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join(" "))
+        }
+    }
+
+    fn extract_meta_value(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+        conn.query_row(
+            "SELECT value FROM story_index_meta WHERE key = ?1",
+            params![key],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read story index meta {}: {}", key, e))
+    }
+
+    /// 下载并解压最新数据包
+    #[tracing::instrument(name = "sync", skip_all)]
+    pub fn sync_data(&self, app: AppHandle) -> Result<(), String> {
+        {
+            let task = self.begin_task("sync")?.with_progress(progress_sink(&app));
+            task.run(|task| self.sync_with_task(&app, task))?;
+        }
+        self.rebuild_index_if_enabled();
+        Ok(())
+    }
+
+    /// 同步或导入成功后按设置自动重建全文索引；失败只记录日志，不影响已完成的更新
+    fn rebuild_index_if_enabled(&self) {
+        if !self.settings.get().auto_index {
+            return;
+        }
+        if let Err(err) = self.rebuild_story_index() {
+            tracing::warn!("自动重建索引失败: {}", err);
+        }
+    }
+
+    fn sync_with_task(&self, app: &AppHandle, task: &TaskHandle) -> Result<(), String> {
+        let source = self.data_source();
+        tracing::info!("开始同步数据 ({})", source.repo);
+        emit_progress(app, task, "准备", 0, 1, "正在初始化同步环境");
+
+        tracing::debug!("创建 HTTP 客户端");
+        let client = Self::create_http_client()?;
+
+        tracing::debug!("获取最新 commit");
+        let remote_commit = match Self::fetch_latest_commit(&client, &source) {
+            Ok(commit) => {
+                tracing::info!("成功获取 commit: {}", &commit);
+                let short = commit.get(..7).unwrap_or(commit.as_str());
+                emit_progress(app, task, "准备", 1, 1, format!("最新版本 {}", short));
+                Some(commit)
+            }
+            Err(err) => {
+                tracing::warn!("获取 commit 失败: {}", err);
+                emit_progress(
+                    app,
+                    task,
+                    "准备",
+                    0,
+                    1,
+                    format!("获取版本信息失败，回退到 {}: {}", source.branch, err),
+                );
+                None
+            }
+        };
+
+        let reference = remote_commit
+            .clone()
+            .unwrap_or_else(|| source.branch.clone());
+        tracing::info!("使用引用: {}", reference);
+
+        // 覆盖旧数据前记录已有剧情，用于生成同步摘要
+        if let Err(err) = self.save_story_snapshot() {
+            tracing::warn!("Failed to snapshot previous stories: {}", err);
+        }
+
+        // 预热可能仍在读取旧数据表，替换前先取消，避免把半新半旧的表写回缓存
+        self.tasks.cancel_kind("prewarm");
+        tracing::info!("开始下载和解压");
+        match self.download_and_extract(&client, app, task, &source, &reference) {
+            Err(err) if err.starts_with(CORRUPT_ARCHIVE_ERROR) => {
+                // 损坏的压缩包已被删除，自动重新下载一次
+                tracing::warn!("数据包损坏，重新下载: {}", err);
+                emit_progress(app, task, "下载", 0, 100, "数据包损坏，正在重新下载");
+                self.download_and_extract(&client, app, task, &source, &reference)?;
+            }
+            result => result?,
+        }
+        tracing::info!("下载和解压完成");
+        self.invalidate_caches();
+
+        if let Err(err) = self.clear_story_index() {
+            tracing::warn!("Failed to reset story index: {}", err);
+        }
+
+        // 写入版本信息
+        tracing::debug!("写入版本信息");
+        let commit_to_store = remote_commit.unwrap_or_else(|| "unknown".to_string());
+        let fetched_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let info = VersionInfo {
+            commit: commit_to_store,
+            fetched_at,
+            source: Some(source),
+        };
+        self.write_version(&info)?;
+
+        let report = match self.build_sync_report() {
+            Ok(report) => Some(report),
+            Err(err) => {
+                tracing::warn!("Failed to build sync report: {}", err);
+                None
+            }
+        };
+
+        tracing::info!("同步完成");
+        emit_sync_complete(app, task, "同步完成", report);
+        Ok(())
+    }
+
+    /// 将当前已安装的剧情（ID → 名称、分类）写入快照；未安装时写入空快照以标记首次安装
+    fn save_story_snapshot(&self) -> Result<(), String> {
+        let stories: HashMap<String, SnapshotStory> = if self.is_installed() {
+            self.collect_stories_for_index()?
+                .into_iter()
+                .map(|indexed| {
+                    let story = SnapshotStory {
+                        name: indexed.story.story_name,
+                        category: Self::format_category_label(
+                            &indexed.entry_type,
+                            &indexed.category_name,
+                        ),
+                    };
+                    (indexed.story.story_id, story)
+                })
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        let snapshot = StorySnapshot {
+            version: self.read_version().map(|info| info.commit),
+            stories,
+        };
+        let content = serde_json::to_string(&snapshot)
+            .map_err(|e| format!("Failed to serialize story snapshot: {}", e))?;
+        fs::write(self.app_data_dir()?.join(STORY_SNAPSHOT_FILE), content)
+            .map_err(|e| format!("Failed to write story snapshot: {}", e))
+    }
+
+    fn read_story_snapshot(&self) -> Option<StorySnapshot> {
+        let path = self.app_data_dir().ok()?.join(STORY_SNAPSHOT_FILE);
+        let content = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// 对比同步前的快照，统计各分类剧情数量、新增剧情与文本总量，并持久化结果
+    fn build_sync_report(&self) -> Result<SyncReport, String> {
+        let stories = self.collect_stories_for_index()?;
+        let snapshot = self.read_story_snapshot();
+        let previous_ids: HashSet<&String> = snapshot
+            .as_ref()
+            .map(|s| s.stories.keys().collect())
+            .unwrap_or_default();
+        let first_install = previous_ids.is_empty();
+
+        let mut category_counts: Vec<SyncCategoryCount> = Vec::new();
+        let mut new_story_count = 0usize;
+        let mut new_story_names = Vec::new();
+        let mut total_text_bytes = 0u64;
+        let story_dir = self.story_dir();
+
+        for indexed in &stories {
+            let label = Self::entry_type_display(&indexed.entry_type);
+            match category_counts.iter_mut().find(|c| c.category == label) {
+                Some(count) => count.count += 1,
+                None => category_counts.push(SyncCategoryCount {
+                    category: label,
+                    count: 1,
+                }),
+            }
+
+            if !first_install && !previous_ids.contains(&indexed.story.story_id) {
+                new_story_count += 1;
+                if new_story_names.len() < SYNC_REPORT_NEW_NAMES_LIMIT {
+                    new_story_names.push(indexed.story.story_name.clone());
+                }
+            }
+
+            if let Ok(meta) =
+                fs::metadata(story_dir.join(format!("{}.txt", indexed.story.story_txt)))
+            {
+                total_text_bytes += meta.len();
+            }
+        }
+        category_counts.sort_by(|a, b| b.count.cmp(&a.count).then(a.category.cmp(&b.category)));
+
+        let report = SyncReport {
+            total_stories: stories.len(),
+            category_counts,
+            first_install,
+            new_story_count,
+            new_story_names,
+            total_text_bytes,
+            previous_version: snapshot.and_then(|s| s.version),
+            current_version: self.read_version().map(|info| info.commit),
+            generated_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0),
+        };
+
+        let content = serde_json::to_string_pretty(&report)
+            .map_err(|e| format!("Failed to serialize sync report: {}", e))?;
+        fs::write(self.app_data_dir()?.join(SYNC_REPORT_FILE), content)
+            .map_err(|e| format!("Failed to write sync report: {}", e))?;
+        Ok(report)
+    }
+
+    /// 与上一次安装相比新增的剧情，按分类分组、组内按 story_sort 排序
+    pub fn get_new_stories(&self) -> Result<NewStories, String> {
+        let stories = self.collect_stories_for_index()?;
+        let snapshot = self.read_story_snapshot().unwrap_or_default();
+        let current_version = self.read_version().map(|info| info.commit);
+
+        if snapshot.stories.is_empty() {
+            return Ok(NewStories {
+                first_install: true,
+                previous_version: snapshot.version,
+                current_version,
+                groups: Vec::new(),
+            });
+        }
+
+        let mut groups: Vec<(u8, NewStoryGroup)> = Vec::new();
+        for indexed in stories {
+            if snapshot.stories.contains_key(&indexed.story.story_id) {
+                continue;
+            }
+            let category = Self::format_category_label(&indexed.entry_type, &indexed.category_name);
+            match groups.iter_mut().find(|(_, g)| g.category == category) {
+                Some((_, group)) => group.stories.push(indexed.story),
+                None => groups.push((
+                    Self::entry_type_rank(&indexed.entry_type),
+                    NewStoryGroup {
+                        category,
+                        stories: vec![indexed.story],
+                    },
+                )),
+            }
+        }
+
+        groups.sort_by(|(rank_a, a), (rank_b, b)| {
+            rank_a.cmp(rank_b).then_with(|| a.category.cmp(&b.category))
+        });
+        let groups = groups
+            .into_iter()
+            .map(|(_, mut group)| {
+                group.stories.sort_by(|a, b| {
+                    a.story_sort
+                        .cmp(&b.story_sort)
+                        .then_with(|| a.story_id.cmp(&b.story_id))
+                });
+                group
+            })
+            .collect();
+
+        Ok(NewStories {
+            first_install: false,
+            previous_version: snapshot.version,
+            current_version,
+            groups,
+        })
+    }
+
+    fn entry_type_rank(entry_type: &str) -> u8 {
+        match entry_type {
+            "MAINLINE" => 0,
+            "ACTIVITY" | "MINI_ACTIVITY" | "STORY_COLLECTION" => 1,
+            "SIDESTORY" => 2,
+            "ROGUELIKE" => 3,
+            "NONE" => 4,
+            _ => 5,
+        }
+    }
+
+    /// 最近一次同步的摘要；尚未同步过时返回 None
+    pub fn get_sync_report(&self) -> Result<Option<SyncReport>, String> {
+        let path = self.app_data_dir()?.join(SYNC_REPORT_FILE);
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map(Some)
+                .map_err(|e| format!("Failed to parse sync report: {}", e)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(format!("Failed to read sync report: {}", err)),
+        }
+    }
+
+    pub fn get_current_version(&self) -> Result<String, String> {
+        if let Some(info) = self.read_version() {
+            let commit_short = if info.commit.len() >= 7 {
+                &info.commit[..7]
+            } else {
+                info.commit.as_str()
+            };
+            Ok(format!(
+                "{} ({})",
+                commit_short,
+                format_timestamp(info.fetched_at)
+            ))
+        } else {
+            Ok("未安装".to_string())
+        }
+    }
+
+    /// 远端最新提交的短哈希；离线或请求失败时返回「未知」，不影响本地版本的显示
+    pub fn get_remote_version(&self) -> Result<String, String> {
+        let client = Self::create_http_client()?;
+        match Self::fetch_latest_commit(&client, &self.data_source()) {
+            Ok(commit) => Ok(short_commit(&commit).to_string()),
+            Err(err) => {
+                tracing::debug!("Failed to fetch remote version: {}", err);
+                Ok("未知".to_string())
+            }
+        }
+    }
+
+    /// 启动时按设置在后台检查数据更新；有更新时发送 update-available 事件
+    pub fn check_update_on_startup(&self, app: &AppHandle) {
+        if !self.settings.get().auto_check_update || !self.is_installed() {
+            return;
+        }
+        let status = self.check_update_detailed();
+        if matches!(status, UpdateStatus::UpdateAvailable { .. }) {
+            let _ = app.emit("update-available", &status);
+        }
+    }
+
+    /// 检查数据更新：区分「有更新」「已是最新」与「无法检查」
+    pub fn check_update_detailed(&self) -> UpdateStatus {
+        let source = self.data_source();
+        self.check_update_with(&source, || {
+            let client = Self::create_http_client()?;
+            Self::fetch_latest_commit(&client, &source)
+        })
+    }
+
+    fn check_update_with<F>(&self, source: &DataSourceProfile, fetch_remote: F) -> UpdateStatus
+    where
+        F: FnOnce() -> Result<String, String>,
+    {
+        let status = self.compare_remote_version(source, fetch_remote);
+        *self
+            .status_cache
+            .last_update_check
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(UpdateCheckResult {
+            status: status.clone(),
+            checked_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0),
+        });
+        status
+    }
+
+    fn compare_remote_version<F>(&self, source: &DataSourceProfile, fetch_remote: F) -> UpdateStatus
+    where
+        F: FnOnce() -> Result<String, String>,
+    {
+        let remote = match fetch_remote() {
+            Ok(commit) => commit,
+            Err(reason) => return UpdateStatus::Unknown { reason },
+        };
+
+        // 已安装数据来自其他仓库时，即便 commit 相同也需要重新同步
+        let same_source = |info: &VersionInfo| {
+            let installed_repo = info
+                .source
+                .as_ref()
+                .map(|s| s.repo.clone())
+                .unwrap_or_else(|| DataSourceProfile::default().repo);
+            installed_repo == source.repo
+        };
+
+        match self.read_version() {
+            Some(current) if current.commit == remote && same_source(&current) => {
+                UpdateStatus::UpToDate
+            }
+            _ => UpdateStatus::UpdateAvailable {
+                remote_short: short_commit(&remote).to_string(),
+            },
+        }
+    }
+
+    fn create_http_client() -> Result<Client, String> {
+        Client::builder()
+            .user_agent("arknights-story-reader")
+            .build()
+            .map_err(|e| format!("Failed to create http client: {}", e))
+    }
+
+    fn fetch_latest_commit(client: &Client, source: &DataSourceProfile) -> Result<String, String> {
+        let url = format!(
+            "{}/{}/commits/{}",
+            REPO_API_BASE, source.repo, source.branch
+        );
+        let response = client
+            .get(&url)
+            .send()
+            .map_err(|e| format!("Failed to request latest commit: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("GitHub API returned status {}", response.status()));
+        }
+
+        let value: serde_json::Value = response
+            .json()
+            .map_err(|e| format!("Failed to parse commit response: {}", e))?;
+
+        value
+            .get("sha")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .ok_or_else(|| "Failed to read commit sha".to_string())
+    }
+
+    fn download_and_extract(
+        &self,
+        client: &Client,
+        app: &AppHandle,
+        task: &TaskHandle,
+        source: &DataSourceProfile,
+        reference: &str,
+    ) -> Result<(), String> {
+        tracing::debug!("download_and_extract 开始");
+        let parent_dir = self
+            .data_dir
+            .parent()
+            .ok_or_else(|| "Invalid data directory".to_string())?;
+        tracing::debug!("parent_dir: {:?}", parent_dir);
+
+        let format = self.archive_format();
+        let format_segment = match format {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::TarGz => "tar.gz",
+        };
+        let download_url = format!(
+            "{}/{}/{}/{}",
+            self.download_base(),
+            source.repo,
+            format_segment,
+            reference
+        );
+        tracing::info!("download_url: {}", download_url);
+        emit_progress(app, task, "下载", 0, 100, format!("从 {} 下载", reference));
+
+        tracing::debug!("发起 HTTP GET 请求");
+        let response = client.get(&download_url).send().map_err(|e| {
+            tracing::error!("HTTP 请求失败: {}", e);
+            format!("Download failed: {}", e)
+        })?;
+
+        tracing::info!("HTTP 状态码: {}", response.status());
+        if !response.status().is_success() {
+            return Err(format!("Download returned status {}", response.status()));
+        }
+
+        let total_bytes = response.content_length().unwrap_or(0);
+        let stall_timeout = Duration::from_secs(self.settings.get().stall_timeout_secs);
+        let mut response = StallWatchdogReader::new(response, stall_timeout);
+        match format {
+            ArchiveFormat::Zip => {
+                self.download_zip(&mut response, total_bytes, parent_dir, app, task)
+            }
+            ArchiveFormat::TarGz => {
+                self.stream_extract_tar_gz(&mut response, total_bytes, parent_dir, app, task)
+            }
+        }
+    }
+
+    /// 先完整下载 ZIP 再解压（ZIP 的中央目录位于文件末尾，无法流式解压）
+    fn download_zip(
+        &self,
+        response: &mut impl Read,
+        total_bytes: u64,
+        parent_dir: &Path,
+        app: &AppHandle,
+        task: &TaskHandle,
+    ) -> Result<(), String> {
+        let zip_path = parent_dir.join("ArknightsGameData.zip");
+        let mut zip_file = fs::File::create(&zip_path)
+            .map_err(|e| format!("Failed to create temp zip file: {}", e))?;
+
+        let mut downloaded: u64 = 0;
+        let mut buffer = [0u8; 8192];
+        loop {
+            if let Err(err) = task.check_cancelled() {
+                drop(zip_file);
+                fs::remove_file(&zip_path).ok();
+                return Err(err);
+            }
+            let bytes_read = response
+                .read(&mut buffer)
+                .map_err(|e| download_read_error("Failed to read download stream", &e))?;
+            if bytes_read == 0 {
+                break;
+            }
+            zip_file
+                .write_all(&buffer[..bytes_read])
+                .map_err(|e| format!("Failed to write zip data: {}", e))?;
+            downloaded += bytes_read as u64;
+
+            let (percent, message) = download_progress(downloaded, total_bytes);
+            emit_progress(app, task, "下载", percent, 100, message);
+        }
+        zip_file
+            .flush()
+            .map_err(|e| format!("Failed to flush zip file: {}", e))?;
+
+        emit_progress(app, task, "下载", 100, 100, "下载完成");
+        self.extract_zip_at(
+            &zip_path,
+            parent_dir,
+            task.token(),
+            &mut |percent, message| emit_progress(app, task, "解压", percent, 100, message),
+        )?;
+        fs::remove_file(&zip_path).ok();
+
+        Ok(())
+    }
+
+    /// 边下载边解压 tar.gz，进度按已下载的压缩字节计算
+    fn stream_extract_tar_gz(
+        &self,
+        response: &mut impl Read,
+        total_bytes: u64,
+        parent_dir: &Path,
+        app: &AppHandle,
+        task: &TaskHandle,
+    ) -> Result<(), String> {
+        let extract_root = Self::prepare_extract_root(parent_dir)?;
+
+        let mut report_downloaded = |downloaded: u64| {
+            let (percent, message) = download_progress(downloaded, total_bytes);
+            emit_progress(
+                app,
+                task,
+                "下载",
+                percent,
+                100,
+                format!("{}，正在解压", message),
+            );
+        };
+        let reader = CountingReader::new(response, SUB_PROGRESS_INTERVAL, &mut report_downloaded);
+        let extracted = match extract_tar_gz_entries(reader, &extract_root, task.token()) {
+            Ok(extracted) => extracted,
+            Err(err) => {
+                fs::remove_dir_all(&extract_root).ok();
+                return Err(err);
+            }
+        };
+        tracing::info!("tar.gz 解压条目数: {}", extracted);
+
+        emit_progress(app, task, "下载", 100, 100, "下载完成");
+        emit_progress(app, task, "解压", 100, 100, "解压完成");
+        self.install_extracted(&extract_root)
+    }
+
+    /// 解压 zip_path 并替换现有数据，on_progress 接收解压百分比与提示文字
+    ///
+    /// 压缩包损坏时删除该文件，避免下次继续使用同一个坏文件
+    #[tracing::instrument(name = "extract", skip_all)]
+    fn extract_zip_at(
+        &self,
+        zip_path: &Path,
+        parent_dir: &Path,
+        cancel: &CancelToken,
+        on_progress: &mut dyn FnMut(usize, String),
+    ) -> Result<(), String> {
+        tracing::info!("开始解压 {:?}", zip_path);
+        on_progress(0, "正在解压数据".to_string());
+        let extract_root = Self::prepare_extract_root(parent_dir)?;
+
+        let mut report =
+            |percent: f64, message: String| on_progress(percent.round() as usize, message);
+        let result = if self.settings.get().parallel_extraction {
+            let workers = std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+                .min(MAX_EXTRACT_WORKERS);
+            extract_zip_entries_parallel(zip_path, &extract_root, workers, cancel, &mut report)
+        } else {
+            extract_zip_entries(
+                zip_path,
+                &extract_root,
+                SUB_PROGRESS_INTERVAL,
+                cancel,
+                &mut report,
+            )
+        };
+        let skipped = match result {
+            Ok(skipped) => skipped,
+            Err(err) => {
+                fs::remove_dir_all(&extract_root).ok();
+                // 用户取消时工作线程以 EXTRACTION_ABORTED 退出，统一报告为取消
+                cancel.check()?;
+                if err.starts_with(CORRUPT_ARCHIVE_ERROR) {
+                    tracing::warn!("压缩包已损坏，删除 {:?}: {}", zip_path, err);
+                    fs::remove_file(zip_path).ok();
+                }
+                return Err(err);
+            }
+        };
+        if skipped > 0 {
+            tracing::warn!("共跳过 {} 个无法解析的条目", skipped);
+        }
+
+        tracing::info!("解压完成");
+        on_progress(100, "解压完成".to_string());
+        self.install_extracted(&extract_root)
+    }
+
+    fn prepare_extract_root(parent_dir: &Path) -> Result<PathBuf, String> {
+        let extract_root = parent_dir.join("ArknightsGameData_extract");
+        if extract_root.exists() {
+            fs::remove_dir_all(&extract_root)
+                .map_err(|e| format!("Failed to clean extract dir: {}", e))?;
+        }
+        fs::create_dir_all(&extract_root)
+            .map_err(|e| format!("Failed to create extract dir: {}", e))?;
+        Ok(extract_root)
+    }
+
+    /// 用解压出的数据根目录替换现有数据，并清理解压目录
+    fn install_extracted(&self, extract_root: &Path) -> Result<(), String> {
+        let extracted_root = find_extracted_root(extract_root, &self.data_source().language)?;
+
+        if self.data_dir.exists() {
+            fs::remove_dir_all(&self.data_dir)
+                .map_err(|e| format!("Failed to remove old data: {}", e))?;
+        }
+
+        match fs::rename(&extracted_root, &self.data_dir) {
+            Ok(_) => {}
+            Err(_) => {
+                copy_dir_all(&extracted_root, &self.data_dir)?;
+                fs::remove_dir_all(&extracted_root).ok();
+            }
+        }
+
+        fs::remove_dir_all(extract_root).ok();
+        Ok(())
+    }
+
+    /// 解压导入的 ZIP 并替换现有数据，on_progress 接收阶段、百分比与提示文字；
+    /// 压缩包自带 version.json（如导出的离线数据包）时沿用其中的版本，否则记为 manual-<时间戳>
+    fn finalize_manual_import(
+        &self,
+        temp_path: &Path,
+        task: &TaskHandle,
+        on_progress: &mut dyn FnMut(&str, usize, String),
+    ) -> Result<Option<SyncReport>, String> {
+        let parent_dir = self
+            .data_dir
+            .parent()
+            .ok_or_else(|| "Invalid data directory".to_string())?;
+
+        if let Err(err) = self.save_story_snapshot() {
+            tracing::warn!("Failed to snapshot previous stories: {}", err);
+        }
+
+        self.tasks.cancel_kind("prewarm");
+        on_progress("导入", 40, "正在解压 ZIP 文件".to_string());
+        self.extract_zip_at(
+            temp_path,
+            parent_dir,
+            task.token(),
+            &mut |percent, message| on_progress("解压", percent, message),
+        )?;
+        fs::remove_file(temp_path).ok();
+        self.invalidate_caches();
+
+        if let Err(err) = self.clear_story_index() {
+            tracing::warn!("Failed to reset story index: {}", err);
+        }
+
+        if self.read_version().is_none() {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0);
+            let info = VersionInfo {
+                commit: format!("manual-{}", timestamp),
+                fetched_at: timestamp,
+                source: Some(self.data_source()),
+            };
+            self.write_version(&info)?;
+        }
+
+        Ok(match self.build_sync_report() {
+            Ok(report) => Some(report),
+            Err(err) => {
+                tracing::warn!("Failed to build sync report: {}", err);
+                None
+            }
+        })
+    }
+
+    #[tracing::instrument(name = "import", skip_all)]
+    pub fn import_zip_from_path<P: AsRef<Path>>(
+        &self,
+        source: P,
+        app: AppHandle,
+    ) -> Result<(), String> {
+        {
+            let task = self
+                .begin_task("import")?
+                .with_progress(progress_sink(&app));
+            task.run(|task| {
+                let report =
+                    self.import_zip_file(source.as_ref(), task, &mut |phase, percent, message| {
+                        emit_progress(&app, task, phase, percent, 100, message)
+                    })?;
+                emit_sync_complete(&app, task, "导入完成", report);
+                Ok(())
+            })?;
+        }
+        self.rebuild_index_if_enabled();
+        Ok(())
+    }
+
+    fn import_zip_file(
+        &self,
+        source_path: &Path,
+        task: &TaskHandle,
+        on_progress: &mut dyn FnMut(&str, usize, String),
+    ) -> Result<Option<SyncReport>, String> {
+        if !source_path.exists() {
+            return Err("ZIP 文件不存在".to_string());
+        }
+
+        let parent_dir = self
+            .data_dir
+            .parent()
+            .ok_or_else(|| "Invalid data directory".to_string())?;
+
+        let temp_path = parent_dir.join("ArknightsGameData_import.zip");
+        on_progress("导入", 0, "正在复制 ZIP 文件".to_string());
+        fs::copy(source_path, &temp_path).map_err(|e| format!("复制 ZIP 文件失败: {}", e))?;
+
+        on_progress("导入", 30, "正在校验 ZIP 文件".to_string());
+        self.finalize_manual_import(&temp_path, task, on_progress)
+    }
+
+    #[tracing::instrument(name = "import", skip_all)]
+    pub fn import_zip_from_bytes(&self, data: &[u8], app: AppHandle) -> Result<(), String> {
+        {
+            let task = self
+                .begin_task("import")?
+                .with_progress(progress_sink(&app));
+            task.run(|task| {
+                let report = self.import_zip_data(data, task, &mut |phase, percent, message| {
+                    emit_progress(&app, task, phase, percent, 100, message)
+                })?;
+                emit_sync_complete(&app, task, "导入完成", report);
+                Ok(())
+            })?;
+        }
+        self.rebuild_index_if_enabled();
+        Ok(())
+    }
+
+    fn import_zip_data(
+        &self,
+        data: &[u8],
+        task: &TaskHandle,
+        on_progress: &mut dyn FnMut(&str, usize, String),
+    ) -> Result<Option<SyncReport>, String> {
+        let parent_dir = self
+            .data_dir
+            .parent()
+            .ok_or_else(|| "Invalid data directory".to_string())?;
+
+        fs::create_dir_all(parent_dir).map_err(|e| format!("无法创建数据目录: {}", e))?;
+
+        let temp_path = parent_dir.join("ArknightsGameData_import.zip");
+        on_progress("导入", 0, "正在写入 ZIP 数据".to_string());
+        fs::write(&temp_path, data).map_err(|e| format!("写入 ZIP 数据失败: {}", e))?;
+
+        on_progress("导入", 30, "正在校验 ZIP 文件".to_string());
+        self.finalize_manual_import(&temp_path, task, on_progress)
+    }
+
+    /// 将当前安装的游戏数据打包为离线数据包，可直接通过 import_from_zip 导入
+    pub fn export_data_bundle<P: AsRef<Path>>(
+        &self,
+        dest_path: P,
+        app: &AppHandle,
+    ) -> Result<(), String> {
+        let task = self
+            .begin_task("bundle-export")?
+            .with_progress(progress_sink(app));
+        task.run(|task| {
+            self.write_data_bundle(dest_path.as_ref(), &mut |current, total| {
+                emit_progress(
+                    app,
+                    task,
+                    "导出",
+                    current,
+                    total,
+                    format!("已打包 {}/{} 个文件", current, total),
+                )
+            })?;
+            emit_progress(app, task, "完成", 1, 1, "导出完成");
+            Ok(())
+        })
+    }
+
+    /// 只打包当前语言的 story 与 excel 目录，外加 version.json，统一放在单个顶层目录下
+    fn write_data_bundle(
+        &self,
+        dest_path: &Path,
+        on_progress: &mut dyn FnMut(usize, usize),
+    ) -> Result<(), String> {
+        if !self.is_installed() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+
+        let gamedata_dir = self.gamedata_dir();
+        let mut files = Vec::new();
+        for folder in ["excel", "story"] {
+            let dir = gamedata_dir.join(folder);
+            if !dir.exists() {
+                continue;
+            }
+            for entry in walkdir::WalkDir::new(&dir) {
+                let entry = entry.map_err(|e| format!("遍历游戏数据失败: {}", e))?;
+                if entry.file_type().is_file() {
+                    files.push(entry.into_path());
+                }
+            }
+        }
+
+        let dest_file =
+            fs::File::create(dest_path).map_err(|e| format!("创建导出文件失败: {}", e))?;
+        let mut writer = zip::ZipWriter::new(dest_file);
+
+        let total = files.len();
+        for (index, path) in files.iter().enumerate() {
+            let relative = path
+                .strip_prefix(&self.data_dir)
+                .map_err(|e| format!("无效的游戏数据路径: {}", e))?;
+            let name = format!(
+                "ArknightsGameData/{}",
+                relative.to_string_lossy().replace('\\', "/")
+            );
+            let bytes = fs::read(path).map_err(|e| format!("读取 {} 失败: {}", name, e))?;
+            write_zip_entry(&mut writer, &name, &bytes)?;
+            on_progress(index + 1, total);
+        }
+
+        if let Ok(bytes) = fs::read(self.version_file_path()) {
+            write_zip_entry(
+                &mut writer,
+                &format!("ArknightsGameData/{}", VERSION_FILE),
+                &bytes,
+            )?;
+        }
+
+        writer
+            .finish()
+            .map_err(|e| format!("完成导出文件失败: {}", e))?;
+        Ok(())
+    }
+
+    /// 导出应用自身数据（索引数据库、设置等），可选附带游戏数据
+    pub fn export_app_data<P: AsRef<Path>>(
+        &self,
+        dest_path: P,
+        include_game_data: bool,
+    ) -> Result<(), String> {
+        // 同步、重建索引等任务写入期间导出会得到不一致的备份
+        let _task = self.begin_task("app-data-export")?;
+        let app_dir = self.app_data_dir()?;
+
+        // 将 WAL 中的内容落盘，保证导出的数据库文件完整
+        if let Some(conn) = self.try_open_index_connection()? {
+            conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
+                .map_err(|e| format!("Failed to checkpoint story index: {}", e))?;
+        }
+        if self.user_db_path.exists() {
+            self.open_user_connection()?
+                .execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
+                .map_err(|e| format!("Failed to checkpoint user database: {}", e))?;
+        }
+
+        let dest_file =
+            fs::File::create(dest_path.as_ref()).map_err(|e| format!("创建导出文件失败: {}", e))?;
+        let mut writer = zip::ZipWriter::new(dest_file);
+
+        let mut files = Vec::new();
+        for name in APP_DATA_FILES {
+            let path = app_dir.join(name);
+            if !path.is_file() {
+                continue;
+            }
+            let bytes = fs::read(&path).map_err(|e| format!("读取 {} 失败: {}", name, e))?;
+            write_zip_entry(&mut writer, name, &bytes)?;
+            files.push(name.to_string());
+        }
+
+        if include_game_data && self.data_dir.exists() {
+            for entry in walkdir::WalkDir::new(&self.data_dir) {
+                let entry = entry.map_err(|e| format!("遍历游戏数据失败: {}", e))?;
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let relative = entry
+                    .path()
+                    .strip_prefix(app_dir)
+                    .map_err(|e| format!("无效的游戏数据路径: {}", e))?;
+                let name = relative.to_string_lossy().replace('\\', "/");
+                let bytes =
+                    fs::read(entry.path()).map_err(|e| format!("读取 {} 失败: {}", name, e))?;
+                write_zip_entry(&mut writer, &name, &bytes)?;
+            }
+        }
+
+        let manifest = AppDataManifest {
+            format_version: APP_DATA_FORMAT_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            exported_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0),
+            files,
+            includes_game_data: include_game_data && self.data_dir.exists(),
+        };
+        let manifest_json = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+        write_zip_entry(&mut writer, APP_DATA_MANIFEST, &manifest_json)?;
+
+        writer
+            .finish()
+            .map_err(|e| format!("完成导出文件失败: {}", e))?;
+        Ok(())
+    }
+
+    /// 各类数据占用的磁盘空间
+    pub fn get_disk_usage(&self) -> Result<DiskUsage, String> {
+        let app_dir = self.app_data_dir()?;
+        let file_size = |path: &Path| fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+        // SQLite 的 WAL/SHM 文件也算在数据库占用内
+        let database_size = |path: &Path| {
+            ["", "-wal", "-shm"]
+                .iter()
+                .map(|suffix| {
+                    let mut name = path.as_os_str().to_owned();
+                    name.push(suffix);
+                    file_size(Path::new(&name))
+                })
+                .sum::<u64>()
+        };
+        let dir_size = |path: &Path| {
+            walkdir::WalkDir::new(path)
+                .into_iter()
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_file())
+                .filter_map(|entry| entry.metadata().ok())
+                .map(|meta| meta.len())
+                .sum::<u64>()
+        };
+
+        let game_data_bytes = dir_size(&self.data_dir);
+        let index_bytes = database_size(&self.index_db_path);
+        let user_data_bytes = database_size(&self.user_db_path);
+        let log_bytes = dir_size(&app_dir.join(logging::LOG_DIR));
+        Ok(DiskUsage {
+            game_data_bytes,
+            index_bytes,
+            user_data_bytes,
+            log_bytes,
+            total_bytes: game_data_bytes + index_bytes + user_data_bytes + log_bytes,
+        })
+    }
+
+    /// 导出用于问题反馈的诊断包：日志、版本、索引状态、兼容性报告、脱敏后的设置、
+    /// 磁盘占用与最近一次调试搜索。默认不含剧情正文与阅读记录，
+    /// include_reading_progress 为 true 时附带阅读进度表
+    pub fn export_diagnostics<P: AsRef<Path>>(
+        &self,
+        dest_path: P,
+        include_reading_progress: bool,
+    ) -> Result<DiagnosticsExport, String> {
+        let dest_path = dest_path.as_ref();
+        let dest_file =
+            fs::File::create(dest_path).map_err(|e| format!("创建诊断文件失败: {}", e))?;
+        let mut writer = zip::ZipWriter::new(dest_file);
+        let mut files = Vec::new();
+        // 单项收集失败时记录错误而不是中止导出，错误本身也是诊断信息
+        let mut add_json = |name: &str, value: Result<Value, String>| -> Result<(), String> {
+            let value = value.unwrap_or_else(|err| serde_json::json!({ "error": err }));
+            let bytes = serde_json::to_vec_pretty(&value)
+                .map_err(|e| format!("Failed to serialize {}: {}", name, e))?;
+            write_zip_entry(&mut writer, name, &bytes)?;
+            files.push(name.to_string());
+            Ok(())
+        };
+        fn to_json<T: serde::Serialize>(value: Result<T, String>) -> Result<Value, String> {
+            value.and_then(|v| serde_json::to_value(v).map_err(|e| e.to_string()))
+        }
+
+        add_json(
+            "logs.json",
+            to_json(logging::recent_logs(DIAGNOSTICS_LOG_LINES)),
+        )?;
+        add_json(
+            "version.json",
+            match fs::read_to_string(self.version_file_path()) {
+                Ok(content) => serde_json::from_str(&content).map_err(|e| e.to_string()),
+                Err(err) => Err(format!("version.json 不可用: {}", err)),
+            },
+        )?;
+        add_json("index_status.json", to_json(self.get_story_index_status()))?;
+        add_json("compat_report.json", to_json(self.get_data_compat_report()))?;
+        add_json("settings.json", self.redacted_settings())?;
+        add_json("disk_usage.json", to_json(self.get_disk_usage()))?;
+
+        let last_search = self
+            .last_search_debug
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+        if let Some(last) = last_search {
+            // 只保留命中的剧情 id 与名称，matched_text 含剧情正文
+            let results: Vec<Value> = last
+                .response
+                .results
+                .iter()
+                .map(|result| {
+                    serde_json::json!({
+                        "storyId": result.story_id,
+                        "storyName": result.story_name,
+                        "category": result.category,
+                    })
+                })
+                .collect();
+            add_json(
+                "last_search_debug.json",
+                Ok(serde_json::json!({
+                    "query": last.query,
+                    "resultCount": results.len(),
+                    "results": results,
+                    "logs": last.response.logs,
+                })),
+            )?;
+        }
+        if include_reading_progress {
+            add_json(
+                "reading_progress.json",
+                to_json(self.get_all_reading_progress()),
+            )?;
+        }
+
+        let manifest = DiagnosticsManifest {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            exported_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0),
+            data_source: self.data_source().id,
+            files,
+            includes_reading_progress: include_reading_progress,
+        };
+        let manifest_json = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+        write_zip_entry(&mut writer, APP_DATA_MANIFEST, &manifest_json)?;
+        writer
+            .finish()
+            .map_err(|e| format!("完成诊断文件失败: {}", e))?;
+
+        let size = fs::metadata(dest_path)
+            .map_err(|e| format!("读取诊断文件失败: {}", e))?
+            .len();
+        Ok(DiagnosticsExport {
+            path: dest_path.to_string_lossy().into_owned(),
+            size,
+        })
+    }
+
+    /// 原样读取 settings.json（保留本版本不认识的字段），并遮蔽令牌、密码等敏感值
+    fn redacted_settings(&self) -> Result<Value, String> {
+        let mut value = match fs::read_to_string(self.settings.path()) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("settings.json 无法解析: {}", e))?,
+            Err(_) => serde_json::to_value(self.settings.get()).map_err(|e| e.to_string())?,
+        };
+        redact_json(&mut value);
+        Ok(value)
+    }
+
+    /// 从 export_app_data 生成的压缩包恢复应用数据
+    pub fn import_app_data<P: AsRef<Path>>(&self, src_path: P) -> Result<(), String> {
+        let _task = self.begin_task("app-data-import")?;
+        let app_dir = self.app_data_dir()?;
+
+        let file =
+            fs::File::open(src_path.as_ref()).map_err(|e| format!("打开备份文件失败: {}", e))?;
+        let mut archive =
+            ZipArchive::new(file).map_err(|e| format!("备份文件不是有效的 ZIP: {}", e))?;
+
+        let manifest: AppDataManifest = {
+            let entry = archive
+                .by_name(APP_DATA_MANIFEST)
+                .map_err(|_| "备份文件缺少清单，无法导入".to_string())?;
+            serde_json::from_reader(entry).map_err(|e| format!("备份清单无效: {}", e))?
+        };
+        if manifest.format_version > APP_DATA_FORMAT_VERSION {
+            return Err(format!(
+                "备份格式版本 {} 高于当前支持的 {}，请升级应用后再导入",
+                manifest.format_version, APP_DATA_FORMAT_VERSION
+            ));
+        }
+
+        // 先解压到临时目录，全部成功后再替换，避免半途失败破坏现有数据
+        let staging = app_dir.join("app_data_import");
+        if staging.exists() {
+            fs::remove_dir_all(&staging).map_err(|e| format!("清理临时目录失败: {}", e))?;
+        }
+        fs::create_dir_all(&staging).map_err(|e| format!("创建临时目录失败: {}", e))?;
+
+        let game_dir_name = self
+            .data_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let result = (|| {
+            for i in 0..archive.len() {
+                let mut entry = archive
+                    .by_index(i)
+                    .map_err(|e| format!("读取备份条目失败: {}", e))?;
+                let Some(relative) = entry.enclosed_name().map(|p| p.to_owned()) else {
+                    continue;
+                };
+                let name = relative.to_string_lossy().replace('\\', "/");
+                let is_app_file = APP_DATA_FILES.contains(&name.as_str());
+                let is_game_file =
+                    manifest.includes_game_data && name.starts_with(&format!("{}/", game_dir_name));
+                if entry.is_dir() || !(is_app_file || is_game_file) {
+                    continue;
+                }
+                let out_path = staging.join(&relative);
+                if let Some(parent) = out_path.parent() {
+                    fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+                }
+                let mut out = fs::File::create(&out_path)
+                    .map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+                std::io::copy(&mut entry, &mut out)
+                    .map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+            }
+            Ok::<(), String>(())
+        })();
+        if let Err(err) = result {
+            fs::remove_dir_all(&staging).ok();
+            return Err(err);
+        }
+
+        for name in APP_DATA_FILES {
+            let staged = staging.join(name);
+            if !staged.is_file() {
+                continue;
+            }
+            let target = app_dir.join(name);
+            if *name == INDEX_DB_FILE || *name == USER_DB_FILE {
+                // 清理旧的 WAL/SHM，避免与恢复的数据库不一致
+                for suffix in ["-wal", "-shm"] {
+                    fs::remove_file(app_dir.join(format!("{}{}", name, suffix))).ok();
+                }
+            }
+            fs::copy(&staged, &target).map_err(|e| format!("恢复 {} 失败: {}", name, e))?;
+        }
+        self.settings.reload();
+
+        let staged_game = staging.join(&game_dir_name);
+        if manifest.includes_game_data && staged_game.is_dir() {
+            if self.data_dir.exists() {
+                fs::remove_dir_all(&self.data_dir)
+                    .map_err(|e| format!("Failed to remove old data: {}", e))?;
+            }
+            if fs::rename(&staged_game, &self.data_dir).is_err() {
+                copy_dir_all(&staged_game, &self.data_dir)?;
+            }
+            self.invalidate_caches();
+        }
+
+        fs::remove_dir_all(&staging).ok();
+        Ok(())
+    }
+
+    fn app_data_dir(&self) -> Result<&Path, String> {
+        self.data_dir
+            .parent()
+            .ok_or_else(|| "Invalid data directory".to_string())
+    }
+
+    fn version_file_path(&self) -> PathBuf {
+        self.data_dir.join(VERSION_FILE)
+    }
+
+    fn read_version(&self) -> Option<VersionInfo> {
+        let path = self.version_file_path();
+        if !path.exists() {
+            return None;
+        }
+        let content = fs::read_to_string(&path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn write_version(&self, info: &VersionInfo) -> Result<(), String> {
+        if !self.data_dir.exists() {
+            fs::create_dir_all(&self.data_dir)
+                .map_err(|e| format!("Failed to create data directory: {}", e))?;
+        }
+        let path = self.version_file_path();
+        let content = serde_json::to_string_pretty(info)
+            .map_err(|e| format!("Failed to serialize version info: {}", e))?;
+        fs::write(&path, content).map_err(|e| format!("Failed to write version info: {}", e))
+    }
+}
+
+fn short_commit(commit: &str) -> &str {
+    commit.get(..7).unwrap_or(commit)
+}
+
+/// 格式化时间戳
+fn format_timestamp(timestamp: i64) -> String {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    let duration = Duration::from_secs(timestamp as u64);
+    let datetime = UNIX_EPOCH + duration;
+
+    if let Ok(elapsed) = SystemTime::now().duration_since(datetime) {
+        let days = elapsed.as_secs() / 86400;
+        if days == 0 {
+            let hours = elapsed.as_secs() / 3600;
+            if hours == 0 {
+                let mins = elapsed.as_secs() / 60;
+                return format!("{}分钟前", mins.max(1));
+            }
+            return format!("{}小时前", hours);
+        } else if days < 30 {
+            return format!("{}天前", days);
+        }
+    }
+
+    "较早前".to_string()
+}
+
+impl DataService {
+    /// 获取所有章节
+    pub fn get_chapters(&self) -> Result<Vec<Chapter>, String> {
+        if !self.is_installed() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+        let table = self.load_table("chapter_table.json")?;
+        let (mut chapters, _) = parse_chapters(&table)?;
+        chapters.sort_by_key(|c| c.chapter_index);
+
+        Ok(chapters)
+    }
+
+    /// 统计 story_review_table 中需要宽松解析或无法解析的条目，便于排查「更新后剧情消失」
+    pub fn get_data_compat_report(&self) -> Result<DataCompatReport, String> {
+        if !self.is_installed() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+
+        let table = self.load_table("story_review_table.json")?;
+        let data = table_entries(&table, "story_review_table.json")?;
+
+        let mut report = DataCompatReport::default();
+        for (entry_id, value) in data.iter() {
+            let Some(unlock_datas) = value.get("infoUnlockDatas").and_then(|v| v.as_array()) else {
+                continue;
+            };
+            for unlock_data in unlock_datas {
+                report.total_entries += 1;
+                match classify_story_entry(unlock_data) {
+                    StoryEntryParse::Strict(_) => report.strict_entries += 1,
+                    StoryEntryParse::Lenient(story, _) => {
+                        report.lenient_ids.push(story.story_id);
+                    }
+                    StoryEntryParse::Failed(_) => {
+                        let story_id = unlock_data
+                            .get("storyId")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("<unknown>");
+                        report.failed_ids.push(format!("{}/{}", entry_id, story_id));
+                    }
+                }
+            }
+        }
+        report.lenient_ids.sort();
+        report.failed_ids.sort();
+        report.lenient_entries = report.lenient_ids.len();
+        report.failed_entries = report.failed_ids.len();
+
+        if let Some(chapter_table) = self.read_optional_table("chapter_table.json")? {
+            let (_, mut skipped) = parse_chapters(&chapter_table)?;
+            skipped.sort();
+            report.skipped_chapters = skipped.len();
+            report.skipped_chapter_ids = skipped;
+        }
+        Ok(report)
+    }
+
+    /// 获取所有活动（按开始时间排序，时间缺失的放在末尾）
+    pub fn get_activities(&self) -> Result<Vec<Activity>, String> {
+        if !self.is_installed() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+        let table = self.load_table("story_review_table.json")?;
+        let data = table_entries(&table, "story_review_table.json")?;
+
+        let mut activities: Vec<Activity> = data
+            .iter()
+            .filter(|(_, value)| {
+                value.get("entryType").and_then(|v| v.as_str()) == Some("ACTIVITY")
+            })
+            .map(|(key, value)| parse_activity(key, value))
+            .collect();
+
+        activities.sort_by(|a, b| {
+            let start = |activity: &Activity| {
+                if activity.start_time <= 0 {
+                    i64::MAX
+                } else {
+                    activity.start_time
+                }
+            };
+            match start(a).cmp(&start(b)) {
+                Ordering::Equal => compare_story_group_ids(&a.id, &b.id),
+                other => other,
+            }
+        });
+
+        Ok(activities)
+    }
+
+    /// 「梳理时间线」：主线、支线、活动与密录按开放时间混排，并按年份分组；
+    /// 无开放时间的条目按 id 排序放在最后（year 为 None）。复刻活动并入原版，位置取原版的开放时间
+    pub fn get_stories_chronological(&self) -> Result<Vec<ChronologyYear>, String> {
+        if !self.is_installed() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+        let table = self.load_table("story_review_table.json")?;
+        let data = table_entries(&table, "story_review_table.json")?;
+
+        let mut entries: Vec<Activity> = data
+            .iter()
+            .filter(|(_, value)| {
+                matches!(
+                    value.get("entryType").and_then(|v| v.as_str()),
+                    Some("MAINLINE" | "SIDESTORY" | "ACTIVITY" | "MINI_ACTIVITY" | "NONE")
+                )
+            })
+            .map(|(key, value)| {
+                let mut entry = parse_activity(key, value);
+                if entry.name.trim().is_empty() {
+                    entry.name = Self::resolve_category_name(&entry.entry_type, key, value);
+                }
+                entry
+            })
+            .filter(|entry| !entry.info_unlock_datas.is_empty())
+            .collect();
+
+        entries.sort_by(|a, b| {
+            let start = |entry: &Activity| {
+                if entry.start_time <= 0 {
+                    i64::MAX
+                } else {
+                    entry.start_time
+                }
+            };
+            match start(a).cmp(&start(b)) {
+                Ordering::Equal => compare_story_group_ids(&a.id, &b.id),
+                other => other,
+            }
+        });
+
+        // 复刻判定与 get_activity_stories_grouped 一致：剧情 id 集合相同，或去掉「复刻」后缀后名称相同
+        let mut merged: Vec<Activity> = Vec::new();
+        let mut by_name: HashMap<String, usize> = HashMap::new();
+        let mut by_story_set: HashMap<Vec<String>, usize> = HashMap::new();
+        for candidate in entries {
+            let base_name = strip_rerun_suffix(&candidate.name).to_string();
+            let mut story_set: Vec<String> = candidate
+                .info_unlock_datas
+                .iter()
+                .map(|s| s.story_id.clone())
+                .collect();
+            story_set.sort();
+
+            let existing = by_story_set
+                .get(&story_set)
+                .or_else(|| by_name.get(&base_name))
+                .copied();
+            let Some(index) = existing else {
+                by_name.insert(base_name, merged.len());
+                by_story_set.insert(story_set, merged.len());
+                merged.push(candidate);
+                continue;
+            };
+
+            let entry = &mut merged[index];
+            // 开放时间缺失时复刻可能排在原版之前，此时改用原版，位置仍取较早的开放时间
+            if entry.is_rerun && !candidate.is_rerun {
+                let start_time = entry.start_time;
+                let stories = std::mem::take(&mut entry.info_unlock_datas);
+                *entry = candidate;
+                if start_time > 0 {
+                    entry.start_time = start_time;
+                }
+                entry.info_unlock_datas.extend(stories);
+            } else {
+                entry.info_unlock_datas.extend(candidate.info_unlock_datas);
+            }
+            let mut seen = HashSet::new();
+            entry.info_unlock_datas.retain(|story| {
+                seen.insert(story.story_id.clone())
+                    && seen.insert(story.story_txt.to_ascii_lowercase())
+            });
+        }
+
+        let mut years: Vec<ChronologyYear> = Vec::new();
+        for entry in merged {
+            let year = (entry.start_time > 0).then(|| timestamp_year(entry.start_time));
+            match years.last_mut() {
+                Some(bucket) if bucket.year == year => bucket.entries.push(entry),
+                _ => years.push(ChronologyYear {
+                    year,
+                    entries: vec![entry],
+                }),
+            }
+        }
+        Ok(years)
+    }
+
+    /// 按名称、代号（appellation）与标签搜索干员，结果按命中质量排序：
+    /// 完全一致 > 前缀 > 包含 > 标签，同级按星级降序；召唤物与装置仅在 include_nonplayable 时返回
+    pub fn search_characters(
+        &self,
+        query: &str,
+        include_nonplayable: bool,
+    ) -> Result<Vec<CharacterMatch>, String> {
+        let query = normalize_character_name(query);
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut matches: Vec<CharacterMatch> = self
+            .character_roster(include_nonplayable)?
+            .into_iter()
+            .filter_map(|summary| {
+                let match_kind = [Some(&summary.name), summary.appellation.as_ref()]
+                    .into_iter()
+                    .flatten()
+                    .map(|candidate| normalize_character_name(candidate))
+                    .filter_map(|candidate| {
+                        if candidate == query {
+                            Some(CharacterMatchKind::Exact)
+                        } else if candidate.starts_with(&query) {
+                            Some(CharacterMatchKind::Prefix)
+                        } else if candidate.contains(&query) {
+                            Some(CharacterMatchKind::Substring)
+                        } else {
+                            None
+                        }
+                    })
+                    .min()
+                    .or_else(|| {
+                        summary
+                            .tags
+                            .iter()
+                            .any(|tag| normalize_character_name(tag).contains(&query))
+                            .then_some(CharacterMatchKind::Tag)
+                    })?;
+                Some(CharacterMatch {
+                    summary,
+                    match_kind,
+                })
+            })
+            .collect();
+        matches.sort_by(|a, b| {
+            a.match_kind
+                .cmp(&b.match_kind)
+                .then_with(|| b.summary.rarity.cmp(&a.summary.rarity))
+                .then_with(|| a.summary.char_id.cmp(&b.summary.char_id))
+        });
+        matches.truncate(CHARACTER_SEARCH_LIMIT);
+        Ok(matches)
+    }
+
+    /// 按名称精确定位干员：依次尝试名称完全一致、代号忽略大小写一致、规范化（NFKC、小写）后一致，
+    /// 同名时优先原版形态。找不到时返回 `NOT_FOUND:干员「名称」:` 加最多 5 个前缀匹配的干员 id（逗号分隔）
+    pub fn get_character_by_name(&self, name: &str) -> Result<CharacterMatch, String> {
+        let name = name.trim();
+        let matches = self.search_characters(name, false)?;
+        let pick = |hit: &dyn Fn(&CharacterMatch) -> bool| {
+            matches
+                .iter()
+                .filter(|m| hit(m))
+                .min_by(|a, b| {
+                    a.summary
+                        .base_char_id
+                        .is_some()
+                        .cmp(&b.summary.base_char_id.is_some())
+                        .then_with(|| a.summary.char_id.cmp(&b.summary.char_id))
+                })
+                .cloned()
+        };
+        let lowered = name.to_lowercase();
+        let found = pick(&|m| m.summary.name == name)
+            .or_else(|| {
+                pick(&|m| {
+                    m.summary
+                        .appellation
+                        .as_deref()
+                        .is_some_and(|a| a.to_lowercase() == lowered)
+                })
+            })
+            .or_else(|| pick(&|m| m.match_kind == CharacterMatchKind::Exact));
+        found.ok_or_else(|| {
+            let candidates: Vec<&str> = matches
+                .iter()
+                .filter(|m| m.match_kind == CharacterMatchKind::Prefix)
+                .take(5)
+                .map(|m| m.summary.char_id.as_str())
+                .collect();
+            format!("NOT_FOUND:干员「{}」:{}", name, candidates.join(","))
+        })
+    }
+
+    /// 干员（含其他形态）的名称，干员不存在时返回 None
+    pub fn get_character_name(&self, char_id: &str) -> Result<Option<String>, String> {
+        if self.read_optional_table("character_table.json")?.is_none() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+        Ok(self.character_entry(char_id).ok().map(|character| {
+            character
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or(char_id)
+                .to_string()
+        }))
+    }
+
+    /// 干员条目：先查 character_table，再查 char_patch_table.patchChars（阿米娅近卫等其他形态），
+    /// 其他形态的条目合并在原版条目之上（见 merge_patch_character）
+    fn character_entry(&self, char_id: &str) -> Result<Value, String> {
+        let characters = self
+            .read_optional_table("character_table.json")?
+            .ok_or_else(|| "character_table.json is missing".to_string())?;
+        if let Some(character) = characters.get(char_id) {
+            return Ok(character.clone());
+        }
+        let patch = self.read_optional_table("char_patch_table.json")?;
+        let patch = patch.as_deref();
+        let entry = patch
+            .and_then(|patch| patch.get("patchChars"))
+            .and_then(|chars| chars.get(char_id))
+            .ok_or_else(|| format!("Unknown character: {}", char_id))?;
+        let base = patch
+            .map(character_form_groups)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|(_, alternates)| alternates.iter().any(|id| id == char_id))
+            .and_then(|(base_id, _)| characters.get(&base_id).cloned());
+        Ok(match base {
+            Some(base) => merge_patch_character(base, entry),
+            None => entry.clone(),
+        })
+    }
+
+    /// handbook_team_table 中的全部势力，按 orderNum 排序，附带所属干员数
+    pub fn get_all_factions(&self) -> Result<Vec<TeamPowerInfo>, String> {
+        let Some(teams) = self.read_optional_table("handbook_team_table.json")? else {
+            return Ok(Vec::new());
+        };
+        let characters = self.read_optional_table("character_table.json")?;
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for (_, character) in characters
+            .as_deref()
+            .into_iter()
+            .flat_map(|table| roster_characters(table, false))
+        {
+            let ids: HashSet<&str> = ["nationId", "groupId", "teamId"]
+                .iter()
+                .filter_map(|key| character.get(*key).and_then(|v| v.as_str()))
+                .collect();
+            for id in ids {
+                *counts.entry(id).or_insert(0) += 1;
+            }
+        }
+
+        let text = |value: &Value, key: &str| {
+            value
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        let mut factions: Vec<TeamPowerInfo> = table_entries(&teams, "handbook_team_table.json")?
+            .iter()
+            .map(|(id, team)| TeamPowerInfo {
+                power_name: text(team, "powerName").unwrap_or_else(|| id.clone()),
+                power_code: text(team, "powerCode"),
+                power_level: team.get("powerLevel").and_then(|v| v.as_i64()).unwrap_or(0),
+                color: text(team, "color"),
+                order_num: team
+                    .get("orderNum")
+                    .and_then(|v| v.as_i64())
+                    .unwrap_or(i64::MAX),
+                is_limited: team
+                    .get("isLimited")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false),
+                operator_count: counts.get(id.as_str()).copied().unwrap_or(0),
+                power_id: id.clone(),
+            })
+            .collect();
+        factions.sort_by(|a, b| {
+            a.order_num
+                .cmp(&b.order_num)
+                .then_with(|| a.power_id.cmp(&b.power_id))
+        });
+        Ok(factions)
+    }
+
+    /// uniequip_table.subProfDict 中的全部职业分支，按职业序号排序，附带所属干员数
+    pub fn get_all_sub_professions(&self) -> Result<Vec<SubProfessionInfo>, String> {
+        let Some(sub_profs) = self
+            .read_optional_table("uniequip_table.json")?
+            .as_deref()
+            .and_then(|table| table.get("subProfDict"))
+            .and_then(|dict| dict.as_object())
+            .cloned()
+        else {
+            return Ok(Vec::new());
+        };
+        let characters = self.read_optional_table("character_table.json")?;
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for (_, character) in characters
+            .as_deref()
+            .into_iter()
+            .flat_map(|table| roster_characters(table, false))
+        {
+            if let Some(id) = character.get("subProfessionId").and_then(|v| v.as_str()) {
+                *counts.entry(id).or_insert(0) += 1;
+            }
+        }
+
+        let mut result: Vec<SubProfessionInfo> = sub_profs
+            .iter()
+            .map(|(id, sub_prof)| SubProfessionInfo {
+                sub_profession_name: sub_prof
+                    .get("subProfessionName")
+                    .and_then(|v| v.as_str())
+                    .map_or_else(|| id.clone(), str::to_string),
+                category: sub_prof
+                    .get("subProfessionCatagory")
+                    .and_then(|v| v.as_i64()),
+                operator_count: counts.get(id.as_str()).copied().unwrap_or(0),
+                sub_profession_id: id.clone(),
+            })
+            .collect();
+        result.sort_by(|a, b| {
+            a.category
+                .unwrap_or(i64::MAX)
+                .cmp(&b.category.unwrap_or(i64::MAX))
+                .then_with(|| a.sub_profession_id.cmp(&b.sub_profession_id))
+        });
+        Ok(result)
+    }
+
+    /// 干员总览：按星级、职业、分支、国家计数，并关联模组、密录与皮肤；
+    /// 关联用的表缺失时只省略该项统计
+    pub fn get_roster_stats(&self) -> Result<RosterStats, String> {
+        let characters = self.read_optional_table("character_table.json")?;
+        let roster: Vec<(&String, &Value)> = characters
+            .as_deref()
+            .into_iter()
+            .flat_map(|table| roster_characters(table, false))
+            .collect();
+        let ids: HashSet<&str> = roster.iter().map(|(id, _)| id.as_str()).collect();
+
+        fn tally(keys: impl Iterator<Item = String>) -> Vec<RosterCount> {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for key in keys {
+                *counts.entry(key).or_insert(0) += 1;
+            }
+            let mut result: Vec<RosterCount> = counts
+                .into_iter()
+                .map(|(key, count)| RosterCount { key, count })
+                .collect();
+            result.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+            result
+        }
+        let field = |key: &'static str| {
+            roster.iter().filter_map(move |(_, character)| {
+                character
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+            })
+        };
+
+        let mut by_rarity = tally(
+            roster
+                .iter()
+                .map(|(_, character)| parse_rarity(character.get("rarity")).to_string()),
+        );
+        by_rarity.sort_by(|a, b| b.key.cmp(&a.key));
+
+        let with_modules = self
+            .read_optional_table("uniequip_table.json")?
+            .as_deref()
+            .and_then(|table| table.get("charEquip"))
+            .and_then(|v| v.as_object())
+            .map(|char_equip| {
+                char_equip
+                    .iter()
+                    .filter(|(id, equips)| {
+                        ids.contains(id.as_str())
+                            && equips.as_array().is_some_and(|list| list.len() > 1)
+                    })
+                    .count()
+            });
+        let with_memories = self
+            .read_optional_table("handbook_info_table.json")?
+            .map(|handbook| {
+                memory_owner_map(&handbook)
+                    .into_values()
+                    .filter(|id| ids.contains(id.as_str()))
+                    .collect::<HashSet<_>>()
+                    .len()
+            });
+        // 默认立绘（#1）与精二立绘（#2）之外的皮肤 id 带有 @
+        let with_extra_skins = self
+            .read_optional_table("skin_table.json")?
+            .as_deref()
+            .and_then(|table| table.get("charSkins"))
+            .and_then(|v| v.as_object())
+            .map(|skins| {
+                skins
+                    .iter()
+                    .filter(|(skin_id, _)| skin_id.contains('@'))
+                    .filter_map(|(_, skin)| skin.get("charId").and_then(|v| v.as_str()))
+                    .filter(|id| ids.contains(id))
+                    .collect::<HashSet<_>>()
+                    .len()
+            });
+
+        Ok(RosterStats {
+            total: roster.len(),
+            by_rarity,
+            by_profession: tally(field("profession")),
+            by_sub_profession: tally(field("subProfessionId")),
+            by_nation: tally(field("nationId")),
+            with_modules,
+            with_memories,
+            with_extra_skins,
+        })
+    }
+
+    /// 干员的全部形态：原版在前，其余按 char_patch_table.infos.tmplIds 顺序；
+    /// 传入其他形态的 id 时同样返回整组，无其他形态时只返回自身
+    pub fn get_character_forms(&self, char_id: &str) -> Result<Vec<CharacterForm>, String> {
+        let form_groups = self
+            .read_optional_table("char_patch_table.json")?
+            .as_deref()
+            .map(character_form_groups)
+            .unwrap_or_default();
+        let (base_id, alternates) = form_groups
+            .iter()
+            .find(|(base, alternates)| {
+                base.as_str() == char_id || alternates.iter().any(|id| id == char_id)
+            })
+            .map(|(base, alternates)| (base.clone(), alternates.clone()))
+            .unwrap_or_else(|| (char_id.to_string(), Vec::new()));
+        let skills_table = self.read_optional_table("skill_table.json")?;
+
+        std::iter::once(base_id.clone())
+            .chain(alternates)
+            .map(|form_id| {
+                let character = self.character_entry(&form_id)?;
+                let text = |key: &str| {
+                    character
+                        .get(key)
+                        .and_then(|v| v.as_str())
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(str::to_string)
+                };
+                let skills = character
+                    .get("skills")
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|skill| skill.get("skillId")?.as_str())
+                    .map(|skill_id| SkillInfo {
+                        skill_id: skill_id.to_string(),
+                        skill_name: skills_table
+                            .as_deref()
+                            .and_then(|table| table.get(skill_id))
+                            .and_then(|s| s.get("levels"))
+                            .and_then(|levels| levels.get(0))
+                            .and_then(|level| level.get("name"))
+                            .and_then(|v| v.as_str())
+                            .map(str::to_string),
+                    })
+                    .collect();
+                Ok(CharacterForm {
+                    name: text("name").unwrap_or_else(|| form_id.clone()),
+                    profession: text("profession"),
+                    is_base: form_id == base_id,
+                    skills,
+                    talents: parse_talents(&character),
+                    char_id: form_id,
+                })
+            })
+            .collect()
+    }
+
+    /// 干员各精英阶段首尾等级的属性（E0 1 级、E0 满级、E1 满级、E2 满级等）
+    pub fn get_character_attributes(&self, char_id: &str) -> Result<CharacterAttributes, String> {
+        let character = self.character_entry(char_id)?;
+        Ok(CharacterAttributes {
+            char_id: char_id.to_string(),
+            phases: parse_phase_attributes(&character),
+        })
+    }
+
+    /// range_table 中的攻击范围格子
+    pub fn get_range(&self, range_id: &str) -> Result<RangeGrid, String> {
+        let ranges = self
+            .read_optional_table("range_table.json")?
+            .ok_or_else(|| "range_table.json is missing".to_string())?;
+        parse_range_grid(&ranges, range_id).ok_or_else(|| format!("Unknown range: {}", range_id))
+    }
+
+    /// 干员各精英阶段的默认攻击范围，以及技能各等级中改写的范围（同一技能相同范围只列一次）
+    pub fn get_character_ranges(&self, char_id: &str) -> Result<CharacterRanges, String> {
+        let character = self.character_entry(char_id)?;
+        let ranges = self
+            .read_optional_table("range_table.json")?
+            .ok_or_else(|| "range_table.json is missing".to_string())?;
+        let skills_table = self.read_optional_table("skill_table.json")?;
+
+        let phases = character
+            .get("phases")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .map(|(phase, entry)| PhaseRange {
+                phase,
+                range: entry
+                    .get("rangeId")
+                    .and_then(|v| v.as_str())
+                    .and_then(|range_id| parse_range_grid(&ranges, range_id)),
+            })
+            .collect();
+
+        let mut skills = Vec::new();
+        let skill_ids = character
+            .get("skills")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|skill| skill.get("skillId").and_then(|v| v.as_str()));
+        for skill_id in skill_ids {
+            let Some(skill) = skills_table.as_deref().and_then(|t| t.get(skill_id)) else {
+                continue;
+            };
+            let levels = skill.get("levels").and_then(|v| v.as_array());
+            let skill_name = levels
+                .and_then(|levels| levels.first())
+                .and_then(|level| level.get("name"))
+                .and_then(|v| v.as_str())
+                .map(str::to_string);
+            let mut seen = HashSet::new();
+            for (index, level) in levels.into_iter().flatten().enumerate() {
+                let Some(range_id) = level.get("rangeId").and_then(|v| v.as_str()) else {
+                    continue;
+                };
+                if !seen.insert(range_id) {
+                    continue;
+                }
+                match parse_range_grid(&ranges, range_id) {
+                    Some(range) => skills.push(SkillRange {
+                        skill_id: skill_id.to_string(),
+                        skill_name: skill_name.clone(),
+                        from_level: index + 1,
+                        range,
+                    }),
+                    None => {
+                        tracing::debug!("Skip unknown range {} of skill {}", range_id, skill_id)
+                    }
+                }
+            }
+        }
+
+        Ok(CharacterRanges {
+            char_id: char_id.to_string(),
+            phases,
+            skills,
+        })
+    }
+
+    /// 干员的模组、解锁条件及各等级的实际效果：uniequip_table.charEquip 列出模组，
+    /// battle_equip_table 提供各等级数据；原始模组或缺少战斗数据的模组 stages 为空
+    pub fn get_character_module_details(
+        &self,
+        char_id: &str,
+    ) -> Result<Vec<CharacterModule>, String> {
+        let Some(uniequip) = self.read_optional_table("uniequip_table.json")? else {
+            return Ok(Vec::new());
+        };
+        let battle_equip = self.read_optional_table("battle_equip_table.json")?;
+        let items = self.read_optional_table("item_table.json")?;
+        let module_ids = uniequip
+            .get("charEquip")
+            .and_then(|c| c.get(char_id))
+            .and_then(|v| v.as_array());
+
+        Ok(module_ids
+            .into_iter()
+            .flatten()
+            .filter_map(|id| id.as_str())
+            .map(|module_id| {
+                let equip = uniequip.get("equipDict").and_then(|d| d.get(module_id));
+                let field = |key: &str| {
+                    equip
+                        .and_then(|e| e.get(key))
+                        .and_then(|v| v.as_str())
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                };
+                let type_name1 = field("typeName1").unwrap_or("ORIGINAL");
+                let is_original = type_name1.eq_ignore_ascii_case("ORIGINAL");
+                let type_name = match field("typeName2") {
+                    Some(suffix) if !is_original => format!("{}-{}", type_name1, suffix),
+                    _ => type_name1.to_string(),
+                };
+                let stages = battle_equip
+                    .as_deref()
+                    .and_then(|table| table.get(module_id))
+                    .map(parse_module_stages)
+                    .unwrap_or_default();
+                let unlock = equip
+                    .filter(|_| !is_original)
+                    .map(|equip| parse_module_unlock(&uniequip, equip, items.as_deref()));
+                CharacterModule {
+                    module_id: module_id.to_string(),
+                    name: field("uniEquipName").unwrap_or(module_id).to_string(),
+                    type_name,
+                    is_original,
+                    stages,
+                    unlock,
+                }
+            })
+            .collect())
+    }
+
+    /// 干员的精英化（phases[].evolveCost）、技能 2-7 级（allSkillLvlup）与
+    /// 各技能专精（skills[].levelUpCostCond）消耗，物品名称经 item_table 解析
+    pub fn get_character_upgrade_costs(
+        &self,
+        char_id: &str,
+    ) -> Result<CharacterUpgradeCosts, String> {
+        let character = self.character_entry(char_id)?;
+        let items = self.read_optional_table("item_table.json")?;
+        let items = items.as_deref();
+        let skills_table = self.read_optional_table("skill_table.json")?;
+        fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+            value
+                .get(key)
+                .and_then(|v| v.as_array())
+                .map_or(&[], Vec::as_slice)
+        }
+
+        let elite = array(&character, "phases")
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(phase, entry)| LevelCost {
+                level: phase as i32,
+                costs: parse_item_costs(entry.get("evolveCost"), items),
+            })
+            .collect();
+        let skill_levels = array(&character, "allSkillLvlup")
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| LevelCost {
+                level: index as i32 + 2,
+                costs: parse_item_costs(entry.get("lvlUpCost"), items),
+            })
+            .collect();
+        let masteries = array(&character, "skills")
+            .iter()
+            .filter_map(|skill| {
+                let skill_id = skill.get("skillId")?.as_str()?;
+                let skill_name = skills_table
+                    .as_deref()
+                    .and_then(|table| table.get(skill_id))
+                    .and_then(|s| s.get("levels"))
+                    .and_then(|levels| levels.get(0))
+                    .and_then(|level| level.get("name"))
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string);
+                let masteries = array(skill, "levelUpCostCond")
+                    .iter()
+                    .enumerate()
+                    .map(|(index, cond)| LevelCost {
+                        level: index as i32 + 1,
+                        costs: parse_item_costs(cond.get("levelUpCost"), items),
+                    })
+                    .collect();
+                Some(SkillMasteryCost {
+                    skill_id: skill_id.to_string(),
+                    skill_name,
+                    masteries,
+                })
+            })
+            .collect();
+
+        Ok(CharacterUpgradeCosts {
+            char_id: char_id.to_string(),
+            elite,
+            skill_levels,
+            masteries,
+        })
+    }
+
+    /// 干员的全部立绘，按 displaySkin.sortId 排序，缺少 sortId 的按 getTime 排在其后
+    pub fn get_character_skins(&self, char_id: &str) -> Result<Vec<SkinInfo>, String> {
+        let Some(skin_table) = self.read_optional_table("skin_table.json")? else {
+            return Ok(Vec::new());
+        };
+        let text = |value: Option<&Value>, key: &str| {
+            value
+                .and_then(|v| v.get(key))
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        let mut skins: Vec<SkinInfo> = skin_table
+            .get("charSkins")
+            .and_then(|v| v.as_object())
+            .into_iter()
+            .flatten()
+            .filter(|(_, skin)| skin.get("charId").and_then(|v| v.as_str()) == Some(char_id))
+            .map(|(skin_id, skin)| {
+                let display = skin.get("displaySkin");
+                SkinInfo {
+                    skin_id: skin_id.clone(),
+                    skin_name: text(display, "skinName"),
+                    skin_group_id: text(display, "skinGroupId")
+                        .or_else(|| text(Some(skin), "skinGroupId")),
+                    skin_group_name: text(display, "skinGroupName"),
+                    avatar_id: text(Some(skin), "avatarId"),
+                    portrait_id: text(Some(skin), "portraitId"),
+                    illust_id: text(Some(skin), "illustId"),
+                    sort_id: display
+                        .and_then(|d| d.get("sortId"))
+                        .and_then(|v| v.as_i64())
+                        .map(|n| n as i32),
+                    get_time: display
+                        .and_then(|d| d.get("getTime"))
+                        .and_then(|v| v.as_i64())
+                        .filter(|t| *t > 0),
+                    drawers: skin_drawers(skin),
+                }
+            })
+            .collect();
+        skins.sort_by(|a, b| {
+            a.sort_id
+                .is_none()
+                .cmp(&b.sort_id.is_none())
+                .then_with(|| a.sort_id.cmp(&b.sort_id))
+                .then_with(|| a.get_time.cmp(&b.get_time))
+                .then_with(|| a.skin_id.cmp(&b.skin_id))
+        });
+        Ok(skins)
+    }
+
+    /// 干员在各已安装语言数据中的名称：当前语言在前，其余按语言目录排序；
+    /// 缺少该语言的 character_table 或其中没有该干员时跳过
+    pub fn get_character_localized_names(
+        &self,
+        char_id: &str,
+    ) -> Result<Vec<LocalizedName>, String> {
+        let current = self.data_source().language;
+        let mut languages = self.installed_languages();
+        languages.sort_by_key(|language| *language != current);
+
+        let mut names = Vec::new();
+        for language in languages {
+            let Some(table) =
+                self.read_optional_language_table(&language, "character_table.json")?
+            else {
+                continue;
+            };
+            let Some(character) = table.get(char_id) else {
+                continue;
+            };
+            let text = |key: &str| {
+                character
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+            };
+            if let Some(name) = text("name") {
+                names.push(LocalizedName {
+                    language,
+                    name,
+                    appellation: text("appellation"),
+                });
+            }
+        }
+        Ok(names)
+    }
+
+    /// 干员的获取方式（character_table）与上线时间（gacha_table 中首次出现的寻访），
+    /// gacha_table 缺失或干员从未出现在寻访中时 is_limited 与 release_ts 为 None
+    pub fn get_character_obtain_info(&self, char_id: &str) -> Result<CharacterObtainInfo, String> {
+        let character = self.character_entry(char_id)?;
+        let pools = match (
+            self.read_optional_table("gacha_table.json")?,
+            character.get("name").and_then(|v| v.as_str()),
+        ) {
+            (Some(gacha_table), Some(name)) => featured_gacha_pools(&gacha_table, name),
+            _ => Vec::new(),
+        };
+        Ok(CharacterObtainInfo {
+            char_id: char_id.to_string(),
+            obtain_approach: character
+                .get("itemObtainApproach")
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string),
+            is_not_obtainable: character
+                .get("isNotObtainable")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            is_limited: (!pools.is_empty()).then(|| pools.iter().all(|(_, limited)| *limited)),
+            release_ts: pools.iter().map(|(open_time, _)| *open_time).min(),
+        })
+    }
+
+    /// 干员的画师（按皮肤顺序，附各自绘制的皮肤）与各语言配音演员，均按首次出现顺序去重
+    pub fn get_character_credits(&self, char_id: &str) -> Result<CharacterCredits, String> {
+        let mut artists: Vec<ArtistCredit> = Vec::new();
+        for skin in self.get_character_skins(char_id)? {
+            for drawer in skin.drawers {
+                match artists.iter_mut().find(|artist| artist.name == drawer) {
+                    Some(artist) => artist.skin_ids.push(skin.skin_id.clone()),
+                    None => artists.push(ArtistCredit {
+                        name: drawer,
+                        skin_ids: vec![skin.skin_id.clone()],
+                    }),
+                }
+            }
+        }
+
+        let mut voice_actors: Vec<CvCredit> = Vec::new();
+        for lang in self
+            .get_character_voice_actors(char_id)?
+            .voice_sets
+            .into_iter()
+            .flat_map(|set| set.languages)
+        {
+            let index = voice_actors
+                .iter()
+                .position(|credit| credit.lang_type == lang.lang_type)
+                .unwrap_or_else(|| {
+                    voice_actors.push(CvCredit {
+                        lang_type: lang.lang_type.clone(),
+                        lang_name: lang.lang_name.clone(),
+                        cv_names: Vec::new(),
+                    });
+                    voice_actors.len() - 1
+                });
+            let credit = &mut voice_actors[index];
+            for cv in lang.cv_names {
+                if !credit.cv_names.contains(&cv) {
+                    credit.cv_names.push(cv);
+                }
+            }
+        }
+
+        Ok(CharacterCredits {
+            char_id: char_id.to_string(),
+            artists,
+            voice_actors,
+        })
+    }
+
+    /// 按画师查找干员：扫描 skin_table 的 drawerList，名称按规范化（NFKC、小写）后比较；
+    /// 结果按干员 id 排序，每名干员附该画师绘制的皮肤
+    pub fn get_operators_by_artist(&self, name: &str) -> Result<Vec<ArtistWork>, String> {
+        let target = normalize_character_name(name);
+        if target.is_empty() {
+            return Ok(Vec::new());
+        }
+        let Some(skin_table) = self.read_optional_table("skin_table.json")? else {
+            return Ok(Vec::new());
+        };
+        let mut skin_ids: Vec<&String> = skin_table
+            .get("charSkins")
+            .and_then(|v| v.as_object())
+            .into_iter()
+            .flatten()
+            .filter(|(_, skin)| {
+                skin_drawers(skin)
+                    .iter()
+                    .any(|drawer| normalize_character_name(drawer) == target)
+            })
+            .map(|(skin_id, _)| skin_id)
+            .collect();
+        skin_ids.sort();
+
+        let characters = self.read_optional_table("character_table.json")?;
+        let mut works: Vec<ArtistWork> = Vec::new();
+        for skin_id in skin_ids {
+            let Some(char_id) = skin_table["charSkins"][skin_id.as_str()]
+                .get("charId")
+                .and_then(|v| v.as_str())
+            else {
+                continue;
+            };
+            match works.iter_mut().find(|work| work.char_id == char_id) {
+                Some(work) => work.skin_ids.push(skin_id.clone()),
+                None => works.push(ArtistWork {
+                    char_id: char_id.to_string(),
+                    char_name: characters
+                        .as_deref()
+                        .and_then(|table| table.get(char_id))
+                        .and_then(|character| character.get("name"))
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string),
+                    skin_ids: vec![skin_id.clone()],
+                }),
+            }
+        }
+        works.sort_by(|a, b| a.char_id.cmp(&b.char_id));
+        Ok(works)
+    }
+
+    /// 干员的召唤物；召唤物条目缺失时跳过，没有召唤物时为空
+    pub fn get_character_tokens(&self, char_id: &str) -> Result<Vec<CharacterToken>, String> {
+        let character = self.character_entry(char_id)?;
+        Ok(character_token_ids(&character)
+            .into_iter()
+            .filter_map(|token_id| {
+                let token = self.character_entry(&token_id).ok()?;
+                Some(parse_character_token(&token_id, &token))
+            })
+            .collect())
+    }
+
+    /// 干员的信赖加成关键帧与满信赖加成（character_table 经表缓存读取，favor_table 可缺失）
+    pub fn get_character_favor(&self, char_id: &str) -> Result<CharacterFavor, String> {
+        let character = self.character_entry(char_id)?;
+        let favor_table = self.read_optional_table("favor_table.json")?;
+
+        let key_frames = parse_favor_key_frames(&character, favor_table.as_deref());
+        let max_bonuses = key_frames
+            .last()
+            .map(|frame| frame.bonuses.clone())
+            .unwrap_or_default();
+        let summary = if max_bonuses.is_empty() {
+            "满信赖无属性加成".to_string()
+        } else {
+            let parts: Vec<String> = max_bonuses
+                .iter()
+                .map(|bonus| format!("{} {}", format_bonus_value(bonus.value), bonus.label))
+                .collect();
+            format!("满信赖 {}", parts.join(" "))
+        };
+        Ok(CharacterFavor {
+            char_id: char_id.to_string(),
+            key_frames,
+            max_bonuses,
+            summary,
+        })
+    }
+
+    /// 干员作为说话人出现过的剧情，按类型分组、组内按开放时间排序；
+    /// 索引中有 story_speakers 表时直接查询，否则逐篇解析（较慢）
+    pub fn get_stories_for_character(&self, char_id: &str) -> Result<Vec<StoryGroup>, String> {
+        let character = self.character_entry(char_id)?;
+        let names: HashSet<String> = ["name", "appellation"]
+            .iter()
+            .filter_map(|key| character.get(*key).and_then(|v| v.as_str()))
+            .map(normalize_character_name)
+            .filter(|name| !name.is_empty())
+            .collect();
+        if names.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let include_hidden = self.include_hidden_in_search();
+        let stories: Vec<IndexedStory> = self
+            .collect_stories_for_index()?
+            .into_iter()
+            .filter(|indexed| {
+                include_hidden || indexed.story.visibility != Some(StoryVisibility::Hidden)
+            })
+            .collect();
+
+        let matched_ids: HashSet<String> = match self.open_index_with_table("story_speakers")? {
+            Some(conn) => {
+                let mut stmt = conn
+                    .prepare("SELECT DISTINCT story_id FROM story_speakers WHERE speaker_norm = ?1")
+                    .map_err(|e| format!("Failed to prepare speaker query: {}", e))?;
+                let mut ids = HashSet::new();
+                for name in &names {
+                    let rows = stmt
+                        .query_map(params![name], |row| row.get::<_, String>(0))
+                        .map_err(|e| format!("Failed to query story speakers: {}", e))?;
+                    for row in rows {
+                        ids.insert(
+                            row.map_err(|e| format!("Failed to read story speaker: {}", e))?,
+                        );
+                    }
+                }
+                ids
+            }
+            None => stories
+                .iter()
+                .filter(|indexed| {
+                    self.read_story_text(&indexed.story.story_txt)
+                        .map(|text| {
+                            let speakers = story_speakers(&parse_story_text(&text).segments);
+                            !speakers.is_disjoint(&names)
+                        })
+                        .unwrap_or(false)
+                })
+                .map(|indexed| indexed.story.story_id.clone())
+                .collect(),
+        };
+
+        let table = self.load_table("story_review_table.json")?;
+        let data = table_entries(&table, "story_review_table.json")?;
+        let start_time = |group: &str| {
+            data.get(group)
+                .and_then(group_start_time)
+                .unwrap_or(i64::MAX)
+        };
+
+        let mut matched: Vec<IndexedStory> = stories
+            .into_iter()
+            .filter(|indexed| matched_ids.contains(&indexed.story.story_id))
+            .collect();
+        matched.sort_by(|a, b| {
+            start_time(&a.story.story_group)
+                .cmp(&start_time(&b.story.story_group))
+                .then_with(|| compare_story_group_ids(&a.story.story_group, &b.story.story_group))
+                .then_with(|| a.story.story_sort.cmp(&b.story.story_sort))
+        });
+        matched.truncate(CHARACTER_STORY_LIMIT);
+
+        let mut groups: Vec<StoryGroup> = Vec::new();
+        for indexed in matched {
+            match groups
+                .iter_mut()
+                .find(|group| group.entry_type == indexed.entry_type)
+            {
+                Some(group) => group.stories.push(indexed.story),
+                None => groups.push(StoryGroup {
+                    id: indexed.entry_type.clone(),
+                    name: Self::entry_type_display(&indexed.entry_type),
+                    entry_type: indexed.entry_type,
+                    stories: vec![indexed.story],
+                    start_time: None,
+                    act_type: None,
+                    extra: None,
+                }),
+            }
+        }
+        groups.sort_by_key(|group| Self::entry_type_rank(&group.entry_type));
+        Ok(groups)
+    }
+
+    /// 获取分类的剧情列表（仅返回分类，不含故事列表）
+    pub fn get_story_categories(
+        &self,
+        include: Option<&[String]>,
+    ) -> Result<Vec<StoryCategory>, String> {
+        if !self.is_installed() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+
+        // include 为空表示全部分类；未请求的分类不会读取/解析
+        let wanted = |id: &str| match include {
+            Some(ids) => ids.iter().any(|item| item == id),
+            None => true,
+        };
+        let flatten = |groups: Vec<StoryGroup>| -> Vec<StoryEntry> {
+            groups.into_iter().flat_map(|group| group.stories).collect()
+        };
+
+        let mut categories = Vec::new();
+        let mut push = |id: &str, name: &str, category_type: &str, stories: Vec<StoryEntry>| {
+            if !stories.is_empty() {
+                categories.push(StoryCategory {
+                    id: id.to_string(),
+                    name: name.to_string(),
+                    category_type: category_type.to_string(),
+                    stories,
+                });
+            }
+        };
+
+        // 主线剧情（保持章节分组顺序）
+        if wanted("mainline") {
+            push(
+                "mainline",
+                "主线剧情",
+                "chapter",
+                flatten(self.get_main_stories_grouped(None)?),
+            );
+        }
+        if wanted("activity") {
+            push(
+                "activity",
+                "活动剧情",
+                "activity",
+                flatten(self.get_activity_stories_grouped(None)?),
+            );
+        }
+        if wanted("sidestory") {
+            push(
+                "sidestory",
+                "支线剧情",
+                "activity",
+                flatten(self.get_sidestory_stories_grouped()?),
+            );
+        }
+        if wanted("memory") {
+            push("memory", "干员密录", "memory", self.get_memory_stories()?);
+        }
+        if wanted("roguelike") {
+            push(
+                "roguelike",
+                "集成战略",
+                "roguelike",
+                flatten(self.get_roguelike_stories_grouped()?),
+            );
+        }
+
+        if wanted("rune") {
+            push(
+                "rune",
+                "危机合约",
+                "rune",
+                flatten(self.get_rune_stories_grouped()?),
+            );
+        }
+
+        if wanted("record") {
+            push(
+                "record",
+                "主线笔记",
+                "record",
+                flatten(self.get_record_stories_grouped()?),
+            );
+        }
+
+        Ok(categories)
+    }
+
+    /// 根据 entryType 解析剧情
+    fn parse_stories_by_entry_type(
+        &self,
+        data: &Map<String, Value>,
+        entry_type: &str,
+    ) -> Result<Vec<StoryEntry>, String> {
+        let mut stories = Vec::new();
+
+        for (id, value) in data.iter() {
+            if let Some(et) = value.get("entryType").and_then(|v| v.as_str()) {
+                if et == entry_type {
+                    if let Some(unlock_datas) =
+                        value.get("infoUnlockDatas").and_then(|v| v.as_array())
+                    {
+                        for unlock_data in unlock_datas {
+                            if let Some(story) = parse_story_entry(id, unlock_data) {
+                                stories.push(story);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        sort_story_entries(&mut stories);
+        Ok(stories)
+    }
+
+    /// 获取主线剧情
+    fn get_main_stories(&self) -> Result<Vec<StoryEntry>, String> {
+        let table = self.load_table("story_review_table.json")?;
+        let data = table_entries(&table, "story_review_table.json")?;
+
+        let mut stories = Vec::new();
+
+        for (id, value) in data.iter() {
+            if let Some(entry_type) = value.get("entryType").and_then(|v| v.as_str()) {
+                if entry_type == "MAINLINE" {
+                    if let Some(unlock_datas) =
+                        value.get("infoUnlockDatas").and_then(|v| v.as_array())
+                    {
+                        for unlock_data in unlock_datas {
+                            if let Some(story) = parse_story_entry(id, unlock_data) {
+                                stories.push(story);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        sort_story_entries(&mut stories);
+        Ok(stories)
+    }
+
+    /// 读取并解析剧情；来回翻看相邻剧情时直接复用最近解析的结果
+    pub fn get_story_content(&self, story_path: &str) -> Result<ParsedStoryContent, String> {
+        let key = self
+            .story_modified(story_path)
+            .map(|modified| StoryContentKey {
+                story_path: story_path.to_string(),
+                modified,
+            });
+        let cache = &self.table_cache;
+        if let Some(key) = &key {
+            let cached = cache
+                .story_contents
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .get(key);
+            if let Some(content) = cached {
+                cache
+                    .story_content_hits
+                    .fetch_add(1, AtomicOrdering::Relaxed);
+                return Ok((*content).clone());
+            }
+        }
+
+        cache
+            .story_content_misses
+            .fetch_add(1, AtomicOrdering::Relaxed);
+        self.parse_and_cache_story(story_path, key)
+    }
+
+    fn parse_and_cache_story(
+        &self,
+        story_path: &str,
+        key: Option<StoryContentKey>,
+    ) -> Result<ParsedStoryContent, String> {
+        let raw_text = self.read_story_text(story_path)?;
+        let parsed = match self.settings.get().nickname {
+            Some(nickname) => parse_story_text_with_nickname(&raw_text, &nickname),
+            None => parse_story_text(&raw_text),
+        };
+        if let Some(key) = key {
+            self.table_cache
+                .story_contents
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .insert(key, Arc::new(parsed.clone()));
+        }
+        Ok(parsed)
+    }
+
+    /// 阅读时预先解析上一篇/下一篇放入剧情缓存，返回每个路径是否已在缓存中。
+    /// 每次最多处理 PREFETCH_MAX_STORIES 个路径，超出部分与失败的路径返回 false；
+    /// 同步等独占任务开始后立即停止，不与其争抢磁盘
+    pub fn prefetch_story_contents(&self, story_paths: &[String]) -> Result<Vec<bool>, String> {
+        let task = self.tasks.start("prefetch", false)?;
+        let mut loaded = vec![false; story_paths.len()];
+        for (index, story_path) in story_paths.iter().take(PREFETCH_MAX_STORIES).enumerate() {
+            if task.token().is_cancelled() || self.tasks.exclusive_task().is_some() {
+                break;
+            }
+            // 预取不计入命中统计，以免掩盖实际阅读时的命中率
+            let Some(key) = self
+                .story_modified(story_path)
+                .map(|modified| StoryContentKey {
+                    story_path: story_path.clone(),
+                    modified,
+                })
+            else {
+                continue;
+            };
+            let cached = self
+                .table_cache
+                .story_contents
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .get(&key)
+                .is_some();
+            loaded[index] = cached || self.parse_and_cache_story(story_path, Some(key)).is_ok();
+        }
+        Ok(loaded)
+    }
+
+    /// 剧情缓存的命中情况，用于调整容量
+    pub fn story_cache_stats(&self) -> StoryCacheStats {
+        let cache = &self.table_cache;
+        StoryCacheStats {
+            hits: cache.story_content_hits.load(AtomicOrdering::Relaxed) as u64,
+            misses: cache.story_content_misses.load(AtomicOrdering::Relaxed) as u64,
+            entries: cache
+                .story_contents
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .entries
+                .len(),
+            capacity: STORY_CONTENT_CACHE_CAPACITY,
+        }
+    }
+
+    /// 剧情文件的修改时间；分段目录取各部分与目录本身（增删部分时变化）中最新的时间
+    fn story_modified(&self, story_path: &str) -> Option<SystemTime> {
+        let story_dir = self.story_dir();
+        let full_path = resolve_within(&story_dir, &format!("{}.txt", story_path)).ok()?;
+        if full_path.is_file() {
+            return fs::metadata(&full_path).and_then(|m| m.modified()).ok();
+        }
+        let dir_path = resolve_within(&story_dir, story_path).ok()?;
+        let mut latest = fs::metadata(&dir_path).and_then(|m| m.modified()).ok()?;
+        for (_, path) in Self::list_story_parts(&dir_path).ok()? {
+            latest = latest.max(fs::metadata(&path).and_then(|m| m.modified()).ok()?);
+        }
+        Some(latest)
+    }
+
+    /// 读取剧情文本；story_path 指向目录时（如分段的月度聊天）按编号顺序拼接其中的各部分
+    pub fn read_story_text(&self, story_path: &str) -> Result<String, String> {
+        let story_dir = self.story_dir();
+        let full_path = resolve_within(&story_dir, &format!("{}.txt", story_path))?;
+        if !full_path.is_file() {
+            let dir_path = resolve_within(&story_dir, story_path)?;
+            if dir_path.is_dir() {
+                return Self::read_story_parts(&dir_path);
+            }
+        }
+
+        fs::read_to_string(&full_path).map_err(|e| format!("Failed to read story file: {}", e))
+    }
+
+    /// 按文件名中的数字（_1, _2, _10）而非字典序排列各部分，部分之间插入可被解析为标题的分隔行
+    fn read_story_parts(dir_path: &Path) -> Result<String, String> {
+        let parts = Self::list_story_parts(dir_path)?;
+
+        let mut merged = String::new();
+        for (index, (_, path)) in parts.iter().enumerate() {
+            let content = fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read story file: {}", e))?;
+            if index > 0 {
+                merged.push_str(&format!("\n[Header] 第 {} 部分\n", index + 1));
+            }
+            merged.push_str(content.trim_end());
+            merged.push('\n');
+        }
+        Ok(merged)
+    }
+
+    /// 目录下的各部分文本（文件名主干, 路径），按编号数值排序
+    fn list_story_parts(dir_path: &Path) -> Result<Vec<(String, PathBuf)>, String> {
+        let mut parts: Vec<(String, PathBuf)> = fs::read_dir(dir_path)
+            .map_err(|e| format!("Failed to read story directory: {}", e))?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("txt"))
+            })
+            .filter_map(|path| {
+                let stem = path.file_stem()?.to_string_lossy().into_owned();
+                Some((stem, path))
+            })
+            .collect();
+        parts.sort_by(|(a, _), (b, _)| compare_story_group_ids(a, b));
+        Ok(parts)
+    }
+
+    /// 月度小队记闻的各部分：每部分作为独立条目，story_txt 指向单个文件，
+    /// 标题取 roguelike_topic_table 的 chatDesc，缺失时为“第 N 节”
+    pub fn get_month_chat_parts(&self, story_id: &str) -> Result<Vec<StoryEntry>, String> {
+        if !self.is_installed() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+        let base = match self.story_entries()?.get(story_id) {
+            Some(indexed) => indexed.story.clone(),
+            None => {
+                let lower = story_id.trim_matches('/').to_ascii_lowercase();
+                StoryEntry {
+                    story_id: story_id.to_string(),
+                    story_name: lower.rsplit('/').next().unwrap_or(&lower).to_string(),
+                    story_code: None,
+                    story_group: roguelike_group_key(&lower).unwrap_or_default(),
+                    story_sort: 0,
+                    avg_tag: None,
+                    avg_tag_kind: None,
+                    story_txt: lower,
+                    story_info: None,
+                    story_review_type: "ROGUELIKE".to_string(),
+                    unlock_type: "NONE".to_string(),
+                    story_dependence: None,
+                    story_can_show: None,
+                    story_can_enter: None,
+                    visibility: Some(StoryVisibility::Normal),
+                    stage_count: None,
+                    required_stages: None,
+                    cost_item_type: None,
+                    cost_item_id: None,
+                    cost_item_count: None,
+                    unlock_desc: None,
+                    favorite: None,
+                    group_favorite: None,
+                    word_count: None,
+                    estimated_minutes: None,
+                    source_act_name: None,
+                    source_stage_code: None,
+                }
+            }
+        };
+        if !roguelike_group_key(&base.story_txt).is_some_and(|key| key.starts_with("MONTH_CHAT_")) {
+            return Err(format!("Story {} 不是月度小队记闻", story_id));
+        }
+
+        let base_path = base.story_txt.trim_matches('/').to_string();
+        let dir_path = resolve_within(&self.story_dir(), &base_path)?;
+        if !dir_path.is_dir() {
+            return Err(format!("Story {} 没有分段文本", story_id));
+        }
+
+        let mut titles: HashMap<String, String> = HashMap::new();
+        if let Some(topic_table) = self.read_optional_table("roguelike_topic_table.json")? {
+            collect_month_chat_titles(&mut titles, &topic_table);
+        }
+
+        Ok(Self::list_story_parts(&dir_path)?
+            .into_iter()
+            .enumerate()
+            .map(|(index, (stem, _))| {
+                let number = index + 1;
+                let mut part = base.clone();
+                part.story_id = format!("{}#{}", base.story_id, number);
+                part.story_name = titles
+                    .get(&stem.to_ascii_lowercase())
+                    .cloned()
+                    .unwrap_or_else(|| format!("第 {} 节", number));
+                part.story_sort = number as i32;
+                part.story_txt = format!("{}/{}", base_path, stem);
+                part.word_count = None;
+                part.estimated_minutes = None;
+                part
+            })
+            .collect())
+    }
+
+    /// 读取剧情简介
+    pub fn read_story_info(&self, info_path: &str) -> Result<String, String> {
+        let base_dir = self.story_dir();
+
+        let trimmed = info_path.trim();
+        if trimmed.is_empty() {
+            return Err("Failed to read info file: empty info path".to_string());
+        }
+
+        let normalized = trimmed
+            .trim_matches(|c| c == '/' || c == '\\')
+            .replace('\\', "/");
+
+        let mut candidates = Vec::new();
+        candidates.push(resolve_within(&base_dir, &format!("{}.txt", normalized))?);
+
+        if normalized.starts_with("info/") {
+            let replaced = normalized.replacen("info/", "[uc]info/", 1);
+            candidates.push(resolve_within(&base_dir, &format!("{}.txt", replaced))?);
+        }
+
+        for candidate in &candidates {
+            match fs::read_to_string(candidate) {
+                Ok(content) => return Ok(content),
+                Err(err) if err.kind() == ErrorKind::NotFound => continue,
+                Err(err) => {
+                    return Err(format!("Failed to read info file: {}", err));
+                }
+            }
+        }
+
+        Err(format!(
+            "Failed to read info file: {} (candidates: {})",
+            info_path,
+            candidates
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    }
+
+    /// 重建剧情全文索引
+    #[tracing::instrument(name = "index", skip_all)]
+    pub fn rebuild_story_index(&self) -> Result<(), String> {
+        if !self.is_installed() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+        let task = self.begin_task("index")?;
+
+        let mut conn = self.open_index_connection()?;
+        Self::init_index_tables(&conn)?;
+
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start index transaction: {}", e))?;
+
+        tx.execute("DELETE FROM story_index", [])
+            .map_err(|e| format!("Failed to clear story index: {}", e))?;
+        tx.execute("DELETE FROM story_stats", [])
+            .map_err(|e| format!("Failed to clear story stats: {}", e))?;
+        tx.execute("DELETE FROM story_speakers", [])
+            .map_err(|e| format!("Failed to clear story speakers: {}", e))?;
+
+        let include_hidden = self.include_hidden_in_search();
+        let indexed_stories: Vec<IndexedStory> = self
+            .collect_stories_for_index()?
+            .into_iter()
+            // 防剧透：默认不把隐藏剧情写入索引，避免通过搜索泄露
+            .filter(|indexed| {
+                include_hidden || indexed.story.visibility != Some(StoryVisibility::Hidden)
+            })
+            .collect();
+        let mut insert_stmt = tx
+            .prepare(
+                "
+            INSERT INTO story_index (
+                story_id,
+                story_name,
+                category,
+                tokenized_content,
+                story_code,
+                raw_content
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        ",
+            )
+            .map_err(|e| format!("Failed to prepare story index insert: {}", e))?;
+        let mut stats_stmt = tx
+            .prepare(
+                "
+            INSERT OR REPLACE INTO story_stats (
+                story_id,
+                content_hash,
+                word_count,
+                dialogue_count,
+                narration_count,
+                decision_count,
+                speaker_count,
+                estimated_minutes,
+                terra_year,
+                terra_month,
+                terra_day
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+        ",
+            )
+            .map_err(|e| format!("Failed to prepare story stats insert: {}", e))?;
+        let mut speaker_stmt = tx
+            .prepare(
+                "INSERT OR IGNORE INTO story_speakers (story_id, speaker_norm) VALUES (?1, ?2)",
+            )
+            .map_err(|e| format!("Failed to prepare story speakers insert: {}", e))?;
+
+        let mut total = 0usize;
+        tracing::info!("开始建立索引，共 {} 篇剧情", indexed_stories.len());
+
+        for (position, indexed) in indexed_stories.iter().enumerate() {
+            // 取消时提前返回，未提交的事务随之回滚，旧索引保持不变
+            task.check_cancelled()?;
+            task.report("建立索引", position, indexed_stories.len());
+            let story_id = &indexed.story.story_id;
+            let story_name = &indexed.story.story_name;
+            let story_path = &indexed.story.story_txt;
+            tracing::trace!("索引剧情 {}", story_id);
+
+            let raw_text = match self.read_story_text(story_path) {
+                Ok(text) => text,
+                Err(err) => {
+                    tracing::warn!("Skip story {}: failed to read text ({})", story_id, err);
+                    continue;
+                }
+            };
+
+            let parsed = parse_story_text(&raw_text);
+            let stats = Self::compute_story_stats(story_id, &parsed.segments);
+            stats_stmt
+                .execute(params![
+                    story_id,
+                    Self::content_hash(&raw_text),
+                    stats.word_count as i64,
+                    stats.dialogue_count as i64,
+                    stats.narration_count as i64,
+                    stats.decision_count as i64,
+                    stats.speaker_count as i64,
+                    stats.estimated_minutes as i64,
+                    stats.terra_date.map(|date| date.year),
+                    stats.terra_date.and_then(|date| date.month),
+                    stats.terra_date.and_then(|date| date.day)
+                ])
+                .map_err(|e| format!("Failed to insert story stats: {}", e))?;
+            for speaker in story_speakers(&parsed.segments) {
+                speaker_stmt
+                    .execute(params![story_id, speaker])
+                    .map_err(|e| format!("Failed to insert story speakers: {}", e))?;
+            }
+            let flattened = Self::flatten_segments(&parsed.segments);
+
+            let combined_raw = if flattened.trim().is_empty() {
+                story_name.clone()
+            } else {
+                format!("{}\n{}", story_name, flattened)
+            };
+
+            let tokenized = Self::build_tokenized_content(&combined_raw);
+            if tokenized.trim().is_empty() {
+                continue;
+            }
+
+            let category_label =
+                Self::format_category_label(&indexed.entry_type, &indexed.category_name);
+
+            insert_stmt
+                .execute(params![
+                    story_id,
+                    story_name,
+                    &category_label,
+                    tokenized,
+                    indexed
+                        .story
+                        .story_code
+                        .as_ref()
+                        .map(|s| normalize_nfkc_lower_strip_marks(s))
+                        .unwrap_or_default(),
+                    combined_raw
+                ])
+                .map_err(|e| format!("Failed to insert story into index: {}", e))?;
+            total += 1;
+        }
+
+        drop(insert_stmt);
+        drop(stats_stmt);
+        drop(speaker_stmt);
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        tx.execute(
+            "
+            INSERT INTO story_index_meta (key, value)
+            VALUES ('last_built_at', ?1)
+            ON CONFLICT(key) DO UPDATE SET value = excluded.value
+        ",
+            params![timestamp.to_string()],
+        )
+        .map_err(|e| format!("Failed to update index metadata: {}", e))?;
+
+        tx.execute(
+            "
+            INSERT INTO story_index_meta (key, value)
+            VALUES ('total_count', ?1)
+            ON CONFLICT(key) DO UPDATE SET value = excluded.value
+        ",
+            params![total.to_string()],
+        )
+        .map_err(|e| format!("Failed to update index total: {}", e))?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit story index rebuild: {}", e))?;
+        tracing::info!("索引建立完成，写入 {} 篇剧情", total);
+
+        Ok(())
+    }
+
+    /// 获取索引状态
+    pub fn get_story_index_status(&self) -> Result<StoryIndexStatus, String> {
+        let Some(conn) = self.try_open_index_connection()? else {
+            return Ok(StoryIndexStatus {
+                ready: false,
+                total: 0,
+                last_built_at: None,
+            });
+        };
+
+        Self::init_index_tables(&conn)?;
+
+        let total: i64 = conn
+            .query_row("SELECT COUNT(*) FROM story_index", [], |row| row.get(0))
+            .unwrap_or(0);
+
+        let last_built_at = Self::extract_meta_value(&conn, "last_built_at")?
+            .and_then(|value| value.parse::<i64>().ok());
+
+        Ok(StoryIndexStatus {
+            ready: total > 0,
+            total: total.max(0) as usize,
+            last_built_at,
+        })
+    }
+
+    fn search_stories_with_index(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Option<Vec<SearchResult>>, String> {
+        let Some(conn) = self.try_open_index_connection()? else {
+            return Ok(None);
+        };
+
+        Self::init_index_tables(&conn)?;
+
+        let total: i64 = conn
+            .query_row("SELECT COUNT(*) FROM story_index", [], |row| row.get(0))
+            .unwrap_or(0);
+        if total == 0 {
+            return Ok(None);
+        }
+
+        let Some(fts_query) = Self::build_fts_query_advanced(query) else {
+            return Ok(Some(Vec::new()));
+        };
+
+        let query_sql = format!(
+            "
+            SELECT story_id, story_name, category, raw_content,
+                   snippet(story_index, -1, '', '', '...', 24) as snip
+            FROM story_index
+            WHERE story_index MATCH ?1
+            ORDER BY bm25(story_index)
+            LIMIT {}
+        ",
+            limit
+        );
+
+        let mut stmt = conn
+            .prepare(&query_sql)
+            .map_err(|e| format!("Failed to prepare story index query: {}", e))?;
+
+        let rows = stmt
+            .query_map(params![fts_query], |row| {
+                let story_id: String = row.get(0)?;
+                let story_name: String = row.get(1)?;
+                let category: String = row.get(2)?;
+                let raw_content: String = row.get(3)?;
+                let snip: String = row.get(4).unwrap_or_else(|_| String::new());
+                Ok((story_id, story_name, category, raw_content, snip))
+            })
+            .map_err(|e| format!("Failed to execute story index query: {}", e))?;
+
+        let query_lower = query.to_lowercase();
+        let mut results = Vec::new();
+        for row in rows {
+            if let Ok((story_id, story_name, category, raw_content, snip)) = row {
+                // 优先使用原始内容提取上下文，避免 tokenized_content 导致的空格断字
+                let mut matched_text = self.extract_context(&raw_content, &query_lower);
+                if matched_text.trim().is_empty() && !snip.trim().is_empty() {
+                    // 兜底：少数情况下 extract_context 未命中，回退 snippet 再做一次去空格优化
+                    let cleaned = snip
+                        .replace('\n', " ")
+                        .replace('\r', " ")
+                        .replace("  ", " ");
+                    matched_text = cleaned;
+                }
+                if matched_text.is_empty() {
+                    let preview: String = raw_content.chars().take(120).collect();
+                    matched_text = if preview.len() < raw_content.len() {
+                        format!("{}...", preview)
+                    } else {
+                        preview
+                    };
+                }
+                results.push(SearchResult {
+                    story_id,
+                    story_name,
+                    matched_text,
+                    category,
+                });
+            }
+        }
+
+        Ok(Some(results))
+    }
+
+    fn search_stories_fallback(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>, String> {
+        let mut results = Vec::new();
+        let query_norm = normalize_nfkc_lower_strip_marks(query);
+
+        let stories = self.collect_stories_for_index()?;
+
+        for indexed in &stories {
+            let story = &indexed.story;
+            let category_label =
+                Self::format_category_label(&indexed.entry_type, &indexed.category_name);
+
+            let story_name_norm = normalize_nfkc_lower_strip_marks(&story.story_name);
+            if story_name_norm.contains(&query_norm) {
+                results.push(SearchResult {
+                    story_id: story.story_id.clone(),
+                    story_name: story.story_name.clone(),
+                    matched_text: story.story_name.clone(),
+                    category: category_label,
+                });
+                if results.len() >= limit {
+                    return Ok(results);
+                }
+                continue;
+            }
+
+            if let Ok(content) = self.read_story_text(&story.story_txt) {
+                let content_norm = normalize_nfkc_lower_strip_marks(&content);
+                if content_norm.contains(&query_norm) {
+                    // Use original content for extracting visible context
+                    let matched_text = self.extract_context(&content, &query_norm);
+                    results.push(SearchResult {
+                        story_id: story.story_id.clone(),
+                        story_name: story.story_name.clone(),
+                        matched_text,
+                        category: category_label,
+                    });
+                    if results.len() >= limit {
+                        return Ok(results);
+                    }
+                }
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// 搜索剧情（混合：索引优先 + 线性扫描补全，防止遗漏）
+    pub fn search_stories(&self, query: &str) -> Result<Vec<SearchResult>, String> {
+        let trimmed = query.trim();
+        if trimmed.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let limit = self.search_result_limit();
+        // 先走索引
+        let mut combined: Vec<SearchResult> = match self.search_stories_with_index(trimmed, limit) {
+            Ok(Some(results)) => results,
+            Ok(None) => Vec::new(),
+            Err(err) => {
+                tracing::warn!(
+                    "Failed to search using index ({}), fallback to linear scan",
+                    err
+                );
+                Vec::new()
+            }
+        };
+
+        // 线性扫描补全（去重 by story_id）
+        let mut seen = std::collections::HashSet::new();
+        for r in &combined {
+            seen.insert(r.story_id.clone());
+        }
+
+        let fallback_results = self.search_stories_fallback(trimmed, limit)?;
+        for r in fallback_results {
+            if seen.insert(r.story_id.clone()) {
+                combined.push(r);
+                if combined.len() >= limit {
+                    break;
+                }
+            }
+        }
+
+        Ok(combined)
+    }
+
+    pub fn search_stories_with_debug(&self, query: &str) -> Result<SearchDebugResponse, String> {
+        let mut logs = Vec::new();
+        let trimmed = query.trim();
+        if trimmed.is_empty() {
+            logs.push("查询为空，直接返回".to_string());
+            return Ok(SearchDebugResponse {
+                results: Vec::new(),
+                logs,
+            });
+        }
+
+        let start_time = Instant::now();
+        let limit = self.search_result_limit();
+        logs.push(format!("开始搜索: \"{}\"", trimmed));
+
+        // Show normalized and FTS query preview
+        let normalized = normalize_nfkc_lower_strip_marks(trimmed);
+        logs.push(format!("规范化后的查询: \"{}\"", normalized));
+        if let Some(fts_query_preview) = Self::build_fts_query_advanced(trimmed) {
+            logs.push(format!("FTS 查询: {}", fts_query_preview));
+        } else {
+            logs.push("FTS 查询为空（可能仅包含标点或无效字符）".to_string());
+        }
+
+        let index_attempt_start = Instant::now();
+        let mut index_results: Vec<SearchResult> = Vec::new();
+        match self.search_stories_with_index(trimmed, limit) {
+            Ok(Some(results)) => {
+                let index_elapsed = index_attempt_start.elapsed();
+                logs.push(format!(
+                    "全文索引查询完成，耗时 {} ms，结果 {} 条",
+                    index_elapsed.as_millis(),
+                    results.len()
+                ));
+                index_results = results;
+            }
+            Ok(None) => {
+                logs.push(format!(
+                    "全文索引不可用或未建立，耗时 {} ms",
+                    index_attempt_start.elapsed().as_millis()
+                ));
+            }
+            Err(err) => {
+                logs.push(format!(
+                    "全文索引查询失败: {} (耗时 {} ms)，将回退线性扫描",
+                    err,
+                    index_attempt_start.elapsed().as_millis()
+                ));
+            }
+        }
+
+        let fallback_start = Instant::now();
+        let fallback_results = self.search_stories_fallback(trimmed, limit)?;
+        logs.push(format!(
+            "线性扫描完成，耗时 {} ms，结果 {} 条",
+            fallback_start.elapsed().as_millis(),
+            fallback_results.len()
+        ));
+        if fallback_results.len() >= limit {
+            logs.push(format!("结果数量达到上限 {} 条，建议缩小检索范围", limit));
+        }
+        // 合并结果（索引优先顺序），去重并截断
+        let mut seen = std::collections::HashSet::new();
+        let mut merged = Vec::new();
+        for r in index_results {
+            if seen.insert(r.story_id.clone()) {
+                merged.push(r);
+                if merged.len() >= limit {
+                    break;
+                }
+            }
+        }
+        let mut added = 0usize;
+        if merged.len() < limit {
+            for r in fallback_results {
+                if seen.insert(r.story_id.clone()) {
+                    merged.push(r);
+                    added += 1;
+                    if merged.len() >= limit {
+                        break;
+                    }
+                }
+            }
+        }
+        if added > 0 {
+            logs.push(format!("线性扫描补全 {} 条结果", added));
+        }
+        logs.push(format!(
+            "搜索总耗时 {} ms",
+            start_time.elapsed().as_millis()
+        ));
+
+        let response = SearchDebugResponse {
+            results: merged,
+            logs,
+        };
+        *self
+            .last_search_debug
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(LastSearchDebug {
+            query: trimmed.to_string(),
+            response: response.clone(),
+        });
+        Ok(response)
+    }
+
+    /// 带进度事件的搜索：优先使用索引；当回退线性扫描时，实时发送遍历进度
+    pub fn search_stories_with_progress(
+        &self,
+        app: &AppHandle,
+        query: &str,
+    ) -> Result<Vec<SearchResult>, String> {
+        let task = self
+            .tasks
+            .start("search", false)?
+            .with_progress(progress_sink(app));
+        task.run(|task| self.search_with_task(app, task, query))
+    }
+
+    fn search_with_task(
+        &self,
+        app: &AppHandle,
+        task: &TaskHandle,
+        query: &str,
+    ) -> Result<Vec<SearchResult>, String> {
+        let trimmed = query.trim();
+        if trimmed.is_empty() {
+            emit_search_complete(app, task, "完成", 1, 1, "查询为空");
+            return Ok(Vec::new());
+        }
+
+        let limit = self.search_result_limit();
+        // 尝试索引
+        match self.search_stories_with_index(trimmed, limit) {
+            Ok(Some(results)) => {
+                emit_search_complete(app, task, "索引检索", 1, 1, "使用全文索引完成");
+                return Ok(results);
+            }
+            Ok(None) => {
+                // fallthrough
+            }
+            Err(_err) => {
+                // fallthrough to fallback scan
+            }
+        }
+
+        // 线性扫描，实时进度
+        let stories = self.collect_stories_for_index()?;
+        let total = stories.len();
+        emit_search_progress(app, task, "线性扫描", 0, total.max(1), "开始遍历");
+
+        let mut results = Vec::new();
+        let query_norm = normalize_nfkc_lower_strip_marks(trimmed);
+        for (idx, indexed) in stories.iter().enumerate() {
+            task.check_cancelled()?;
+            let story = &indexed.story;
+            let category_label =
+                Self::format_category_label(&indexed.entry_type, &indexed.category_name);
+
+            let story_name_norm = normalize_nfkc_lower_strip_marks(&story.story_name);
+            if story_name_norm.contains(&query_norm) {
+                results.push(SearchResult {
+                    story_id: story.story_id.clone(),
+                    story_name: story.story_name.clone(),
+                    matched_text: story.story_name.clone(),
+                    category: category_label.clone(),
+                });
+            } else if let Ok(content) = self.read_story_text(&story.story_txt) {
+                let content_norm = normalize_nfkc_lower_strip_marks(&content);
+                if content_norm.contains(&query_norm) {
+                    let matched_text = self.extract_context(&content, &query_norm);
+                    results.push(SearchResult {
+                        story_id: story.story_id.clone(),
+                        story_name: story.story_name.clone(),
+                        matched_text,
+                        category: category_label.clone(),
+                    });
+                }
+            }
+
+            // 提前结束时最后一次进度必须发出
+            let current = (idx + 1).min(total);
+            let message = format!("已扫描 {} / {}", idx + 1, total);
+            if results.len() >= limit {
+                emit_search_complete(app, task, "线性扫描", current, total.max(1), message);
+                break;
+            }
+            emit_search_progress(app, task, "线性扫描", current, total.max(1), message);
+        }
+
+        Ok(results)
+    }
+
+    pub fn get_story_entry(&self, story_id: &str) -> Result<StoryEntry, String> {
+        self.story_entries()?
+            .get(story_id)
+            .map(|indexed| indexed.story.clone())
+            .ok_or_else(|| format!("Story {} 不存在", story_id))
+    }
+
+    /// 打开剧情所需的全部信息；简介、统计、进度、前后篇任一读取失败时对应字段为 None
+    pub fn get_story_detail(&self, story_id: &str) -> Result<StoryDetail, String> {
+        let entry = self.get_story_entry(story_id)?;
+        fn optional<T>(story_id: &str, part: &str, result: Result<Option<T>, String>) -> Option<T> {
+            result.unwrap_or_else(|err| {
+                tracing::debug!("Skip {} for {}: {}", part, story_id, err);
+                None
+            })
+        }
+
+        let synopsis = entry
+            .story_info
+            .as_deref()
+            .filter(|info| !info.trim().is_empty())
+            .and_then(|info| optional(story_id, "synopsis", self.read_story_info(info).map(Some)));
+        let stats = optional(
+            story_id,
+            "stats",
+            self.open_stats_connection()
+                .and_then(|conn| self.story_stats_for(conn.as_ref(), &entry))
+                .map(Some),
+        );
+        let progress = optional(story_id, "progress", self.get_reading_progress(story_id));
+        let neighbors = optional(
+            story_id,
+            "neighbors",
+            self.get_story_neighbors(story_id, false).map(Some),
+        );
+        let required_stages = optional(
+            story_id,
+            "required stages",
+            self.resolve_stages(&entry)
+                .map(|stages| (!stages.is_empty()).then_some(stages)),
+        );
+
+        let dependence_name = entry
+            .story_dependence
+            .as_deref()
+            .and_then(|dependence| self.get_story_entry(dependence).ok())
+            .map(|dependence| dependence.story_name);
+
+        Ok(StoryDetail {
+            entry,
+            synopsis,
+            stats,
+            progress,
+            neighbors,
+            dependence_name,
+            required_stages,
+        })
+    }
+
+    /// 沿 storyDependence 追溯解锁前置剧情，按阅读顺序（最早的前置在前）返回；
+    /// 遇到循环或超过深度上限时停止，无法解析的 id 作为最后一环保留
+    pub fn get_story_dependencies(&self, story_id: &str) -> Result<Vec<StoryDependency>, String> {
+        let entries = self.story_entries()?;
+        let current = entries
+            .get(story_id)
+            .ok_or_else(|| format!("Story {} 不存在", story_id))?;
+
+        let mut chain = Vec::new();
+        let mut visited: HashSet<&str> = HashSet::from([story_id]);
+        let mut next = current.story.story_dependence.as_deref();
+        while let Some(dependence) = next.map(str::trim).filter(|id| !id.is_empty()) {
+            if chain.len() >= STORY_DEPENDENCY_DEPTH_LIMIT || !visited.insert(dependence) {
+                tracing::debug!(
+                    "Stop resolving {} at {}: cycle or depth limit",
+                    story_id,
+                    dependence
+                );
+                break;
+            }
+            let indexed = entries.get(dependence);
+            chain.push(StoryDependency {
+                story_id: dependence.to_string(),
+                story: indexed.map(|indexed| indexed.story.clone()),
+                category: indexed.map(|indexed| {
+                    Self::format_category_label(&indexed.entry_type, &indexed.category_name)
+                }),
+            });
+            next = indexed.and_then(|indexed| indexed.story.story_dependence.as_deref());
+        }
+        chain.reverse();
+        Ok(chain)
+    }
+
+    /// 将剧情的 requiredStages 解析为关卡编号与名称（stage_table 经表缓存读取）
+    pub fn resolve_required_stages(&self, story_id: &str) -> Result<Vec<ResolvedStage>, String> {
+        let entry = self.get_story_entry(story_id)?;
+        self.resolve_stages(&entry)
+    }
+
+    fn resolve_stages(&self, entry: &StoryEntry) -> Result<Vec<ResolvedStage>, String> {
+        let Some(required) = entry.required_stages.as_deref().filter(|s| !s.is_empty()) else {
+            return Ok(Vec::new());
+        };
+        let stage_table = self.read_optional_table("stage_table.json")?;
+        Ok(required
+            .iter()
+            .map(|stage| resolve_stage(stage_table.as_deref(), stage))
+            .collect())
+    }
+
+    /// 「手气不错」：在满足筛选条件的剧情中等概率抽取一篇，没有符合条件的剧情时返回 NO_MATCH
+    pub fn get_random_story(&self, filter: &RandomStoryFilter) -> Result<RandomStory, String> {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        self.pick_random_story(filter, seed)
+    }
+
+    fn pick_random_story(
+        &self,
+        filter: &RandomStoryFilter,
+        seed: u64,
+    ) -> Result<RandomStory, String> {
+        let entries = self.story_entries()?;
+        let read_ids = if filter.unread_only {
+            self.read_story_ids()?
+        } else {
+            HashSet::new()
+        };
+        let word_counts = match filter.min_word_count {
+            Some(_) => self.cached_story_stats()?,
+            None => HashMap::new(),
+        };
+        // 旧版前端传入的 MINI_ACTIVITY 在索引中已细分为故事集与小型活动
+        let entry_types: Option<Vec<&str>> = filter
+            .entry_types
+            .as_deref()
+            .filter(|types| !types.is_empty())
+            .map(|types| {
+                types
+                    .iter()
+                    .flat_map(|entry_type| match entry_type.as_str() {
+                        "MINI_ACTIVITY" => vec!["STORY_COLLECTION", "ACTIVITY"],
+                        other => vec![other],
+                    })
+                    .collect()
+            });
+        let include_hidden = self.include_hidden_in_search();
+
+        let mut candidates: Vec<&IndexedStory> = entries
+            .values()
+            .filter(|indexed| {
+                include_hidden || indexed.story.visibility != Some(StoryVisibility::Hidden)
+            })
+            .filter(|indexed| {
+                entry_types
+                    .as_ref()
+                    .is_none_or(|types| types.contains(&indexed.entry_type.as_str()))
+            })
+            .filter(|indexed| !read_ids.contains(&indexed.story.story_id))
+            .filter(|indexed| match filter.min_word_count {
+                // 没有缓存统计（索引未建立）的剧情无法判断字数，一律排除
+                Some(min) => word_counts
+                    .get(&indexed.story.story_id)
+                    .is_some_and(|(word_count, _)| *word_count >= min),
+                None => true,
+            })
+            .collect();
+        if candidates.is_empty() {
+            return Err("NO_MATCH".to_string());
+        }
+        // HashMap 的遍历顺序不固定，排序后同一种子总能抽到同一篇
+        candidates.sort_by(|a, b| a.story.story_id.cmp(&b.story.story_id));
+
+        let picked = candidates[SplitMix64::new(seed).below(candidates.len())];
+        let neighbors = self
+            .get_story_neighbors(&picked.story.story_id, false)
+            .map_err(|err| tracing::debug!("Skip neighbors: {}", err))
+            .ok();
+        Ok(RandomStory {
+            story: picked.story.clone(),
+            category: Self::format_category_label(&picked.entry_type, &picked.category_name),
+            neighbors,
+        })
+    }
+
+    /// story_id 查找表；命中缓存时不再读取 story_review_table
+    fn story_entries(&self) -> Result<Arc<HashMap<String, IndexedStory>>, String> {
+        let path = self.excel_path("story_review_table.json");
+        let modified = fs::metadata(&path)
+            .ok()
+            .and_then(|meta| meta.modified().ok());
+        if let Some(cached) = self
+            .table_cache
+            .entries
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .as_ref()
+        {
+            if modified.is_some() && cached.path == path && cached.modified == modified {
+                return Ok(cached.entries.clone());
+            }
+        }
+
+        let entries: Arc<HashMap<String, IndexedStory>> = Arc::new(
+            self.collect_stories_for_index()?
+                .into_iter()
+                .map(|indexed| (indexed.story.story_id.clone(), indexed))
+                .collect(),
+        );
+        *self
+            .table_cache
+            .entries
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(CachedEntries {
+            path,
+            modified,
+            entries: entries.clone(),
+        });
+        Ok(entries)
+    }
+
+    /// 剧情表之外另有来源的分组：集成战略（story_table 路径 id）、
+    /// 主线笔记（zone_table recordId）与危机合约回退
+    fn supplementary_story_groups(&self) -> Result<Vec<StoryGroup>, String> {
+        let mut groups = self.get_roguelike_stories_grouped()?;
+        groups.extend(self.get_rune_stories_grouped()?);
+        groups.extend(self.get_record_stories_grouped()?);
+        Ok(groups)
+    }
+
+    /// 列表接口展示的全部剧情：剧情表条目加上 supplementary_story_groups 中的剧情，
+    /// 同一 id 只保留首次出现的条目
+    fn listed_stories(&self) -> Result<Vec<StoryEntry>, String> {
+        let mut seen = HashSet::new();
+        let mut stories: Vec<StoryEntry> = Vec::new();
+        let indexed = self
+            .collect_stories_for_index()?
+            .into_iter()
+            .map(|indexed| indexed.story);
+        let grouped = self
+            .supplementary_story_groups()?
+            .into_iter()
+            .flat_map(|group| group.stories);
+        for story in indexed.chain(grouped) {
+            if seen.insert(story.story_id.clone()) {
+                stories.push(story);
+            }
+        }
+        Ok(stories)
+    }
+
+    /// 列表接口展示的全部 (分组键, story_id)：先按剧情表顺序列出剧情表分组，
+    /// 再追加 supplementary_story_groups 的分组；同一分组内的重复剧情只计一次
+    fn listed_group_members(&self) -> Result<Vec<(String, String)>, String> {
+        let indexed = self
+            .collect_stories_for_index()?
+            .into_iter()
+            .map(|indexed| (indexed.story.story_group, indexed.story.story_id));
+        let grouped = self
+            .supplementary_story_groups()?
+            .into_iter()
+            .flat_map(|group| {
+                let group_id = group.id;
+                group
+                    .stories
+                    .into_iter()
+                    .map(move |story| (group_id.clone(), story.story_id))
+            });
+        let mut seen = HashSet::new();
+        Ok(indexed
+            .chain(grouped)
+            .filter(|member| seen.insert(member.clone()))
+            .collect())
+    }
+
+    /// 提取匹配文本的上下文
+    fn extract_context(&self, content: &str, query: &str) -> String {
+        if content.is_empty() || query.is_empty() {
+            return String::new();
+        }
+
+        let content_lower = normalize_nfkc_lower_strip_marks(content);
+
+        if let Some(pos) = content_lower.find(query) {
+            return Self::build_context_snippet(content, pos, query.len());
+        }
+
+        for token in query.split_whitespace().filter(|t| !t.is_empty()) {
+            if let Some(pos) = content_lower.find(token) {
+                return Self::build_context_snippet(content, pos, token.len());
+            }
+        }
+
+        String::new()
+    }
+
+    fn build_context_snippet(content: &str, byte_start: usize, pattern_bytes: usize) -> String {
+        let prefix = match content.get(..byte_start) {
+            Some(slice) => slice,
+            None => return String::new(),
+        };
+
+        let byte_end = byte_start.saturating_add(pattern_bytes).min(content.len());
+        let matched_slice = match content.get(byte_start..byte_end) {
+            Some(slice) => slice,
+            None => "",
+        };
+
+        let start_char_index = prefix.chars().count();
+        let matched_char_len = matched_slice.chars().count();
+
+        let chars: Vec<char> = content.chars().collect();
+        if chars.is_empty() {
+            return String::new();
+        }
+
+        let window = 50usize;
+        let snippet_start = start_char_index.saturating_sub(window);
+        let snippet_end = (start_char_index + matched_char_len + window).min(chars.len());
+
+        let snippet: String = chars[snippet_start..snippet_end].iter().collect();
+        if snippet.is_empty() {
+            return String::new();
+        }
+
+        format!("...{}...", snippet.trim())
+    }
+
+    pub fn get_main_stories_grouped(
+        &self,
+        tag_filter: Option<&[AvgTagKind]>,
+    ) -> Result<Vec<StoryGroup>, String> {
+        if !self.is_installed() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+
+        let table = self.load_table("story_review_table.json")?;
+        let data = table_entries(&table, "story_review_table.json")?;
+
+        // 按分组ID收集主线剧情
+        let mut groups: Vec<StoryGroup> = Vec::new();
+
+        for (id, value) in data.iter() {
+            if let Some(et) = value.get("entryType").and_then(|v| v.as_str()) {
+                if et == "MAINLINE" {
+                    let group_name = value
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("未知章节");
+
+                    if let Some(unlock_datas) =
+                        value.get("infoUnlockDatas").and_then(|v| v.as_array())
+                    {
+                        let mut stories = Vec::new();
+                        for unlock_data in unlock_datas {
+                            if let Some(story) = parse_story_entry(id, unlock_data) {
+                                stories.push(story);
+                            }
+                        }
+                        sort_story_entries(&mut stories);
+                        groups.push(StoryGroup {
+                            id: id.clone(),
+                            name: group_name.to_string(),
+                            entry_type: et.to_string(),
+                            start_time: group_start_time(value),
+                            stories,
+                            act_type: None,
+                            extra: None,
+                        });
+                    }
+                }
+            }
+        }
+
+        groups.sort_by(|a, b| compare_story_group_ids(&a.id, &b.id));
+        Ok(filter_groups_by_tag(groups, tag_filter))
+    }
+
+    /// 活动剧情分组；复刻活动与原版合并为一组，沿用原版名称并标记 has_rerun
+    pub fn get_activity_stories_grouped(
+        &self,
+        tag_filter: Option<&[AvgTagKind]>,
+    ) -> Result<Vec<StoryGroup>, String> {
+        if !self.is_installed() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+
+        let table = self.load_table("story_review_table.json")?;
+        let data = table_entries(&table, "story_review_table.json")?;
+
+        let mut groups: Vec<StoryGroup> = Vec::new();
+
+        for (id, value) in data.iter() {
+            if let Some(et) = value.get("entryType").and_then(|v| v.as_str()) {
+                let act_type = value.get("actType").and_then(|v| v.as_str()).unwrap_or("");
+                // 支线（ACTIVITY_STORY）由 get_sidestory_stories_grouped 单独返回
+                if matches!(
+                    classify_activity_entry(et, act_type),
+                    Some(ActivityKind::StoryCollection | ActivityKind::MiniActivity)
+                ) {
+                    let activity_name = value
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("未知活动");
+
+                    if let Some(unlock_datas) =
+                        value.get("infoUnlockDatas").and_then(|v| v.as_array())
+                    {
+                        let mut stories = Vec::new();
+                        for unlock_data in unlock_datas {
+                            if let Some(story) = parse_story_entry(id, unlock_data) {
+                                stories.push(story);
+                            }
+                        }
+
+                        if !stories.is_empty() {
+                            sort_story_entries(&mut stories);
+                            let sort_id = value
+                                .get("id")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or_else(|| id.as_str());
+
+                            groups.push(StoryGroup {
+                                id: sort_id.to_string(),
+                                name: activity_name.to_string(),
+                                entry_type: et.to_string(),
+                                start_time: group_start_time(value),
+                                stories,
+                                act_type: Some(act_type.to_string()),
+                                extra: Some(StoryGroupExtra::default()),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        // 按活动开始时间排序（旧活动在前，时间缺失的放在末尾）
+        groups.sort_by(|a, b| {
+            match a
+                .start_time
+                .unwrap_or(i64::MAX)
+                .cmp(&b.start_time.unwrap_or(i64::MAX))
+            {
+                Ordering::Equal => compare_story_group_ids(&a.id, &b.id),
+                other => other,
+            }
+        });
+
+        // 复刻判定：剧情 id 集合相同，或去掉「复刻」后缀后名称相同
+        let mut merged: Vec<StoryGroup> = Vec::new();
+        let mut by_name: HashMap<String, usize> = HashMap::new();
+        let mut by_story_set: HashMap<Vec<String>, usize> = HashMap::new();
+        for candidate in groups {
+            let base_name = strip_rerun_suffix(&candidate.name).to_string();
+            let mut story_set: Vec<String> = candidate
+                .stories
+                .iter()
+                .map(|s| s.story_id.clone())
+                .collect();
+            story_set.sort();
+
+            let existing = by_story_set
+                .get(&story_set)
+                .or_else(|| by_name.get(&base_name))
+                .copied();
+            let Some(index) = existing else {
+                by_name.insert(base_name, merged.len());
+                by_story_set.insert(story_set, merged.len());
+                merged.push(candidate);
+                continue;
+            };
+
+            let group = &mut merged[index];
+            group.extra.get_or_insert_with(Default::default).has_rerun = true;
+            // 开始时间缺失时复刻可能排在原版之前，此时改用原版名称与 id
+            if is_rerun_activity(&group.name, "") && !is_rerun_activity(&candidate.name, "") {
+                group.name = candidate.name;
+                group.id = candidate.id;
+            }
+            let mut seen: HashSet<String> = group
+                .stories
+                .iter()
+                .flat_map(|s| [s.story_id.clone(), s.story_txt.to_ascii_lowercase()])
+                .collect();
+            for story in candidate.stories {
+                let txt = story.story_txt.to_ascii_lowercase();
+                if seen.contains(&story.story_id) || seen.contains(&txt) {
+                    continue;
+                }
+                seen.insert(story.story_id.clone());
+                seen.insert(txt);
+                group.stories.push(story);
+            }
+            by_story_set.entry(story_set).or_insert(index);
+        }
+
+        self.attach_activity_meta(&mut merged)?;
+        Ok(filter_groups_by_tag(merged, tag_filter))
+    }
+
+    /// 从 activity_table 补充活动 id、代号、横幅与主题色；表缺失或匹配不到时只填代号，
+    /// 表中没有横幅字段时 banner_id 为 None
+    fn attach_activity_meta(&self, groups: &mut [StoryGroup]) -> Result<(), String> {
+        let activity_table = self.read_optional_table("activity_table.json")?;
+        for group in groups.iter_mut() {
+            let act_code = activity_code(&group.stories);
+            let extra = group.extra.get_or_insert_with(Default::default);
+            extra.act_code = act_code;
+
+            let Some(table) = activity_table.as_deref() else {
+                continue;
+            };
+            let Some((act_id, basic)) = match_activity(table, &group.id, &group.name) else {
+                continue;
+            };
+            let text = |value: Option<&Value>, key: &str| {
+                value
+                    .and_then(|v| v.get(key))
+                    .and_then(|v| v.as_str())
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+            };
+            let home = table.get("homeActConfig").and_then(|v| v.get(act_id));
+            extra.banner_id = ["bannerId", "bannerImgId", "picId"]
+                .iter()
+                .find_map(|key| text(Some(basic), key).or_else(|| text(home, key)));
+            extra.theme_color = text(home, "actTopBarColor");
+            extra.act_id = Some(act_id.to_string());
+        }
+        Ok(())
+    }
+
+    pub fn get_sidestory_stories_grouped(&self) -> Result<Vec<StoryGroup>, String> {
+        if !self.is_installed() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+
+        let table = self.load_table("story_review_table.json")?;
+        let data = table_entries(&table, "story_review_table.json")?;
+
+        let mut groups: Vec<StoryGroup> = Vec::new();
+
+        for (id, value) in data.iter() {
+            let Some(entry_type) = value.get("entryType").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let act_type = value.get("actType").and_then(|v| v.as_str()).unwrap_or("");
+            // 支线=大型活动（actType = ACTIVITY_STORY）
+            if classify_activity_entry(entry_type, act_type) == Some(ActivityKind::SideStory) {
+                let group_name = value
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("支线剧情");
+
+                if let Some(unlock_datas) = value.get("infoUnlockDatas").and_then(|v| v.as_array())
+                {
+                    let mut stories = Vec::new();
+                    for unlock_data in unlock_datas {
+                        if let Some(story) = parse_story_entry(id, unlock_data) {
+                            stories.push(story);
+                        }
+                    }
+                    if !stories.is_empty() {
+                        sort_story_entries(&mut stories);
+                        groups.push(StoryGroup {
+                            id: id.clone(),
+                            name: group_name.to_string(),
+                            entry_type: entry_type.to_string(),
+                            start_time: group_start_time(value),
+                            stories,
+                            act_type: Some(act_type.to_string()),
+                            extra: None,
+                        });
+                    }
+                }
+            }
+        }
+
+        groups.sort_by(|a, b| compare_story_group_ids(&a.id, &b.id));
+        self.attach_activity_meta(&mut groups)?;
+        Ok(groups)
+    }
+
+    pub fn get_roguelike_stories_grouped(&self) -> Result<Vec<StoryGroup>, String> {
+        if !self.is_installed() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+
+        // 首先读取 meta，提取 contentPath -> desc 映射（用于更友好的命名）
+        // 海外服数据包可能缺少部分表，缺失时退化为空映射/空分组
+        let meta_value = self.read_optional_table("story_review_meta_table.json")?;
+
+        let mut path_desc_map: HashMap<String, String> = HashMap::new();
+        // 广义扫描：meta 中所有含 contentPath 的对象都尝试收集（兼容结构变动）
+        fn collect_content_paths(map: &mut HashMap<String, String>, val: &Value) {
+            match val {
+                Value::Object(obj) => {
+                    if let Some(cp) = obj.get("contentPath").and_then(|x| x.as_str()) {
+                        let lower = cp.to_ascii_lowercase();
+                        if lower.starts_with("obt/roguelike/") {
+                            let desc = obj
+                                .get("desc")
+                                .and_then(|x| x.as_str())
+                                .or_else(|| obj.get("name").and_then(|x| x.as_str()))
+                                .or_else(|| obj.get("rawBrief").and_then(|x| x.as_str()))
+                                .unwrap_or("")
+                                .trim()
+                                .to_string();
+                            if !desc.is_empty() {
+                                map.insert(lower, desc);
+                            }
+                        }
+                    }
+                    for v in obj.values() {
+                        collect_content_paths(map, v);
+                    }
+                }
+                Value::Array(arr) => {
+                    for v in arr {
+                        collect_content_paths(map, v);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(meta_value) = &meta_value {
+            collect_content_paths(&mut path_desc_map, meta_value);
+        }
+
+        // 使用 story_table 作为权威来源，枚举所有 Obt/Roguelike 文本
+        let Some(story_table) = self.read_optional_table("story_table.json")? else {
+            return Ok(Vec::new());
+        };
+        let table_obj = table_entries(&story_table, "story_table.json")?;
+
+        // 结局回响按 endbookGroupData 单独分组，其余文本沿用主题分组
+        let topics = self.roguelike_topics()?;
+        let mut endbook_chapters: HashMap<String, EndbookChapter> = HashMap::new();
+        if let Some(topic_table) = self.read_optional_table("roguelike_topic_table.json")? {
+            collect_endbook_chapters(&mut endbook_chapters, &topic_table);
+        }
+        let mut endbook_labels: HashMap<String, String> = HashMap::new();
+
+        let mut grouped: HashMap<String, Vec<StoryEntry>> = HashMap::new();
+        let mut counters: HashMap<String, i32> = HashMap::new();
+
+        for key in table_obj.keys() {
+            let lower = key.to_ascii_lowercase();
+            if !lower.starts_with("obt/roguelike/") {
+                continue;
+            }
+            let topic_key = roguelike_group_key(&lower).unwrap_or_else(|| "ROGUE".to_string());
+            let desc = path_desc_map.get(&lower).cloned();
+            let (group_key, name, sort, avg_tag) = match endbook_chapters.get(&lower) {
+                Some(chapter) => {
+                    let group_key = format!(
+                        "{}_ENDBOOK_{}",
+                        topic_key,
+                        chapter.ending_id.to_ascii_uppercase()
+                    );
+                    endbook_labels.entry(group_key.clone()).or_insert_with(|| {
+                        format!(
+                            "{} · {}",
+                            roguelike_group_label(&topic_key, &topics),
+                            chapter.ending_name
+                        )
+                    });
+                    let name = chapter.title.clone().or(desc).unwrap_or_else(|| {
+                        format!("{} · 第{}章", chapter.ending_name, chapter.sequence)
+                    });
+                    (
+                        group_key,
+                        name,
+                        chapter.sequence,
+                        Some("结局回响".to_string()),
+                    )
+                }
+                None => {
+                    let sort = counters
+                        .entry(topic_key.clone())
+                        .and_modify(|x| *x += 1)
+                        .or_insert(1);
+                    // 取最后一段作为兜底标题
+                    let name = desc
+                        .unwrap_or_else(|| key.split('/').next_back().unwrap_or(key).to_string());
+                    (topic_key, name, *sort, None)
+                }
+            };
+
+            let entry = StoryEntry {
+                story_id: key.clone(),
+                story_name: name,
+                story_code: None,
+                story_group: group_key.clone(),
+                story_sort: sort,
+                avg_tag_kind: Some(classify_avg_tag(avg_tag.as_deref())),
+                avg_tag,
+                story_txt: lower.clone(),
+                story_info: None,
+                story_review_type: "ROGUELIKE".to_string(),
+                unlock_type: "NONE".to_string(),
+                story_dependence: None,
+                story_can_show: None,
+                story_can_enter: None,
+                visibility: Some(StoryVisibility::Normal),
+                stage_count: None,
+                required_stages: None,
+                cost_item_type: None,
+                cost_item_id: None,
+                cost_item_count: None,
+                unlock_desc: None,
+                favorite: None,
+                group_favorite: None,
+                word_count: None,
+                estimated_minutes: None,
+                source_act_name: None,
+                source_stage_code: None,
+            };
+
+            grouped.entry(group_key).or_default().push(entry);
+        }
+
+        // 按分组键排序后再换成主题名，StoryEntry.story_group 仍保留原始分组键
+        let mut out: Vec<(String, Vec<StoryEntry>)> = grouped.into_iter().collect();
+        out.sort_by(|a, b| compare_story_group_ids(&a.0, &b.0));
+        Ok(out
+            .into_iter()
+            .map(|(key, mut stories)| {
+                sort_story_entries(&mut stories);
+                let name = endbook_labels
+                    .remove(&key)
+                    .unwrap_or_else(|| roguelike_group_label(&key, &topics));
+                StoryGroup {
+                    id: key,
+                    name,
+                    entry_type: "ROGUELIKE".to_string(),
+                    start_time: None,
+                    stories,
+                    act_type: None,
+                    extra: None,
+                }
+            })
+            .collect())
+    }
+
+    /// 集成战略主题名称（roguelike_topic_table 缺失时为空）
+    fn roguelike_topics(&self) -> Result<HashMap<String, String>, String> {
+        Ok(self
+            .read_optional_table("roguelike_topic_table.json")?
+            .map(|table| roguelike_topic_names(&table))
+            .unwrap_or_default())
+    }
+
+    /// 危机合约赛季名称与剧情描述（crisis_table 与 crisis_v2_table，缺失时为空）
+    fn rune_metadata(&self) -> Result<RuneMetadata, String> {
+        let mut meta = RuneMetadata::default();
+        for table_file in ["crisis_table.json", "crisis_v2_table.json"] {
+            if let Some(table) = self.read_optional_table(table_file)? {
+                collect_rune_metadata(&mut meta.seasons, &mut meta.titles, &table);
+            }
+        }
+        Ok(meta)
+    }
+
+    /// 危机合约剧情按赛季分组：story_review_table 的 RUNE 条目优先，
+    /// story_table 中未被提及的 obt/rune 文本作为兜底
+    pub fn get_rune_stories_grouped(&self) -> Result<Vec<StoryGroup>, String> {
+        if !self.is_installed() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+
+        let RuneMetadata { seasons, titles } = self.rune_metadata()?;
+        let table = self.load_table("story_review_table.json")?;
+        let data = table_entries(&table, "story_review_table.json")?;
+
+        let mut grouped: HashMap<String, Vec<StoryEntry>> = HashMap::new();
+        let mut seen_paths = HashSet::new();
+        for story in self.parse_stories_by_entry_type(data, "RUNE")? {
+            let season = rune_season_key(&story.story_txt)
+                .unwrap_or_else(|| story.story_group.to_ascii_lowercase());
+            seen_paths.insert(story.story_txt.to_ascii_lowercase());
+            grouped.entry(season).or_default().push(story);
+        }
+
+        if let Some(story_table) = self.read_optional_table("story_table.json")? {
+            let table_obj = table_entries(&story_table, "story_table.json")?;
+            let mut keys: Vec<&String> = table_obj.keys().collect();
+            keys.sort_by(|a, b| compare_story_group_ids(a, b));
+
+            for key in keys {
+                let lower = key.to_ascii_lowercase();
+                if seen_paths.contains(&lower) {
+                    continue;
+                }
+                let Some(season) = rune_season_key(&lower) else {
+                    continue;
+                };
+                let stories = grouped.entry(season.clone()).or_default();
+                let sort = stories.iter().map(|s| s.story_sort).max().unwrap_or(0) + 1;
+                let name = titles.get(&lower).cloned().unwrap_or_else(|| {
+                    format!("{} {}", rune_season_label(&season, &seasons), sort)
+                });
+
+                stories.push(StoryEntry {
+                    story_id: key.clone(),
+                    story_name: name,
+                    story_code: None,
+                    story_group: season,
+                    story_sort: sort,
+                    avg_tag: None,
+                    avg_tag_kind: Some(AvgTagKind::Other),
+                    story_txt: lower,
+                    story_info: None,
+                    story_review_type: "RUNE".to_string(),
+                    unlock_type: "NONE".to_string(),
+                    story_dependence: None,
+                    story_can_show: None,
+                    story_can_enter: None,
+                    visibility: Some(StoryVisibility::Normal),
+                    stage_count: None,
+                    required_stages: None,
+                    cost_item_type: None,
+                    cost_item_id: None,
+                    cost_item_count: None,
+                    unlock_desc: None,
+                    favorite: None,
+                    group_favorite: None,
+                    word_count: None,
+                    estimated_minutes: None,
+                    source_act_name: None,
+                    source_stage_code: None,
+                });
+            }
+        }
+
+        let mut out: Vec<(String, Vec<StoryEntry>)> = grouped.into_iter().collect();
+        out.sort_by(|a, b| compare_story_group_ids(&a.0, &b.0));
+        Ok(out
+            .into_iter()
+            .map(|(season, mut stories)| {
+                sort_story_entries(&mut stories);
+                StoryGroup {
+                    name: rune_season_label(&season, &seasons),
+                    id: season,
+                    entry_type: "RUNE".to_string(),
+                    start_time: None,
+                    stories,
+                    act_type: None,
+                    extra: None,
+                }
+            })
+            .collect())
+    }
+
+    /// 主线笔记：以 zone_table 的 zoneRecordGroupedData 为准按区域分组，
+    /// 每条笔记附带需通关的关卡（rewards.bindStageId）与解锁说明；
+    /// story_review_table 中未被覆盖的 RECORD 条目按 storyGroup 追加
+    pub fn get_record_stories_grouped(&self) -> Result<Vec<StoryGroup>, String> {
+        if !self.is_installed() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+
+        let zone_table = self.read_optional_table("zone_table.json")?;
+        let stage_table = self.read_optional_table("stage_table.json")?;
+        let stages = stage_table.as_deref();
+
+        let mut grouped: HashMap<String, Vec<StoryEntry>> = HashMap::new();
+        let mut seen_paths = HashSet::new();
+
+        let record_groups = zone_table
+            .as_deref()
+            .and_then(|table| table.get("zoneRecordGroupedData"))
+            .and_then(|v| v.as_object());
+        for (zone_key, zone_records) in record_groups.into_iter().flatten() {
+            let zone_id = zone_records
+                .get("zoneId")
+                .and_then(|v| v.as_str())
+                .unwrap_or(zone_key);
+            let records = zone_records.get("records").and_then(|v| v.as_array());
+            for (index, record) in records.into_iter().flatten().enumerate() {
+                let text = |value: &Value, field: &str| {
+                    value
+                        .get(field)
+                        .and_then(|v| v.as_str())
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(str::to_string)
+                };
+                let rewards: &[Value] = record
+                    .get("rewards")
+                    .and_then(|v| v.as_array())
+                    .map_or(&[], |v| v.as_slice());
+                let Some(story_txt) = text(record, "textPath")
+                    .or_else(|| rewards.iter().find_map(|reward| text(reward, "textPath")))
+                else {
+                    continue;
+                };
+                let Some(record_id) = text(record, "recordId") else {
+                    continue;
+                };
+
+                let mut stage_ids: Vec<String> = Vec::new();
+                for stage_id in rewards.iter().filter_map(|r| text(r, "bindStageId")) {
+                    if !stage_ids.contains(&stage_id) {
+                        stage_ids.push(stage_id);
+                    }
+                }
+                let codes: Vec<String> =
+                    stage_ids.iter().map(|id| stage_code(stages, id)).collect();
+                let required_stages: Vec<RequiredStage> = stage_ids
+                    .iter()
+                    .map(|stage_id| RequiredStage {
+                        stage_id: stage_id.clone(),
+                        min_state: "PASS".to_string(),
+                        max_state: "COMPLETE".to_string(),
+                    })
+                    .collect();
+
+                seen_paths.insert(story_txt.to_ascii_lowercase());
+                grouped
+                    .entry(zone_id.to_string())
+                    .or_default()
+                    .push(StoryEntry {
+                        story_id: record_id.clone(),
+                        story_name: text(record, "recordTitleName").unwrap_or(record_id),
+                        story_code: codes.first().cloned(),
+                        story_group: zone_id.to_string(),
+                        story_sort: index as i32 + 1,
+                        avg_tag: None,
+                        avg_tag_kind: Some(AvgTagKind::Other),
+                        story_txt,
+                        story_info: None,
+                        story_review_type: "RECORD".to_string(),
+                        unlock_type: if stage_ids.is_empty() {
+                            "NONE".to_string()
+                        } else {
+                            "STAGE_PASS".to_string()
+                        },
+                        story_dependence: text(record, "preRecordId"),
+                        story_can_show: None,
+                        story_can_enter: None,
+                        visibility: Some(StoryVisibility::Normal),
+                        stage_count: Some(stage_ids.len() as i32),
+                        required_stages: (!required_stages.is_empty()).then_some(required_stages),
+                        cost_item_type: None,
+                        cost_item_id: None,
+                        cost_item_count: None,
+                        unlock_desc: (!codes.is_empty())
+                            .then(|| format!("通关 {} 后解锁", codes.join("、"))),
+                        favorite: None,
+                        group_favorite: None,
+                        word_count: None,
+                        estimated_minutes: None,
+                        source_act_name: None,
+                        source_stage_code: None,
+                    });
+            }
+        }
+
+        let table = self.load_table("story_review_table.json")?;
+        let data = table_entries(&table, "story_review_table.json")?;
+        for story in self.parse_stories_by_entry_type(data, "RECORD")? {
+            if seen_paths.insert(story.story_txt.to_ascii_lowercase()) {
+                grouped
+                    .entry(story.story_group.clone())
+                    .or_default()
+                    .push(story);
+            }
+        }
+
+        let mut out: Vec<(String, Vec<StoryEntry>)> = grouped.into_iter().collect();
+        out.sort_by(|a, b| compare_story_group_ids(&a.0, &b.0));
+        Ok(out
+            .into_iter()
+            .map(|(zone_id, mut stories)| {
+                sort_story_entries(&mut stories);
+                StoryGroup {
+                    name: zone_display_name(zone_table.as_deref(), &zone_id),
+                    id: zone_id,
+                    entry_type: "RECORD".to_string(),
+                    start_time: None,
+                    stories,
+                    act_type: None,
+                    extra: None,
+                }
+            })
+            .collect())
+    }
+
+    /// 导航分组：顺序与 main/activity/sidestory/roguelike/record/rune 各列表接口一致
+    fn navigation_groups(&self) -> Result<Arc<Vec<NavigationGroup>>, String> {
+        let path = self.excel_path("story_review_table.json");
+        let modified = fs::metadata(&path)
+            .ok()
+            .and_then(|meta| meta.modified().ok());
+        if let Some(cached) = self
+            .table_cache
+            .navigation
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .as_ref()
+        {
+            if modified.is_some() && cached.path == path && cached.modified == modified {
+                return Ok(cached.groups.clone());
+            }
+        }
+
+        let sources = [
+            ("mainline", self.get_main_stories_grouped(None)?),
+            ("activity", self.get_activity_stories_grouped(None)?),
+            ("sidestory", self.get_sidestory_stories_grouped()?),
+            ("roguelike", self.get_roguelike_stories_grouped()?),
+            ("record", self.get_record_stories_grouped()?),
+            ("rune", self.get_rune_stories_grouped()?),
+        ];
+        let groups: Arc<Vec<NavigationGroup>> = Arc::new(
+            sources
+                .into_iter()
+                .flat_map(|(category, groups)| {
+                    groups.into_iter().map(move |group| NavigationGroup {
+                        category,
+                        name: group.name,
+                        stories: group.stories,
+                    })
+                })
+                .collect(),
+        );
+
+        *self
+            .table_cache
+            .navigation
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(CachedNavigation {
+            path,
+            modified,
+            groups: groups.clone(),
+        });
+        Ok(groups)
+    }
+
+    /// 上一篇/下一篇；cross_groups 为 true 时在分组边界处滚动到同一分类的相邻分组
+    pub fn get_story_neighbors(
+        &self,
+        story_id: &str,
+        cross_groups: bool,
+    ) -> Result<StoryNeighbors, String> {
+        if !self.is_installed() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+
+        let groups = self.navigation_groups()?;
+        let (group_index, position) = groups
+            .iter()
+            .enumerate()
+            .find_map(|(index, group)| {
+                group
+                    .stories
+                    .iter()
+                    .position(|story| story.story_id == story_id)
+                    .map(|position| (index, position))
+            })
+            .ok_or_else(|| format!("Story {} 不存在", story_id))?;
+
+        let group = &groups[group_index];
+        let mut prev = position
+            .checked_sub(1)
+            .map(|index| group.stories[index].clone());
+        let mut next = group.stories.get(position + 1).cloned();
+        if cross_groups {
+            let same_category = |other: &&NavigationGroup| other.category == group.category;
+            if prev.is_none() {
+                prev = groups[..group_index]
+                    .iter()
+                    .rev()
+                    .filter(same_category)
+                    .find_map(|other| other.stories.last().cloned());
+            }
+            if next.is_none() {
+                next = groups[group_index + 1..]
+                    .iter()
+                    .filter(same_category)
+                    .find_map(|other| other.stories.first().cloned());
+            }
+        }
+
+        Ok(StoryNeighbors {
+            prev,
+            next,
+            group_name: group.name.clone(),
+            position: position + 1,
+            total: group.stories.len(),
+        })
+    }
+
+    /// 剧情树版本：数据 commit、语言与各依赖表的修改时间的哈希，不读取表内容
+    pub fn get_story_tree_version(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.read_version()
+            .map(|info| info.commit)
+            .hash(&mut hasher);
+        self.data_source().language.hash(&mut hasher);
+        for table_file in STORY_TREE_TABLES {
+            let modified = fs::metadata(self.excel_path(table_file))
+                .and_then(|meta| meta.modified())
+                .ok();
+            (table_file, modified).hash(&mut hasher);
+        }
+        format!("{:016x}", hasher.finish())
+    }
+
+    /// 一次性返回所有分类的分组与剧情，供前端整体缓存
+    pub fn get_story_tree(&self) -> Result<StoryTree, String> {
+        if !self.is_installed() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+
+        let tree_version = self.get_story_tree_version();
+        let memory_groups = self
+            .get_memory_stories_grouped()?
+            .into_iter()
+            .map(|group| StoryGroup {
+                id: group.char_id,
+                name: group.char_name,
+                entry_type: "NONE".to_string(),
+                start_time: None,
+                stories: group.stories,
+                act_type: None,
+                extra: None,
+            })
+            .collect();
+        let sources = [
+            ("mainline", "主线", self.get_main_stories_grouped(None)?),
+            ("activity", "活动", self.get_activity_stories_grouped(None)?),
+            ("sidestory", "支线", self.get_sidestory_stories_grouped()?),
+            ("roguelike", "肉鸽", self.get_roguelike_stories_grouped()?),
+            ("memory", "密录", memory_groups),
+            ("record", "笔记", self.get_record_stories_grouped()?),
+            ("rune", "危机合约", self.get_rune_stories_grouped()?),
+        ];
+
+        let nodes: Vec<StoryTreeNode> = sources
+            .into_iter()
+            .map(|(id, name, groups)| {
+                let groups: Vec<StoryTreeGroup> = groups
+                    .into_iter()
+                    .map(|group| StoryTreeGroup {
+                        name: group.name,
+                        count: group.stories.len(),
+                        stories: group.stories,
+                    })
+                    .collect();
+                StoryTreeNode {
+                    id: id.to_string(),
+                    name: name.to_string(),
+                    count: groups.iter().map(|group| group.count).sum(),
+                    groups,
+                }
+            })
+            .collect();
+        let tree = StoryTree {
+            tree_version,
+            total: nodes.iter().map(|node| node.count).sum(),
+            nodes,
+        };
+
+        if let Ok(bytes) = serde_json::to_vec(&tree) {
+            if bytes.len() > STORY_TREE_WARN_BYTES {
+                tracing::warn!(
+                    "Story tree payload is {:.1} MB",
+                    bytes.len() as f64 / 1024.0 / 1024.0
+                );
+            }
+        }
+        Ok(tree)
+    }
+
+    /// 按干员分组的密录：通过 handbook_info_table 解析剧情所属干员，
+    /// character_table 提供名称与星级；无法解析的剧情归入「未知干员」
+    /// 密录及其所属干员 id（handbook_info_table 的 handbookAvgList，按 storyId 或 storySetId 匹配），
+    /// 无法归属的密录干员 id 为空；分组列表与干员页共用
+    fn memory_stories_with_owner(&self) -> Result<Vec<(String, StoryEntry)>, String> {
+        let stories = self.get_memory_stories()?;
+        let owners = match self.read_optional_table("handbook_info_table.json")? {
+            Some(handbook) => memory_owner_map(&handbook),
+            None => HashMap::new(),
+        };
+        Ok(stories
+            .into_iter()
+            .map(|story| {
+                let char_id = owners
+                    .get(&story.story_id)
+                    .or_else(|| owners.get(&story.story_group))
+                    .cloned()
+                    .unwrap_or_default();
+                (char_id, story)
+            })
+            .collect())
+    }
+
+    /// 同一干员的密录按 storyGroup（章节）再按 storySort 排序
+    fn sort_memory_stories(stories: &mut [StoryEntry]) {
+        stories.sort_by(
+            |a, b| match compare_story_group_ids(&a.story_group, &b.story_group) {
+                Ordering::Equal => a.story_sort.cmp(&b.story_sort),
+                other => other,
+            },
+        );
+    }
+
+    /// 干员页的「干员密录」：该干员的全部密录（含解锁条件），没有密录时为空
+    pub fn get_character_memories(&self, char_id: &str) -> Result<Vec<StoryEntry>, String> {
+        let mut stories: Vec<StoryEntry> = self
+            .memory_stories_with_owner()?
+            .into_iter()
+            .filter(|(owner, _)| owner == char_id)
+            .map(|(_, story)| story)
+            .collect();
+        Self::sort_memory_stories(&mut stories);
+        Ok(stories)
+    }
+
+    pub fn get_memory_stories_grouped(&self) -> Result<Vec<MemoryGroup>, String> {
+        let stories = self.memory_stories_with_owner()?;
+        let characters = self.read_optional_table("character_table.json")?;
+
+        let mut groups: HashMap<String, MemoryGroup> = HashMap::new();
+        for (char_id, story) in stories {
+            let group = groups.entry(char_id.clone()).or_insert_with(|| {
+                let character = characters.as_ref().and_then(|table| table.get(&char_id));
+                let char_name = character
+                    .and_then(|c| c.get("name"))
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string);
+                MemoryGroup {
+                    char_name: match (char_id.is_empty(), char_name) {
+                        (true, _) => "未知干员".to_string(),
+                        (false, Some(name)) => name,
+                        (false, None) => char_id.clone(),
+                    },
+                    rarity: parse_rarity(character.and_then(|c| c.get("rarity"))),
+                    char_id,
+                    stories: Vec::new(),
+                }
+            });
+            group.stories.push(story);
+        }
+
+        let mut groups: Vec<MemoryGroup> = groups.into_values().collect();
+        for group in &mut groups {
+            Self::sort_memory_stories(&mut group.stories);
+        }
+        // 高星级在前，同星级按名称排序；未知干员放在最后
+        groups.sort_by(|a, b| {
+            a.char_id
+                .is_empty()
+                .cmp(&b.char_id.is_empty())
+                .then_with(|| b.rarity.cmp(&a.rarity))
+                .then_with(|| a.char_name.cmp(&b.char_name))
+        });
+        Ok(groups)
+    }
+
+    pub fn get_memory_stories(&self) -> Result<Vec<StoryEntry>, String> {
+        if !self.is_installed() {
+            return Err("NOT_INSTALLED".to_string());
+        }
+
+        let table = self.load_table("story_review_table.json")?;
+        let data = table_entries(&table, "story_review_table.json")?;
+
+        let mut stories = self.parse_stories_by_entry_type(data, "NONE")?;
+
+        // 解锁条件来自 handbook_info_table，芯片名称来自 item_table，两表缺失时仅跳过描述
+        let unlocks = match self.read_optional_table("handbook_info_table.json")? {
+            Some(handbook) => memory_unlock_map(&handbook),
+            None => HashMap::new(),
+        };
+        let items = self.read_optional_table("item_table.json")?;
+        let sources = self.memory_sources()?;
+        for story in &mut stories {
+            let unlock = unlocks
+                .get(&story.story_id)
+                .or_else(|| unlocks.get(&story.story_group))
+                .cloned();
+            apply_memory_unlock(story, unlock.as_deref(), items.as_deref());
+            if let Some(source) = sources
+                .get(&story.story_id)
+                .or_else(|| sources.get(&story.story_group))
+            {
+                story.source_act_name = source.act_name.clone();
+                story.source_stage_code = source.stage_code.clone();
+            }
+        }
+        Ok(stories)
+    }
+
+    /// 密录来源：storyId / storySetId -> 活动名称与关卡编号；活动名称取自 activity_table，
+    /// 关卡编号取自 stage_table，任一表缺失时对应项为空，不影响密录本身
+    fn memory_sources(&self) -> Result<HashMap<String, MemorySource>, String> {
+        let Some(meta) = self.read_optional_table("story_review_meta_table.json")? else {
+            return Ok(HashMap::new());
+        };
+        let archives = memory_archive_map(&meta);
+        if archives.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let activities = self.read_optional_table("activity_table.json")?;
+        let stages = self.read_optional_table("stage_table.json")?;
+        Ok(archives
+            .into_iter()
+            .map(|(story_id, archive)| {
+                let act_name = activities
+                    .as_deref()
+                    .and_then(|table| table.get("basicInfo"))
+                    .and_then(|info| info.get(&archive.act_id))
+                    .and_then(|basic| basic.get("name"))
+                    .and_then(|v| v.as_str())
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string);
+                let stage_code = archive
+                    .stage_id
+                    .as_deref()
+                    .map(|stage_id| stage_code(stages.as_deref(), stage_id));
+                (
+                    story_id,
+                    MemorySource {
+                        act_name,
+                        stage_code,
+                    },
+                )
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tasks::TASK_CANCELLED;
+
+    #[test]
+    fn read_story_info_supports_uc_prefix() {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let temp_root = std::env::temp_dir().join(format!("story_reader_test_{}", timestamp));
+        let data_dir = temp_root.join("ArknightsGameData");
+        let info_dir = data_dir.join("zh_CN/gamedata/story/[uc]info/demo");
+        fs::create_dir_all(&info_dir).unwrap();
+        fs::write(info_dir.join("sample.txt"), "test summary").unwrap();
+
+        let service = DataService::new(temp_root.clone());
+
+        let content = service
+            .read_story_info("info/demo/sample")
+            .expect("should read summary from [uc]info directory");
+        assert_eq!(content, "test summary");
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    fn temp_root(label: &str) -> PathBuf {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("story_reader_{}_{}", label, timestamp))
+    }
+
+    #[test]
+    fn check_update_without_local_version_reports_available() {
+        let temp_root = temp_root("update_none");
+        let service = DataService::new(temp_root.clone());
+
+        let status =
+            service.check_update_with(
+                &service.data_source(),
+                || Ok("0123456789abcdef".to_string()),
+            );
+        assert_eq!(
+            status,
+            UpdateStatus::UpdateAvailable {
+                remote_short: "0123456".to_string()
+            }
+        );
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn check_update_with_equal_commit_is_up_to_date() {
+        let temp_root = temp_root("update_equal");
+        let service = DataService::new(temp_root.clone());
+        service
+            .write_version(&VersionInfo {
+                commit: "0123456789abcdef".to_string(),
+                fetched_at: 0,
+                source: None,
+            })
+            .unwrap();
+
+        let status =
+            service.check_update_with(
+                &service.data_source(),
+                || Ok("0123456789abcdef".to_string()),
+            );
+        assert_eq!(status, UpdateStatus::UpToDate);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn check_update_network_error_is_unknown() {
+        let temp_root = temp_root("update_offline");
+        let service = DataService::new(temp_root.clone());
+        service
+            .write_version(&VersionInfo {
+                commit: "0123456789abcdef".to_string(),
+                fetched_at: 0,
+                source: None,
+            })
+            .unwrap();
+
+        let status = service.check_update_with(&service.data_source(), || {
+            Err("connection refused".to_string())
+        });
+        assert_eq!(
+            status,
+            UpdateStatus::Unknown {
+                reason: "connection refused".to_string()
+            }
+        );
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    fn write_fixture_zip(path: &Path, entries: &[(&str, Vec<u8>)]) {
+        let file = fs::File::create(path).unwrap();
+        let mut writer = zip::ZipWriter::new(file);
+        let options =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for (name, data) in entries {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn extraction_progress_is_monotonic_by_bytes() {
+        let temp_root = temp_root("extract_progress");
+        fs::create_dir_all(&temp_root).unwrap();
+        let zip_path = temp_root.join("fixture.zip");
+
+        let mut entries: Vec<(String, Vec<u8>)> = (0..40)
+            .map(|i| (format!("Root/tiny_{:02}.txt", i), vec![b'a'; 16]))
+            .collect();
+        entries.insert(
+            3,
+            (
+                "Root/large.json".to_string(),
+                (0..4_000_000u32).map(|i| (i % 251) as u8).collect(),
+            ),
+        );
+        let borrowed: Vec<(&str, Vec<u8>)> = entries
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.clone()))
+            .collect();
+        write_fixture_zip(&zip_path, &borrowed);
+
+        let extract_root = temp_root.join("out");
+        fs::create_dir_all(&extract_root).unwrap();
+        let mut percents = Vec::new();
+        extract_zip_entries(
+            &zip_path,
+            &extract_root,
+            Duration::ZERO,
+            &CancelToken::default(),
+            &mut |percent, _| percents.push(percent),
+        )
+        .unwrap();
+
+        assert!(
+            percents.len() > entries.len(),
+            "large entry should emit sub-progress"
+        );
+        assert!(percents.windows(2).all(|w| w[0] <= w[1]));
+        assert!((percents.last().copied().unwrap() - 100.0).abs() < f64::EPSILON);
+        // 大文件之前的 3 个小文件在字节占比上几乎可以忽略
+        assert!(percents[2] < 1.0);
+        assert_eq!(
+            fs::metadata(extract_root.join("Root/large.json"))
+                .unwrap()
+                .len(),
+            4_000_000
+        );
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    fn seed_index_meta(service: &DataService, key: &str, value: &str) {
+        let conn = service.open_index_connection().unwrap();
+        DataService::init_index_tables(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO story_index_meta (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn app_data_round_trip_restores_index_and_settings() {
+        let temp_root = temp_root("app_data_round_trip");
+        let service = DataService::new(temp_root.clone());
+        seed_index_meta(&service, "last_built_at", "42");
+        fs::write(temp_root.join(SETTINGS_FILE), r#"{"fontSize":18}"#).unwrap();
+        let story_dir = service.data_dir.join("zh_CN/gamedata/story");
+        fs::create_dir_all(&story_dir).unwrap();
+        fs::write(story_dir.join("a.txt"), "game data").unwrap();
+
+        let backup = temp_root.join("backup.zip");
+        service.export_app_data(&backup, false).unwrap();
+
+        // 模拟换机：清空应用数据后恢复
+        seed_index_meta(&service, "last_built_at", "0");
+        fs::remove_file(temp_root.join(SETTINGS_FILE)).unwrap();
+        service.import_app_data(&backup).unwrap();
+
+        let conn = service.open_index_connection().unwrap();
+        assert_eq!(
+            DataService::extract_meta_value(&conn, "last_built_at").unwrap(),
+            Some("42".to_string())
+        );
+        assert_eq!(
+            fs::read_to_string(temp_root.join(SETTINGS_FILE)).unwrap(),
+            r#"{"fontSize":18}"#
+        );
+
+        let mut archive = ZipArchive::new(fs::File::open(&backup).unwrap()).unwrap();
+        assert!(archive
+            .by_name("ArknightsGameData/zh_CN/gamedata/story/a.txt")
+            .is_err());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn app_data_round_trip_with_game_data() {
+        let temp_root = temp_root("app_data_game");
+        let service = DataService::new(temp_root.clone());
+        let story_dir = service.data_dir.join("zh_CN/gamedata/story");
+        fs::create_dir_all(&story_dir).unwrap();
+        fs::write(story_dir.join("a.txt"), "game data").unwrap();
+
+        let backup = temp_root.join("backup.zip");
+        service.export_app_data(&backup, true).unwrap();
+        fs::remove_dir_all(&service.data_dir).unwrap();
+
+        service.import_app_data(&backup).unwrap();
+        assert_eq!(
+            fs::read_to_string(story_dir.join("a.txt")).unwrap(),
+            "game data"
+        );
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn app_data_import_rejects_newer_manifest_and_busy_state() {
+        let temp_root = temp_root("app_data_reject");
+        fs::create_dir_all(&temp_root).unwrap();
+        let service = DataService::new(temp_root.clone());
+
+        let backup = temp_root.join("future.zip");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&backup).unwrap());
+        let manifest = format!(
+            r#"{{"format_version":{},"app_version":"9.9.9","exported_at":0,"files":[]}}"#,
+            APP_DATA_FORMAT_VERSION + 1
+        );
+        write_zip_entry(&mut writer, APP_DATA_MANIFEST, manifest.as_bytes()).unwrap();
+        writer.finish().unwrap();
+
+        let err = service.import_app_data(&backup).unwrap_err();
+        assert!(err.contains("格式版本"), "unexpected error: {}", err);
+
+        let _sync = service.begin_task("sync").unwrap();
+        let err = service.import_app_data(&backup).unwrap_err();
+        assert_eq!(err, "BUSY:sync");
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn app_data_export_is_busy_while_task_runs() {
+        let temp_root = temp_root("app_data_export_busy");
+        fs::create_dir_all(&temp_root).unwrap();
+        let service = DataService::new(temp_root.clone());
+        let backup = temp_root.join("backup.zip");
+
+        let index = service.begin_task("index").unwrap();
+        assert_eq!(
+            service.export_app_data(&backup, false).unwrap_err(),
+            "BUSY:index"
+        );
+        assert!(!backup.exists());
+
+        drop(index);
+        service.export_app_data(&backup, false).unwrap();
+        assert!(backup.exists());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn data_source_switch_changes_paths_and_persists() {
+        let temp_root = temp_root("data_source");
+        let service = DataService::new(temp_root.clone());
+        assert_eq!(service.data_source().language, "zh_CN");
+
+        let profile = service.set_data_source("en").unwrap();
+        assert_eq!(profile.language, "en_US");
+        assert_eq!(
+            service.excel_path("story_review_table.json"),
+            temp_root.join("ArknightsGameData/en_US/gamedata/excel/story_review_table.json")
+        );
+
+        // 新实例从 settings.json 读取
+        let reloaded = DataService::new(temp_root.clone());
+        assert_eq!(reloaded.data_source().id, "en");
+        assert!(service.set_data_source("unknown").is_err());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn check_update_detects_installed_data_from_other_repo() {
+        let temp_root = temp_root("update_other_repo");
+        let service = DataService::new(temp_root.clone());
+        service
+            .write_version(&VersionInfo {
+                commit: "0123456789abcdef".to_string(),
+                fetched_at: 0,
+                source: None,
+            })
+            .unwrap();
+        let global = service.set_data_source("en").unwrap();
+
+        let status = service.check_update_with(&global, || Ok("0123456789abcdef".to_string()));
+        assert!(matches!(status, UpdateStatus::UpdateAvailable { .. }));
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn roguelike_groups_degrade_when_tables_are_missing() {
+        let temp_root = temp_root("missing_tables");
+        let service = DataService::new(temp_root.clone());
+        service.set_data_source("jp").unwrap();
+        let excel_dir = service.gamedata_dir().join("excel");
+        fs::create_dir_all(&excel_dir).unwrap();
+        fs::write(excel_dir.join("story_review_table.json"), "{}").unwrap();
+
+        assert!(service.is_installed());
+        assert!(service.get_roguelike_stories_grouped().unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    fn write_excel_table(service: &DataService, table_file: &str, value: &Value) {
+        let excel_dir = service.gamedata_dir().join("excel");
+        fs::create_dir_all(&excel_dir).unwrap();
+        fs::write(excel_dir.join(table_file), value.to_string()).unwrap();
+        service.invalidate_caches();
+    }
+
+    #[test]
+    fn story_entries_tolerate_schema_drift() {
+        let missing_sort = serde_json::json!({
+            "storyId": "act1_st01",
+            "storyName": "缺少排序",
+            "storyGroup": "act1",
+            "storyTxt": "activities/act1/level_st01",
+            "storyReviewType": "ACTIVITY_STORY",
+            "unLockType": "NONE"
+        });
+        let story = parse_story_entry("act1", &missing_sort).unwrap();
+        assert_eq!(story.story_sort, 0);
+
+        let extra_field = serde_json::json!({
+            "storyId": "act1_st02",
+            "storyName": "额外字段",
+            "storyGroup": "act1",
+            "storySort": 2,
+            "storyTxt": "activities/act1/level_st02",
+            "storyReviewType": "ACTIVITY_STORY",
+            "unLockType": "NONE",
+            "storyVoiceInfo": { "nested": [1, 2, 3] }
+        });
+        assert!(matches!(
+            classify_story_entry(&extra_field),
+            StoryEntryParse::Strict(_)
+        ));
+
+        let retyped = serde_json::json!({
+            "storyId": "act1_st03",
+            "storyName": "字段类型变化",
+            "storySort": "3",
+            "storyTxt": "activities/act1/level_st03",
+            "unLockType": { "type": "NONE" }
+        });
+        let story = parse_story_entry("act1", &retyped).unwrap();
+        assert_eq!(story.story_sort, 3);
+        assert_eq!(story.unlock_type, "");
+
+        let broken = serde_json::json!({ "storyId": "act1_st04", "storyName": "无文本" });
+        assert!(parse_story_entry("act1", &broken).is_none());
+
+        let temp_root = temp_root("compat_report");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "act1": {
+                    "entryType": "ACTIVITY",
+                    "infoUnlockDatas": [missing_sort, extra_field, retyped, broken]
+                }
+            }),
+        );
+        let report = service.get_data_compat_report().unwrap();
+        assert_eq!(report.total_entries, 4);
+        assert_eq!(report.strict_entries, 2);
+        assert_eq!(report.lenient_ids, vec!["act1_st03".to_string()]);
+        assert_eq!(report.failed_ids, vec!["act1/act1_st04".to_string()]);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    fn story_json(story_id: &str, name: &str, group: &str, sort: i32) -> Value {
+        serde_json::json!({
+            "storyId": story_id,
+            "storyName": name,
+            "storyGroup": group,
+            "storySort": sort,
+            "storyTxt": format!("obt/{}/{}", group, story_id),
+            "storyReviewType": "COMPLETE",
+            "unLockType": "NONE"
+        })
+    }
+
+    #[test]
+    fn sync_report_counts_new_stories_against_snapshot() {
+        let temp_root = temp_root("sync_report");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [story_json("main_00_01", "序章", "main_0", 1)]
+                }
+            }),
+        );
+
+        // 首次安装：没有旧快照
+        let first = service.build_sync_report().unwrap();
+        assert!(first.first_install);
+        assert_eq!(first.new_story_count, 0);
+
+        service.save_story_snapshot().unwrap();
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [
+                        story_json("main_00_01", "序章", "main_0", 1),
+                        story_json("main_00_02", "新章节", "main_0", 2)
+                    ]
+                },
+                "act1": {
+                    "name": "新活动",
+                    "entryType": "ACTIVITY",
+                    "infoUnlockDatas": [story_json("act1_st01", "活动剧情", "act1", 1)]
+                }
+            }),
+        );
+        let story_file = service.story_dir().join("obt/act1/act1_st01.txt");
+        fs::create_dir_all(story_file.parent().unwrap()).unwrap();
+        fs::write(&story_file, "0123456789").unwrap();
+
+        let report = service.build_sync_report().unwrap();
+        assert!(!report.first_install);
+        assert_eq!(report.total_stories, 3);
+        assert_eq!(report.new_story_count, 2);
+        assert!(report.new_story_names.contains(&"活动剧情".to_string()));
+        assert_eq!(report.total_text_bytes, 10);
+        let main = report
+            .category_counts
+            .iter()
+            .find(|c| c.category == "主线")
+            .unwrap();
+        assert_eq!(main.count, 2);
+        assert_eq!(
+            service.get_sync_report().unwrap().unwrap().new_story_count,
+            2
+        );
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn new_stories_are_grouped_and_sorted_against_snapshot() {
+        let temp_root = temp_root("new_stories");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [story_json("main_00_01", "序章", "main_0", 1)]
+                }
+            }),
+        );
+
+        let first = service.get_new_stories().unwrap();
+        assert!(first.first_install);
+        assert!(first.groups.is_empty());
+
+        service.save_story_snapshot().unwrap();
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [
+                        story_json("main_00_01", "序章", "main_0", 1),
+                        story_json("main_00_02", "新章节", "main_0", 2)
+                    ]
+                },
+                "act1": {
+                    "name": "新活动",
+                    "entryType": "ACTIVITY",
+                    "infoUnlockDatas": [
+                        story_json("act1_st02", "活动剧情二", "act1", 2),
+                        story_json("act1_st01", "活动剧情一", "act1", 1)
+                    ]
+                }
+            }),
+        );
+
+        let result = service.get_new_stories().unwrap();
+        assert!(!result.first_install);
+        let categories: Vec<&str> = result.groups.iter().map(|g| g.category.as_str()).collect();
+        assert_eq!(categories, vec!["主线 | 主线 (main_0)", "活动 | 新活动"]);
+        assert_eq!(result.groups[0].stories[0].story_id, "main_00_02");
+        let activity_ids: Vec<&str> = result.groups[1]
+            .stories
+            .iter()
+            .map(|s| s.story_id.as_str())
+            .collect();
+        assert_eq!(activity_ids, vec!["act1_st01", "act1_st02"]);
+        assert_eq!(result.groups[1].stories[0].story_txt, "obt/act1/act1_st01");
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    fn write_fixture_tar_gz(path: &Path, entries: &[(&str, Vec<u8>)]) {
+        let file = fs::File::create(path).unwrap();
+        let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+
+        // codeload 生成的归档以 pax 全局头开头
+        let comment = b"52 comment=0123456789abcdef0123456789abcdef01234567\n";
+        let mut header = tar::Header::new_ustar();
+        header.set_entry_type(tar::EntryType::XGlobalHeader);
+        header.set_path("pax_global_header").unwrap();
+        header.set_size(comment.len() as u64);
+        header.set_cksum();
+        builder.append(&header, &comment[..]).unwrap();
+
+        for (name, data) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, &data[..]).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn tar_gz_and_zip_archives_extract_to_same_root() {
+        let temp_root = temp_root("archive_formats");
+        fs::create_dir_all(&temp_root).unwrap();
+        let table = b"{\"main_0\": {}}".to_vec();
+        let entries = [
+            (
+                "ArknightsGameData-master/zh_CN/gamedata/excel/story_review_table.json",
+                table.clone(),
+            ),
+            (
+                "ArknightsGameData-master/zh_CN/gamedata/story/obt/main/level_main_00-01_beg.txt",
+                b"[HEADER]".to_vec(),
+            ),
+        ];
+
+        let tar_path = temp_root.join("fixture.tar.gz");
+        write_fixture_tar_gz(&tar_path, &entries);
+        let tar_root = temp_root.join("tar");
+        fs::create_dir_all(&tar_root).unwrap();
+        let extracted = extract_tar_gz_entries(
+            fs::File::open(&tar_path).unwrap(),
+            &tar_root,
+            &CancelToken::default(),
+        )
+        .unwrap();
+        assert_eq!(extracted, 2);
+
+        let zip_path = temp_root.join("fixture.zip");
+        write_fixture_zip(&zip_path, &entries);
+        let zip_root = temp_root.join("zip");
+        fs::create_dir_all(&zip_root).unwrap();
+        extract_zip_entries(
+            &zip_path,
+            &zip_root,
+            Duration::from_secs(60),
+            &CancelToken::default(),
+            &mut |_, _| {},
+        )
+        .unwrap();
+
+        for root in [&tar_root, &zip_root] {
+            let data_root = find_extracted_root(root, "zh_CN").unwrap();
+            assert_eq!(data_root.file_name().unwrap(), "ArknightsGameData-master");
+            let content =
+                fs::read(data_root.join("zh_CN/gamedata/excel/story_review_table.json")).unwrap();
+            assert_eq!(content, table);
+        }
+        assert!(!tar_root.join("pax_global_header").exists());
+
+        assert!(enclosed_path(Path::new("../evil.txt")).is_none());
+        assert!(enclosed_path(Path::new("/etc/passwd")).is_none());
+        assert_eq!(
+            enclosed_path(Path::new("./Root/a.txt")).unwrap(),
+            PathBuf::from("Root/a.txt")
+        );
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    /// 先返回一段数据，之后永久阻塞，模拟静默断开的移动网络连接
+    struct HangingReader {
+        sent: bool,
+    }
+
+    impl Read for HangingReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if !self.sent {
+                self.sent = true;
+                buf[..4].copy_from_slice(b"data");
+                return Ok(4);
+            }
+            loop {
+                std::thread::park();
+            }
+        }
+    }
+
+    #[test]
+    fn stall_watchdog_aborts_silent_stream() {
+        let mut reader =
+            StallWatchdogReader::new(HangingReader { sent: false }, Duration::from_millis(50));
+        let mut buf = [0u8; 16];
+        assert_eq!(reader.read(&mut buf).unwrap(), 4);
+        assert_eq!(&buf[..4], b"data");
+
+        let started = Instant::now();
+        let err = reader.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(download_read_error("ignored", &err), DOWNLOAD_STALLED_ERROR);
+
+        let mut complete = StallWatchdogReader::new(
+            std::io::Cursor::new(vec![7u8; 200_000]),
+            Duration::from_secs(5),
+        );
+        let mut collected = Vec::new();
+        complete.read_to_end(&mut collected).unwrap();
+        assert_eq!(collected.len(), 200_000);
+    }
+
+    #[test]
+    fn data_bundle_round_trips_through_zip_import() {
+        let temp_root = temp_root("data_bundle");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [story_json("main_00_01", "序章", "main_0", 1)]
+                }
+            }),
+        );
+        let story_file = service.story_dir().join("obt/main_0/main_00_01.txt");
+        fs::create_dir_all(story_file.parent().unwrap()).unwrap();
+        fs::write(&story_file, "[name=\"阿米娅\"]博士。").unwrap();
+        let unrelated = service.gamedata_dir().join("levels/huge.json");
+        fs::create_dir_all(unrelated.parent().unwrap()).unwrap();
+        fs::write(&unrelated, "{}").unwrap();
+        service
+            .write_version(&VersionInfo {
+                commit: "abcdef1234567".to_string(),
+                fetched_at: 1,
+                source: None,
+            })
+            .unwrap();
+
+        let bundle_path = temp_root.join("bundle.zip");
+        let mut progress = Vec::new();
+        service
+            .write_data_bundle(&bundle_path, &mut |current, total| {
+                progress.push((current, total))
+            })
+            .unwrap();
+        assert_eq!(progress.last(), Some(&(2, 2)));
+
+        fs::remove_dir_all(&service.data_dir).unwrap();
+        assert!(!service.is_installed());
+
+        let task = service.begin_task("import").unwrap();
+        let mut phases = Vec::new();
+        service
+            .import_zip_file(&bundle_path, &task, &mut |phase, _, _| {
+                phases.push(phase.to_string())
+            })
+            .unwrap();
+        assert!(phases.iter().any(|phase| phase == "解压"));
+
+        assert!(service.is_installed());
+        assert_eq!(service.read_version().unwrap().commit, "abcdef1234567");
+        assert_eq!(
+            fs::read_to_string(&story_file).unwrap(),
+            "[name=\"阿米娅\"]博士。"
+        );
+        assert!(!unrelated.exists());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn extracted_root_is_found_for_odd_layouts() {
+        let temp_root = temp_root("extract_layouts");
+        let marker = "zh_CN/gamedata/excel/story_review_table.json";
+        let touch = |path: PathBuf| {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "{}").unwrap();
+        };
+
+        // 语言目录直接位于顶层：解压目录本身就是数据根目录
+        let top_level = temp_root.join("top_level");
+        touch(top_level.join(marker));
+        assert_eq!(find_extracted_root(&top_level, "zh_CN").unwrap(), top_level);
+
+        // 多套一层目录
+        let nested = temp_root.join("nested");
+        touch(nested.join("pack/ArknightsGameData").join(marker));
+        assert_eq!(
+            find_extracted_root(&nested, "zh_CN").unwrap(),
+            nested.join("pack/ArknightsGameData")
+        );
+
+        // __MACOSX 排在前面且包含同名的资源分叉文件
+        let macos = temp_root.join("macos");
+        touch(macos.join("__MACOSX/ArknightsGameData").join(marker));
+        touch(macos.join("ArknightsGameData").join(marker));
+        assert_eq!(
+            find_extracted_root(&macos, "zh_CN").unwrap(),
+            macos.join("ArknightsGameData")
+        );
+
+        let broken = temp_root.join("broken");
+        touch(broken.join("something/else.json"));
+        let err = find_extracted_root(&broken, "zh_CN").unwrap_err();
+        assert!(err.contains(marker), "{}", err);
+        assert!(err.contains("something"), "{}", err);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    /// 用 ASCII 占位名写入后再替换为原始字节，得到不带 UTF-8 标记的非 UTF-8 文件名
+    fn write_raw_name_zip(path: &Path, entries: &[(Vec<u8>, &[u8])]) {
+        let placeholders: Vec<String> = entries
+            .iter()
+            .enumerate()
+            .map(|(i, (raw, _))| {
+                let prefix = format!("#{}#", i);
+                format!("{}{}", prefix, "x".repeat(raw.len() - prefix.len()))
+            })
+            .collect();
+        let owned: Vec<(&str, Vec<u8>)> = placeholders
+            .iter()
+            .zip(entries)
+            .map(|(name, (_, data))| (name.as_str(), data.to_vec()))
+            .collect();
+        write_fixture_zip(path, &owned);
+
+        let mut bytes = fs::read(path).unwrap();
+        for (placeholder, (raw, _)) in placeholders.iter().zip(entries) {
+            let needle = placeholder.as_bytes();
+            let mut pos = 0;
+            while let Some(offset) = bytes[pos..].windows(needle.len()).position(|w| w == needle) {
+                let start = pos + offset;
+                bytes[start..start + needle.len()].copy_from_slice(raw);
+                pos = start + needle.len();
+            }
+        }
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn gbk_entry_names_are_decoded_and_bad_names_counted() {
+        let temp_root = temp_root("gbk_names");
+        fs::create_dir_all(&temp_root).unwrap();
+        let (gbk_name, _, _) = encoding_rs::GBK.encode("明日方舟/剧情/序章.txt");
+        assert!(std::str::from_utf8(&gbk_name).is_err());
+
+        let zip_path = temp_root.join("gbk.zip");
+        write_raw_name_zip(
+            &zip_path,
+            &[
+                (gbk_name.to_vec(), b"prologue"),
+                (b"../escape.txt".to_vec(), b"nope"),
+            ],
+        );
+        let extract_root = temp_root.join("gbk_out");
+        fs::create_dir_all(&extract_root).unwrap();
+        let skipped = extract_zip_entries(
+            &zip_path,
+            &extract_root,
+            Duration::from_secs(60),
+            &CancelToken::default(),
+            &mut |_, _| {},
+        )
+        .unwrap();
+        assert_eq!(skipped, 1);
+        assert_eq!(
+            fs::read(extract_root.join("明日方舟/剧情/序章.txt")).unwrap(),
+            b"prologue"
+        );
+        assert!(!temp_root.join("escape.txt").exists());
+
+        let bad_zip = temp_root.join("bad.zip");
+        let bad_entries: Vec<(Vec<u8>, &[u8])> = (0..SKIPPED_ENTRY_LIMIT + 1)
+            .map(|i| (format!("../bad_{}.txt", i).into_bytes(), &b"x"[..]))
+            .collect();
+        write_raw_name_zip(&bad_zip, &bad_entries);
+        let bad_root = temp_root.join("bad_out");
+        fs::create_dir_all(&bad_root).unwrap();
+        let err = extract_zip_entries(
+            &bad_zip,
+            &bad_root,
+            Duration::from_secs(60),
+            &CancelToken::default(),
+            &mut |_, _| {},
+        )
+        .unwrap_err();
+        assert!(err.contains("../bad_0.txt"), "{}", err);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn corrupt_archives_are_classified_and_removed() {
+        let temp_root = temp_root("corrupt_archive");
+        let service = DataService::new(temp_root.clone());
+        fs::create_dir_all(&temp_root).unwrap();
+
+        let garbage_path = temp_root.join("ArknightsGameData.zip");
+        let garbage: Vec<u8> = (0..4096u32).map(|i| (i * 7919 % 251) as u8).collect();
+        fs::write(&garbage_path, garbage).unwrap();
+        let err = service
+            .extract_zip_at(
+                &garbage_path,
+                &temp_root,
+                &CancelToken::default(),
+                &mut |_, _| {},
+            )
+            .unwrap_err();
+        assert!(err.starts_with(CORRUPT_ARCHIVE_ERROR), "{}", err);
+        assert!(!garbage_path.exists());
+        assert!(!temp_root.join("ArknightsGameData_extract").exists());
+
+        // 破坏条目数据，CRC 校验失败时应指出具体条目
+        let zip_path = temp_root.join("damaged.zip");
+        let name = "Root/zh_CN/gamedata/story/large.txt";
+        let payload: Vec<u8> = (0..200_000u32).map(|i| (i % 97) as u8).collect();
+        write_fixture_zip(&zip_path, &[(name, payload)]);
+        let mut bytes = fs::read(&zip_path).unwrap();
+        let data_start = 30 + name.len();
+        for byte in &mut bytes[data_start + 40..data_start + 60] {
+            *byte ^= 0xFF;
+        }
+        fs::write(&zip_path, bytes).unwrap();
+        let extract_root = temp_root.join("damaged_out");
+        fs::create_dir_all(&extract_root).unwrap();
+        let err = extract_zip_entries(
+            &zip_path,
+            &extract_root,
+            Duration::from_secs(60),
+            &CancelToken::default(),
+            &mut |_, _| {},
+        )
+        .unwrap_err();
+        assert!(err.starts_with(CORRUPT_ARCHIVE_ERROR), "{}", err);
+        assert!(err.contains(name), "{}", err);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    fn snapshot_tree(root: &Path) -> Vec<(String, Vec<u8>)> {
+        let mut files: Vec<(String, Vec<u8>)> = walkdir::WalkDir::new(root)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| {
+                let relative = entry.path().strip_prefix(root).unwrap();
+                (
+                    relative.to_string_lossy().replace('\\', "/"),
+                    fs::read(entry.path()).unwrap(),
+                )
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn parallel_extraction_matches_serial_output() {
+        let temp_root = temp_root("parallel_extract");
+        fs::create_dir_all(&temp_root).unwrap();
+        let zip_path = temp_root.join("fixture.zip");
+        let mut entries: Vec<(String, Vec<u8>)> = (0..300)
+            .map(|i| {
+                (
+                    format!(
+                        "Root/zh_CN/gamedata/story/obt/dir_{}/story_{}.txt",
+                        i % 17,
+                        i
+                    ),
+                    format!("[name=\"{}\"]line {}", i, i)
+                        .repeat(i % 50 + 1)
+                        .into_bytes(),
+                )
+            })
+            .collect();
+        entries.push((
+            "Root/zh_CN/gamedata/excel/large.json".to_string(),
+            (0..2_000_000u32).map(|i| (i % 253) as u8).collect(),
+        ));
+        let borrowed: Vec<(&str, Vec<u8>)> = entries
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.clone()))
+            .collect();
+        write_fixture_zip(&zip_path, &borrowed);
+
+        let serial_root = temp_root.join("serial");
+        fs::create_dir_all(&serial_root).unwrap();
+        extract_zip_entries(
+            &zip_path,
+            &serial_root,
+            Duration::from_secs(60),
+            &CancelToken::default(),
+            &mut |_, _| {},
+        )
+        .unwrap();
+
+        let parallel_root = temp_root.join("parallel");
+        fs::create_dir_all(&parallel_root).unwrap();
+        let mut last_percent = 0.0;
+        extract_zip_entries_parallel(
+            &zip_path,
+            &parallel_root,
+            4,
+            &CancelToken::default(),
+            &mut |percent, _| {
+                assert!(percent >= last_percent);
+                last_percent = percent;
+            },
+        )
+        .unwrap();
+
+        assert_eq!(last_percent, 100.0);
+        let serial = snapshot_tree(&serial_root);
+        assert_eq!(serial.len(), 301);
+        assert_eq!(serial, snapshot_tree(&parallel_root));
+
+        // 任一条目损坏时整体失败并指出该条目
+        let damaged_path = temp_root.join("damaged.zip");
+        let name = "Root/zh_CN/gamedata/excel/large.json";
+        write_fixture_zip(
+            &damaged_path,
+            &[
+                (name, (0..2_000_000u32).map(|i| (i % 253) as u8).collect()),
+                ("Root/ok.txt", b"fine".to_vec()),
+            ],
+        );
+        let mut bytes = fs::read(&damaged_path).unwrap();
+        let data_start = 30 + name.len();
+        for byte in &mut bytes[data_start + 100..data_start + 140] {
+            *byte ^= 0xFF;
+        }
+        fs::write(&damaged_path, bytes).unwrap();
+        let damaged_root = temp_root.join("damaged_out");
+        fs::create_dir_all(&damaged_root).unwrap();
+        let err = extract_zip_entries_parallel(
+            &damaged_path,
+            &damaged_root,
+            2,
+            &CancelToken::default(),
+            &mut |_, _| {},
+        )
+        .unwrap_err();
+        assert!(err.starts_with(CORRUPT_ARCHIVE_ERROR), "{}", err);
+        assert!(err.contains(name), "{}", err);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn busy_flag_rejects_concurrent_operations() {
+        let temp_root = temp_root("busy_flag");
+        let service = DataService::new(temp_root.clone());
+        assert!(!service.get_busy_state().busy);
+
+        let (held_tx, held_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let holder = service.clone();
+        let worker = std::thread::spawn(move || {
+            let _guard = holder.begin_task("sync").unwrap();
+            held_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+        });
+        held_rx.recv().unwrap();
+
+        let state = service.get_busy_state();
+        assert!(state.busy);
+        assert_eq!(state.task.as_deref(), Some("sync"));
+        assert_eq!(service.begin_task("import").err().unwrap(), "BUSY:sync");
+        assert_eq!(service.set_data_source("en").unwrap_err(), "BUSY:sync");
+
+        release_tx.send(()).unwrap();
+        worker.join().unwrap();
+        assert!(!service.get_busy_state().busy);
+        assert!(service.begin_task("import").is_ok());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn long_paths_are_created_in_extended_form() {
+        let temp_root = temp_root("long_paths");
+        let mut deep = temp_root.clone();
+        while deep.as_os_str().len() <= 300 {
+            deep.push("ArknightsGameData_extract_long_segment");
+        }
+        let file_path = deep.join("level_main_00-01_beg.txt");
+        assert!(file_path.as_os_str().len() > 260);
+
+        let extended = long_path(&file_path);
+        assert!(extended.to_string_lossy().starts_with(r"\\?\"));
+        assert_eq!(long_path(&extended), extended);
+        fs::create_dir_all(extended.parent().unwrap()).unwrap();
+        fs::write(&extended, "ok").unwrap();
+        assert_eq!(fs::read_to_string(&extended).unwrap(), "ok");
+
+        let with_dots = long_path(&temp_root.join("a").join("..").join("b.txt"));
+        assert!(with_dots.ends_with("b.txt"));
+        assert!(!with_dots.to_string_lossy().contains(".."));
+
+        let _ = fs::remove_dir_all(long_path(&temp_root));
+    }
+
+    #[test]
+    fn read_story_text_rejects_traversal_and_ignores_case() {
+        let temp_root = temp_root("story_paths");
+        let service = DataService::new(temp_root.clone());
+        let story_file = service
+            .story_dir()
+            .join("obt/rogue/rogue_1/level_rogue1_01.txt");
+        fs::create_dir_all(story_file.parent().unwrap()).unwrap();
+        fs::write(&story_file, "[HEADER]").unwrap();
+        fs::write(temp_root.join("secret.txt"), "secret").unwrap();
+
+        assert_eq!(
+            service
+                .read_story_text("obt/rogue/rogue_1/level_rogue1_01")
+                .unwrap(),
+            "[HEADER]"
+        );
+        assert_eq!(
+            service
+                .read_story_text("/Obt/Rogue/rogue_1/LEVEL_ROGUE1_01")
+                .unwrap(),
+            "[HEADER]"
+        );
+        assert_eq!(
+            service
+                .read_story_text("obt\\rogue\\rogue_1\\level_rogue1_01")
+                .unwrap(),
+            "[HEADER]"
+        );
+
+        for path in [
+            "../../../../secret",
+            "obt/../../../../secret",
+            "C:/Windows/win",
+        ] {
+            let err = service.read_story_text(path).unwrap_err();
+            assert!(err.starts_with(INVALID_PATH_ERROR), "{}: {}", path, err);
+        }
+        assert!(service
+            .read_story_info("../../../secret")
+            .unwrap_err()
+            .starts_with(INVALID_PATH_ERROR));
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    fn write_month_chat_parts(service: &DataService) {
+        let dir = service
+            .story_dir()
+            .join("obt/rogue/rogue_1/month_chat_rogue_1_1");
+        fs::create_dir_all(&dir).unwrap();
+        for part in [10, 2, 1] {
+            fs::write(
+                dir.join(format!("month_chat_rogue_1_1_{}.txt", part)),
+                format!("[name=\"干员\"]第{}段", part),
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn story_parts_are_concatenated_in_numeric_order() {
+        let temp_root = temp_root("month_chat_order");
+        let service = DataService::new(temp_root.clone());
+        write_month_chat_parts(&service);
+
+        let merged = service
+            .read_story_text("obt/rogue/rogue_1/month_chat_rogue_1_1")
+            .unwrap();
+        let first = merged.find("第1段").unwrap();
+        let second = merged.find("第2段").unwrap();
+        let tenth = merged.find("第10段").unwrap();
+        assert!(first < second && second < tenth, "{}", merged);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn story_part_separator_parses_as_header() {
+        let temp_root = temp_root("month_chat_header");
+        let service = DataService::new(temp_root.clone());
+        write_month_chat_parts(&service);
+
+        let merged = service
+            .read_story_text("obt/rogue/rogue_1/month_chat_rogue_1_1")
+            .unwrap();
+        let parsed = parse_story_text(&merged);
+        let headers: Vec<&str> = parsed
+            .segments
+            .iter()
+            .filter_map(|segment| match segment {
+                StorySegment::Header { title } => Some(title.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(headers, vec!["第 2 部分", "第 3 部分"]);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn excel_tables_are_cached_until_invalidated() {
+        let temp_root = temp_root("table_cache");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [story_json("main_00_01", "序章", "main_0", 1)]
+                }
+            }),
+        );
+        let reads = || service.table_cache.disk_reads.load(AtomicOrdering::Relaxed);
+
+        assert_eq!(service.get_memory_stories().unwrap().len(), 0);
+        assert_eq!(reads(), 1);
+        // 克隆共享同一份缓存，再次读取不会访问磁盘
+        let clone = service.clone();
+        assert_eq!(clone.get_main_stories_grouped(None).unwrap().len(), 1);
+        service.get_story_categories(None).unwrap();
+        assert_eq!(reads(), 1);
+
+        service.invalidate_caches();
+        service.get_story_categories(None).unwrap();
+        assert_eq!(reads(), 2);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn prewarm_fills_table_cache_and_yields_to_busy_tasks() {
+        let temp_root = temp_root("prewarm");
+        let service = DataService::new(temp_root.clone());
+        assert!(service.prewarm_tables().unwrap().is_none());
+
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [story_json("main_00_01", "序章", "main_0", 1)]
+                }
+            }),
+        );
+
+        let guard = service.begin_task("sync").unwrap();
+        assert!(service.prewarm_tables().unwrap().is_none());
+        drop(guard);
+
+        // 同步开始时会取消正在进行的预热
+        let prewarm = service.tasks.start("prewarm", false).unwrap();
+        assert_eq!(service.tasks.cancel_kind("prewarm"), 1);
+        assert!(service.prewarm_with_task(&prewarm).unwrap().is_none());
+        drop(prewarm);
+        assert!(service.tasks.list().is_empty());
+
+        let report = service.prewarm_tables().unwrap().unwrap();
+        assert_eq!(report.tables, 1);
+        assert_eq!(report.stories, 1);
+        let reads = service.table_cache.disk_reads.load(AtomicOrdering::Relaxed);
+        service.get_main_stories_grouped(None).unwrap();
+        assert_eq!(
+            service.table_cache.disk_reads.load(AtomicOrdering::Relaxed),
+            reads
+        );
+
+        service
+            .settings
+            .update(|settings| {
+                settings.prewarm_cache = false;
+                Ok(())
+            })
+            .unwrap();
+        assert!(service.prewarm_tables().unwrap().is_none());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn story_categories_cover_all_entry_types_and_honor_include() {
+        let temp_root = temp_root("story_categories");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_1": {
+                    "entryType": "MAINLINE",
+                    "name": "黑暗时代·下",
+                    "infoUnlockDatas": [story_json("main_01_01", "1-1", "main_1", 1)]
+                },
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "name": "黑暗时代·上",
+                    "infoUnlockDatas": [
+                        story_json("main_00_02", "0-2", "main_0", 2),
+                        story_json("main_00_01", "0-1", "main_0", 1)
+                    ]
+                },
+                "act1mini": {
+                    "entryType": "MINI_ACTIVITY",
+                    "name": "小型活动",
+                    "startTime": 10,
+                    "infoUnlockDatas": [story_json("act1mini_01", "mini", "act1mini", 1)]
+                },
+                "story_1": {
+                    "entryType": "NONE",
+                    "infoUnlockDatas": [story_json("story_1_1", "密录", "story_1", 1)]
+                },
+                "rune_1": {
+                    "entryType": "RUNE",
+                    "infoUnlockDatas": [story_json("rune_1_1", "合约", "rune_1", 1)]
+                }
+            }),
+        );
+
+        let categories = service.get_story_categories(None).unwrap();
+        let ids: Vec<&str> = categories.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["mainline", "activity", "memory", "rune"]);
+        let mainline: Vec<&str> = categories[0]
+            .stories
+            .iter()
+            .map(|s| s.story_id.as_str())
+            .collect();
+        assert_eq!(mainline, vec!["main_00_01", "main_00_02", "main_01_01"]);
+
+        let include = vec!["rune".to_string(), "mainline".to_string()];
+        let subset = service.get_story_categories(Some(&include)).unwrap();
+        let ids: Vec<&str> = subset.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["mainline", "rune"]);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn activities_keep_table_ids_and_normalize_times() {
+        let temp_root = temp_root("activities");
+        let service = DataService::new(temp_root.clone());
+        // 摘自 story_review_table.json 的活动条目（字段已裁剪）
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "act9d0": {
+                    "id": "act9d0",
+                    "name": "生于黑夜",
+                    "entryType": "ACTIVITY",
+                    "actType": "ACTIVITY_STORY",
+                    "startTime": 1566975600,
+                    "endTime": 0,
+                    "infoUnlockDatas": [story_json("act9d0_st01", "序章", "act9d0", 1)]
+                },
+                "act_entry": {
+                    "id": "act4d0",
+                    "name": "骑兵与猎人·复刻",
+                    "entryType": "ACTIVITY",
+                    "actType": "ACTIVITY_STORY",
+                    "startTime": 1557900000,
+                    "endTime": 1559000000,
+                    "infoUnlockDatas": []
+                },
+                "act_mini": {
+                    "name": "未定档活动",
+                    "entryType": "ACTIVITY",
+                    "startTime": -1,
+                    "endTime": -1,
+                    "infoUnlockDatas": []
+                },
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [story_json("main_00_01", "序章", "main_0", 1)]
+                }
+            }),
+        );
+
+        let activities = service.get_activities().unwrap();
+        let ids: Vec<&str> = activities.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, vec!["act4d0", "act9d0", "act_mini"]);
+
+        assert!(activities[0].is_rerun);
+        assert_eq!(activities[0].end_time, Some(1559000000));
+        assert!(!activities[1].is_rerun);
+        assert_eq!(activities[1].end_time, None);
+        assert_eq!(activities[1].info_unlock_datas.len(), 1);
+        assert_eq!(activities[2].end_time, None);
+        assert_eq!(activities[2].act_type, "");
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn memory_stories_group_by_operator() {
+        let temp_root = temp_root("memory_grouped");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "story_amiya_set_1": {
+                    "entryType": "NONE",
+                    "infoUnlockDatas": [
+                        story_json("story_amiya_set_1_2", "下", "story_amiya_set_1", 2),
+                        story_json("story_amiya_set_1_1", "上", "story_amiya_set_1", 1)
+                    ]
+                },
+                "story_silverash_set_1": {
+                    "entryType": "NONE",
+                    "infoUnlockDatas": [
+                        story_json("story_silverash_set_1_1", "往昔一瞥", "story_silverash_set_1", 1)
+                    ]
+                },
+                "story_orphan_set_1": {
+                    "entryType": "NONE",
+                    "infoUnlockDatas": [
+                        story_json("story_orphan_set_1_1", "无主", "story_orphan_set_1", 1)
+                    ]
+                }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "handbook_info_table.json",
+            &serde_json::json!({
+                "handbookDict": {
+                    "char_002_amiya": {
+                        "charID": "char_002_amiya",
+                        "handbookAvgList": [{ "storySetId": "story_amiya_set_1", "avgList": [] }]
+                    },
+                    "char_172_svrash": {
+                        "charID": "char_172_svrash",
+                        "handbookAvgList": [{
+                            "storySetId": "other_set",
+                            "avgList": [{ "storyId": "story_silverash_set_1_1" }]
+                        }]
+                    }
+                }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_002_amiya": { "name": "阿米娅", "rarity": "TIER_5" },
+                "char_172_svrash": { "name": "银灰", "rarity": 5 }
+            }),
+        );
+
+        let groups = service.get_memory_stories_grouped().unwrap();
+        let summary: Vec<(&str, &str, u8)> = groups
+            .iter()
+            .map(|g| (g.char_id.as_str(), g.char_name.as_str(), g.rarity))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("char_172_svrash", "银灰", 6),
+                ("char_002_amiya", "阿米娅", 5),
+                ("", "未知干员", 0)
+            ]
+        );
+        let amiya: Vec<&str> = groups[1]
+            .stories
+            .iter()
+            .map(|s| s.story_id.as_str())
+            .collect();
+        assert_eq!(amiya, vec!["story_amiya_set_1_1", "story_amiya_set_1_2"]);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn memory_stories_carry_unlock_requirements() {
+        let temp_root = temp_root("memory_unlock");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "story_amiya_set_1": {
+                    "entryType": "NONE",
+                    "infoUnlockDatas": [
+                        story_json("story_amiya_set_1_1", "上", "story_amiya_set_1", 1),
+                        story_json("story_amiya_set_1_2", "下", "story_amiya_set_1", 2)
+                    ]
+                },
+                "story_plain_set_1": {
+                    "entryType": "NONE",
+                    "infoUnlockDatas": [story_json("story_plain_set_1_1", "无条件", "story_plain_set_1", 1)]
+                }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "handbook_info_table.json",
+            &serde_json::json!({
+                "handbookDict": {
+                    "char_002_amiya": {
+                        "charID": "char_002_amiya",
+                        "handbookAvgList": [{
+                            "storySetId": "story_amiya_set_1",
+                            "unlockParam": [
+                                { "unlockType": "FAVOR", "unlockParam1": "50", "unlockParam2": null },
+                                { "unlockType": "ITEM", "unlockParam1": "3211", "unlockParam2": "2" }
+                            ],
+                            "avgList": [
+                                { "storyId": "story_amiya_set_1_1" },
+                                { "storyId": "story_amiya_set_1_2" }
+                            ]
+                        }]
+                    }
+                }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "item_table.json",
+            &serde_json::json!({ "items": { "3211": { "name": "先锋芯片" } } }),
+        );
+
+        let stories = service.get_memory_stories().unwrap();
+        let amiya: Vec<&StoryEntry> = stories
+            .iter()
+            .filter(|s| s.story_group == "story_amiya_set_1")
+            .collect();
+        assert_eq!(amiya.len(), 2);
+        for story in amiya {
+            assert_eq!(story.unlock_type, "FAVOR");
+            assert_eq!(story.cost_item_id.as_deref(), Some("3211"));
+            assert_eq!(story.cost_item_count, Some(2));
+            assert_eq!(
+                story.unlock_desc.as_deref(),
+                Some("解锁需要信赖值50，消耗先锋芯片×2")
+            );
+        }
+        let plain = stories
+            .iter()
+            .find(|s| s.story_id == "story_plain_set_1_1")
+            .unwrap();
+        assert_eq!(plain.unlock_desc, None);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn rune_stories_group_by_crisis_season() {
+        let temp_root = temp_root("rune_grouped");
+        let service = DataService::new(temp_root.clone());
+        let mut review_story = story_json("rune_s2_01", "行动简报", "rune_season_2", 1);
+        review_story["storyTxt"] = Value::from("Obt/Rune/rune_season_2/level_01");
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "rune_season_2": { "entryType": "RUNE", "infoUnlockDatas": [review_story] }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "crisis_table.json",
+            &serde_json::json!({
+                "seasonInfo": [
+                    { "seasonId": "rune_season_1", "name": "蛮鳞行动" },
+                    { "seasonId": "rune_season_2", "name": "燃灰行动" }
+                ]
+            }),
+        );
+        write_excel_table(
+            &service,
+            "crisis_v2_table.json",
+            &serde_json::json!({
+                "seasonInfoDataMap": {
+                    "crisis_v2_season_1_1": { "seasonId": "crisis_v2_season_1_1", "name": "荒芜拉普兰德行动" }
+                },
+                "storyData": [
+                    { "storyTxt": "Obt/Rune/crisis_v2_season_1_1/level_02", "desc": "荒原来信" }
+                ]
+            }),
+        );
+        write_excel_table(
+            &service,
+            "story_table.json",
+            &serde_json::json!({
+                "Obt/Rune/rune_season_2/level_01": {},
+                "Obt/Rune/rune_season_2/level_02": {},
+                "Obt/Rune/crisis_v2_season_1_1/level_02": {},
+                "Obt/Rune/crisis_v2_season_1_1/level_01": {},
+                "Obt/Roguelike/ro1/level_01": {}
+            }),
+        );
+
+        let groups = service.get_rune_stories_grouped().unwrap();
+        let summary: Vec<(&str, Vec<&str>)> = groups
+            .iter()
+            .map(|group| {
+                (
+                    group.name.as_str(),
+                    group
+                        .stories
+                        .iter()
+                        .map(|s| s.story_name.as_str())
+                        .collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("燃灰行动", vec!["行动简报", "燃灰行动 2"]),
+                ("荒芜拉普兰德行动", vec!["荒芜拉普兰德行动 1", "荒原来信"]),
+            ]
+        );
+
+        let indexed = service.collect_stories_for_index().unwrap();
+        assert_eq!(indexed.len(), 1);
+        assert_eq!(
+            DataService::format_category_label(&indexed[0].entry_type, &indexed[0].category_name),
+            "危机合约 | 燃灰行动"
+        );
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn roguelike_group_keys_map_to_topic_names() {
+        let topics = roguelike_topic_names(&serde_json::json!({
+            "topics": {
+                "rogue_1": { "id": "rogue_1", "name": "傀影与猩红孤钻" },
+                "rogue_2": { "id": "rogue_2", "name": "水月与深蓝之树" }
+            }
+        }));
+
+        assert_eq!(roguelike_group_label("RO1", &topics), "傀影与猩红孤钻");
+        assert_eq!(roguelike_group_label("ROGUE_2", &topics), "水月与深蓝之树");
+        assert_eq!(
+            roguelike_group_label("MONTH_CHAT_ROGUE_1", &topics),
+            "傀影与猩红孤钻 · 月度小队记闻"
+        );
+        assert_eq!(roguelike_group_label("RO9", &topics), "RO9");
+        assert_eq!(
+            roguelike_group_key("Obt/Rogue/rogue_1/month_chat_rogue_1_1").as_deref(),
+            Some("MONTH_CHAT_ROGUE_1")
+        );
+        assert_eq!(
+            roguelike_group_key("obt/roguelike/ro2/level_rogue2_1").as_deref(),
+            Some("RO2")
+        );
+    }
+
+    #[test]
+    fn roguelike_groups_use_topic_names_and_keep_keys() {
+        let temp_root = temp_root("roguelike_topics");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(&service, "story_review_table.json", &serde_json::json!({}));
+        write_excel_table(
+            &service,
+            "story_table.json",
+            &serde_json::json!({
+                "Obt/Roguelike/ro2/level_rogue2_1": {},
+                "Obt/Roguelike/ro1/level_rogue1_1": {}
+            }),
+        );
+        write_excel_table(
+            &service,
+            "roguelike_topic_table.json",
+            &serde_json::json!({
+                "topics": { "rogue_1": { "id": "rogue_1", "name": "傀影与猩红孤钻" } }
+            }),
+        );
+
+        let groups = service.get_roguelike_stories_grouped().unwrap();
+        let labels: Vec<&str> = groups.iter().map(|group| group.name.as_str()).collect();
+        assert_eq!(labels, vec!["傀影与猩红孤钻", "RO2"]);
+        assert_eq!(groups[0].stories[0].story_group, "RO1");
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn roguelike_endbooks_group_by_ending_in_declared_order() {
+        let temp_root = temp_root("roguelike_endbook");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(&service, "story_review_table.json", &serde_json::json!({}));
+        write_excel_table(
+            &service,
+            "story_table.json",
+            &serde_json::json!({
+                "Obt/Roguelike/ro1/endbook/endbook_1_a": {},
+                "Obt/Roguelike/ro1/endbook/endbook_1_b": {},
+                "Obt/Roguelike/ro1/endbook/endbook_2_a": {},
+                "Obt/Roguelike/ro1/level_rogue1_1": {}
+            }),
+        );
+        // 裁剪自 roguelike_topic_table.json
+        write_excel_table(
+            &service,
+            "roguelike_topic_table.json",
+            &serde_json::json!({
+                "topics": { "rogue_1": { "id": "rogue_1", "name": "傀影与猩红孤钻" } },
+                "details": {
+                    "rogue_1": {
+                        "endbookGroupData": {
+                            "ending_1": {
+                                "endingId": "ending_1",
+                                "endbookName": "猩红的终幕",
+                                "chapters": [
+                                    { "sortId": 2, "textId": "Obt/Roguelike/ro1/endbook/endbook_1_a", "title": "谢幕" },
+                                    { "sortId": 1, "textId": "Obt/Roguelike/ro1/endbook/endbook_1_b", "title": "开场" }
+                                ]
+                            },
+                            "ending_2": {
+                                "endingId": "ending_2",
+                                "endbookName": "无人的剧院",
+                                "chapters": [
+                                    { "sortId": 1, "textId": "Obt/Roguelike/ro1/endbook/endbook_2_a" }
+                                ]
+                            }
+                        }
+                    }
+                }
+            }),
+        );
+
+        let groups = service.get_roguelike_stories_grouped().unwrap();
+        let summary: Vec<(&str, Vec<&str>)> = groups
+            .iter()
+            .map(|group| {
+                (
+                    group.name.as_str(),
+                    group
+                        .stories
+                        .iter()
+                        .map(|s| s.story_name.as_str())
+                        .collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("傀影与猩红孤钻", vec!["level_rogue1_1"]),
+                ("傀影与猩红孤钻 · 猩红的终幕", vec!["开场", "谢幕"]),
+                ("傀影与猩红孤钻 · 无人的剧院", vec!["无人的剧院 · 第1章"]),
+            ]
+        );
+        assert_eq!(groups[1].stories[0].avg_tag.as_deref(), Some("结局回响"));
+        assert_eq!(groups[1].stories[0].story_group, "RO1_ENDBOOK_ENDING_1");
+        assert_eq!(groups[0].stories[0].avg_tag, None);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn record_stories_expose_required_stages() {
+        let temp_root = temp_root("record_stories");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(&service, "story_review_table.json", &serde_json::json!({}));
+        write_excel_table(
+            &service,
+            "zone_table.json",
+            &serde_json::json!({
+                "zones": { "main_10": { "zoneNameFirst": "第十章", "zoneNameSecond": "破碎日冕" } },
+                "zoneRecordGroupedData": {
+                    "main_10": {
+                        "zoneId": "main_10",
+                        "records": [{
+                            "recordId": "main_10_record_1",
+                            "zoneId": "main_10",
+                            "recordTitleName": "笔记一",
+                            "rewards": [
+                                { "bindStageId": "main_10-16", "textPath": "Obt/Record/main_10/record_1" },
+                                { "bindStageId": "main_10-17" }
+                            ]
+                        }]
+                    }
+                }
+            }),
+        );
+
+        // stage_table 缺失时回退为关卡 id
+        let groups = service.get_record_stories_grouped().unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].name, "破碎日冕");
+        let record = &groups[0].stories[0];
+        let stage_ids: Vec<&str> = record
+            .required_stages
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|stage| stage.stage_id.as_str())
+            .collect();
+        assert_eq!(stage_ids, vec!["main_10-16", "main_10-17"]);
+        assert_eq!(record.story_txt, "Obt/Record/main_10/record_1");
+        assert_eq!(
+            record.unlock_desc.as_deref(),
+            Some("通关 main_10-16、main_10-17 后解锁")
+        );
+
+        write_excel_table(
+            &service,
+            "stage_table.json",
+            &serde_json::json!({
+                "stages": {
+                    "main_10-16": { "code": "10-16" },
+                    "main_10-17": { "code": "10-17" }
+                }
+            }),
+        );
+        let groups = service.get_record_stories_grouped().unwrap();
+        let record = &groups[0].stories[0];
+        assert_eq!(record.story_code.as_deref(), Some("10-16"));
+        assert_eq!(
+            record.unlock_desc.as_deref(),
+            Some("通关 10-16、10-17 后解锁")
+        );
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn story_neighbors_follow_group_order() {
+        let temp_root = temp_root("story_neighbors");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_1": {
+                    "entryType": "MAINLINE",
+                    "name": "黑暗时代·下",
+                    "infoUnlockDatas": [story_json("main_01_01", "1-1", "main_1", 1)]
+                },
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "name": "黑暗时代·上",
+                    "infoUnlockDatas": [
+                        story_json("main_00_02", "0-2", "main_0", 2),
+                        story_json("main_00_01", "0-1", "main_0", 1)
+                    ]
+                },
+                "act1mini": {
+                    "entryType": "MINI_ACTIVITY",
+                    "name": "小型活动",
+                    "infoUnlockDatas": [story_json("act1mini_01", "mini", "act1mini", 1)]
+                }
+            }),
+        );
+        let id = |story: &Option<StoryEntry>| story.as_ref().map(|s| s.story_id.clone());
+
+        let neighbors = service.get_story_neighbors("main_00_02", false).unwrap();
+        assert_eq!(id(&neighbors.prev).as_deref(), Some("main_00_01"));
+        assert_eq!(id(&neighbors.next), None);
+        assert_eq!(neighbors.group_name, "黑暗时代·上");
+        assert_eq!((neighbors.position, neighbors.total), (2, 2));
+
+        let reads = service.table_cache.disk_reads.load(AtomicOrdering::Relaxed);
+        let neighbors = service.get_story_neighbors("main_00_02", true).unwrap();
+        assert_eq!(id(&neighbors.next).as_deref(), Some("main_01_01"));
+        // 跨组滚动不跨越分类
+        let neighbors = service.get_story_neighbors("main_01_01", true).unwrap();
+        assert_eq!(id(&neighbors.prev).as_deref(), Some("main_00_02"));
+        assert_eq!(id(&neighbors.next), None);
+        assert_eq!(
+            service.table_cache.disk_reads.load(AtomicOrdering::Relaxed),
+            reads
+        );
+
+        assert!(service.get_story_neighbors("missing", true).is_err());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn story_tree_composes_all_categories_with_counts() {
+        let temp_root = temp_root("story_tree");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "name": "黑暗时代·上",
+                    "infoUnlockDatas": [
+                        story_json("main_00_01", "0-1", "main_0", 1),
+                        story_json("main_00_02", "0-2", "main_0", 2)
+                    ]
+                },
+                "story_1": {
+                    "entryType": "NONE",
+                    "infoUnlockDatas": [story_json("story_1_1", "密录", "story_1", 1)]
+                }
+            }),
+        );
+
+        let tree = service.get_story_tree().unwrap();
+        let ids: Vec<&str> = tree.nodes.iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "mainline",
+                "activity",
+                "sidestory",
+                "roguelike",
+                "memory",
+                "record",
+                "rune"
+            ]
+        );
+        assert_eq!(tree.total, 3);
+        assert_eq!(tree.nodes[0].count, 2);
+        assert_eq!(tree.nodes[0].groups[0].name, "黑暗时代·上");
+        assert_eq!(tree.nodes[4].groups[0].name, "未知干员");
+        assert_eq!(tree.tree_version, service.get_story_tree_version());
+
+        let json = serde_json::to_value(&tree).unwrap();
+        assert!(json.get("treeVersion").is_some());
+        assert!(json["nodes"][0]["groups"][0]["stories"][0]
+            .get("storyId")
+            .is_some());
+
+        write_excel_table(&service, "zone_table.json", &serde_json::json!({}));
+        assert_ne!(tree.tree_version, service.get_story_tree_version());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn reading_progress_upserts_and_survives_index_rebuild() {
+        let temp_root = temp_root("reading_progress");
+        let service = DataService::new(temp_root.clone());
+
+        assert_eq!(service.get_reading_progress("main_00_01").unwrap(), None);
+        service
+            .set_reading_progress("main_00_01", 3, 10, 25.0)
+            .unwrap();
+        service
+            .set_reading_progress("main_00_01", 7, 0, 140.0)
+            .unwrap();
+        service
+            .set_reading_progress("act1_st01", 1, 0, 5.0)
+            .unwrap();
+
+        let progress = service.get_reading_progress("main_00_01").unwrap().unwrap();
+        assert_eq!(progress.segment_index, 7);
+        assert_eq!(progress.char_offset, 0);
+        assert_eq!(progress.percent, 100.0);
+        assert_eq!(service.get_all_reading_progress().unwrap().len(), 2);
+
+        // 模拟索引版本升级：旧索引被删除并重建
+        let conn = service.open_index_connection().unwrap();
+        DataService::init_index_tables(&conn).unwrap();
+        conn.execute(
+            "UPDATE story_index_meta SET value = '0' WHERE key = 'index_version'",
+            [],
+        )
+        .unwrap();
+        DataService::init_index_tables(&conn).unwrap();
+        drop(conn);
+        service.clear_story_index().unwrap();
+
+        let progress = service.get_reading_progress("main_00_01").unwrap().unwrap();
+        assert_eq!(progress.segment_index, 7);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn bookmarks_round_trip_with_story_names() {
+        let temp_root = temp_root("bookmarks");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [story_json("main_00_01", "序章", "main_0", 1)]
+                }
+            }),
+        );
+
+        let long_excerpt = "晨".repeat(BOOKMARK_EXCERPT_LIMIT + 50);
+        let first = service
+            .add_bookmark("main_00_01", 4, &long_excerpt, "重要")
+            .unwrap();
+        assert_eq!(first.excerpt.chars().count(), BOOKMARK_EXCERPT_LIMIT);
+        assert_eq!(first.story_name.as_deref(), Some("序章"));
+        assert_eq!(first.category.as_deref(), Some("主线 | 主线 (main_0)"));
+        let second = service.add_bookmark("act1_st01", 0, "片段", "").unwrap();
+        assert_eq!(second.story_name, None);
+
+        assert_eq!(service.list_bookmarks(None).unwrap().len(), 2);
+        let filtered = service.list_bookmarks(Some("main_00_01")).unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].segment_index, 4);
+
+        service
+            .update_bookmark_note(first.id, "改过的笔记")
+            .unwrap();
+        let updated = service.list_bookmarks(Some("main_00_01")).unwrap();
+        assert_eq!(updated[0].note, "改过的笔记");
+        assert!(service.update_bookmark_note(9999, "x").is_err());
+
+        // 重建索引不影响书签
+        service.clear_story_index().unwrap();
+        service.delete_bookmark(second.id).unwrap();
+        let remaining = service.list_bookmarks(None).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, first.id);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn favorites_toggle_resolve_and_annotate_groups() {
+        let temp_root = temp_root("favorites");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "name": "黑暗时代·上",
+                    "infoUnlockDatas": [
+                        story_json("main_00_01", "0-1", "main_0", 1),
+                        story_json("main_00_02", "0-2", "main_0", 2)
+                    ]
+                }
+            }),
+        );
+
+        assert!(service
+            .toggle_favorite(FavoriteKind::Story, "main_00_02")
+            .unwrap());
+        assert!(service
+            .toggle_favorite(FavoriteKind::Group, "main_0")
+            .unwrap());
+        assert!(service
+            .toggle_favorite(FavoriteKind::Story, "gone")
+            .unwrap());
+        assert!(!service
+            .toggle_favorite(FavoriteKind::Story, "gone")
+            .unwrap());
+        assert!(service
+            .is_favorite(FavoriteKind::Story, "main_00_02")
+            .unwrap());
+        assert!(!service
+            .is_favorite(FavoriteKind::Group, "main_00_02")
+            .unwrap());
+
+        let favorites = service.list_favorites().unwrap();
+        assert_eq!(favorites.len(), 2);
+        let group = favorites
+            .iter()
+            .find(|item| item.kind == FavoriteKind::Group)
+            .unwrap();
+        assert_eq!(group.group_name.as_deref(), Some("黑暗时代·上"));
+        assert_eq!(group.story_count, Some(2));
+        let story = favorites
+            .iter()
+            .find(|item| item.kind == FavoriteKind::Story)
+            .unwrap();
+        assert_eq!(story.story.as_ref().unwrap().story_name, "0-2");
+
+        let groups = service.annotate_favorites(service.get_main_stories_grouped(None).unwrap());
+        let flags: Vec<(Option<bool>, Option<bool>)> = groups[0]
+            .stories
+            .iter()
+            .map(|s| (s.favorite, s.group_favorite))
+            .collect();
+        assert_eq!(
+            flags,
+            vec![(Some(false), Some(true)), (Some(true), Some(true))]
+        );
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn recent_stories_dedup_order_and_prune() {
+        let temp_root = temp_root("recent_stories");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [
+                        story_json("main_00_01", "0-1", "main_0", 1),
+                        story_json("main_00_02", "0-2", "main_0", 2)
+                    ]
+                }
+            }),
+        );
+
+        service
+            .record_story_opened("obt/main_0/main_00_01.txt")
+            .unwrap();
+        service.record_story_opened("obt/unknown/story").unwrap();
+        service.touch_history("main_00_02", 2_000).unwrap();
+        service.touch_history("main_00_01", 3_000).unwrap();
+        service.report_read_session("main_00_01", 45).unwrap();
+
+        let recent = service.get_recent_stories(10).unwrap();
+        let ids: Vec<&str> = recent.iter().map(|r| r.story.story_id.as_str()).collect();
+        assert_eq!(ids, vec!["main_00_01", "main_00_02"]);
+        assert_eq!(recent[0].last_opened_at, 3_000);
+        assert_eq!(recent[0].read_seconds, 45);
+        assert_eq!(service.get_recent_stories(1).unwrap().len(), 1);
+
+        for i in 0..RECENT_HISTORY_LIMIT + 5 {
+            service
+                .touch_history(&format!("filler_{}", i), 10_000 + i as i64)
+                .unwrap();
+        }
+        let count: i64 = service
+            .open_user_connection()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM reading_history", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, RECENT_HISTORY_LIMIT as i64);
+        assert!(service.get_recent_stories(10).unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn recent_stories_include_roguelike_and_record_stories() {
+        let temp_root = temp_root("recent_stories_sources");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(&service, "story_review_table.json", &serde_json::json!({}));
+        write_excel_table(
+            &service,
+            "story_table.json",
+            &serde_json::json!({ "Obt/Roguelike/ro1/level_rogue1_1": {} }),
+        );
+        write_excel_table(
+            &service,
+            "zone_table.json",
+            &serde_json::json!({
+                "zoneRecordGroupedData": {
+                    "main_1": {
+                        "zoneId": "main_1",
+                        "records": [{
+                            "recordId": "z_rec_1_1",
+                            "textPath": "Obt/Record/main_1/record_1_1"
+                        }]
+                    }
+                }
+            }),
+        );
+
+        service
+            .record_story_opened("Obt/Roguelike/ro1/level_rogue1_1.txt")
+            .unwrap();
+        service
+            .record_story_opened("obt/record/main_1/record_1_1")
+            .unwrap();
+
+        let recent = service.get_recent_stories(10).unwrap();
+        let ids: Vec<&str> = recent.iter().map(|r| r.story.story_id.as_str()).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&"z_rec_1_1"));
+        assert!(ids.contains(&"Obt/Roguelike/ro1/level_rogue1_1"));
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn continue_reading_skips_and_prunes_stale_stories() {
+        let temp_root = temp_root("continue_reading");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "name": "黑暗时代·上",
+                    "infoUnlockDatas": [
+                        story_json("main_00_01", "0-1", "main_0", 1),
+                        story_json("main_00_02", "0-2", "main_0", 2)
+                    ]
+                }
+            }),
+        );
+        assert!(service.get_continue_reading().unwrap().is_none());
+
+        service
+            .set_reading_progress("main_00_01", 2, 0, 40.0)
+            .unwrap();
+        service
+            .set_reading_progress("main_00_02", 9, 0, 100.0)
+            .unwrap();
+        service
+            .set_reading_progress("removed_story", 1, 0, 10.0)
+            .unwrap();
+        // 让已删除的剧情成为最近的候选
+        service
+            .touch_history("removed_story", i64::MAX / 2)
+            .unwrap();
+
+        let card = service.get_continue_reading().unwrap().unwrap();
+        assert_eq!(card.story.story_id, "main_00_01");
+        assert_eq!(card.progress.percent, 40.0);
+        let neighbors = card.neighbors.unwrap();
+        assert_eq!(neighbors.group_name, "黑暗时代·上");
+        assert_eq!(neighbors.position, 1);
+
+        assert_eq!(service.get_reading_progress("removed_story").unwrap(), None);
+        assert!(service.get_recent_stories(10).unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn continue_reading_keeps_progress_outside_story_review_table() {
+        let temp_root = temp_root("continue_reading_sources");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(&service, "story_review_table.json", &serde_json::json!({}));
+        write_excel_table(
+            &service,
+            "story_table.json",
+            &serde_json::json!({ "Obt/Roguelike/ro1/level_rogue1_1": {} }),
+        );
+        write_excel_table(
+            &service,
+            "zone_table.json",
+            &serde_json::json!({
+                "zoneRecordGroupedData": {
+                    "main_1": {
+                        "zoneId": "main_1",
+                        "records": [{
+                            "recordId": "z_rec_1_1",
+                            "recordTitleName": "笔记",
+                            "textPath": "Obt/Record/main_1/record_1_1"
+                        }]
+                    }
+                }
+            }),
+        );
+
+        service
+            .set_reading_progress("Obt/Roguelike/ro1/level_rogue1_1", 1, 0, 30.0)
+            .unwrap();
+        service
+            .set_reading_progress("z_rec_1_1", 1, 0, 20.0)
+            .unwrap();
+        service.touch_history("z_rec_1_1", 5_000).unwrap();
+
+        let card = service.get_continue_reading().unwrap().unwrap();
+        assert_eq!(card.story.story_id, "z_rec_1_1");
+
+        service
+            .set_reading_progress("z_rec_1_1", 9, 0, 100.0)
+            .unwrap();
+        let card = service.get_continue_reading().unwrap().unwrap();
+        assert_eq!(card.story.story_id, "Obt/Roguelike/ro1/level_rogue1_1");
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn group_read_counts_combine_progress_and_manual_state() {
+        let temp_root = temp_root("read_counts");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [
+                        story_json("main_00_01", "0-1", "main_0", 1),
+                        story_json("main_00_02", "0-2", "main_0", 2),
+                        story_json("main_00_03", "0-3", "main_0", 3)
+                    ]
+                },
+                "act1": {
+                    "entryType": "ACTIVITY",
+                    "infoUnlockDatas": [
+                        story_json("act1_01", "ST-1", "act1", 1),
+                        story_json("act1_02", "ST-2", "act1", 2)
+                    ]
+                }
+            }),
+        );
+
+        service
+            .set_reading_progress("main_00_01", 9, 0, 100.0)
+            .unwrap();
+        service
+            .set_reading_progress("main_00_02", 3, 0, 50.0)
+            .unwrap();
+        service.set_story_read("main_00_03", true).unwrap();
+        let counts = service.get_group_read_counts().unwrap();
+        let main = counts.iter().find(|c| c.group_key == "main_0").unwrap();
+        assert_eq!((main.read, main.total), (2, 3));
+
+        // 手动标记未读覆盖 100% 进度
+        service.set_story_read("main_00_01", false).unwrap();
+        assert_eq!(service.mark_group_read("act1").unwrap(), 2);
+        assert!(service.mark_group_read("missing").is_err());
+
+        let counts = service.get_group_read_counts().unwrap();
+        let main = counts.iter().find(|c| c.group_key == "main_0").unwrap();
+        let act = counts.iter().find(|c| c.group_key == "act1").unwrap();
+        assert_eq!((main.read, main.total), (1, 3));
+        assert_eq!((act.read, act.total), (2, 2));
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn reading_to_the_end_overrides_earlier_unread_mark() {
+        let temp_root = temp_root("read_state_order");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [story_json("main_00_01", "0-1", "main_0", 1)]
+                }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "story_table.json",
+            &serde_json::json!({
+                "Obt/Roguelike/ro1/level_rogue1_1": {},
+                "Obt/Roguelike/ro1/level_rogue1_2": {}
+            }),
+        );
+
+        service.set_story_read("main_00_01", false).unwrap();
+        service
+            .open_user_connection()
+            .unwrap()
+            .execute("UPDATE read_state SET updated_at = 1", [])
+            .unwrap();
+        service
+            .set_reading_progress("main_00_01", 9, 0, 100.0)
+            .unwrap();
+        assert!(service.read_story_ids().unwrap().contains("main_00_01"));
+
+        assert_eq!(service.mark_group_read("RO1").unwrap(), 2);
+        let counts = service.get_group_read_counts().unwrap();
+        let keys: Vec<&str> = counts.iter().map(|c| c.group_key.as_str()).collect();
+        assert_eq!(keys, vec!["main_0", "RO1"]);
+        assert_eq!((counts[1].read, counts[1].total), (2, 2));
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn story_stats_cached_and_on_demand_agree() {
+        let temp_root = temp_root("story_stats");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [
+                        story_json("main_00_01", "0-1", "main_0", 1),
+                        story_json("main_00_02", "0-2", "main_0", 2)
+                    ]
+                }
+            }),
+        );
+        let story_dir = service.story_dir().join("obt/main_0");
+        fs::create_dir_all(&story_dir).unwrap();
+        fs::write(
+            story_dir.join("main_00_01.txt"),
+            "[name=\"阿米娅\"]博士，醒醒。\n[name=\"凯尔希\"]没时间了。\n[name=\"阿米娅\"]我们走吧。\n荒野上 风声呼啸。\n[Decision(options=\"走吧;再等等\", values=\"1;2\")]\n",
+        )
+        .unwrap();
+        fs::write(story_dir.join("main_00_02.txt"), "雨停了。\n").unwrap();
+
+        let on_demand = service.get_story_stats("main_00_01").unwrap();
+        assert_eq!(on_demand.dialogue_count, 3);
+        assert_eq!(on_demand.narration_count, 1);
+        assert_eq!(on_demand.decision_count, 1);
+        assert_eq!(on_demand.speaker_count, 2);
+        assert_eq!(on_demand.word_count, 6 + 5 + 5 + 8 + 5);
+        assert_eq!(on_demand.estimated_minutes, 1);
+
+        service.rebuild_story_index().unwrap();
+        let conn = service.open_stats_connection().unwrap().unwrap();
+        let cached_rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM story_stats", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(cached_rows, 2);
+        assert_eq!(service.get_story_stats("main_00_01").unwrap(), on_demand);
+
+        // 文本变化后缓存失效，回退到现场解析
+        fs::write(story_dir.join("main_00_02.txt"), "雨停了。\n天晴了。\n").unwrap();
+        let group = service.get_group_stats("main_0").unwrap();
+        assert_eq!(group.story_count, 2);
+        assert_eq!(group.narration_count, 3);
+        assert_eq!(group.word_count, on_demand.word_count + 8);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn chronological_stories_group_by_year_with_undated_fallback() {
+        let temp_root = temp_root("chronological");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_1": {
+                    "entryType": "MAINLINE",
+                    "name": "黑暗时代·下",
+                    "startTime": 1_557_000_000,
+                    "infoUnlockDatas": [story_json("main_01_01", "1-1", "main_1", 1)]
+                },
+                "act4d0": {
+                    "entryType": "ACTIVITY",
+                    "name": "骑兵与猎人",
+                    "startTime": 1_580_000_000,
+                    "infoUnlockDatas": [story_json("act4d0_01", "MB-1", "act4d0", 1)]
+                },
+                "act3d0": {
+                    "entryType": "ACTIVITY",
+                    "name": "遗尘漫步",
+                    "startTime": 1_560_000_000,
+                    "infoUnlockDatas": [story_json("act3d0_01", "GT-1", "act3d0", 1)]
+                },
+                "act3d0_rerun": {
+                    "entryType": "ACTIVITY",
+                    "name": "遗尘漫步·复刻",
+                    "startTime": 1_600_000_000,
+                    "infoUnlockDatas": [story_json("act3d0_01", "GT-1", "act3d0", 1)]
+                },
+                "story_12fce_set_1": {
+                    "entryType": "NONE",
+                    "infoUnlockDatas": [story_json("story_12fce_1", "密录", "story_12fce_set_1", 1)]
+                },
+                "story_2fce_set_1": {
+                    "entryType": "NONE",
+                    "startTime": 0,
+                    "infoUnlockDatas": [story_json("story_2fce_1", "密录", "story_2fce_set_1", 1)]
+                },
+                "rogue_1": {
+                    "entryType": "ROGUELIKE",
+                    "startTime": 1_560_000_000,
+                    "infoUnlockDatas": [story_json("rogue_1_01", "肉鸽", "rogue_1", 1)]
+                }
+            }),
+        );
+
+        let years = service.get_stories_chronological().unwrap();
+        let layout: Vec<(Option<i32>, Vec<&str>)> = years
+            .iter()
+            .map(|bucket| {
+                (
+                    bucket.year,
+                    bucket
+                        .entries
+                        .iter()
+                        .map(|entry| entry.id.as_str())
+                        .collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            layout,
+            vec![
+                (Some(2019), vec!["main_1", "act3d0"]),
+                (Some(2020), vec!["act4d0"]),
+                (None, vec!["story_2fce_set_1", "story_12fce_set_1"]),
+            ]
+        );
+        assert_eq!(years[2].entries[0].name, "干员密录 (story_2fce_set_1)");
+        assert_eq!(years[0].entries[0].entry_type, "MAINLINE");
+        assert_eq!(years[0].entries[1].name, "遗尘漫步");
+        assert_eq!(years[0].entries[1].info_unlock_datas.len(), 1);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn grouped_stories_filter_by_avg_tag_kind() {
+        let temp_root = temp_root("avg_tag_filter");
+        let service = DataService::new(temp_root.clone());
+        let tagged = |id: &str, group: &str, sort: i32, tag: &str| {
+            let mut story = story_json(id, id, group, sort);
+            story["avgTag"] = Value::String(tag.to_string());
+            story
+        };
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_1": {
+                    "entryType": "MAINLINE",
+                    "name": "黑暗时代·下",
+                    "infoUnlockDatas": [
+                        tagged("main_01_01_beg", "main_1", 1, "行动前"),
+                        tagged("main_01_01_end", "main_1", 2, "行动后"),
+                        tagged("main_01_02", "main_1", 3, "幕间")
+                    ]
+                },
+                "main_2": {
+                    "entryType": "MAINLINE",
+                    "name": "异卵同生",
+                    "infoUnlockDatas": [
+                        tagged("main_02_01_beg", "main_2", 1, "行动前")
+                    ]
+                },
+                "act1": {
+                    "entryType": "ACTIVITY",
+                    "name": "骑兵与猎人",
+                    "infoUnlockDatas": [
+                        tagged("act1_01", "act1", 1, "特别篇"),
+                        story_json("act1_02", "act1_02", "act1", 2)
+                    ]
+                }
+            }),
+        );
+
+        let all = service.get_main_stories_grouped(None).unwrap();
+        let kinds: Vec<Option<AvgTagKind>> =
+            all[0].stories.iter().map(|s| s.avg_tag_kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Some(AvgTagKind::Before),
+                Some(AvgTagKind::After),
+                Some(AvgTagKind::Interlude)
+            ]
+        );
+        assert_eq!(all[0].stories[2].avg_tag.as_deref(), Some("幕间"));
+
+        let interludes = service
+            .get_main_stories_grouped(Some(&[AvgTagKind::Interlude]))
+            .unwrap();
+        assert_eq!(interludes.len(), 1);
+        assert_eq!(interludes[0].name, "黑暗时代·下");
+        assert_eq!(interludes[0].stories.len(), 1);
+        assert_eq!(interludes[0].stories[0].story_id, "main_01_02");
+
+        // 未知标签与缺失标签都归为 Other
+        let activities = service.get_activity_stories_grouped(None).unwrap();
+        assert!(activities[0]
+            .stories
+            .iter()
+            .all(|s| s.avg_tag_kind == Some(AvgTagKind::Other)));
+        let others = service
+            .get_activity_stories_grouped(Some(&[AvgTagKind::Interlude, AvgTagKind::Other]))
+            .unwrap();
+        assert_eq!(others[0].stories.len(), 2);
+        assert!(service
+            .get_activity_stories_grouped(Some(&[AvgTagKind::Before]))
+            .unwrap()
+            .is_empty());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn activity_reruns_merge_into_original_group() {
+        let temp_root = temp_root("rerun_merge");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "act5d0": {
+                    "entryType": "ACTIVITY",
+                    "name": "沃伦姆德的薄暮",
+                    "startTime": 1_570_000_000,
+                    "infoUnlockDatas": [
+                        story_json("act5d0_01", "CB-1", "act5d0", 1),
+                        story_json("act5d0_02", "CB-2", "act5d0", 2)
+                    ]
+                },
+                "act5d0_rep": {
+                    "entryType": "ACTIVITY",
+                    "name": "沃伦姆德的薄暮·复刻",
+                    "startTime": 1_600_000_000,
+                    "infoUnlockDatas": [
+                        story_json("act5d0_01", "CB-1", "act5d0", 1),
+                        story_json("act5d0_02", "CB-2", "act5d0", 2)
+                    ]
+                },
+                "act6d0": {
+                    "entryType": "ACTIVITY",
+                    "name": "午夜邮差",
+                    "startTime": 1_580_000_000,
+                    "infoUnlockDatas": [story_json("act6d0_01", "MP-1", "act6d0", 1)]
+                },
+                "act6d0_rep": {
+                    "entryType": "ACTIVITY",
+                    "name": "午夜邮差 复刻",
+                    "startTime": 1_610_000_000,
+                    "infoUnlockDatas": [
+                        story_json("act6d0_rep_01", "MP-1", "act6d0", 1),
+                        story_json("act6d0_rep_02", "MP-EX", "act6d0_rep", 2)
+                    ]
+                }
+            }),
+        );
+        // 复刻条目与原版使用同一份剧情文本
+        let mut rerun_story = story_json("act6d0_rep_01", "MP-1", "act6d0", 1);
+        rerun_story["storyTxt"] = Value::String("obt/act6d0/act6d0_01".to_string());
+        let mut table = service
+            .load_table("story_review_table.json")
+            .unwrap()
+            .as_ref()
+            .clone();
+        table["act6d0_rep"]["infoUnlockDatas"][0] = rerun_story;
+        write_excel_table(&service, "story_review_table.json", &table);
+
+        let groups = service.get_activity_stories_grouped(None).unwrap();
+        let layout: Vec<(&str, bool, Vec<&str>)> = groups
+            .iter()
+            .map(|group| {
+                (
+                    group.name.as_str(),
+                    group.extra.as_ref().is_some_and(|extra| extra.has_rerun),
+                    group.stories.iter().map(|s| s.story_id.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            layout,
+            vec![
+                ("沃伦姆德的薄暮", true, vec!["act5d0_01", "act5d0_02"]),
+                ("午夜邮差", true, vec!["act6d0_01", "act6d0_rep_02"]),
+            ]
+        );
+
+        let categories: HashSet<String> = service
+            .collect_stories_for_index()
+            .unwrap()
+            .into_iter()
+            .map(|indexed| indexed.category_name)
+            .collect();
+        assert_eq!(
+            categories,
+            HashSet::from(["沃伦姆德的薄暮".to_string(), "午夜邮差".to_string()])
+        );
+        assert_eq!(service.collect_stories_for_index().unwrap().len(), 4);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn grouped_endpoints_keep_group_id_and_entry_type() {
+        let temp_root = temp_root("story_group_struct");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "name": "黑暗时代·上",
+                    "startTime": 1_556_676_000,
+                    "infoUnlockDatas": [story_json("main_00_01", "0-1", "main_0", 1)]
+                },
+                "act1": {
+                    "entryType": "MINI_ACTIVITY",
+                    "name": "Pine Soot",
+                    "startTime": 0,
+                    "infoUnlockDatas": [story_json("act1_01", "PS-1", "act1", 1)]
+                },
+                "act2side": {
+                    "entryType": "ACTIVITY",
+                    "actType": "ACTIVITY_STORY",
+                    "name": "喧闹法则",
+                    "infoUnlockDatas": [story_json("act2side_01", "CB-1", "act2side", 1)]
+                }
+            }),
+        );
+
+        let main = service.get_main_stories_grouped(None).unwrap();
+        assert_eq!(main[0].id, "main_0");
+        assert_eq!(main[0].entry_type, "MAINLINE");
+        assert_eq!(main[0].start_time, Some(1_556_676_000));
+        assert!(main[0].extra.is_none());
+
+        let activities = service.get_activity_stories_grouped(None).unwrap();
+        let mini = activities.iter().find(|group| group.id == "act1").unwrap();
+        assert_eq!(mini.entry_type, "MINI_ACTIVITY");
+        assert_eq!(mini.start_time, None);
+        assert!(!mini.extra.as_ref().unwrap().has_rerun);
+
+        let sidestories = service.get_sidestory_stories_grouped().unwrap();
+        assert_eq!(sidestories[0].id, "act2side");
+        assert_eq!(sidestories[0].clone().into_pair().0, "喧闹法则");
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn sort_story_entries_breaks_ties_by_code_tag_and_id() {
+        let entry = |id: &str, sort: i32, code: Option<&str>, tag: Option<&str>| {
+            let mut value = story_json(id, id, "main_8", sort);
+            if let Some(code) = code {
+                value["storyCode"] = Value::String(code.to_string());
+            }
+            if let Some(tag) = tag {
+                value["avgTag"] = Value::String(tag.to_string());
+            }
+            parse_story_entry("main_8", &value).unwrap()
+        };
+        let mut stories = vec![
+            entry("main_08_10_end", 1, Some("8-10"), Some("行动后")),
+            entry("main_08_02_beg", 1, Some("8-2"), Some("行动前")),
+            entry("main_08_01_end", 1, Some("8-1"), Some("行动后")),
+            entry("main_08_01_mid", 1, Some("8-1"), Some("幕间")),
+            entry("main_08_01_beg", 1, Some("8-1"), Some("行动前")),
+            entry("main_08_b", 1, None, None),
+            entry("main_08_a", 1, None, None),
+            entry("main_08_00", 0, Some("8-99"), None),
+        ];
+        sort_story_entries(&mut stories);
+        let ids: Vec<&str> = stories.iter().map(|s| s.story_id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "main_08_00",
+                "main_08_01_beg",
+                "main_08_01_end",
+                "main_08_01_mid",
+                "main_08_02_beg",
+                "main_08_10_end",
+                "main_08_a",
+                "main_08_b",
+            ]
+        );
+    }
+
+    #[test]
+    fn story_entry_lookup_is_cached_after_first_call() {
+        let temp_root = temp_root("story_entry_cache");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [
+                        story_json("main_00_01", "0-1", "main_0", 1),
+                        story_json("main_00_02", "0-2", "main_0", 2)
+                    ]
+                }
+            }),
+        );
+
+        assert_eq!(
+            service.get_story_entry("main_00_02").unwrap().story_name,
+            "0-2"
+        );
+        // 清空表缓存后，第二次查找若读取 story_review_table 就会产生磁盘读取
+        service.table_cache.tables.write().unwrap().clear();
+        let reads = service.table_cache.disk_reads.load(AtomicOrdering::Relaxed);
+        assert_eq!(
+            service.get_story_entry("main_00_01").unwrap().story_id,
+            "main_00_01"
+        );
+        assert!(service.get_story_entry("missing").is_err());
+        assert_eq!(
+            service.table_cache.disk_reads.load(AtomicOrdering::Relaxed),
+            reads
+        );
+
+        // 数据更新后查找表随之失效
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [story_json("main_00_03", "0-3", "main_0", 3)]
+                }
+            }),
+        );
+        assert!(service.get_story_entry("main_00_01").is_err());
+        assert!(service.get_story_entry("main_00_03").is_ok());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn story_detail_degrades_missing_parts_to_none() {
+        let temp_root = temp_root("story_detail");
+        let service = DataService::new(temp_root.clone());
+        let mut with_info = story_json("main_00_01", "0-1", "main_0", 1);
+        with_info["storyInfo"] = Value::String("info/obt/main/main_00_01".to_string());
+        let mut missing_info = story_json("main_00_02", "0-2", "main_0", 2);
+        missing_info["storyInfo"] = Value::String("info/obt/main/missing".to_string());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "name": "黑暗时代·上",
+                    "infoUnlockDatas": [with_info, missing_info]
+                }
+            }),
+        );
+        let story_dir = service.story_dir();
+        fs::create_dir_all(story_dir.join("info/obt/main")).unwrap();
+        fs::write(story_dir.join("info/obt/main/main_00_01.txt"), "切尔诺伯格").unwrap();
+        fs::create_dir_all(story_dir.join("obt/main_0")).unwrap();
+        fs::write(story_dir.join("obt/main_0/main_00_01.txt"), "雨停了。\n").unwrap();
+        service
+            .set_reading_progress("main_00_01", 0, 0, 30.0)
+            .unwrap();
+
+        let detail = service.get_story_detail("main_00_01").unwrap();
+        assert_eq!(detail.synopsis.as_deref(), Some("切尔诺伯格"));
+        assert_eq!(detail.stats.unwrap().narration_count, 1);
+        assert_eq!(detail.progress.unwrap().percent, 30.0);
+        assert_eq!(
+            detail.neighbors.unwrap().next.unwrap().story_id,
+            "main_00_02"
+        );
+
+        // 简介与正文文件都缺失：只有可选部分为 None，整体调用仍然成功
+        let detail = service.get_story_detail("main_00_02").unwrap();
+        assert_eq!(detail.entry.story_name, "0-2");
+        assert!(detail.synopsis.is_none());
+        assert!(detail.stats.is_none());
+        assert!(detail.progress.is_none());
+        assert_eq!(detail.neighbors.unwrap().position, 2);
+
+        assert!(service.get_story_detail("missing").is_err());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn hidden_stories_are_flagged_filtered_and_kept_out_of_index() {
+        let temp_root = temp_root("story_visibility");
+        let service = DataService::new(temp_root.clone());
+        let mut hidden = story_json("main_15_01", "15-1", "main_15", 1);
+        hidden["storyCanShow"] = serde_json::json!(0);
+        let mut locked = story_json("main_15_02", "15-2", "main_15", 2);
+        locked["storyCanShow"] = serde_json::json!(1);
+        locked["storyCanEnter"] = serde_json::json!(0);
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_14": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [story_json("main_14_01", "14-1", "main_14", 1)]
+                },
+                "main_15": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [hidden, locked]
+                },
+                "main_16": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [{
+                        "storyId": "main_16_01",
+                        "storyName": "16-1",
+                        "storyGroup": "main_16",
+                        "storySort": 1,
+                        "storyTxt": "obt/main_16/main_16_01",
+                        "storyCanShow": 0
+                    }]
+                }
+            }),
+        );
+        for (group, id) in [
+            ("main_14", "main_14_01"),
+            ("main_15", "main_15_01"),
+            ("main_15", "main_15_02"),
+            ("main_16", "main_16_01"),
+        ] {
+            let dir = service.story_dir().join("obt").join(group);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join(format!("{}.txt", id)), "罗德岛的故事。\n").unwrap();
+        }
+
+        let groups = service.get_main_stories_grouped(None).unwrap();
+        let visibility: Vec<(&str, Option<StoryVisibility>)> = groups
+            .iter()
+            .flat_map(|group| group.stories.iter())
+            .map(|story| (story.story_id.as_str(), story.visibility))
+            .collect();
+        assert_eq!(
+            visibility,
+            vec![
+                ("main_14_01", Some(StoryVisibility::Normal)),
+                ("main_15_01", Some(StoryVisibility::Hidden)),
+                ("main_15_02", Some(StoryVisibility::Locked)),
+                ("main_16_01", Some(StoryVisibility::Hidden)),
+            ]
+        );
+
+        let visible = DataService::exclude_hidden_stories(groups);
+        let ids: Vec<&str> = visible
+            .iter()
+            .flat_map(|group| group.stories.iter().map(|s| s.story_id.as_str()))
+            .collect();
+        assert_eq!(ids, vec!["main_14_01", "main_15_02"]);
+        assert_eq!(visible.len(), 2);
+
+        let indexed_ids = |service: &DataService| {
+            let conn = service.open_index_connection().unwrap();
+            let mut stmt = conn
+                .prepare("SELECT story_id FROM story_index ORDER BY story_id")
+                .unwrap();
+            let rows = stmt.query_map([], |row| row.get::<_, String>(0)).unwrap();
+            rows.collect::<Result<Vec<_>, _>>().unwrap()
+        };
+        service.rebuild_story_index().unwrap();
+        assert_eq!(indexed_ids(&service), vec!["main_14_01", "main_15_02"]);
+
+        service.set_include_hidden_in_search(true).unwrap();
+        service.rebuild_story_index().unwrap();
+        assert_eq!(indexed_ids(&service).len(), 4);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn classify_activity_entry_covers_known_act_types() {
+        let cases = [
+            ("ACTIVITY", "ACTIVITY_STORY", Some(ActivityKind::SideStory)),
+            (
+                "ACTIVITY",
+                "MINI_STORY",
+                Some(ActivityKind::StoryCollection),
+            ),
+            (
+                "MINI_ACTIVITY",
+                "MINI_STORY",
+                Some(ActivityKind::StoryCollection),
+            ),
+            ("MINI_ACTIVITY", "NONE", Some(ActivityKind::MiniActivity)),
+            ("ACTIVITY", "", Some(ActivityKind::MiniActivity)),
+            ("MAINLINE", "MAIN_STORY", None),
+            ("NONE", "NONE", None),
+        ];
+        for (entry_type, act_type, expected) in cases {
+            assert_eq!(
+                classify_activity_entry(entry_type, act_type),
+                expected,
+                "{} / {}",
+                entry_type,
+                act_type
+            );
+        }
+    }
+
+    #[test]
+    fn sidestory_and_activity_groups_are_disjoint() {
+        let temp_root = temp_root("activity_kind_disjoint");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "act3d0": {
+                    "entryType": "ACTIVITY",
+                    "actType": "ACTIVITY_STORY",
+                    "name": "遗尘漫步",
+                    "infoUnlockDatas": [story_json("act3d0_01", "WD-1", "act3d0", 1)]
+                },
+                "act11mini": {
+                    "entryType": "MINI_ACTIVITY",
+                    "actType": "MINI_STORY",
+                    "name": "将进酒",
+                    "infoUnlockDatas": [story_json("act11mini_01", "IW-1", "act11mini", 1)]
+                },
+                "act5d1": {
+                    "entryType": "MINI_ACTIVITY",
+                    "actType": "NONE",
+                    "name": "午间逸话",
+                    "infoUnlockDatas": [story_json("act5d1_01", "SP-1", "act5d1", 1)]
+                }
+            }),
+        );
+
+        let sidestories = service.get_sidestory_stories_grouped().unwrap();
+        let activities = service.get_activity_stories_grouped(None).unwrap();
+        let side_ids: Vec<&str> = sidestories.iter().map(|g| g.id.as_str()).collect();
+        let mut activity_ids: Vec<&str> = activities.iter().map(|g| g.id.as_str()).collect();
+        activity_ids.sort();
+        assert_eq!(side_ids, vec!["act3d0"]);
+        assert_eq!(activity_ids, vec!["act11mini", "act5d1"]);
+        assert_eq!(sidestories[0].act_type.as_deref(), Some("ACTIVITY_STORY"));
+        let collection = activities.iter().find(|g| g.id == "act11mini").unwrap();
+        assert_eq!(collection.act_type.as_deref(), Some("MINI_STORY"));
+
+        let labels: HashMap<String, String> = service
+            .collect_stories_for_index()
+            .unwrap()
+            .into_iter()
+            .map(|indexed| {
+                let label =
+                    DataService::format_category_label(&indexed.entry_type, &indexed.category_name);
+                (indexed.story.story_id, label)
+            })
+            .collect();
+        assert_eq!(labels["act3d0_01"], "支线 | 遗尘漫步");
+        assert_eq!(labels["act11mini_01"], "故事集 | 将进酒");
+        assert_eq!(labels["act5d1_01"], "活动 | 午间逸话");
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn story_characters_match_operators_and_keep_npcs() {
+        let temp_root = temp_root("story_characters");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_1001_amiya2": { "name": "阿米娅", "appellation": "Amiya", "rarity": "TIER_5" },
+                "char_002_amiya": { "name": "阿米娅", "appellation": "Amiya", "rarity": "TIER_5" },
+                "char_003_kalts": { "name": "凯尔希", "appellation": "Kal'tsit", "rarity": 5 }
+            }),
+        );
+        let story_dir = service.story_dir().join("obt/main_0");
+        fs::create_dir_all(&story_dir).unwrap();
+        fs::write(
+            story_dir.join("main_00_01.txt"),
+            "[name=\"阿米娅\"]博士，醒醒。\n[name=\"整合运动士兵\"]站住！\n[name=\"Kal'tsit\"]没时间了。\n[name=\"阿米娅\"]我们走吧。\n",
+        )
+        .unwrap();
+        fs::write(story_dir.join("main_00_02.txt"), "雨停了。\n风声呼啸。\n").unwrap();
+
+        let characters = service
+            .get_story_characters("obt/main_0/main_00_01")
+            .unwrap();
+        let names: Vec<&str> = characters.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["阿米娅", "整合运动士兵", "Kal'tsit"]);
+
+        assert_eq!(characters[0].char_id.as_deref(), Some("char_002_amiya"));
+        assert_eq!(characters[0].avatar_id.as_deref(), Some("char_002_amiya"));
+        assert_eq!(characters[0].rarity, Some(5));
+        assert_eq!(characters[0].line_count, 2);
+
+        assert_eq!(characters[1].char_id, None);
+        assert_eq!(characters[1].rarity, None);
+        assert_eq!(characters[1].line_count, 1);
+
+        // name 不匹配时回退到 appellation
+        assert_eq!(characters[2].char_id.as_deref(), Some("char_003_kalts"));
+        assert_eq!(characters[2].rarity, Some(6));
+
+        assert!(service
+            .get_story_characters("obt/main_0/main_00_02")
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            service.table_cache.story_characters.read().unwrap().len(),
+            2
+        );
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn stories_for_character_only_match_speakers() {
+        let temp_root = temp_root("character_stories");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_003_kalts": { "name": "凯尔希", "appellation": "Kal'tsit", "rarity": 5 }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "name": "黑暗时代·上",
+                    "startTime": 1_556_676_000,
+                    "infoUnlockDatas": [
+                        story_json("main_00_01", "0-1", "main_0", 1),
+                        story_json("main_00_02", "0-2", "main_0", 2)
+                    ]
+                },
+                "act1": {
+                    "entryType": "MINI_ACTIVITY",
+                    "name": "午间逸话",
+                    "startTime": 1_500_000_000,
+                    "infoUnlockDatas": [story_json("act1_01", "SP-1", "act1", 1)]
+                }
+            }),
+        );
+        let write = |group: &str, id: &str, text: &str| {
+            let dir = service.story_dir().join("obt").join(group);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join(format!("{}.txt", id)), text).unwrap();
+        };
+        write("main_0", "main_00_01", "[name=\"凯尔希\"]没时间了。\n");
+        // 仅被提及，不是说话人
+        write(
+            "main_0",
+            "main_00_02",
+            "[name=\"阿米娅\"]凯尔希医生在哪里？\n",
+        );
+        write("act1", "act1_01", "[name=\"Kal'tsit\"]Hold still.\n");
+
+        let expected = vec![
+            ("MAINLINE".to_string(), vec!["main_00_01".to_string()]),
+            ("ACTIVITY".to_string(), vec!["act1_01".to_string()]),
+        ];
+        let summarize = |groups: Vec<StoryGroup>| -> Vec<(String, Vec<String>)> {
+            groups
+                .into_iter()
+                .map(|g| {
+                    (
+                        g.entry_type,
+                        g.stories.into_iter().map(|s| s.story_id).collect(),
+                    )
+                })
+                .collect()
+        };
+
+        // 尚未建立索引时逐篇扫描
+        let scanned = service.get_stories_for_character("char_003_kalts").unwrap();
+        assert_eq!(summarize(scanned), expected);
+
+        service.rebuild_story_index().unwrap();
+        assert!(service
+            .open_index_with_table("story_speakers")
+            .unwrap()
+            .is_some());
+        let indexed = service.get_stories_for_character("char_003_kalts").unwrap();
+        assert_eq!(indexed[0].name, "主线");
+        assert_eq!(summarize(indexed), expected);
+
+        assert!(service.get_stories_for_character("char_404_none").is_err());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn parse_terra_date_handles_partial_and_suffixed_dates() {
+        let date = |year, month, day| Some(TerraDate { year, month, day });
+        assert_eq!(
+            parse_terra_date("罗德岛医疗部\n1099年1月27日 11:38 A.M."),
+            date(1099, Some(1), Some(27))
+        );
+        assert_eq!(
+            parse_terra_date("龙门近卫局 1097年 7月 3日 PM 11:20"),
+            date(1097, Some(7), Some(3))
+        );
+        assert_eq!(
+            parse_terra_date("１０９８年１２月"),
+            date(1098, Some(12), None)
+        );
+        assert_eq!(parse_terra_date("泰拉历1094年 夏"), date(1094, None, None));
+        // 月份不合法时只保留年份
+        assert_eq!(parse_terra_date("1101年13月2日"), date(1101, None, None));
+        assert_eq!(parse_terra_date("三年前的切尔诺伯格"), None);
+        assert_eq!(parse_terra_date("第12年"), None);
+        assert_eq!(parse_terra_date("11:38 A.M."), None);
+    }
+
+    #[test]
+    fn terra_timeline_sorts_dated_stories_and_lists_undated() {
+        let temp_root = temp_root("terra_timeline");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [
+                        story_json("main_00_01", "0-1", "main_0", 1),
+                        story_json("main_00_02", "0-2", "main_0", 2),
+                        story_json("main_00_03", "0-3", "main_0", 3)
+                    ]
+                }
+            }),
+        );
+        let story_dir = service.story_dir().join("obt/main_0");
+        fs::create_dir_all(&story_dir).unwrap();
+        fs::write(
+            story_dir.join("main_00_01.txt"),
+            "[AnimText(id=\"at1\")]<p=1>切尔诺伯格</><p=2>1097年7月3日 11:38 A.M.</>\n[name=\"阿米娅\"]博士。\n",
+        )
+        .unwrap();
+        fs::write(
+            story_dir.join("main_00_02.txt"),
+            "[Subtitle(text=\"1094年\", alignment=\"center\")]\n雨停了。\n",
+        )
+        .unwrap();
+        // 叙述中提到的年份不算开场日期
+        fs::write(story_dir.join("main_00_03.txt"), "1099年的雪下得很大。\n").unwrap();
+
+        let check = |timeline: TerraTimeline| {
+            let dated: Vec<(&str, TerraDate)> = timeline
+                .dated
+                .iter()
+                .map(|item| (item.story.story_id.as_str(), item.date))
+                .collect();
+            assert_eq!(
+                dated,
+                vec![
+                    (
+                        "main_00_02",
+                        TerraDate {
+                            year: 1094,
+                            month: None,
+                            day: None
+                        }
+                    ),
+                    (
+                        "main_00_01",
+                        TerraDate {
+                            year: 1097,
+                            month: Some(7),
+                            day: Some(3)
+                        }
+                    ),
+                ]
+            );
+            assert_eq!(timeline.undated.len(), 1);
+            assert_eq!(timeline.undated[0].story_id, "main_00_03");
+        };
+
+        check(service.get_stories_by_terra_date().unwrap());
+        service.rebuild_story_index().unwrap();
+        check(service.get_stories_by_terra_date().unwrap());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn attach_story_stats_keeps_order_and_leaves_missing_stats_empty() {
+        let temp_root = temp_root("attach_stats");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [
+                        story_json("main_00_01", "0-1", "main_0", 1),
+                        story_json("main_00_02", "0-2", "main_0", 2),
+                        story_json("main_00_03", "0-3", "main_0", 3)
+                    ]
+                }
+            }),
+        );
+        let story_dir = service.story_dir().join("obt/main_0");
+        fs::create_dir_all(&story_dir).unwrap();
+        fs::write(story_dir.join("main_00_01.txt"), "雨停了。\n").unwrap();
+        fs::write(story_dir.join("main_00_02.txt"), "风声呼啸。\n").unwrap();
+        // main_00_03 没有文本，索引中不会有统计
+
+        let ids = |groups: &[StoryGroup]| -> Vec<String> {
+            groups
+                .iter()
+                .flat_map(|g| g.stories.iter().map(|s| s.story_id.clone()))
+                .collect()
+        };
+        let groups = service.get_main_stories_grouped(None).unwrap();
+        let expected_ids = ids(&groups);
+
+        // 索引未建立：不附加任何统计
+        let without_index = service.attach_story_stats(groups.clone());
+        assert_eq!(ids(&without_index), expected_ids);
+        assert!(without_index[0]
+            .stories
+            .iter()
+            .all(|s| s.word_count.is_none() && s.estimated_minutes.is_none()));
+
+        service.rebuild_story_index().unwrap();
+        let enriched = service.attach_story_stats(groups);
+        assert_eq!(ids(&enriched), expected_ids);
+        let stories = &enriched[0].stories;
+        assert_eq!(stories[0].word_count, Some(4));
+        assert_eq!(stories[0].estimated_minutes, Some(1));
+        assert_eq!(stories[1].word_count, Some(5));
+        assert_eq!(stories[2].word_count, None);
+        assert_eq!(stories[2].estimated_minutes, None);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn random_story_is_seeded_and_honors_filters() {
+        let temp_root = temp_root("random_story");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "name": "黑暗时代·上",
+                    "infoUnlockDatas": [
+                        story_json("main_00_01", "0-1", "main_0", 1),
+                        story_json("main_00_02", "0-2", "main_0", 2)
+                    ]
+                },
+                "act1": {
+                    "entryType": "MINI_ACTIVITY",
+                    "name": "午间逸话",
+                    "infoUnlockDatas": [story_json("act1_01", "SP-1", "act1", 1)]
+                }
+            }),
+        );
+        let story_dir = service.story_dir().join("obt/main_0");
+        fs::create_dir_all(&story_dir).unwrap();
+        fs::write(story_dir.join("main_00_01.txt"), "雨停了。\n").unwrap();
+        fs::write(story_dir.join("main_00_02.txt"), "荒野上风声呼啸。\n").unwrap();
+
+        let any = RandomStoryFilter::default();
+        let first = service.pick_random_story(&any, 42).unwrap();
+        for _ in 0..3 {
+            let again = service.pick_random_story(&any, 42).unwrap();
+            assert_eq!(again.story.story_id, first.story.story_id);
+        }
+        let picked: HashSet<String> = (0..64)
+            .map(|seed| {
+                service
+                    .pick_random_story(&any, seed)
+                    .unwrap()
+                    .story
+                    .story_id
+            })
+            .collect();
+        assert_eq!(picked.len(), 3);
+
+        let legacy = RandomStoryFilter {
+            entry_types: Some(vec!["MINI_ACTIVITY".to_string()]),
+            ..RandomStoryFilter::default()
+        };
+        assert_eq!(
+            service
+                .pick_random_story(&legacy, 3)
+                .unwrap()
+                .story
+                .story_id,
+            "act1_01"
+        );
+
+        let mainline = RandomStoryFilter {
+            entry_types: Some(vec!["MAINLINE".to_string()]),
+            unread_only: true,
+            min_word_count: None,
+        };
+        service.set_story_read("main_00_01", true).unwrap();
+        for seed in 0..16 {
+            let story = service.pick_random_story(&mainline, seed).unwrap();
+            assert_eq!(story.story.story_id, "main_00_02");
+            assert_eq!(story.category, "主线 | 黑暗时代·上");
+            let neighbors = story.neighbors.unwrap();
+            assert_eq!((neighbors.position, neighbors.total), (2, 2));
+        }
+
+        service.set_story_read("main_00_02", true).unwrap();
+        assert_eq!(
+            service.pick_random_story(&mainline, 7).unwrap_err(),
+            "NO_MATCH"
+        );
+
+        // 字数筛选依赖索引统计，索引未建立时无法匹配
+        let long = RandomStoryFilter {
+            min_word_count: Some(5),
+            ..RandomStoryFilter::default()
+        };
+        assert_eq!(service.pick_random_story(&long, 1).unwrap_err(), "NO_MATCH");
+        service.rebuild_story_index().unwrap();
+        assert_eq!(
+            service.pick_random_story(&long, 1).unwrap().story.story_id,
+            "main_00_02"
+        );
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn random_story_skips_hidden_stories_unless_included() {
+        let temp_root = temp_root("random_story_hidden");
+        let service = DataService::new(temp_root.clone());
+        let mut hidden = story_json("main_00_02", "0-2", "main_0", 2);
+        hidden["storyCanShow"] = serde_json::json!(0);
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [story_json("main_00_01", "0-1", "main_0", 1), hidden]
+                }
+            }),
+        );
+
+        let any = RandomStoryFilter::default();
+        for seed in 0..16 {
+            assert_eq!(
+                service
+                    .pick_random_story(&any, seed)
+                    .unwrap()
+                    .story
+                    .story_id,
+                "main_00_01"
+            );
+        }
+        service.set_include_hidden_in_search(true).unwrap();
+        let picked: HashSet<String> = (0..64)
+            .map(|seed| {
+                service
+                    .pick_random_story(&any, seed)
+                    .unwrap()
+                    .story
+                    .story_id
+            })
+            .collect();
+        assert_eq!(picked.len(), 2);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn activity_meta_matches_by_id_then_by_name() {
+        let temp_root = temp_root("activity_meta");
+        let service = DataService::new(temp_root.clone());
+        let mut side = story_json("act3d0_01", "遗尘漫步 1", "act3d0", 1);
+        side["storyCode"] = Value::String("WD-1".to_string());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "act3d0": {
+                    "entryType": "ACTIVITY",
+                    "actType": "ACTIVITY_STORY",
+                    "name": "遗尘漫步",
+                    "infoUnlockDatas": [side]
+                },
+                "1stact": {
+                    "entryType": "MINI_ACTIVITY",
+                    "actType": "MINI_STORY",
+                    "name": "午间 逸话",
+                    "infoUnlockDatas": [story_json("1stact_01", "SP-1", "1stact", 1)]
+                },
+                "act0old": {
+                    "entryType": "MINI_ACTIVITY",
+                    "name": "骑兵与猎人",
+                    "infoUnlockDatas": [story_json("act0old_01", "GT-1", "act0old", 1)]
+                }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "activity_table.json",
+            &serde_json::json!({
+                "basicInfo": {
+                    "act3d0": { "id": "act3d0", "type": "DEFAULT", "name": "遗尘漫步" },
+                    "act5mini": {
+                        "id": "act5mini",
+                        "type": "MINISTORY",
+                        "name": "午间逸话",
+                        "bannerImgId": "act5mini_banner"
+                    },
+                    "act5mini_rep": { "id": "act5mini_rep", "type": "MINISTORY", "name": "午间逸话·复刻" },
+                    "act9d0": { "id": "act9d0", "type": "DEFAULT", "name": "骑兵与猎人A" }
+                },
+                "homeActConfig": {
+                    "act3d0": { "actId": "act3d0", "actTopBarColor": "#5D6E8C" }
+                }
+            }),
+        );
+
+        let sidestories = service.get_sidestory_stories_grouped().unwrap();
+        let extra = sidestories[0].extra.as_ref().unwrap();
+        assert_eq!(extra.act_id.as_deref(), Some("act3d0"));
+        assert_eq!(extra.act_code.as_deref(), Some("WD"));
+        // 表中没有横幅字段时不用活动 id 代替
+        assert_eq!(extra.banner_id, None);
+        assert_eq!(extra.theme_color.as_deref(), Some("#5D6E8C"));
+
+        let activities = service.get_activity_stories_grouped(None).unwrap();
+        let extra_of = |id: &str| {
+            activities
+                .iter()
+                .find(|group| group.id == id)
+                .and_then(|group| group.extra.clone())
+                .unwrap()
+        };
+        // 名称去掉空白后匹配到原版，而不是复刻
+        let by_name = extra_of("1stact");
+        assert_eq!(by_name.act_id.as_deref(), Some("act5mini"));
+        assert_eq!(by_name.banner_id.as_deref(), Some("act5mini_banner"));
+        assert_eq!(by_name.theme_color, None);
+        // 名称只是相近的活动不匹配
+        let unmatched = extra_of("act0old");
+        assert_eq!(unmatched.act_id, None);
+        assert_eq!(unmatched.banner_id, None);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn related_stories_weight_rare_speakers_and_skip_same_group() {
+        let temp_root = temp_root("related_stories");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [
+                        story_json("main_00_01", "0-1", "main_0", 1),
+                        story_json("main_00_02", "0-2", "main_0", 2)
+                    ]
+                },
+                "act1": { "entryType": "MINI_ACTIVITY", "infoUnlockDatas": [story_json("act1_01", "A-1", "act1", 1)] },
+                "act2": { "entryType": "MINI_ACTIVITY", "infoUnlockDatas": [story_json("act2_01", "B-1", "act2", 1)] },
+                "act3": { "entryType": "MINI_ACTIVITY", "infoUnlockDatas": [story_json("act3_01", "C-1", "act3", 1)] },
+                "act4": { "entryType": "MINI_ACTIVITY", "infoUnlockDatas": [story_json("act4_01", "D-1", "act4", 1)] }
+            }),
+        );
+
+        // 索引未建立时没有推荐
+        assert!(service
+            .get_related_stories("main_00_01", None)
+            .unwrap()
+            .is_empty());
+
+        let conn = service.open_index_connection().unwrap();
+        DataService::init_index_tables(&conn).unwrap();
+        for (story_id, speakers) in [
+            ("main_00_01", vec!["阿米娅", "凯尔希", "可露希尔"]),
+            ("main_00_02", vec!["阿米娅", "凯尔希"]),
+            ("act1_01", vec!["阿米娅"]),
+            ("act2_01", vec!["阿米娅", "凯尔希"]),
+            ("act3_01", vec!["陈"]),
+            ("act4_01", vec!["可露希尔"]),
+        ] {
+            for speaker in speakers {
+                conn.execute(
+                    "INSERT INTO story_speakers (story_id, speaker_norm) VALUES (?1, ?2)",
+                    params![story_id, speaker],
+                )
+                .unwrap();
+            }
+        }
+
+        let related = service.get_related_stories("main_00_01", None).unwrap();
+        let ids: Vec<&str> = related.iter().map(|r| r.story.story_id.as_str()).collect();
+        // 同组的 main_00_02 与没有共同说话人的 act3_01 不出现；只共享稀有角色的 act4 排在只共享常见角色的 act1 之前
+        assert_eq!(ids, vec!["act2_01", "act4_01", "act1_01"]);
+        assert_eq!(related[0].shared_speakers, vec!["凯尔希", "阿米娅"]);
+        assert!(related[1].score > related[2].score);
+
+        let limited = service.get_related_stories("main_00_01", Some(1)).unwrap();
+        assert_eq!(limited.len(), 1);
+        assert!(service.get_related_stories("missing", None).is_err());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn required_stages_resolve_codes_and_echo_unknown_ids() {
+        let temp_root = temp_root("required_stages");
+        let service = DataService::new(temp_root.clone());
+        let mut story = story_json("main_10_01", "10-1", "main_10", 1);
+        story["requiredStages"] = serde_json::json!([
+            { "stageId": "main_10-02", "minState": "PASS", "maxState": "COMPLETE" },
+            { "stageId": "main_10-99", "minState": "PASS", "maxState": "COMPLETE" }
+        ]);
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_10": { "entryType": "MAINLINE", "infoUnlockDatas": [story] }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "stage_table.json",
+            &serde_json::json!({
+                "stages": { "main_10-02": { "code": "10-2", "name": "贪婪" } }
+            }),
+        );
+
+        let stages = service.resolve_required_stages("main_10_01").unwrap();
+        assert_eq!(
+            stages,
+            vec![
+                ResolvedStage {
+                    stage_id: "main_10-02".to_string(),
+                    code: "10-2".to_string(),
+                    name: Some("贪婪".to_string()),
+                    min_state: "PASS".to_string(),
+                    max_state: "COMPLETE".to_string(),
+                },
+                ResolvedStage {
+                    stage_id: "main_10-99".to_string(),
+                    code: "main_10-99".to_string(),
+                    name: None,
+                    min_state: "PASS".to_string(),
+                    max_state: "COMPLETE".to_string(),
+                },
+            ]
+        );
+        let detail = service.get_story_detail("main_10_01").unwrap();
+        assert_eq!(detail.required_stages, Some(stages));
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn chapter_reading_order_interleaves_stage_markers() {
+        let temp_root = temp_root("reading_order");
+        let service = DataService::new(temp_root.clone());
+        let entry = |id: &str, code: &str, tag: &str, sort: i32| {
+            let mut value = story_json(id, id, "main_10", sort);
+            value["storyCode"] = Value::String(code.to_string());
+            value["avgTag"] = Value::String(tag.to_string());
+            value
+        };
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_10": {
+                    "entryType": "MAINLINE",
+                    "name": "破碎日冕",
+                    "infoUnlockDatas": [
+                        entry("level_main_10-01_beg", "10-1", "行动前", 1),
+                        entry("level_main_10-01_end", "10-1", "行动后", 2),
+                        entry("level_main_10-02_beg", "10-2", "行动前", 3),
+                        entry("level_main_10-02_spst", "10-2", "幕间", 4),
+                        entry("level_main_10-03_end", "10-3", "行动后", 5),
+                        entry("level_main_10-17_beg", "10-17", "行动前", 6),
+                        entry("level_main_10-17_end", "10-17 (磨难)", "行动后", 7)
+                    ]
+                }
+            }),
+        );
+
+        let items = service.get_chapter_reading_order("main_10").unwrap();
+        let layout: Vec<String> = items
+            .iter()
+            .map(|item| match item.kind {
+                ReadingOrderKind::Story => item.story.as_ref().unwrap().story_id.clone(),
+                ReadingOrderKind::StageMarker => {
+                    format!("[{}]", item.stage_code.as_deref().unwrap())
+                }
+            })
+            .collect();
+        assert_eq!(
+            layout,
+            vec![
+                "level_main_10-01_beg",
+                "[10-1]",
+                "level_main_10-01_end",
+                "level_main_10-02_beg",
+                "[10-2]",
+                "level_main_10-02_spst",
+                "[10-3]",
+                "level_main_10-03_end",
+                "level_main_10-17_beg",
+                "[10-17]",
+                "level_main_10-17_end",
+            ]
+        );
+        assert!(service.get_chapter_reading_order("main_99").is_err());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn chapters_parse_leniently_and_report_skipped_entries() {
+        let temp_root = temp_root("chapters_compat");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(&service, "story_review_table.json", &serde_json::json!({}));
+        write_excel_table(
+            &service,
+            "chapter_table.json",
+            &serde_json::json!({
+                "main_1": {
+                    "chapterId": "main_1",
+                    "chapterName": "觉醒",
+                    "chapterName2": "怒号光明",
+                    "chapterIndex": 1,
+                    "startZoneId": "main_4",
+                    "endZoneId": "main_8",
+                    "chapterEndStageId": "main_08-17"
+                },
+                "main_0": {
+                    "chapterId": "main_0",
+                    "chapterName": "序章",
+                    "chapterName2": "黑暗时代",
+                    "chapterIndex": 0,
+                    "preposedChapterId": null,
+                    "startZoneId": "main_0",
+                    "endZoneId": "main_3",
+                    "chapterEndStageId": "main_03-08",
+                    "chapterTheme": { "color": "#333333", "nested": [1, 2] }
+                },
+                "main_broken": { "chapterId": "main_broken", "chapterIndex": "二" }
+            }),
+        );
+
+        let chapters = service.get_chapters().unwrap();
+        let ids: Vec<&str> = chapters.iter().map(|c| c.chapter_id.as_str()).collect();
+        assert_eq!(ids, vec!["main_0", "main_1"]);
+        assert_eq!(chapters[1].preposed_chapter_id, None);
+        assert_eq!(chapters[1].chapter_name2, "怒号光明");
+
+        let report = service.get_data_compat_report().unwrap();
+        assert_eq!(report.skipped_chapters, 1);
+        assert_eq!(report.skipped_chapter_ids, vec!["main_broken".to_string()]);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn story_dependencies_follow_chain_and_keep_dangling_ids() {
+        let temp_root = temp_root("story_dependencies");
+        let service = DataService::new(temp_root.clone());
+        let entry = |id: &str, name: &str, sort: i32, dependence: Option<&str>| {
+            let mut value = story_json(id, name, "act1", sort);
+            if let Some(dependence) = dependence {
+                value["storyDependence"] = Value::String(dependence.to_string());
+            }
+            value
+        };
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "act1": {
+                    "entryType": "MINI_ACTIVITY",
+                    "name": "午间逸话",
+                    "infoUnlockDatas": [
+                        entry("act1_01", "序幕", 1, None),
+                        entry("act1_02", "相遇", 2, Some("act1_01")),
+                        entry("act1_03", "告别", 3, Some("act1_02")),
+                        entry("act1_04", "番外", 4, Some("act1_missing")),
+                        entry("act1_05", "循环甲", 5, Some("act1_06")),
+                        entry("act1_06", "循环乙", 6, Some("act1_05"))
+                    ]
+                }
+            }),
+        );
+
+        let chain = service.get_story_dependencies("act1_03").unwrap();
+        let names: Vec<&str> = chain
+            .iter()
+            .map(|d| d.story.as_ref().unwrap().story_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["序幕", "相遇"]);
+        assert_eq!(chain[0].category.as_deref(), Some("活动 | 午间逸话"));
+
+        let dangling = service.get_story_dependencies("act1_04").unwrap();
+        assert_eq!(dangling.len(), 1);
+        assert_eq!(dangling[0].story_id, "act1_missing");
+        assert!(dangling[0].story.is_none());
+
+        let cycle = service.get_story_dependencies("act1_05").unwrap();
+        assert_eq!(cycle.len(), 1);
+        assert!(service
+            .get_story_dependencies("act1_01")
+            .unwrap()
+            .is_empty());
+
+        let detail = service.get_story_detail("act1_03").unwrap();
+        assert_eq!(detail.dependence_name.as_deref(), Some("相遇"));
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn month_chat_parts_are_listed_with_titles_and_readable() {
+        let temp_root = temp_root("month_chat_parts");
+        let service = DataService::new(temp_root.clone());
+        write_month_chat_parts(&service);
+        write_excel_table(&service, "story_review_table.json", &serde_json::json!({}));
+        write_excel_table(
+            &service,
+            "roguelike_topic_table.json",
+            &serde_json::json!({
+                "details": { "rogue_1": { "monthSquad": { "month_team_1": { "chatData": [
+                    { "chatStoryId": "Obt/Rogue/rogue_1/month_chat_rogue_1_1/month_chat_rogue_1_1_2",
+                      "chatDesc": "篝火旁" }
+                ] } } } }
+            }),
+        );
+
+        let parts = service
+            .get_month_chat_parts("obt/rogue/rogue_1/month_chat_rogue_1_1")
+            .unwrap();
+        let names: Vec<&str> = parts.iter().map(|p| p.story_name.as_str()).collect();
+        assert_eq!(names, vec!["第 1 节", "篝火旁", "第 3 节"]);
+        assert_eq!(
+            parts[2].story_txt,
+            "obt/rogue/rogue_1/month_chat_rogue_1_1/month_chat_rogue_1_1_10"
+        );
+        assert_eq!(parts[0].story_group, "MONTH_CHAT_ROGUE_1");
+        assert_eq!(parts[1].story_sort, 2);
+        assert_eq!(
+            service.read_story_text(&parts[2].story_txt).unwrap(),
+            "[name=\"干员\"]第10段"
+        );
+        assert!(service
+            .get_month_chat_parts("obt/roguelike/ro1/level_rogue1_01")
+            .is_err());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn memory_stories_carry_archive_source() {
+        let temp_root = temp_root("memory_source");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "story_amiya_set_1": {
+                    "entryType": "NONE",
+                    "name": "阿米娅",
+                    "infoUnlockDatas": [story_json("story_amiya_set_1_1", "上", "story_amiya_set_1", 1)]
+                },
+                "story_plain_set_1": {
+                    "entryType": "NONE",
+                    "name": "无来源",
+                    "infoUnlockDatas": [story_json("story_plain_set_1_1", "无来源", "story_plain_set_1", 1)]
+                }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "story_review_meta_table.json",
+            &serde_json::json!({
+                "actArchiveData": { "components": { "act11d0": {
+                    "actId": "act11d0",
+                    "story": { "stories": [
+                        { "storySetId": "story_amiya_set_1", "stageId": "act11d0_01" }
+                    ] }
+                } } }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "activity_table.json",
+            &serde_json::json!({ "basicInfo": { "act11d0": { "name": "踏寻往昔之风" } } }),
+        );
+        write_excel_table(
+            &service,
+            "stage_table.json",
+            &serde_json::json!({ "stages": { "act11d0_01": { "code": "WR-1" } } }),
+        );
+
+        let stories = service.get_memory_stories().unwrap();
+        assert_eq!(stories.len(), 2);
+        let amiya = stories
+            .iter()
+            .find(|s| s.story_id == "story_amiya_set_1_1")
+            .unwrap();
+        assert_eq!(amiya.source_act_name.as_deref(), Some("踏寻往昔之风"));
+        assert_eq!(amiya.source_stage_code.as_deref(), Some("WR-1"));
+        let plain = stories
+            .iter()
+            .find(|s| s.story_id == "story_plain_set_1_1")
+            .unwrap();
+        assert_eq!(plain.source_act_name, None);
+        assert_eq!(plain.source_stage_code, None);
+
+        let entries = service.story_entries().unwrap();
+        assert_eq!(
+            entries["story_amiya_set_1_1"].category_name,
+            "阿米娅 · 来自「踏寻往昔之风」"
+        );
+        assert_eq!(entries["story_plain_set_1_1"].category_name, "无来源");
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn characters_list_fills_sub_profession_name() {
+        let temp_root = temp_root("characters_list");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_4010_etlchi": {
+                    "name": "隐德来希", "rarity": "TIER_6", "profession": "TANK",
+                    "subProfessionId": "artsprotector", "nationId": "laterano",
+                    "groupId": null, "teamId": null
+                },
+                "char_4011_lessng": {
+                    "name": "止颂", "rarity": "TIER_6", "profession": "WARRIOR",
+                    "subProfessionId": "unknownsub", "nationId": "leithanien"
+                },
+                "token_10020_ling_soul1": { "name": "清平", "profession": "TOKEN" }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "uniequip_table.json",
+            &serde_json::json!({
+                "subProfDict": {
+                    "artsprotector": {
+                        "subProfessionId": "artsprotector",
+                        "subProfessionName": "驭法铁卫",
+                        "subProfessionCatagory": 2
+                    }
+                }
+            }),
+        );
+
+        let list = service
+            .get_characters_list(&CharacterListFilter::default())
+            .unwrap()
+            .items;
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].char_id, "char_4010_etlchi");
+        assert_eq!(list[0].sub_profession_id.as_deref(), Some("artsprotector"));
+        assert_eq!(list[0].sub_profession_name.as_deref(), Some("驭法铁卫"));
+        assert_eq!(list[0].nation_id.as_deref(), Some("laterano"));
+        assert_eq!(list[0].group_id, None);
+        assert_eq!(list[1].sub_profession_id.as_deref(), Some("unknownsub"));
+        assert_eq!(list[1].sub_profession_name, None);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn characters_list_filters_sorts_and_pages() {
+        let temp_root = temp_root("characters_list_filter");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_002_amiya": {
+                    "name": "阿米娅", "rarity": "TIER_5", "profession": "CASTER",
+                    "subProfessionId": "corecaster", "nationId": "rhodes"
+                },
+                "char_010_chen": {
+                    "name": "陈", "rarity": "TIER_6", "profession": "WARRIOR",
+                    "subProfessionId": "sword", "nationId": "lungmen"
+                },
+                "char_017_huang": {
+                    "name": "煌", "rarity": "TIER_6", "profession": "WARRIOR",
+                    "subProfessionId": "centurion", "nationId": "rhodes"
+                },
+                "char_123_fang": {
+                    "name": "芬", "rarity": "TIER_3", "profession": "PIONEER",
+                    "subProfessionId": "pioneer", "nationId": "kazimierz"
+                },
+                "char_4000_token": { "name": "召唤物", "rarity": "TIER_1", "profession": "TOKEN" },
+                "trap_001_crate": { "name": "障碍物", "rarity": "TIER_1", "profession": "TRAP" }
+            }),
+        );
+        let ids = |page: &CharacterPage| -> Vec<String> {
+            page.items.iter().map(|c| c.char_id.clone()).collect()
+        };
+
+        let all = service
+            .get_characters_list(&CharacterListFilter::default())
+            .unwrap();
+        assert_eq!(all.total, 4);
+        assert_eq!(
+            ids(&all),
+            vec![
+                "char_002_amiya",
+                "char_010_chen",
+                "char_017_huang",
+                "char_123_fang"
+            ]
+        );
+
+        let rhodes = service
+            .get_characters_list(&CharacterListFilter {
+                profession: Some(vec!["WARRIOR".to_string(), "CASTER".to_string()]),
+                nation: Some("rhodes".to_string()),
+                sort: Some(CharacterSort::Rarity),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(ids(&rhodes), vec!["char_017_huang", "char_002_amiya"]);
+
+        let page = service
+            .get_characters_list(&CharacterListFilter {
+                rarity: Some(vec![6, 5]),
+                sort: Some(CharacterSort::Rarity),
+                offset: Some(1),
+                limit: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(page.total, 3);
+        assert_eq!(ids(&page), vec!["char_017_huang"]);
+
+        let by_name = service
+            .get_characters_list(&CharacterListFilter {
+                sub_profession: Some("sword".to_string()),
+                sort: Some(CharacterSort::Name),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(ids(&by_name), vec!["char_010_chen"]);
+
+        let everything = service
+            .get_characters_list(&CharacterListFilter {
+                include_nonplayable: true,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(everything.total, 6);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn parse_rarity_accepts_legacy_and_tier_formats() {
+        let value = serde_json::json!({
+            "legacy": 5,
+            "tier": "TIER_6",
+            "malformed": "TIER_X",
+            "negative": -1
+        });
+        // 旧版数字从 0 起计
+        assert_eq!(parse_rarity(value.get("legacy")), 6);
+        assert_eq!(parse_rarity(value.get("tier")), 6);
+        assert_eq!(parse_rarity(value.get("malformed")), 0);
+        assert_eq!(parse_rarity(value.get("negative")), 0);
+        assert_eq!(parse_rarity(value.get("missing")), 0);
+    }
+
+    #[test]
+    fn voice_actors_are_parsed_per_language_and_voice_set() {
+        let temp_root = temp_root("voice_actors");
+        let service = DataService::new(temp_root.clone());
+        assert!(service
+            .get_character_voice_actors("char_002_amiya")
+            .unwrap()
+            .voice_sets
+            .is_empty());
+
+        write_excel_table(
+            &service,
+            "charword_table.json",
+            &serde_json::json!({
+                "voiceLangDict": {
+                    "char_002_amiya": {
+                        "wordkey": "char_002_amiya",
+                        "charId": "char_002_amiya",
+                        "dict": {
+                            "KR": { "wordkey": "char_002_amiya", "voiceLangType": "KR", "cvName": ["김현지"] },
+                            "JP": { "wordkey": "char_002_amiya", "voiceLangType": "JP", "cvName": "黒沢ともよ" },
+                            "CN_TOPOLECT": { "voiceLangType": "CN_TOPOLECT", "cvName": ["甲", "乙"], "voicePath": "voice_custom/amiya" }
+                        }
+                    },
+                    "char_002_amiya@epoque#4": {
+                        "wordkey": "char_002_amiya@epoque#4",
+                        "charId": "char_002_amiya",
+                        "dict": { "JP": { "voiceLangType": "JP", "cvName": [] } }
+                    },
+                    "char_003_kalts": { "charId": "char_003_kalts", "dict": {} }
+                },
+                "voiceLangTypeDict": { "JP": { "name": "日文" } }
+            }),
+        );
+
+        let actors = service
+            .get_character_voice_actors("char_002_amiya")
+            .unwrap();
+        assert_eq!(actors.voice_sets.len(), 2);
+        let default = &actors.voice_sets[0];
+        assert_eq!(default.word_key, "char_002_amiya");
+        assert!(!default.is_alternate && default.has_topolect);
+        let langs: Vec<&str> = default
+            .languages
+            .iter()
+            .map(|l| l.lang_type.as_str())
+            .collect();
+        assert_eq!(langs, vec!["JP", "CN_TOPOLECT", "KR"]);
+        assert_eq!(default.languages[0].lang_name, "日文");
+        assert_eq!(default.languages[0].cv_names, vec!["黒沢ともよ"]);
+        assert_eq!(default.languages[1].lang_name, "CN_TOPOLECT");
+        assert_eq!(default.languages[1].cv_names, vec!["甲", "乙"]);
+        assert_eq!(
+            default.languages[1].voice_path.as_deref(),
+            Some("voice_custom/amiya")
+        );
+
+        let skin = &actors.voice_sets[1];
+        assert!(skin.is_alternate && !skin.has_topolect);
+        assert!(skin.languages[0].cv_names.is_empty());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn handbook_profile_splits_fields_and_medical_report() {
+        let temp_root = temp_root("handbook_profile");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "handbook_info_table.json",
+            &serde_json::json!({
+                "handbookDict": {
+                    "char_002_amiya": {
+                        "storyTextAudio": [
+                            { "storyTitle": "基础档案", "stories": [{ "storyText":
+                                "【代号】阿米娅\n【性别】女\n【出身地】雷姆必拓\n【生日】12月23日\n【矿石病感染情况】\n体表有源石结晶分布，参照医学检测报告，确认为感染者。\n\n【体检医师报告】\n造影检测结果显示，该干员体内脏器轮廓模糊。\n其源石结晶密度较高。"
+                            }] },
+                            { "storyTitle": "客观履历", "stories": [{ "storyText": "罗德岛的公开领导人。" }] }
+                        ]
+                    },
+                    "char_003_kalts": {
+                        "storyTextAudio": [
+                            { "storyTitle": "基础档案", "stories": [{ "storyText": "【代号】凯尔希\n【生日】" }] }
+                        ]
+                    }
+                }
+            }),
+        );
+
+        let amiya = service.get_character_profile("char_002_amiya").unwrap();
+        let keys: Vec<&str> = amiya.profile.iter().map(|f| f.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec!["代号", "性别", "出身地", "生日", "矿石病感染情况"]
+        );
+        assert_eq!(amiya.profile[2].value, "雷姆必拓");
+        assert_eq!(
+            amiya.profile[4].value,
+            "体表有源石结晶分布，参照医学检测报告，确认为感染者。"
+        );
+        assert_eq!(
+            amiya.medical_report.as_deref(),
+            Some("造影检测结果显示，该干员体内脏器轮廓模糊。\n其源石结晶密度较高。")
+        );
+        assert_eq!(amiya.sections.len(), 2);
+        assert_eq!(amiya.sections[1].text, "罗德岛的公开领导人。");
+        assert_eq!(amiya.sections[0].unlock_desc, "直接解锁");
+
+        let kalts = service.get_character_profile("char_003_kalts").unwrap();
+        assert_eq!(kalts.profile.len(), 2);
+        assert!(!kalts.profile.iter().any(|f| f.key == "性别"));
+        assert_eq!(kalts.profile[1].value, "");
+        assert_eq!(kalts.medical_report, None);
+
+        assert!(service.get_character_profile("char_999_none").is_err());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn search_characters_ranks_by_match_quality() {
+        let temp_root = temp_root("character_search");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_002_amiya": { "name": "阿米娅", "appellation": "Amiya", "rarity": "TIER_5", "profession": "CASTER", "tagList": ["输出", "支援"] },
+                "char_1001_amiya2": { "name": "阿米娅", "appellation": "Amiya", "rarity": "TIER_5", "profession": "WARRIOR", "tagList": ["输出"] },
+                "char_4000_jnight": { "name": "正义骑士号", "appellation": "'Justice Knight'", "rarity": "TIER_1", "tagList": ["支援"] },
+                "char_003_kalts": { "name": "凯尔希", "appellation": "Kal'tsit", "rarity": "TIER_6", "tagList": ["治疗", "支援"] },
+                "char_010_chen": { "name": "陈", "appellation": "Ch'en", "rarity": 5, "tagList": ["输出"] },
+                "token_10000_silent_healrb": { "name": "医疗无人机", "rarity": "TIER_1", "profession": "TOKEN" },
+                "char_4001_wall": { "name": "无人机路障", "rarity": "TIER_1", "profession": "TRAP" }
+            }),
+        );
+
+        let by_name = service.search_characters("阿米", false).unwrap();
+        assert_eq!(by_name.len(), 2);
+        assert!(by_name
+            .iter()
+            .all(|m| m.match_kind == CharacterMatchKind::Prefix));
+
+        let exact = service.search_characters("  AMIYA ", false).unwrap();
+        assert_eq!(exact[0].summary.char_id, "char_002_amiya");
+        assert_eq!(exact[0].match_kind, CharacterMatchKind::Exact);
+
+        let mixed = service.search_characters("支援", false).unwrap();
+        let ids: Vec<&str> = mixed.iter().map(|m| m.summary.char_id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec!["char_003_kalts", "char_002_amiya", "char_4000_jnight"]
+        );
+        assert!(mixed
+            .iter()
+            .all(|m| m.match_kind == CharacterMatchKind::Tag));
+
+        let substring = service.search_characters("knight", false).unwrap();
+        assert_eq!(substring[0].match_kind, CharacterMatchKind::Substring);
+
+        assert!(service
+            .search_characters("无人机", false)
+            .unwrap()
+            .is_empty());
+        let everything = service.search_characters("无人机", true).unwrap();
+        let ids: Vec<&str> = everything
+            .iter()
+            .map(|m| m.summary.char_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["char_4001_wall", "token_10000_silent_healrb"]);
+        assert!(service
+            .search_characters("不存在的干员", false)
+            .unwrap()
+            .is_empty());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn character_favor_reports_key_frames_and_max_bonus() {
+        let temp_root = temp_root("character_favor");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_002_amiya": { "name": "阿米娅", "favorKeyFrames": [
+                    { "level": 0, "data": { "maxHp": 0, "atk": 0, "def": 0, "magicResistance": 0.0 } },
+                    { "level": 50, "data": { "maxHp": 300, "atk": 30, "def": 0, "magicResistance": 0.0 } }
+                ] },
+                "char_150_snakek": { "name": "蛇屠箱", "favorKeyFrames": [
+                    { "level": 0, "data": { "def": 0 } },
+                    { "level": 50, "data": { "def": 80 } }
+                ] },
+                "char_285_medic2": { "name": "Lancet-2", "favorKeyFrames": null }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "favor_table.json",
+            &serde_json::json!({ "favorFrames": [
+                { "level": 0, "data": { "favorPoint": 0, "percent": 0 } },
+                { "level": 50, "data": { "favorPoint": 2400, "percent": 100 } }
+            ] }),
+        );
+
+        let amiya = service.get_character_favor("char_002_amiya").unwrap();
+        assert_eq!(amiya.key_frames.len(), 2);
+        assert!(amiya.key_frames[0].bonuses.is_empty());
+        assert_eq!(amiya.key_frames[1].percent, 100);
+        let attributes: Vec<&str> = amiya
+            .max_bonuses
+            .iter()
+            .map(|b| b.attribute.as_str())
+            .collect();
+        assert_eq!(attributes, vec!["maxHp", "atk"]);
+        assert_eq!(amiya.summary, "满信赖 +300 生命 +30 攻击");
+
+        let snake = service.get_character_favor("char_150_snakek").unwrap();
+        assert_eq!(snake.max_bonuses.len(), 1);
+        assert_eq!(snake.summary, "满信赖 +80 防御");
+
+        let lancet = service.get_character_favor("char_285_medic2").unwrap();
+        assert!(lancet.key_frames.is_empty());
+        assert_eq!(lancet.summary, "满信赖无属性加成");
+
+        assert!(service.get_character_favor("char_999_none").is_err());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn character_attributes_cover_each_phase() {
+        let temp_root = temp_root("character_attributes");
+        let service = DataService::new(temp_root.clone());
+        let frame = |level: i64, hp: i64, atk: i64, def: i64, res: f64| {
+            serde_json::json!({ "level": level, "data": {
+                "maxHp": hp, "atk": atk, "def": def, "magicResistance": res,
+                "cost": 21, "blockCnt": 3, "moveSpeed": 1.0, "attackSpeed": 100.0,
+                "baseAttackTime": 1.2, "respawnTime": 70
+            } })
+        };
+        // 裁剪自 character_table.json 的 6★ 与 1★ 条目
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_017_huang": { "name": "煌", "rarity": "TIER_6", "phases": [
+                    { "maxLevel": 50, "attributesKeyFrames": [frame(1, 1354, 388, 197, 0.0), frame(50, 1894, 568, 275, 0.0)] },
+                    { "maxLevel": 80, "attributesKeyFrames": [frame(1, 1894, 568, 275, 0.0), frame(80, 2488, 717, 357, 0.0)] },
+                    { "maxLevel": 90, "attributesKeyFrames": [frame(1, 2488, 717, 357, 0.0), frame(90, 2943, 852, 413, 0.0)] }
+                ] },
+                "char_285_medic2": { "name": "Lancet-2", "rarity": "TIER_1", "phases": [
+                    { "maxLevel": 30, "attributesKeyFrames": [
+                        { "level": 1, "data": { "maxHp": 710, "atk": 130, "def": 50, "magicResistance": 0.0, "cost": 3 } },
+                        { "level": 30, "data": { "maxHp": 1025, "atk": 165, "def": 60, "magicResistance": 0.0, "cost": 3 } }
+                    ] }
+                ] }
+            }),
+        );
+
+        let huang = service.get_character_attributes("char_017_huang").unwrap();
+        assert_eq!(huang.phases.len(), 3);
+        assert_eq!(huang.phases[0].start.max_hp, 1354);
+        assert_eq!(huang.phases[0].max_level, 50);
+        assert_eq!(huang.phases[1].end.atk, 717);
+        let e2 = &huang.phases[2];
+        assert_eq!((e2.phase, e2.min_level, e2.max_level), (2, 1, 90));
+        assert_eq!(e2.end.def, 413);
+        assert_eq!(e2.end.block_cnt, 3);
+        assert_eq!(e2.end.respawn_time, 70);
+
+        let lancet = service.get_character_attributes("char_285_medic2").unwrap();
+        assert_eq!(lancet.phases.len(), 1);
+        assert_eq!(lancet.phases[0].end.max_hp, 1025);
+        assert_eq!(lancet.phases[0].end.cost, 3);
+        assert_eq!(lancet.phases[0].end.block_cnt, 0);
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn character_ranges_resolve_phase_and_skill_grids() {
+        let temp_root = temp_root("character_ranges");
+        let service = DataService::new(temp_root.clone());
+        let row = |row: i64, cols: std::ops::RangeInclusive<i64>| -> Vec<serde_json::Value> {
+            cols.map(|col| serde_json::json!({ "row": row, "col": col }))
+                .collect()
+        };
+        let melee: Vec<_> = (-1..=1).flat_map(|r| row(r, 0..=1)).collect();
+        let sniper: Vec<_> = (-1..=1).flat_map(|r| row(r, 0..=4)).collect();
+        write_excel_table(
+            &service,
+            "range_table.json",
+            &serde_json::json!({
+                "1-1": { "id": "1-1", "direction": 1, "grids": [] },
+                "2-1": { "id": "2-1", "direction": 1, "grids": melee },
+                "3-10": { "id": "3-10", "direction": 1, "grids": sniper }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_010_chen": {
+                    "name": "陈",
+                    "phases": [{ "rangeId": "2-1" }, { "rangeId": "2-1" }, { "rangeId": "9-9" }],
+                    "skills": [{ "skillId": "skchr_chen_3" }, { "skillId": "skchr_missing" }]
+                }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "skill_table.json",
+            &serde_json::json!({
+                "skchr_chen_3": { "levels": [
+                    { "name": "赤霄·绝影", "rangeId": null },
+                    { "name": "赤霄·绝影", "rangeId": "3-10" },
+                    { "name": "赤霄·绝影", "rangeId": "3-10" }
+                ] }
+            }),
+        );
+
+        let self_only = service.get_range("1-1").unwrap();
+        assert!(self_only.cells.is_empty());
+        let long = service.get_range("3-10").unwrap();
+        assert_eq!(long.cells.len(), 15);
+        assert!(long.cells.contains(&(1, 4)));
+        let err = service.get_range("0-0").unwrap_err();
+        assert!(err.contains("0-0"), "{}", err);
+
+        let ranges = service.get_character_ranges("char_010_chen").unwrap();
+        assert_eq!(ranges.phases.len(), 3);
+        let phase0 = ranges.phases[0].range.as_ref().unwrap();
+        assert_eq!(phase0.cells.len(), 6);
+        assert!(phase0.cells.contains(&(-1, 0)) && phase0.cells.contains(&(1, 1)));
+        assert_eq!(ranges.phases[2].range, None);
+        assert_eq!(ranges.skills.len(), 1);
+        assert_eq!(ranges.skills[0].from_level, 2);
+        assert_eq!(ranges.skills[0].skill_name.as_deref(), Some("赤霄·绝影"));
+        assert_eq!(ranges.skills[0].range.id, "3-10");
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn module_details_join_battle_equip_stages() {
+        let temp_root = temp_root("module_details");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "uniequip_table.json",
+            &serde_json::json!({
+                "equipDict": {
+                    "uniequip_001_nian": { "uniEquipName": "年的证章", "typeName1": "ORIGINAL", "typeName2": null },
+                    "uniequip_002_nian": { "uniEquipName": "岁", "typeName1": "UNY", "typeName2": "X" },
+                    "uniequip_003_nian": { "uniEquipName": "赤金", "typeName1": "UNY", "typeName2": "Y" }
+                },
+                "charEquip": { "char_2014_nian": ["uniequip_001_nian", "uniequip_002_nian", "uniequip_003_nian"] }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "battle_equip_table.json",
+            &serde_json::json!({
+                "uniequip_002_nian": { "phases": [
+                    { "equipLevel": 1, "parts": [
+                        { "target": "TRAIT", "overrideTraitDataBundle": { "candidates": [
+                            { "additionalDescription": "攻击范围内有友方单位时防御力+10%", "requiredPotentialRank": 0,
+                              "blackboard": [{ "key": "def", "value": 0.1 }] }
+                        ] }, "addOrOverrideTalentDataBundle": { "candidates": null } }
+                    ], "attributeBlackboard": [{ "key": "max_hp", "value": 200 }, { "key": "def", "value": 40 }] },
+                    { "equipLevel": 2, "parts": [
+                        { "target": "TALENT", "overrideTraitDataBundle": { "candidates": null },
+                          "addOrOverrideTalentDataBundle": { "candidates": [
+                            { "talentIndex": 0, "name": "铁御", "upgradeDescription": "护盾值提升", "requiredPotentialRank": 0,
+                              "blackboard": [{ "key": "value", "value": 0.35 }] },
+                            { "talentIndex": 0, "name": "铁御", "upgradeDescription": "护盾值提升（潜能5）", "requiredPotentialRank": 4,
+                              "blackboard": [{ "key": "value", "value": 0.4 }] }
+                          ] } }
+                    ], "attributeBlackboard": [{ "key": "max_hp", "value": 260 }] }
+                ] },
+                "uniequip_003_nian": { "phases": [
+                    { "equipLevel": 1, "parts": [
+                        { "target": "TRAIT_DATA_ONLY", "overrideTraitDataBundle": { "candidates": [
+                            { "additionalDescription": null, "overrideDescripton": "可以阻挡四个敌人",
+                              "blackboard": [{ "key": "block_cnt", "value": 4 }] }
+                        ] } }
+                    ], "attributeBlackboard": [{ "key": "atk", "value": 30 }] }
+                ] }
+            }),
+        );
+
+        let modules = service
+            .get_character_module_details("char_2014_nian")
+            .unwrap();
+        assert_eq!(modules.len(), 3);
+        assert!(modules[0].is_original && modules[0].stages.is_empty());
+        assert_eq!(modules[0].type_name, "ORIGINAL");
+
+        let x = &modules[1];
+        assert_eq!(x.type_name, "UNY-X");
+        assert_eq!(x.stages.len(), 2);
+        assert_eq!(x.stages[0].attributes.len(), 2);
+        assert_eq!(x.stages[0].attributes[1].value, 40.0);
+        assert_eq!(
+            x.stages[0].trait_descriptions,
+            vec!["攻击范围内有友方单位时防御力+10%"]
+        );
+        assert!(x.stages[0].talents.is_empty());
+        let talent = &x.stages[1].talents;
+        assert_eq!(talent.len(), 1);
+        assert_eq!(talent[0].description.as_deref(), Some("护盾值提升"));
+        assert_eq!(talent[0].blackboard[0].value, 0.35);
+
+        let y = &modules[2];
+        assert_eq!(y.type_name, "UNY-Y");
+        assert_eq!(y.stages[0].trait_descriptions, vec!["可以阻挡四个敌人"]);
+        assert_eq!(y.stages[0].attributes[0].key, "atk");
+
+        assert!(service
+            .get_character_module_details("char_002_amiya")
+            .unwrap()
+            .is_empty());
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn upgrade_costs_resolve_item_names_and_counts() {
+        let temp_root = temp_root("upgrade_costs");
+        let service = DataService::new(temp_root.clone());
+        let cost = |id: &str, count: i64| serde_json::json!({ "id": id, "count": count, "type": "MATERIAL" });
+        // 裁剪自 character_table.json 的 6★ 条目
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_103_angel": {
+                    "name": "能天使",
+                    "phases": [
+                        { "evolveCost": null },
+                        { "evolveCost": [cost("3281", 5), cost("30012", 12)] },
+                        { "evolveCost": [cost("3282", 4), cost("31014", 8)] }
+                    ],
+                    "allSkillLvlup": [
+                        { "lvlUpCost": [cost("3301", 5)] },
+                        { "lvlUpCost": [cost("3302", 5), cost("30011", 4)] }
+                    ],
+                    "skills": [{
+                        "skillId": "skchr_angel_3",
+                        "levelUpCostCond": [
+                            { "levelUpCost": [cost("3303", 8), cost("30073", 4)] },
+                            { "levelUpCost": [cost("3303", 12), cost("31023", 3)] },
+                            { "levelUpCost": [cost("3303", 15), cost("30115", 5), cost("30104", 6)] }
+                        ]
+                    }]
+                }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "item_table.json",
+            &serde_json::json!({ "items": {
+                "3303": { "name": "技巧概要·卷3" },
+                "30115": { "name": "聚合剂" },
+                "30104": { "name": "RMA70-24" },
+                "3282": { "name": "狙击双芯片" }
+            } }),
+        );
+        write_excel_table(
+            &service,
+            "skill_table.json",
+            &serde_json::json!({ "skchr_angel_3": { "levels": [{ "name": "过载模式" }] } }),
+        );
 
-        Ok(SearchDebugResponse {
-            results: merged,
-            logs,
-        })
+        let costs = service
+            .get_character_upgrade_costs("char_103_angel")
+            .unwrap();
+        assert_eq!(costs.elite.len(), 2);
+        assert_eq!(costs.elite[1].level, 2);
+        assert_eq!(costs.elite[1].costs[0].item_name, "狙击双芯片");
+        assert_eq!(costs.elite[0].costs[0].item_name, "3281");
+        assert_eq!(costs.skill_levels[1].level, 3);
+        assert_eq!(costs.skill_levels[1].costs.len(), 2);
+
+        let skill = &costs.masteries[0];
+        assert_eq!(skill.skill_name.as_deref(), Some("过载模式"));
+        assert_eq!(skill.masteries.len(), 3);
+        let m3: Vec<(&str, i32)> = skill.masteries[2]
+            .costs
+            .iter()
+            .map(|c| (c.item_name.as_str(), c.count))
+            .collect();
+        assert_eq!(
+            m3,
+            vec![("技巧概要·卷3", 15), ("聚合剂", 5), ("RMA70-24", 6)]
+        );
+
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
-    /// 带进度事件的搜索：优先使用索引；当回退线性扫描时，实时发送遍历进度
-    pub fn search_stories_with_progress(
-        &self,
-        app: &AppHandle,
-        query: &str,
-    ) -> Result<Vec<SearchResult>, String> {
-        let trimmed = query.trim();
-        if trimmed.is_empty() {
-            emit_search_progress(app, "完成", 1, 1, "查询为空");
-            return Ok(Vec::new());
-        }
+    #[test]
+    fn character_skins_follow_display_order() {
+        let temp_root = temp_root("character_skins");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "skin_table.json",
+            &serde_json::json!({ "charSkins": {
+                "char_002_amiya@epoque#4": {
+                    "charId": "char_002_amiya", "avatarId": "char_002_amiya_epoque#4",
+                    "displaySkin": { "skinName": "寰宇独奏", "skinGroupId": "epoque", "skinGroupName": "0011制造", "sortId": 10, "getTime": 1688688000 }
+                },
+                "char_002_amiya#1": {
+                    "charId": "char_002_amiya", "avatarId": "char_002_amiya",
+                    "displaySkin": { "skinName": null, "skinGroupId": "ILLUST_0", "sortId": -1, "getTime": 0 }
+                },
+                "char_002_amiya@test": {
+                    "charId": "char_002_amiya",
+                    "displaySkin": { "skinName": "未排序", "getTime": 1700000000 }
+                },
+                "char_003_kalts#1": { "charId": "char_003_kalts", "displaySkin": { "sortId": -1 } }
+            } }),
+        );
 
-        // 尝试索引
-        match self.search_stories_with_index(trimmed) {
-            Ok(Some(results)) => {
-                emit_search_progress(app, "索引检索", 1, 1, "使用全文索引完成");
-                return Ok(results);
-            }
-            Ok(None) => {
-                // fallthrough
-            }
-            Err(_err) => {
-                // fallthrough to fallback scan
-            }
-        }
+        let skins = service.get_character_skins("char_002_amiya").unwrap();
+        let ids: Vec<&str> = skins.iter().map(|s| s.skin_id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "char_002_amiya#1",
+                "char_002_amiya@epoque#4",
+                "char_002_amiya@test"
+            ]
+        );
+        assert_eq!(skins[0].skin_group_id.as_deref(), Some("ILLUST_0"));
+        assert_eq!(skins[0].get_time, None);
+        assert_eq!(skins[1].sort_id, Some(10));
+        assert_eq!(skins[1].get_time, Some(1688688000));
+        assert_eq!(skins[1].skin_name.as_deref(), Some("寰宇独奏"));
 
-        // 线性扫描，实时进度
-        let stories = self.collect_stories_for_index()?;
-        let total = stories.len();
-        emit_search_progress(app, "线性扫描", 0, total.max(1), "开始遍历");
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-        let mut results = Vec::new();
-        let query_norm = normalize_nfkc_lower_strip_marks(trimmed);
-        for (idx, indexed) in stories.iter().enumerate() {
-            let story = &indexed.story;
-            let category_label =
-                Self::format_category_label(&indexed.entry_type, &indexed.category_name);
+    #[test]
+    fn module_unlock_lists_missions_and_item_costs() {
+        let temp_root = temp_root("module_unlock");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "uniequip_table.json",
+            &serde_json::json!({
+                "equipDict": {
+                    "uniequip_001_amiya": { "uniEquipName": "阿米娅的证章", "typeName1": "ORIGINAL" },
+                    "uniequip_002_amiya": {
+                        "uniEquipName": "柏喙", "typeName1": "CCR", "typeName2": "X",
+                        "unlockEvolvePhase": "PHASE_2", "unlockLevel": 60, "unlockFavorPoint": 100,
+                        "missionList": ["uniequip_002_amiya_1", "uniequip_002_amiya_2"],
+                        "itemCost": {
+                            "2": [{ "id": "mod_update_token_1", "count": 20, "type": "MATERIAL" }],
+                            "1": [
+                                { "id": "mod_unlock_token", "count": 1, "type": "MATERIAL" },
+                                { "id": "4001", "count": 50000, "type": "GOLD" }
+                            ]
+                        }
+                    }
+                },
+                "missions": {
+                    "uniequip_002_amiya_1": { "desc": "以阿米娅通关主线1-7" },
+                    "uniequip_002_amiya_2": {}
+                },
+                "charEquip": { "char_002_amiya": ["uniequip_001_amiya", "uniequip_002_amiya"] }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "item_table.json",
+            &serde_json::json!({ "items": {
+                "mod_unlock_token": { "name": "模组数据块" },
+                "4001": { "name": "龙门币" }
+            } }),
+        );
 
-            let story_name_norm = normalize_nfkc_lower_strip_marks(&story.story_name);
-            if story_name_norm.contains(&query_norm) {
-                results.push(SearchResult {
-                    story_id: story.story_id.clone(),
-                    story_name: story.story_name.clone(),
-                    matched_text: story.story_name.clone(),
-                    category: category_label.clone(),
-                });
-            } else if let Ok(content) = self.read_story_text(&story.story_txt) {
-                let content_norm = normalize_nfkc_lower_strip_marks(&content);
-                if content_norm.contains(&query_norm) {
-                    let matched_text = self.extract_context(&content, &query_norm);
-                    results.push(SearchResult {
-                        story_id: story.story_id.clone(),
-                        story_name: story.story_name.clone(),
-                        matched_text,
-                        category: category_label.clone(),
-                    });
+        let modules = service
+            .get_character_module_details("char_002_amiya")
+            .unwrap();
+        assert_eq!(modules[0].unlock, None);
+        let unlock = modules[1].unlock.as_ref().unwrap();
+        assert_eq!(
+            (unlock.evolve_phase, unlock.level, unlock.favor),
+            (2, 60, Some(100))
+        );
+        assert_eq!(unlock.summary, "精二60级、信赖100、完成2个任务");
+        assert_eq!(unlock.missions.len(), 2);
+        assert_eq!(
+            unlock.missions[0].description.as_deref(),
+            Some("以阿米娅通关主线1-7")
+        );
+        assert_eq!(unlock.missions[1].description, None);
+
+        let levels: Vec<i32> = unlock.item_costs.iter().map(|c| c.level).collect();
+        assert_eq!(levels, vec![1, 2]);
+        let first: Vec<(&str, i32)> = unlock.item_costs[0]
+            .costs
+            .iter()
+            .map(|c| (c.item_name.as_str(), c.count))
+            .collect();
+        assert_eq!(first, vec![("模组数据块", 1), ("龙门币", 50000)]);
+        assert_eq!(
+            unlock.item_costs[1].costs[0].item_name,
+            "mod_update_token_1"
+        );
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn character_memories_follow_handbook_owner() {
+        let temp_root = temp_root("character_memories");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "story_amiya_set_1": {
+                    "entryType": "NONE",
+                    "infoUnlockDatas": [
+                        story_json("story_amiya_set_1_2", "下", "story_amiya_set_1", 2),
+                        story_json("story_amiya_set_1_1", "上", "story_amiya_set_1", 1)
+                    ]
+                },
+                "story_silverash_set_1": {
+                    "entryType": "NONE",
+                    "infoUnlockDatas": [story_json("story_silverash_set_1_1", "往昔一瞥", "story_silverash_set_1", 1)]
                 }
-            }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "handbook_info_table.json",
+            &serde_json::json!({
+                "handbookDict": {
+                    "char_002_amiya": {
+                        "charID": "char_002_amiya",
+                        "handbookAvgList": [{
+                            "storySetId": "story_amiya_set_1",
+                            "unlockParam": [{ "unlockType": "FAVOR", "unlockParam1": "50" }],
+                            "avgList": []
+                        }]
+                    },
+                    "char_172_svrash": {
+                        "charID": "char_172_svrash",
+                        "handbookAvgList": [{ "storySetId": "story_silverash_set_1", "avgList": [] }]
+                    }
+                }
+            }),
+        );
 
-            emit_search_progress(
-                app,
-                "线性扫描",
-                (idx + 1).min(total),
-                total.max(1),
-                format!("已扫描 {} / {}", idx + 1, total),
-            );
+        let memories = service.get_character_memories("char_002_amiya").unwrap();
+        let ids: Vec<&str> = memories.iter().map(|s| s.story_id.as_str()).collect();
+        assert_eq!(ids, vec!["story_amiya_set_1_1", "story_amiya_set_1_2"]);
+        assert!(memories
+            .iter()
+            .all(|s| s.unlock_desc.as_deref() == Some("解锁需要信赖值50")));
 
-            if results.len() >= SEARCH_RESULT_LIMIT {
-                break;
-            }
-        }
+        assert!(service
+            .get_character_memories("char_003_kalts")
+            .unwrap()
+            .is_empty());
 
-        Ok(results)
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
-    pub fn get_story_entry(&self, story_id: &str) -> Result<StoryEntry, String> {
-        let stories = self.collect_stories_for_index()?;
-        for indexed in stories {
-            if indexed.story.story_id == story_id {
-                return Ok(indexed.story);
-            }
-        }
-        Err(format!("Story {} 不存在", story_id))
+    #[test]
+    fn voice_asset_paths_follow_language_dirs() {
+        assert_eq!(
+            voice_asset_path("CN_MANDARIN", "char_002_amiya/CN_001", None),
+            "voice_cn/char_002_amiya/cn_001.mp3"
+        );
+        assert_eq!(
+            voice_asset_path("JP", "char_002_amiya@epoque#4/CN_042", None),
+            "voice/char_002_amiya@epoque#4/cn_042.mp3"
+        );
+        assert_eq!(
+            voice_asset_path("LINKAGE", "char_4077_palico/CN_001", None),
+            "voice_linkage/char_4077_palico/cn_001.mp3"
+        );
+
+        let temp_root = temp_root("voice_assets");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "charword_table.json",
+            &serde_json::json!({
+                "charWords": {
+                    "char_002_amiya_CN_002": { "charId": "char_002_amiya", "wordKey": "char_002_amiya", "voiceId": "CN_002",
+                        "voiceIndex": 2, "voiceTitle": "交谈1", "voiceAsset": "char_002_amiya/CN_002" },
+                    "char_002_amiya_CN_001": { "charId": "char_002_amiya", "wordKey": "char_002_amiya", "voiceId": "CN_001",
+                        "voiceIndex": 1, "voiceTitle": "任命助理", "voiceText": "博士，您工作辛苦了。", "voiceAsset": "char_002_amiya/CN_001" },
+                    "char_002_amiya@epoque#4_CN_001": { "charId": "char_002_amiya", "wordKey": "char_002_amiya@epoque#4",
+                        "voiceId": "CN_001", "voiceIndex": 1 },
+                    "char_003_kalts_CN_001": { "charId": "char_003_kalts", "voiceId": "CN_001" }
+                },
+                "voiceLangDict": {
+                    "char_002_amiya": { "charId": "char_002_amiya", "dict": {
+                        "CN_TOPOLECT": { "voiceLangType": "CN_TOPOLECT", "cvName": ["甲"], "voicePath": "char_002_amiya_cn_topolect" }
+                    } },
+                    "char_002_amiya@epoque#4": { "charId": "char_002_amiya", "dict": {} }
+                }
+            }),
+        );
+
+        let assets = service
+            .get_character_voice_assets("char_002_amiya", "cn_topolect")
+            .unwrap();
+        assert_eq!(assets.lang, "CN_TOPOLECT");
+        assert!(assets.has_alternate_sets);
+        let paths: Vec<&str> = assets.lines.iter().map(|l| l.asset_path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "voice_custom/char_002_amiya_cn_topolect/cn_001.mp3",
+                "voice_custom/char_002_amiya_cn_topolect/cn_002.mp3",
+                "voice_custom/char_002_amiya@epoque#4/cn_001.mp3",
+            ]
+        );
+        assert_eq!(assets.lines[0].voice_title.as_deref(), Some("任命助理"));
+
+        let kr = service
+            .get_character_voice_assets("char_002_amiya", "KR")
+            .unwrap();
+        assert_eq!(kr.lines[0].asset_path, "voice_kr/char_002_amiya/cn_001.mp3");
+        assert!(service
+            .get_character_voice_assets("char_002_amiya", "../x")
+            .is_err());
+
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
-    /// 提取匹配文本的上下文
-    fn extract_context(&self, content: &str, query: &str) -> String {
-        if content.is_empty() || query.is_empty() {
-            return String::new();
-        }
+    #[test]
+    fn character_tokens_resolve_linked_entries() {
+        let temp_root = temp_root("character_tokens");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_003_kalts": {
+                    "name": "凯尔希", "profession": "MEDIC",
+                    "displayTokenDict": { "token_10012_kalts_mon3tr": true },
+                    "skills": [{ "skillId": "skchr_kalts_3", "overrideTokenKey": "token_10012_kalts_mon3tr" }]
+                },
+                "char_2023_ling": {
+                    "name": "令", "profession": "SUPPORT",
+                    "displayTokenDict": { "token_10020_ling_soul1": true, "token_10020_ling_soul2": true },
+                    "tokenKey": "token_10020_ling_soul3"
+                },
+                "char_010_chen": { "name": "陈", "profession": "WARRIOR" },
+                "token_10012_kalts_mon3tr": {
+                    "name": "Mon3tr", "profession": "TOKEN", "description": "召唤物",
+                    "trait": { "candidates": [{ "overrideDescripton": "不会成为敌人的攻击目标" }] },
+                    "talents": [{ "candidates": [
+                        { "name": "回路", "description": "初始描述" },
+                        { "name": "回路", "description": "精二描述" }
+                    ] }],
+                    "phases": [{ "maxLevel": 50, "attributesKeyFrames": [
+                        { "level": 1, "data": { "maxHp": 2000, "atk": 500 } },
+                        { "level": 50, "data": { "maxHp": 3000, "atk": 700 } }
+                    ] }]
+                },
+                "token_10020_ling_soul1": { "name": "清平", "profession": "TOKEN", "description": "召唤物一" },
+                "token_10020_ling_soul2": { "name": "逍遥", "profession": "TOKEN" },
+                "char_4000_token": { "name": "令的召唤物", "profession": "TOKEN" }
+            }),
+        );
 
-        let content_lower = normalize_nfkc_lower_strip_marks(content);
+        let kalts = service.get_character_tokens("char_003_kalts").unwrap();
+        assert_eq!(kalts.len(), 1);
+        let mon3tr = &kalts[0];
+        assert_eq!(mon3tr.name, "Mon3tr");
+        assert_eq!(
+            mon3tr.trait_description.as_deref(),
+            Some("不会成为敌人的攻击目标")
+        );
+        assert_eq!(mon3tr.talents[0].description.as_deref(), Some("精二描述"));
+        assert_eq!(mon3tr.phases[0].end.max_hp, 3000);
 
-        if let Some(pos) = content_lower.find(query) {
-            return Self::build_context_snippet(content, pos, query.len());
-        }
+        let ling = service.get_character_tokens("char_2023_ling").unwrap();
+        let names: Vec<&str> = ling.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["清平", "逍遥"]);
+        assert_eq!(ling[0].trait_description.as_deref(), Some("召唤物一"));
 
-        for token in query.split_whitespace().filter(|t| !t.is_empty()) {
-            if let Some(pos) = content_lower.find(token) {
-                return Self::build_context_snippet(content, pos, token.len());
-            }
-        }
+        assert!(service
+            .get_character_tokens("char_010_chen")
+            .unwrap()
+            .is_empty());
 
-        String::new()
+        let found = service.search_characters("令", false).unwrap();
+        let ids: Vec<&str> = found.iter().map(|m| m.summary.char_id.as_str()).collect();
+        assert_eq!(ids, vec!["char_2023_ling"]);
+
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
-    fn build_context_snippet(content: &str, byte_start: usize, pattern_bytes: usize) -> String {
-        let prefix = match content.get(..byte_start) {
-            Some(slice) => slice,
-            None => return String::new(),
-        };
+    #[test]
+    fn character_forms_group_amiya_chain() {
+        let temp_root = temp_root("character_forms");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_002_amiya": {
+                    "name": "阿米娅", "profession": "CASTER", "rarity": "TIER_5",
+                    "skills": [{ "skillId": "skchr_amiya_1" }],
+                    "talents": [{ "candidates": [{ "name": "情绪吸收", "description": "攻击回复技力" }] }]
+                },
+                "char_010_chen": { "name": "陈", "profession": "WARRIOR", "rarity": "TIER_6" }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "char_patch_table.json",
+            &serde_json::json!({
+                "infos": {
+                    "char_002_amiya": {
+                        "tmplIds": ["char_002_amiya", "char_1001_amiya2", "char_1037_amiya3"],
+                        "default": "char_002_amiya"
+                    }
+                },
+                "patchChars": {
+                    "char_1001_amiya2": {
+                        "name": "阿米娅", "profession": "WARRIOR", "rarity": "TIER_5",
+                        "skills": [{ "skillId": "skchr_amiya2_1" }],
+                        "talents": [{ "candidates": [{ "name": "望见", "description": "近卫天赋" }] }],
+                        "phases": [{ "maxLevel": 50, "attributesKeyFrames": [
+                            { "level": 1, "data": { "maxHp": 1200, "atk": 300 } },
+                            { "level": 50, "data": { "maxHp": 1600, "atk": 400 } }
+                        ] }]
+                    },
+                    "char_1037_amiya3": {
+                        "name": "阿米娅", "profession": "MEDIC", "rarity": "TIER_5"
+                    }
+                }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "skill_table.json",
+            &serde_json::json!({
+                "skchr_amiya2_1": { "levels": [{ "name": "影霄" }] }
+            }),
+        );
 
-        let byte_end = byte_start.saturating_add(pattern_bytes).min(content.len());
-        let matched_slice = match content.get(byte_start..byte_end) {
-            Some(slice) => slice,
-            None => "",
+        let forms = service.get_character_forms("char_1001_amiya2").unwrap();
+        let ids: Vec<&str> = forms.iter().map(|f| f.char_id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec!["char_002_amiya", "char_1001_amiya2", "char_1037_amiya3"]
+        );
+        assert!(forms[0].is_base && !forms[1].is_base);
+        assert_eq!(forms[1].profession.as_deref(), Some("WARRIOR"));
+        assert_eq!(forms[1].skills[0].skill_name.as_deref(), Some("影霄"));
+        assert_eq!(forms[0].skills[0].skill_name, None);
+        assert_eq!(forms[1].talents[0].name.as_deref(), Some("望见"));
+        // 未覆盖技能的形态沿用原版技能
+        assert_eq!(forms[2].skills[0].skill_id, "skchr_amiya_1");
+
+        let chen = service.get_character_forms("char_010_chen").unwrap();
+        assert_eq!(chen.len(), 1);
+        assert!(chen[0].is_base);
+
+        let guard = service
+            .get_character_attributes("char_1001_amiya2")
+            .unwrap();
+        assert_eq!(guard.phases[0].end.max_hp, 1600);
+
+        let found = service.search_characters("阿米娅", false).unwrap();
+        let summary: Vec<(&str, bool, Option<&str>)> = found
+            .iter()
+            .map(|m| {
+                (
+                    m.summary.char_id.as_str(),
+                    m.summary.has_alternate_forms,
+                    m.summary.base_char_id.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("char_002_amiya", true, None),
+                ("char_1001_amiya2", true, Some("char_002_amiya")),
+                ("char_1037_amiya3", true, Some("char_002_amiya")),
+            ]
+        );
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn character_commands_share_one_character_table_parse() {
+        let temp_root = temp_root("character_table_cache");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_010_chen": {
+                    "name": "陈", "profession": "WARRIOR", "rarity": "TIER_6",
+                    "favorKeyFrames": [
+                        { "level": 0, "data": { "atk": 0 } },
+                        { "level": 50, "data": { "atk": 60 } }
+                    ],
+                    "phases": [{ "maxLevel": 50, "attributesKeyFrames": [
+                        { "level": 1, "data": { "maxHp": 1000 } },
+                        { "level": 50, "data": { "maxHp": 1500 } }
+                    ] }],
+                    "skills": [{ "skillId": "skchr_chen_1" }]
+                }
+            }),
+        );
+        let table_reads = |table: &str| {
+            service
+                .table_cache
+                .table_reads
+                .read()
+                .unwrap()
+                .get(table)
+                .copied()
+                .unwrap_or(0)
         };
 
-        let start_char_index = prefix.chars().count();
-        let matched_char_len = matched_slice.chars().count();
+        service.get_character_attributes("char_010_chen").unwrap();
+        service.get_character_favor("char_010_chen").unwrap();
+        // 克隆共享同一份缓存
+        service
+            .clone()
+            .get_character_upgrade_costs("char_010_chen")
+            .unwrap();
+        service.get_character_forms("char_010_chen").unwrap();
+        assert_eq!(table_reads("character_table.json"), 1);
 
-        let chars: Vec<char> = content.chars().collect();
-        if chars.is_empty() {
-            return String::new();
-        }
+        service.invalidate_caches();
+        service.get_character_attributes("char_010_chen").unwrap();
+        assert_eq!(table_reads("character_table.json"), 2);
 
-        let window = 50usize;
-        let snippet_start = start_char_index.saturating_sub(window);
-        let snippet_end = (start_char_index + matched_char_len + window).min(chars.len());
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-        let snippet: String = chars[snippet_start..snippet_end].iter().collect();
-        if snippet.is_empty() {
-            return String::new();
-        }
+    #[test]
+    fn handbook_sections_describe_unlock_conditions() {
+        let temp_root = temp_root("handbook_unlock");
+        let service = DataService::new(temp_root.clone());
+        let story = |title: &str, unlock_type: &str, param: &str| {
+            serde_json::json!({ "storyTitle": title, "stories": [{
+                "storyText": "……", "unLockType": unlock_type, "unLockParam": param,
+                "unLockString": "完成特殊任务"
+            }] })
+        };
+        write_excel_table(
+            &service,
+            "handbook_info_table.json",
+            &serde_json::json!({
+                "handbookDict": {
+                    "char_010_chen": {
+                        "storyTextAudio": [
+                            story("基础档案", "DIRECT", ""),
+                            story("档案资料一", "FAVOR", "20"),
+                            story("档案资料四", "FAVOR", "150"),
+                            story("晋升记录", "AWAKE", "2"),
+                            story("特殊档案", "ACTIVITY", "act_1"),
+                            { "storyTitle": "空白", "stories": [] }
+                        ]
+                    }
+                }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "favor_table.json",
+            &serde_json::json!({
+                "favorFrames": [
+                    { "level": 0, "data": { "percent": 0 } },
+                    { "level": 20, "data": { "percent": 40 } }
+                ]
+            }),
+        );
 
-        format!("...{}...", snippet.trim())
+        let chen = service.get_character_profile("char_010_chen").unwrap();
+        let descs: Vec<&str> = chen
+            .sections
+            .iter()
+            .map(|s| s.unlock_desc.as_str())
+            .collect();
+        assert_eq!(
+            descs,
+            vec![
+                "直接解锁",
+                "信赖达到40%解锁",
+                "信赖达到150解锁",
+                "晋升至精英阶段2解锁",
+                "完成特殊任务",
+                "直接解锁",
+            ]
+        );
+        assert_eq!(chen.sections[1].unlock_type.as_deref(), Some("FAVOR"));
+        assert_eq!(chen.sections[1].unlock_param.as_deref(), Some("20"));
+
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
-    pub fn get_main_stories_grouped(&self) -> Result<Vec<(String, Vec<StoryEntry>)>, String> {
-        if !self.is_installed() {
-            return Err("NOT_INSTALLED".to_string());
-        }
+    #[test]
+    fn faction_and_sub_profession_catalogs_count_operators() {
+        let temp_root = temp_root("catalogs");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_010_chen": {
+                    "name": "陈", "profession": "WARRIOR", "subProfessionId": "sword",
+                    "nationId": "lungmen", "groupId": null, "teamId": null
+                },
+                "char_2025_shu": {
+                    "name": "黍", "profession": "SUPPORT", "subProfessionId": "healer",
+                    "nationId": "yan", "groupId": "sui", "teamId": null
+                },
+                "char_2023_ling": {
+                    "name": "令", "profession": "SUPPORT", "subProfessionId": "summoner",
+                    "nationId": "yan", "groupId": "sui", "teamId": null
+                },
+                "char_4000_token": {
+                    "name": "召唤物", "profession": "TOKEN", "subProfessionId": "summoner",
+                    "nationId": "yan"
+                },
+                "token_10020_ling_soul1": { "name": "清平", "profession": "TOKEN", "nationId": "yan" }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "handbook_team_table.json",
+            &serde_json::json!({
+                "sui": { "powerId": "sui", "orderNum": 30, "powerLevel": 1, "powerName": "岁", "powerCode": "SUI" },
+                "yan": { "powerId": "yan", "orderNum": 10, "powerLevel": 0, "powerName": "炎", "color": "ff0000" },
+                "lungmen": { "powerId": "lungmen", "orderNum": 20, "powerLevel": 0, "powerName": "龙门" },
+                "kazimierz": { "powerId": "kazimierz", "orderNum": 5, "powerLevel": 0, "powerName": "卡西米尔", "isLimited": true }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "uniequip_table.json",
+            &serde_json::json!({
+                "subProfDict": {
+                    "summoner": { "subProfessionId": "summoner", "subProfessionName": "召唤师", "subProfessionCatagory": 7 },
+                    "sword": { "subProfessionId": "sword", "subProfessionName": "剑豪", "subProfessionCatagory": 2 },
+                    "healer": { "subProfessionId": "healer", "subProfessionName": "疗养师", "subProfessionCatagory": 7 }
+                }
+            }),
+        );
 
-        let story_review_file = self
-            .data_dir
-            .join("zh_CN/gamedata/excel/story_review_table.json");
+        let factions = service.get_all_factions().unwrap();
+        let summary: Vec<(&str, usize)> = factions
+            .iter()
+            .map(|f| (f.power_id.as_str(), f.operator_count))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("kazimierz", 0), ("yan", 2), ("lungmen", 1), ("sui", 2)]
+        );
+        assert!(factions[0].is_limited);
+        assert_eq!(factions[1].power_name, "炎");
+        assert_eq!(factions[3].power_level, 1);
+
+        let sub_profs = service.get_all_sub_professions().unwrap();
+        let summary: Vec<(&str, usize)> = sub_profs
+            .iter()
+            .map(|p| (p.sub_profession_name.as_str(), p.operator_count))
+            .collect();
+        assert_eq!(summary, vec![("剑豪", 1), ("疗养师", 1), ("召唤师", 1)]);
 
-        let content = fs::read_to_string(&story_review_file)
-            .map_err(|e| format!("Failed to read story review file: {}", e))?;
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-        let data: HashMap<String, Value> = serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse story review data: {}", e))?;
+    #[test]
+    fn roster_stats_join_optional_tables() {
+        let temp_root = temp_root("roster_stats");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_002_amiya": {
+                    "name": "阿米娅", "rarity": "TIER_5", "profession": "CASTER",
+                    "subProfessionId": "corecaster", "nationId": "rhodes"
+                },
+                "char_010_chen": {
+                    "name": "陈", "rarity": "TIER_6", "profession": "WARRIOR",
+                    "subProfessionId": "sword", "nationId": "lungmen"
+                },
+                "char_017_huang": {
+                    "name": "煌", "rarity": "TIER_6", "profession": "WARRIOR",
+                    "subProfessionId": "centurion", "nationId": "rhodes"
+                },
+                "char_4000_token": { "name": "召唤物", "rarity": "TIER_1", "profession": "TOKEN" },
+                "trap_001_crate": { "name": "障碍物", "rarity": "TIER_1", "profession": "TRAP", "nationId": "rhodes" }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "uniequip_table.json",
+            &serde_json::json!({
+                "charEquip": {
+                    "char_010_chen": ["uniequip_001_chen", "uniequip_002_chen"],
+                    "char_002_amiya": ["uniequip_001_amiya"]
+                }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "handbook_info_table.json",
+            &serde_json::json!({
+                "handbookDict": {
+                    "char_002_amiya": { "charID": "char_002_amiya", "handbookAvgList": [
+                        { "storySetId": "story_amiya_set_1", "avgList": [{ "storyId": "story_amiya_set_1_1" }] },
+                        { "storySetId": "story_amiya_set_2", "avgList": [] }
+                    ] },
+                    "char_017_huang": { "charID": "char_017_huang", "handbookAvgList": [
+                        { "storySetId": "story_huang_set_1", "avgList": [] }
+                    ] }
+                }
+            }),
+        );
 
-        // 按分组ID收集主线剧情
-        let mut groups: Vec<(String, String, Vec<StoryEntry>)> = Vec::new();
+        let stats = service.get_roster_stats().unwrap();
+        assert_eq!(stats.total, 3);
+        let counts = |list: &[RosterCount]| {
+            list.iter()
+                .map(|c| (c.key.clone(), c.count))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            counts(&stats.by_rarity),
+            vec![("6".to_string(), 2), ("5".to_string(), 1)]
+        );
+        assert_eq!(
+            counts(&stats.by_profession),
+            vec![("WARRIOR".to_string(), 2), ("CASTER".to_string(), 1)]
+        );
+        assert_eq!(stats.by_sub_profession.len(), 3);
+        assert_eq!(
+            counts(&stats.by_nation),
+            vec![("rhodes".to_string(), 2), ("lungmen".to_string(), 1)]
+        );
+        assert_eq!(stats.with_modules, Some(1));
+        assert_eq!(stats.with_memories, Some(2));
+        // skin_table 缺失时只省略皮肤统计
+        assert_eq!(stats.with_extra_skins, None);
+
+        write_excel_table(
+            &service,
+            "skin_table.json",
+            &serde_json::json!({
+                "charSkins": {
+                    "char_010_chen#1": { "charId": "char_010_chen" },
+                    "char_010_chen#2": { "charId": "char_010_chen" },
+                    "char_002_amiya@epoque#4": { "charId": "char_002_amiya" },
+                    "char_4000_token@test#1": { "charId": "char_4000_token" }
+                }
+            }),
+        );
+        assert_eq!(
+            service.get_roster_stats().unwrap().with_extra_skins,
+            Some(1)
+        );
 
-        for (id, value) in data.iter() {
-            if let Some(et) = value.get("entryType").and_then(|v| v.as_str()) {
-                if et == "MAINLINE" {
-                    let group_name = value
-                        .get("name")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("未知章节");
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-                    if let Some(unlock_datas) =
-                        value.get("infoUnlockDatas").and_then(|v| v.as_array())
-                    {
-                        let mut stories = Vec::new();
-                        for unlock_data in unlock_datas {
-                            if let Ok(story) =
-                                serde_json::from_value::<StoryEntry>(unlock_data.clone())
-                            {
-                                stories.push(story);
-                            }
-                        }
-                        stories.sort_by_key(|s| s.story_sort);
-                        groups.push((id.clone(), group_name.to_string(), stories));
-                    }
+    #[test]
+    fn character_by_name_prefers_exact_hits() {
+        let temp_root = temp_root("character_by_name");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_002_amiya": { "name": "阿米娅", "appellation": "Amiya", "profession": "CASTER", "rarity": "TIER_5" },
+                "char_010_chen": { "name": "陈", "appellation": "Ch'en", "profession": "WARRIOR", "rarity": "TIER_6" },
+                "char_1013_chen2": { "name": "假日威龙陈", "appellation": "Ch'en the Holungday", "profession": "SNIPER", "rarity": "TIER_6" },
+                "char_4119_wanqin": { "name": "万顷", "profession": "SPECIAL", "rarity": "TIER_5" },
+                "char_4120_wanqiu": { "name": "万秋", "profession": "SUPPORT", "rarity": "TIER_4" }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "char_patch_table.json",
+            &serde_json::json!({
+                "infos": { "char_002_amiya": { "tmplIds": ["char_002_amiya", "char_1001_amiya2"] } },
+                "patchChars": {
+                    "char_1001_amiya2": { "name": "阿米娅", "appellation": "Amiya", "profession": "WARRIOR", "rarity": "TIER_5" }
                 }
-            }
-        }
+            }),
+        );
+
+        assert_eq!(
+            service
+                .get_character_by_name(" 陈 ")
+                .unwrap()
+                .summary
+                .char_id,
+            "char_010_chen"
+        );
+        // 同名形态取原版
+        assert_eq!(
+            service
+                .get_character_by_name("阿米娅")
+                .unwrap()
+                .summary
+                .char_id,
+            "char_002_amiya"
+        );
+        assert_eq!(
+            service
+                .get_character_by_name("ch'en")
+                .unwrap()
+                .summary
+                .char_id,
+            "char_010_chen"
+        );
+        assert_eq!(
+            service
+                .get_character_by_name("ＡＭＩＹＡ")
+                .unwrap()
+                .summary
+                .char_id,
+            "char_002_amiya"
+        );
 
-        groups.sort_by(|a, b| compare_story_group_ids(&a.0, &b.0));
+        assert_eq!(
+            service.get_character_by_name("万").unwrap_err(),
+            "NOT_FOUND:干员「万」:char_4119_wanqin,char_4120_wanqiu"
+        );
+        assert_eq!(
+            service.get_character_by_name("银灰").unwrap_err(),
+            "NOT_FOUND:干员「银灰」:"
+        );
 
-        Ok(groups
-            .into_iter()
-            .map(|(_, name, stories)| (name, stories))
-            .collect())
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
-    pub fn get_activity_stories_grouped(&self) -> Result<Vec<(String, Vec<StoryEntry>)>, String> {
-        if !self.is_installed() {
-            return Err("NOT_INSTALLED".to_string());
-        }
+    #[test]
+    fn character_credits_merge_artists_and_voice_actors() {
+        let temp_root = temp_root("character_credits");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_002_amiya": { "name": "阿米娅" },
+                "char_003_kalts": { "name": "凯尔希" }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "skin_table.json",
+            &serde_json::json!({
+                "charSkins": {
+                    "char_002_amiya#1": { "charId": "char_002_amiya",
+                        "displaySkin": { "sortId": -1, "drawerList": ["唯@W"] } },
+                    "char_002_amiya#2": { "charId": "char_002_amiya",
+                        "displaySkin": { "sortId": 0, "drawerList": ["唯@W", " "] } },
+                    "char_002_amiya@epoque#4": { "charId": "char_002_amiya",
+                        "displaySkin": { "sortId": 5, "drawerList": ["Skade", "唯@W"] } },
+                    "char_003_kalts#1": { "charId": "char_003_kalts",
+                        "displaySkin": { "sortId": -1, "drawerList": ["唯@w"] } },
+                    "char_003_kalts@test#1": { "charId": "char_003_kalts",
+                        "displaySkin": { "sortId": 3, "drawerList": ["Skade"] } }
+                }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "charword_table.json",
+            &serde_json::json!({
+                "voiceLangDict": {
+                    "char_002_amiya": { "wordkey": "char_002_amiya", "charId": "char_002_amiya", "dict": {
+                        "JP": { "cvName": ["黒沢ともよ"] },
+                        "CN_MANDARIN": { "cvName": ["文森"] }
+                    } },
+                    "char_002_amiya@epoque#4": { "wordkey": "char_002_amiya@epoque#4", "charId": "char_002_amiya", "dict": {
+                        "JP": { "cvName": ["黒沢ともよ", "代役"] }
+                    } }
+                }
+            }),
+        );
 
-        let story_review_file = self
-            .data_dir
-            .join("zh_CN/gamedata/excel/story_review_table.json");
+        let credits = service.get_character_credits("char_002_amiya").unwrap();
+        let artists: Vec<(&str, Vec<&str>)> = credits
+            .artists
+            .iter()
+            .map(|a| {
+                (
+                    a.name.as_str(),
+                    a.skin_ids.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            artists,
+            vec![
+                (
+                    "唯@W",
+                    vec![
+                        "char_002_amiya#1",
+                        "char_002_amiya#2",
+                        "char_002_amiya@epoque#4"
+                    ]
+                ),
+                ("Skade", vec!["char_002_amiya@epoque#4"]),
+            ]
+        );
+        assert_eq!(credits.voice_actors[0].lang_type, "JP");
+        assert_eq!(credits.voice_actors[0].cv_names, vec!["黒沢ともよ", "代役"]);
+        assert_eq!(credits.voice_actors[1].cv_names, vec!["文森"]);
+
+        let works = service.get_operators_by_artist("唯@w").unwrap();
+        let summary: Vec<(&str, Option<&str>, usize)> = works
+            .iter()
+            .map(|w| (w.char_id.as_str(), w.char_name.as_deref(), w.skin_ids.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("char_002_amiya", Some("阿米娅"), 3),
+                ("char_003_kalts", Some("凯尔希"), 1),
+            ]
+        );
+        assert!(service.get_operators_by_artist("无名").unwrap().is_empty());
 
-        let content = fs::read_to_string(&story_review_file)
-            .map_err(|e| format!("Failed to read story review file: {}", e))?;
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-        let data: HashMap<String, Value> = serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse story review data: {}", e))?;
+    #[test]
+    fn character_obtain_info_reads_gacha_pools() {
+        let temp_root = temp_root("character_obtain");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_017_huang": { "name": "煌", "itemObtainApproach": "招募寻访", "isNotObtainable": false },
+                "char_2014_nian": { "name": "年", "itemObtainApproach": "招募寻访", "isNotObtainable": false },
+                "char_4016_kazema": { "name": "风丸", "itemObtainApproach": "活动获得", "isNotObtainable": false },
+                "char_1502_crosly": { "name": "弑君者", "itemObtainApproach": "", "isNotObtainable": true }
+            }),
+        );
 
-        let mut groups: Vec<(String, Vec<StoryEntry>, i64, String)> = Vec::new();
+        // 缺少 gacha_table 时只返回 character_table 中的信息
+        let huang = service.get_character_obtain_info("char_017_huang").unwrap();
+        assert_eq!(huang.obtain_approach.as_deref(), Some("招募寻访"));
+        assert_eq!((huang.is_limited, huang.release_ts), (None, None));
+
+        write_excel_table(
+            &service,
+            "gacha_table.json",
+            &serde_json::json!({
+                "gachaPoolClient": [
+                    { "gachaPoolId": "SINGLE_1", "gachaRuleType": "NORMAL", "openTime": 1556000000,
+                      "gachaPoolSummary": "「煌」出现率上升" },
+                    { "gachaPoolId": "SINGLE_2", "gachaRuleType": "NORMAL", "openTime": 1600000000,
+                      "gachaPoolDetail": "★★★★★★：「煌」「年」" },
+                    { "gachaPoolId": "LIMITED_1", "gachaRuleType": "LIMITED", "openTime": 1580000000,
+                      "gachaPoolSummary": "「年」限定寻访" }
+                ]
+            }),
+        );
+        let huang = service.get_character_obtain_info("char_017_huang").unwrap();
+        assert_eq!(huang.is_limited, Some(false));
+        assert_eq!(huang.release_ts, Some(1556000000));
+
+        let kazema = service
+            .get_character_obtain_info("char_4016_kazema")
+            .unwrap();
+        assert_eq!(kazema.obtain_approach.as_deref(), Some("活动获得"));
+        assert!(!kazema.is_not_obtainable);
+        assert_eq!((kazema.is_limited, kazema.release_ts), (None, None));
+
+        let crosly = service
+            .get_character_obtain_info("char_1502_crosly")
+            .unwrap();
+        assert_eq!(crosly.obtain_approach, None);
+        assert!(crosly.is_not_obtainable);
+
+        let found = service.search_characters("弑君者", false).unwrap();
+        assert!(found[0].summary.is_not_obtainable);
 
-        for (_id, value) in data.iter() {
-            if let Some(et) = value.get("entryType").and_then(|v| v.as_str()) {
-                if et == "ACTIVITY" || et == "MINI_ACTIVITY" {
-                    let activity_name = value
-                        .get("name")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("未知活动");
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-                    if let Some(unlock_datas) =
-                        value.get("infoUnlockDatas").and_then(|v| v.as_array())
-                    {
-                        let mut stories = Vec::new();
-                        for unlock_data in unlock_datas {
-                            if let Ok(story) =
-                                serde_json::from_value::<StoryEntry>(unlock_data.clone())
-                            {
-                                stories.push(story);
-                            }
-                        }
+    #[test]
+    fn localized_names_read_each_installed_language() {
+        let temp_root = temp_root("localized_names");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_003_kalts": { "name": "凯尔希", "appellation": "Kal'tsit" },
+                "char_4999_cn_only": { "name": "国服限定" }
+            }),
+        );
+        let write_language = |language: &str, table: serde_json::Value| {
+            let path = service.excel_path_for(language, "character_table.json");
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, table.to_string()).unwrap();
+        };
+        write_language(
+            "en_US",
+            serde_json::json!({ "char_003_kalts": { "name": "Kal'tsit", "appellation": "Kal'tsit" } }),
+        );
+        write_language(
+            "ja_JP",
+            serde_json::json!({ "char_003_kalts": { "name": "ケルシー", "appellation": "Kal'tsit" } }),
+        );
+        // 只有剧情、没有 excel 的目录不算已安装
+        fs::create_dir_all(
+            service
+                .data_dir
+                .join("ko_KR")
+                .join("gamedata")
+                .join("story"),
+        )
+        .unwrap();
+
+        let names = service
+            .get_character_localized_names("char_003_kalts")
+            .unwrap();
+        let summary: Vec<(&str, &str)> = names
+            .iter()
+            .map(|n| (n.language.as_str(), n.name.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("zh_CN", "凯尔希"),
+                ("en_US", "Kal'tsit"),
+                ("ja_JP", "ケルシー")
+            ]
+        );
+        assert_eq!(names[0].appellation.as_deref(), Some("Kal'tsit"));
 
-                        if !stories.is_empty() {
-                            stories.sort_by_key(|s| s.story_sort);
-                            let start_time = value
-                                .get("startTime")
-                                .and_then(|v| v.as_i64())
-                                .unwrap_or(i64::MAX);
-                            let normalized_start = if start_time <= 0 {
-                                i64::MAX
-                            } else {
-                                start_time
-                            };
-                            let sort_id = value
-                                .get("id")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_else(|| _id.as_str());
+        let cn_only = service
+            .get_character_localized_names("char_4999_cn_only")
+            .unwrap();
+        assert_eq!(cn_only.len(), 1);
 
-                            groups.push((
-                                activity_name.to_string(),
-                                stories,
-                                normalized_start,
-                                sort_id.to_string(),
-                            ));
-                        }
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn patch_characters_merge_over_base_entry() {
+        let temp_root = temp_root("patch_merge");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({
+                "char_002_amiya": {
+                    "name": "阿米娅", "appellation": "Amiya", "profession": "CASTER", "rarity": "TIER_5",
+                    "nationId": "rhodes",
+                    "skills": [{ "skillId": "skchr_amiya_1" }],
+                    "talents": [
+                        { "candidates": [{ "name": "情绪吸收", "description": "原版天赋一" }] },
+                        { "candidates": [{ "name": "奇美拉", "description": "原版天赋二" }] }
+                    ],
+                    "phases": [{ "maxLevel": 50, "attributesKeyFrames": [
+                        { "level": 1, "data": { "maxHp": 700 } },
+                        { "level": 50, "data": { "maxHp": 900 } }
+                    ] }]
+                }
+            }),
+        );
+
+        // 旧数据没有 char_patch_table：其他形态查询失败但不影响原版
+        assert_eq!(
+            service.get_character_forms("char_002_amiya").unwrap().len(),
+            1
+        );
+        assert!(service
+            .get_character_attributes("char_1001_amiya2")
+            .is_err());
+
+        write_excel_table(
+            &service,
+            "char_patch_table.json",
+            &serde_json::json!({
+                "infos": { "char_002_amiya": { "tmplIds": ["char_002_amiya", "char_1001_amiya2"] } },
+                "patchChars": {
+                    "char_1001_amiya2": {
+                        "profession": "WARRIOR", "skills": null,
+                        "talents": [{ "candidates": [{ "name": "望见", "description": "近卫天赋" }] }],
+                        "phases": [{ "maxLevel": 50, "attributesKeyFrames": [
+                            { "level": 1, "data": { "maxHp": 1200 } },
+                            { "level": 50, "data": { "maxHp": 1600 } }
+                        ] }]
                     }
                 }
-            }
-        }
+            }),
+        );
 
-        // 按活动开始时间排序（旧活动在前，时间缺失的放在末尾）
-        groups.sort_by(|a, b| match a.2.cmp(&b.2) {
-            Ordering::Equal => compare_story_group_ids(&a.3, &b.3),
-            other => other,
-        });
+        let forms = service.get_character_forms("char_002_amiya").unwrap();
+        let guard = &forms[1];
+        assert_eq!(guard.char_id, "char_1001_amiya2");
+        assert_eq!(guard.name, "阿米娅");
+        assert_eq!(guard.profession.as_deref(), Some("WARRIOR"));
+        assert_eq!(guard.skills[0].skill_id, "skchr_amiya_1");
+        let talents: Vec<&str> = guard
+            .talents
+            .iter()
+            .filter_map(|t| t.description.as_deref())
+            .collect();
+        assert_eq!(talents, vec!["近卫天赋", "原版天赋二"]);
+        let attributes = service
+            .get_character_attributes("char_1001_amiya2")
+            .unwrap();
+        assert_eq!(attributes.phases[0].end.max_hp, 1600);
+
+        let found = service.search_characters("amiya", false).unwrap();
+        let summary: Vec<(&str, bool)> = found
+            .iter()
+            .map(|m| (m.summary.char_id.as_str(), m.summary.is_patch))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("char_002_amiya", false), ("char_1001_amiya2", true)]
+        );
+        assert_eq!(found[1].summary.profession.as_deref(), Some("WARRIOR"));
 
-        Ok(groups
-            .into_iter()
-            .map(|(name, stories, _, _)| (name, stories))
-            .collect())
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
-    pub fn get_sidestory_stories_grouped(&self) -> Result<Vec<(String, Vec<StoryEntry>)>, String> {
-        if !self.is_installed() {
-            return Err("NOT_INSTALLED".to_string());
+    #[test]
+    fn cloned_services_serve_reads_while_long_task_runs() {
+        let temp_root = temp_root("concurrent_reads");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(&service, "story_review_table.json", &serde_json::json!({}));
+        write_excel_table(&service, "chapter_table.json", &serde_json::json!({}));
+
+        // 模拟正在进行的同步：独占任务一直持有，直到读请求全部完成
+        let (held_tx, held_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let holder = service.clone();
+        let sync = std::thread::spawn(move || {
+            let _guard = holder.begin_task("sync").unwrap();
+            held_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+        });
+        held_rx.recv().unwrap();
+
+        // 两个读请求互相等待对方进入后才返回，只有并行执行才能都完成
+        let barrier = Arc::new(std::sync::Barrier::new(2));
+        let (done_tx, done_rx) = mpsc::channel();
+        let readers: Vec<_> = (0..2)
+            .map(|_| {
+                let reader = service.clone();
+                let barrier = Arc::clone(&barrier);
+                let done_tx = done_tx.clone();
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    let chapters = reader.get_chapters();
+                    let version = reader.get_story_tree_version();
+                    barrier.wait();
+                    done_tx.send((chapters.is_ok(), version.len())).unwrap();
+                })
+            })
+            .collect();
+        for _ in 0..2 {
+            let (chapters_ok, version_len) = done_rx
+                .recv_timeout(std::time::Duration::from_secs(10))
+                .expect("读请求被阻塞");
+            assert!(chapters_ok);
+            assert_eq!(version_len, 16);
         }
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert!(service.get_busy_state().busy);
 
-        let story_review_file = self
-            .data_dir
-            .join("zh_CN/gamedata/excel/story_review_table.json");
+        // 克隆共享设置：一个克隆的修改对其他克隆立即可见
+        service.clone().set_include_hidden_in_search(true).unwrap();
+        assert!(service.include_hidden_in_search());
 
-        let content = fs::read_to_string(&story_review_file)
-            .map_err(|e| format!("Failed to read story review file: {}", e))?;
+        release_tx.send(()).unwrap();
+        sync.join().unwrap();
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-        let data: HashMap<String, Value> = serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse story review data: {}", e))?;
+    #[test]
+    fn cancelled_extraction_stops_and_cleans_up() {
+        let temp_root = temp_root("cancel_extract");
+        fs::create_dir_all(&temp_root).unwrap();
+        let service = DataService::new(temp_root.clone());
+        let zip_path = temp_root.join("fixture.zip");
+        let entries: Vec<(&str, Vec<u8>)> =
+            vec![("a.txt", vec![b'a'; 1024]), ("b.txt", vec![b'b'; 1024])];
+        write_fixture_zip(&zip_path, &entries);
+
+        let cancel = CancelToken::default();
+        cancel.cancel();
+        let serial_root = temp_root.join("serial");
+        fs::create_dir_all(&serial_root).unwrap();
+        let err = extract_zip_entries(
+            &zip_path,
+            &serial_root,
+            Duration::from_secs(60),
+            &cancel,
+            &mut |_, _| {},
+        )
+        .unwrap_err();
+        assert_eq!(err, EXTRACTION_ABORTED);
+        assert!(!serial_root.join("a.txt").exists());
+
+        // 用户取消统一报告为 CANCELLED，且不会被当作损坏的压缩包删除
+        let err = service
+            .extract_zip_at(&zip_path, &temp_root, &cancel, &mut |_, _| {})
+            .unwrap_err();
+        assert_eq!(err, TASK_CANCELLED);
+        assert!(zip_path.exists());
+        assert!(!temp_root.join("ArknightsGameData_extract").exists());
 
-        let mut groups: Vec<(String, Vec<StoryEntry>, String)> = Vec::new();
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-        for (id, value) in data.iter() {
-            let Some(entry_type) = value.get("entryType").and_then(|v| v.as_str()) else {
-                continue;
-            };
-            let act_type = value.get("actType").and_then(|v| v.as_str()).unwrap_or("");
-            // 支线=大型活动（ACTIVITY + ACTIVITY_STORY）
-            if entry_type == "ACTIVITY" && act_type == "ACTIVITY_STORY" {
-                let group_name = value
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("支线剧情");
+    #[test]
+    fn diagnostics_redact_token_and_skip_reading_data_by_default() {
+        let temp_root = temp_root("diagnostics");
+        fs::create_dir_all(&temp_root).unwrap();
+        let service = DataService::new(temp_root.clone());
+        let token = format!("ghp_{}", "Zx9Yw8Vu".repeat(5));
+        fs::write(
+            temp_root.join(SETTINGS_FILE),
+            serde_json::json!({
+                "fontSize": 18,
+                "githubToken": token,
+                "downloadMirror": format!("https://mirror.example/?access_token={}", token),
+            })
+            .to_string(),
+        )
+        .unwrap();
+        service.set_reading_progress("story_a", 3, 10, 0.5).unwrap();
+
+        let dest = temp_root.join("diagnostics.zip");
+        let export = service.export_diagnostics(&dest, false).unwrap();
+        assert_eq!(export.size, fs::metadata(&dest).unwrap().len());
+
+        let mut archive = ZipArchive::new(fs::File::open(&dest).unwrap()).unwrap();
+        let read_entry = |archive: &mut ZipArchive<fs::File>, name: &str| {
+            let mut content = String::new();
+            archive
+                .by_name(name)
+                .unwrap()
+                .read_to_string(&mut content)
+                .unwrap();
+            content
+        };
+        let settings: Value =
+            serde_json::from_str(&read_entry(&mut archive, "settings.json")).unwrap();
+        assert_eq!(settings["githubToken"], "[REDACTED]");
+        assert_eq!(settings["fontSize"], 18);
+        assert!(!settings.to_string().contains(&token));
+        let manifest: Value =
+            serde_json::from_str(&read_entry(&mut archive, APP_DATA_MANIFEST)).unwrap();
+        assert_eq!(manifest["includesReadingProgress"], false);
+        assert!(archive.by_name("disk_usage.json").is_ok());
+        assert!(archive.by_name("reading_progress.json").is_err());
+
+        let export = service.export_diagnostics(&dest, true).unwrap();
+        let mut archive = ZipArchive::new(fs::File::open(&export.path).unwrap()).unwrap();
+        assert!(read_entry(&mut archive, "reading_progress.json").contains("story_a"));
 
-                if let Some(unlock_datas) = value.get("infoUnlockDatas").and_then(|v| v.as_array())
-                {
-                    let mut stories = Vec::new();
-                    for unlock_data in unlock_datas {
-                        if let Ok(story) = serde_json::from_value::<StoryEntry>(unlock_data.clone())
-                        {
-                            stories.push(story);
-                        }
-                    }
-                    if !stories.is_empty() {
-                        stories.sort_by_key(|s| s.story_sort);
-                        groups.push((group_name.to_string(), stories, id.clone()));
-                    }
-                }
-            }
-        }
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-        groups.sort_by(|a, b| compare_story_group_ids(&a.2, &b.2));
-        Ok(groups
-            .into_iter()
-            .map(|(name, stories, _)| (name, stories))
-            .collect())
+    #[test]
+    fn story_content_cache_hits_until_file_changes() {
+        let temp_root = temp_root("story_content_cache");
+        let service = DataService::new(temp_root.clone());
+        let story_file = service
+            .story_dir()
+            .join("activities/act1/level_act1_01.txt");
+        fs::create_dir_all(story_file.parent().unwrap()).unwrap();
+        fs::write(&story_file, "[name=\"阿米娅\"]第一版").unwrap();
+        let story_path = "activities/act1/level_act1_01";
+
+        let first = service.get_story_content(story_path).unwrap();
+        // 克隆共享同一缓存，第二次读取不再访问磁盘
+        let second = service.clone().get_story_content(story_path).unwrap();
+        assert_eq!(
+            serde_json::to_value(&first).unwrap(),
+            serde_json::to_value(&second).unwrap()
+        );
+        let stats = service.story_cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+
+        fs::write(&story_file, "[name=\"阿米娅\"]第二版").unwrap();
+        let later = SystemTime::now() + Duration::from_secs(60);
+        fs::File::options()
+            .write(true)
+            .open(&story_file)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        let updated = service.get_story_content(story_path).unwrap();
+        assert!(serde_json::to_string(&updated).unwrap().contains("第二版"));
+        let stats = service.story_cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 2, 1));
+
+        service.invalidate_caches();
+        service.get_story_content(story_path).unwrap();
+        assert_eq!(service.story_cache_stats().misses, 3);
+
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
-    pub fn get_roguelike_stories_grouped(&self) -> Result<Vec<(String, Vec<StoryEntry>)>, String> {
-        if !self.is_installed() {
-            return Err("NOT_INSTALLED".to_string());
+    #[test]
+    fn prefetch_fills_story_cache_and_skips_failures() {
+        let temp_root = temp_root("prefetch_stories");
+        let service = DataService::new(temp_root.clone());
+        let story_dir = service.story_dir().join("activities/act1");
+        fs::create_dir_all(&story_dir).unwrap();
+        for name in ["level_act1_01", "level_act1_02"] {
+            fs::write(story_dir.join(format!("{}.txt", name)), "[HEADER]").unwrap();
         }
+        let paths: Vec<String> = [
+            "activities/act1/level_act1_01",
+            "activities/act1/missing",
+            "activities/act1/level_act1_02",
+        ]
+        .iter()
+        .map(|path| path.to_string())
+        .collect();
+
+        assert_eq!(
+            service.prefetch_story_contents(&paths).unwrap(),
+            vec![true, false, true]
+        );
+        let stats = service.story_cache_stats();
+        assert_eq!((stats.entries, stats.hits, stats.misses), (2, 0, 0));
+        service
+            .get_story_content("activities/act1/level_act1_02")
+            .unwrap();
+        assert_eq!(service.story_cache_stats().hits, 1);
+
+        // 同步进行中时不预取
+        service.invalidate_caches();
+        let sync = service.begin_task("sync").unwrap();
+        assert_eq!(
+            service.prefetch_story_contents(&paths[..1]).unwrap(),
+            vec![false]
+        );
+        drop(sync);
+        assert_eq!(service.story_cache_stats().entries, 0);
 
-        // 首先读取 meta，提取 contentPath -> desc 映射（用于更友好的命名）
-        let meta_file = self
-            .data_dir
-            .join("zh_CN/gamedata/excel/story_review_meta_table.json");
-        let meta_content = fs::read_to_string(&meta_file)
-            .map_err(|e| format!("Failed to read story review meta file: {}", e))?;
-        let meta_value: Value = serde_json::from_str(&meta_content)
-            .map_err(|e| format!("Failed to parse story review meta data: {}", e))?;
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-        let mut path_desc_map: HashMap<String, String> = HashMap::new();
-        // 广义扫描：meta 中所有含 contentPath 的对象都尝试收集（兼容结构变动）
-        fn collect_content_paths(map: &mut HashMap<String, String>, val: &Value) {
-            match val {
-                Value::Object(obj) => {
-                    if let Some(cp) = obj.get("contentPath").and_then(|x| x.as_str()) {
-                        let lower = cp.to_ascii_lowercase();
-                        if lower.starts_with("obt/roguelike/") {
-                            let desc = obj
-                                .get("desc")
-                                .and_then(|x| x.as_str())
-                                .or_else(|| obj.get("name").and_then(|x| x.as_str()))
-                                .or_else(|| obj.get("rawBrief").and_then(|x| x.as_str()))
-                                .unwrap_or("")
-                                .trim()
-                                .to_string();
-                            if !desc.is_empty() {
-                                map.insert(lower, desc);
-                            }
-                        }
-                    }
-                    for v in obj.values() {
-                        collect_content_paths(map, v);
-                    }
-                }
-                Value::Array(arr) => {
-                    for v in arr {
-                        collect_content_paths(map, v);
-                    }
-                }
-                _ => {}
-            }
+    #[test]
+    fn app_status_snapshot_shape() {
+        let temp_root = temp_root("app_status");
+        fs::create_dir_all(&temp_root).unwrap();
+        let service = DataService::new(temp_root.clone());
+        service
+            .write_version(&VersionInfo {
+                commit: "0123456789abcdef".to_string(),
+                fetched_at: 1_700_000_000,
+                source: None,
+            })
+            .unwrap();
+        assert!(service.get_app_status().last_update_check.is_none());
+        service.check_update_with(
+            &service.data_source(),
+            || Ok("0123456789abcdef".to_string()),
+        );
+        let _index = service.begin_task("index").unwrap();
+
+        let mut status = serde_json::to_value(service.get_app_status()).unwrap();
+        // 时间与大小随环境变化，只保留结构
+        status["lastUpdateCheck"]["checkedAt"] = Value::from(0);
+        status["tasks"][0]["startedAt"] = Value::from(0);
+        status["tasks"][0]["id"] = Value::from(0);
+        for (_, bytes) in status["diskUsage"].as_object_mut().unwrap() {
+            *bytes = Value::from(0);
         }
-        collect_content_paths(&mut path_desc_map, &meta_value);
+        assert_eq!(
+            status,
+            serde_json::json!({
+                "installed": false,
+                "version": {
+                    "commit": "0123456789abcdef",
+                    "commitShort": "0123456",
+                    "fetchedAt": 1_700_000_000,
+                    "sourceId": null
+                },
+                "language": "zh_CN",
+                "index": {
+                    "ready": false,
+                    "total": 0,
+                    "lastBuiltAt": null,
+                    "building": true
+                },
+                "diskUsage": {
+                    "gameDataBytes": 0,
+                    "indexBytes": 0,
+                    "userDataBytes": 0,
+                    "logBytes": 0,
+                    "totalBytes": 0
+                },
+                "tasks": [{
+                    "id": 0,
+                    "kind": "index",
+                    "phase": null,
+                    "current": 0,
+                    "total": 0,
+                    "exclusive": true,
+                    "cancelled": false,
+                    "startedAt": 0
+                }],
+                "lastUpdateCheck": {
+                    "status": { "status": "upToDate" },
+                    "checkedAt": 0
+                },
+                "storyCache": {
+                    "hits": 0,
+                    "misses": 0,
+                    "entries": 0,
+                    "capacity": STORY_CONTENT_CACHE_CAPACITY
+                }
+            })
+        );
 
-        // 使用 story_table 作为权威来源，枚举所有 Obt/Roguelike 文本
-        let story_table_file = self.data_dir.join("zh_CN/gamedata/excel/story_table.json");
-        let story_table_content = fs::read_to_string(&story_table_file)
-            .map_err(|e| format!("Failed to read story table file: {}", e))?;
-        let table_obj: HashMap<String, Value> = serde_json::from_str(&story_table_content)
-            .map_err(|e| format!("Failed to parse story table: {}", e))?;
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-        let mut grouped: HashMap<String, Vec<StoryEntry>> = HashMap::new();
-        let mut counters: HashMap<String, i32> = HashMap::new();
+    #[test]
+    fn deep_links_resolve_existing_targets_only() {
+        use crate::deep_link::resolve_deep_link;
+        use crate::error::AppError;
+        use crate::models::DeepLinkNavigation;
+
+        let temp_root = temp_root("deep_links");
+        let service = DataService::new(temp_root.clone());
+        assert_eq!(
+            resolve_deep_link(&service, "arkstory://story/act1_st01").unwrap_err(),
+            AppError::NotInstalled
+        );
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "act1": {
+                    "name": "活动一",
+                    "entryType": "ACTIVITY",
+                    "infoUnlockDatas": [{
+                        "storyId": "act1_st01",
+                        "storyName": "序章",
+                        "storyGroup": "act1",
+                        "storySort": 1,
+                        "storyTxt": "activities/act1/level_st01",
+                        "storyReviewType": "ACTIVITY_STORY",
+                        "unLockType": "NONE"
+                    }]
+                }
+            }),
+        );
+        write_excel_table(
+            &service,
+            "character_table.json",
+            &serde_json::json!({ "char_002_amiya": { "name": "阿米娅" } }),
+        );
 
-        for (key, _v) in table_obj.into_iter() {
-            let lower = key.to_ascii_lowercase();
-            if !lower.starts_with("obt/roguelike/") {
-                continue;
+        match resolve_deep_link(&service, "arkstory://story/act1_st01").unwrap() {
+            DeepLinkNavigation::Story { story } => {
+                assert_eq!(story.story_txt, "activities/act1/level_st01")
             }
-            let group_key = lower
-                .split('/')
-                .nth(2)
-                .map(|s| s.to_uppercase())
-                .unwrap_or_else(|| "ROGUE".to_string());
-            let sort = counters
-                .entry(group_key.clone())
-                .and_modify(|x| *x += 1)
-                .or_insert(1);
-            let name = path_desc_map.get(&lower).cloned().unwrap_or_else(|| {
-                // 取最后一段作为兜底标题
-                key.split('/').last().unwrap_or(&key).to_string()
-            });
-
-            let entry = StoryEntry {
-                story_id: key.clone(),
-                story_name: name,
-                story_code: None,
-                story_group: group_key.clone(),
-                story_sort: *sort,
-                avg_tag: None,
-                story_txt: lower.clone(),
-                story_info: None,
-                story_review_type: "ROGUELIKE".to_string(),
-                unlock_type: "NONE".to_string(),
-                story_dependence: None,
-                story_can_show: None,
-                story_can_enter: None,
-                stage_count: None,
-                required_stages: None,
-                cost_item_type: None,
-                cost_item_id: None,
-                cost_item_count: None,
-            };
-
-            grouped.entry(group_key).or_default().push(entry);
+            other => panic!("unexpected navigation {:?}", other),
         }
+        let character = resolve_deep_link(&service, "arkstory://character/char_002_amiya").unwrap();
+        assert_eq!(
+            serde_json::to_value(character).unwrap(),
+            serde_json::json!({ "kind": "character", "charId": "char_002_amiya", "name": "阿米娅" })
+        );
+        assert!(matches!(
+            resolve_deep_link(&service, "arkstory://story/act9_st01"),
+            Err(AppError::NotFound { .. })
+        ));
+        assert!(matches!(
+            resolve_deep_link(&service, "arkstory://character/char_999_none"),
+            Err(AppError::NotFound { .. })
+        ));
+        assert!(matches!(
+            resolve_deep_link(&service, "arkstory://chapter/main_0"),
+            Err(AppError::InvalidLink { .. })
+        ));
 
-        let mut out: Vec<(String, Vec<StoryEntry>)> = grouped
-            .into_iter()
-            .map(|(name, mut stories)| {
-                stories.sort_by_key(|e| e.story_sort);
-                (name, stories)
-            })
-            .collect();
-        out.sort_by(|a, b| compare_story_group_ids(&a.0, &b.0));
-        Ok(out)
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
-    pub fn get_memory_stories(&self) -> Result<Vec<StoryEntry>, String> {
-        if !self.is_installed() {
-            return Err("NOT_INSTALLED".to_string());
-        }
-
-        let story_review_file = self
-            .data_dir
-            .join("zh_CN/gamedata/excel/story_review_table.json");
-
-        let content = fs::read_to_string(&story_review_file)
-            .map_err(|e| format!("Failed to read story review file: {}", e))?;
+    #[test]
+    fn search_respects_result_limit_setting() {
+        let temp_root = temp_root("search_limit");
+        let service = DataService::new(temp_root.clone());
+        let stories: Vec<Value> = (1..=5)
+            .map(|i| {
+                story_json(
+                    &format!("main_00_0{}", i),
+                    &format!("雪境{}", i),
+                    "main_0",
+                    i,
+                )
+            })
+            .collect();
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": { "entryType": "MAINLINE", "infoUnlockDatas": stories }
+            }),
+        );
+        assert_eq!(service.search_stories("雪境").unwrap().len(), 5);
 
-        let data: HashMap<String, Value> = serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse story review data: {}", e))?;
+        service
+            .update_settings(crate::settings::SettingsPatch {
+                search_result_limit: Some(3),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(service.search_stories("雪境").unwrap().len(), 3);
+        assert_eq!(
+            service
+                .search_stories_with_debug("雪境")
+                .unwrap()
+                .results
+                .len(),
+            3
+        );
 
-        let stories = self.parse_stories_by_entry_type(&data, "NONE")?;
-        Ok(stories)
+        let _ = fs::remove_dir_all(&temp_root);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
     #[test]
-    fn read_story_info_supports_uc_prefix() {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_nanos();
-        let temp_root = std::env::temp_dir().join(format!("story_reader_test_{}", timestamp));
-        let data_dir = temp_root.join("ArknightsGameData");
-        let info_dir = data_dir.join("zh_CN/gamedata/story/[uc]info/demo");
-        fs::create_dir_all(&info_dir).unwrap();
-        fs::write(info_dir.join("sample.txt"), "test summary").unwrap();
+    fn settings_drive_nickname_mirror_and_auto_index() {
+        let temp_root = temp_root("settings_wiring");
+        let service = DataService::new(temp_root.clone());
+        write_excel_table(
+            &service,
+            "story_review_table.json",
+            &serde_json::json!({
+                "main_0": {
+                    "entryType": "MAINLINE",
+                    "infoUnlockDatas": [story_json("main_00_01", "序章", "main_0", 1)]
+                }
+            }),
+        );
+        let story_file = service.story_dir().join("obt/main_0/main_00_01.txt");
+        fs::create_dir_all(story_file.parent().unwrap()).unwrap();
+        fs::write(&story_file, "[name=\"阿米娅\"]  {@nickname}，欢迎回来。").unwrap();
 
-        let service = DataService {
-            data_dir: data_dir.clone(),
-            index_db_path: temp_root.join("story_index.db"),
+        let dialogue_text = |service: &DataService| match &service
+            .get_story_content("obt/main_0/main_00_01")
+            .unwrap()
+            .segments[0]
+        {
+            StorySegment::Dialogue { text, .. } => text.clone(),
+            other => panic!("unexpected segment {:?}", other),
         };
-
-        let content = service
-            .read_story_info("info/demo/sample")
-            .expect("should read summary from [uc]info directory");
-        assert_eq!(content, "test summary");
+        assert_eq!(dialogue_text(&service), "博士，欢迎回来。");
+        assert_eq!(service.download_base(), REPO_DOWNLOAD_BASE);
+
+        service
+            .update_settings(crate::settings::SettingsPatch {
+                nickname: Some("刀客塔".to_string()),
+                download_mirror: Some("https://mirror.example/codeload/".to_string()),
+                auto_index: Some(true),
+                ..Default::default()
+            })
+            .unwrap();
+        // 称呼变更后不再返回缓存中按旧称呼解析的结果
+        assert_eq!(dialogue_text(&service), "刀客塔，欢迎回来。");
+        assert_eq!(service.download_base(), "https://mirror.example/codeload");
+
+        assert!(!service.get_story_index_status().unwrap().ready);
+        service.rebuild_index_if_enabled();
+        assert!(service.get_story_index_status().unwrap().ready);
 
         let _ = fs::remove_dir_all(&temp_root);
     }
@@ -0,0 +1,148 @@
+use std::sync::Mutex;
+
+use percent_encoding::percent_decode_str;
+
+use crate::data_service::DataService;
+use crate::error::AppError;
+use crate::models::DeepLinkNavigation;
+
+/// 应用注册的 URL scheme，链接形如 arkstory://story/1stact_level_a001_01_beg
+pub const DEEP_LINK_SCHEME: &str = "arkstory";
+/// 应用运行中收到链接时发给前端的事件，载荷为原始 URL，前端再调用 resolve_deep_link
+pub const DEEP_LINK_EVENT: &str = "deep-link";
+
+/// 冷启动时携带的链接：此时前端尚未监听事件，由前端就绪后取走一次
+#[derive(Default)]
+pub struct PendingDeepLink(Mutex<Option<String>>);
+
+impl PendingDeepLink {
+    pub fn set(&self, url: String) {
+        *self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(url);
+    }
+
+    pub fn take(&self) -> Option<String> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take()
+    }
+}
+
+/// 链接指向的目标，尚未校验是否存在
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeepLinkTarget {
+    Story(String),
+    Character(String),
+    Search(String),
+}
+
+/// 解析 `arkstory://{story|character|search}/{参数}`：参数按 UTF-8 百分号解码，
+/// 忽略查询串、片段与多余的斜杠；格式不符时返回 `INVALID_LINK:{url}`
+pub fn parse_deep_link(url: &str) -> Result<DeepLinkTarget, String> {
+    let invalid = || format!("INVALID_LINK:{}", url);
+    let (scheme, rest) = url.trim().split_once(':').ok_or_else(invalid)?;
+    if !scheme.eq_ignore_ascii_case(DEEP_LINK_SCHEME) {
+        return Err(invalid());
+    }
+    let path = rest
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .trim_matches('/');
+    let (kind, raw_value) = path.split_once('/').ok_or_else(invalid)?;
+    let value = percent_decode_str(raw_value)
+        .decode_utf8()
+        .map_err(|_| invalid())?;
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(invalid());
+    }
+
+    // 剧情与干员 id 不含斜杠；搜索词可以包含
+    match kind.to_ascii_lowercase().as_str() {
+        "story" if !value.contains('/') => Ok(DeepLinkTarget::Story(value.to_string())),
+        "character" if !value.contains('/') => Ok(DeepLinkTarget::Character(value.to_string())),
+        "search" => Ok(DeepLinkTarget::Search(value.to_string())),
+        _ => Err(invalid()),
+    }
+}
+
+/// 解析链接并确认目标存在，返回前端执行的跳转指令
+pub fn resolve_deep_link(service: &DataService, url: &str) -> Result<DeepLinkNavigation, AppError> {
+    let target = parse_deep_link(url)?;
+    if let DeepLinkTarget::Search(query) = target {
+        return Ok(DeepLinkNavigation::Search { query });
+    }
+    if !service.is_installed() {
+        return Err(AppError::NotInstalled);
+    }
+    match target {
+        DeepLinkTarget::Story(story_id) => service
+            .get_story_entry(&story_id)
+            .map(|story| DeepLinkNavigation::Story {
+                story: Box::new(story),
+            })
+            .map_err(|_| AppError::NotFound {
+                what: format!("剧情 {}", story_id),
+                candidates: Vec::new(),
+            }),
+        DeepLinkTarget::Character(char_id) => match service.get_character_name(&char_id)? {
+            Some(name) => Ok(DeepLinkNavigation::Character { char_id, name }),
+            None => Err(AppError::NotFound {
+                what: format!("干员 {}", char_id),
+                candidates: Vec::new(),
+            }),
+        },
+        DeepLinkTarget::Search(_) => unreachable!("search links return early"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_story_character_and_search_links() {
+        assert_eq!(
+            parse_deep_link("arkstory://story/1stact_level_a001_01_beg").unwrap(),
+            DeepLinkTarget::Story("1stact_level_a001_01_beg".to_string())
+        );
+        assert_eq!(
+            parse_deep_link("ARKSTORY://Character/char_002_amiya/?from=share#top").unwrap(),
+            DeepLinkTarget::Character("char_002_amiya".to_string())
+        );
+        // 百分号编码的中文搜索词，可以包含编码后的斜杠
+        assert_eq!(
+            parse_deep_link(
+                "arkstory://search/%E9%98%BF%E7%B1%B3%E5%A8%85%20%E5%87%AF%E5%B0%94%E5%B8%8C"
+            )
+            .unwrap(),
+            DeepLinkTarget::Search("阿米娅 凯尔希".to_string())
+        );
+        assert_eq!(
+            parse_deep_link("arkstory:///search/%E7%BD%97%E5%BE%B7%E5%B2%9B%2F%E5%88%87%E5%9F%8E")
+                .unwrap(),
+            DeepLinkTarget::Search("罗德岛/切城".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_links() {
+        for url in [
+            "https://story/1stact_level_a001_01_beg",
+            "arkstory://story",
+            "arkstory://story/",
+            "arkstory://story/%20",
+            "arkstory://story/a/b",
+            "arkstory://stage/main_00-01",
+            "arkstory://search/%E9%98%BF%E7",
+            "1stact_level_a001_01_beg",
+        ] {
+            let err = parse_deep_link(url).unwrap_err();
+            assert_eq!(err, format!("INVALID_LINK:{}", url));
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::path::PathBuf;
+
+/// 命令层返回给前端的结构化错误，序列化为 `{ code, message, details }`：
+/// code 为稳定的错误码，message 为可直接展示的说明，details 为可选的附加信息。
+/// DataService 仍返回 `Result<_, String>`，命令层通过 `From<String>` 识别旧的错误码前缀
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppError {
+    /// 数据尚未同步/安装
+    NotInstalled,
+    Network {
+        source: String,
+    },
+    Io {
+        path: Option<PathBuf>,
+        source: String,
+    },
+    /// 数据表或剧情文件解析失败；table 为 None 时来源未知
+    Parse {
+        table: Option<String>,
+        source: String,
+    },
+    /// candidates 为可供调用方选择的相近结果（如干员 id）
+    NotFound {
+        what: String,
+        candidates: Vec<String>,
+    },
+    Database {
+        source: String,
+    },
+    /// 已有独占任务（同步、建索引、导入等）在执行
+    Busy {
+        task: String,
+    },
+    Cancelled,
+    /// path 为被拒绝的路径，旧错误字符串未附带时为 None
+    InvalidPath {
+        path: Option<String>,
+    },
+    /// 无法识别的 arkstory:// 链接
+    InvalidLink {
+        url: String,
+    },
+    /// detail 为解压时的具体错误
+    CorruptArchive {
+        detail: Option<String>,
+    },
+    /// 尚未归类的错误，原样保留消息
+    Other {
+        message: String,
+    },
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotInstalled => "NOT_INSTALLED",
+            AppError::Network { .. } => "NETWORK",
+            AppError::Io { .. } => "IO",
+            AppError::Parse { .. } => "PARSE",
+            AppError::NotFound { .. } => "NOT_FOUND",
+            AppError::Database { .. } => "DATABASE",
+            AppError::Busy { .. } => "BUSY",
+            AppError::Cancelled => "CANCELLED",
+            AppError::InvalidPath { .. } => "INVALID_PATH",
+            AppError::InvalidLink { .. } => "INVALID_LINK",
+            AppError::CorruptArchive { .. } => "CORRUPT_ARCHIVE",
+            AppError::Other { .. } => "UNKNOWN",
+        }
+    }
+
+    fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            AppError::Io {
+                path: Some(path), ..
+            } => Some(serde_json::json!({ "path": path.to_string_lossy() })),
+            AppError::Parse {
+                table: Some(table), ..
+            } => Some(serde_json::json!({ "table": table })),
+            AppError::NotFound { what, candidates } => Some(serde_json::json!({
+                "what": what,
+                "candidates": candidates,
+            })),
+            AppError::Busy { task } => Some(serde_json::json!({ "task": task })),
+            AppError::InvalidLink { url } => Some(serde_json::json!({ "url": url })),
+            AppError::InvalidPath { path: Some(path) } => Some(serde_json::json!({ "path": path })),
+            AppError::CorruptArchive {
+                detail: Some(detail),
+            } => Some(serde_json::json!({ "detail": detail })),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotInstalled => write!(f, "数据未安装，请先同步数据"),
+            AppError::Network { source } => write!(f, "网络请求失败：{}", source),
+            AppError::Io {
+                path: Some(path),
+                source,
+            } => write!(f, "读写 {} 失败：{}", path.display(), source),
+            AppError::Io { path: None, source } => write!(f, "读写文件失败：{}", source),
+            AppError::Parse {
+                table: Some(table),
+                source,
+            } => write!(f, "解析 {} 失败：{}", table, source),
+            AppError::Parse {
+                table: None,
+                source,
+            } => write!(f, "解析数据失败：{}", source),
+            AppError::NotFound { what, .. } => write!(f, "未找到{}", what),
+            AppError::Database { source } => write!(f, "数据库错误：{}", source),
+            AppError::Busy { task } => write!(f, "正在执行「{}」，请稍后再试", task),
+            AppError::Cancelled => write!(f, "操作已取消"),
+            AppError::InvalidPath { path: Some(path) } => write!(f, "路径无效：{}", path),
+            AppError::InvalidPath { path: None } => write!(f, "路径无效"),
+            AppError::InvalidLink { .. } => write!(f, "无法识别的链接"),
+            AppError::CorruptArchive { .. } => write!(f, "数据包已损坏，请重新同步"),
+            AppError::Other { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Payload<'a> {
+            code: &'a str,
+            message: String,
+            details: Option<serde_json::Value>,
+        }
+        Payload {
+            code: self.code(),
+            message: self.to_string(),
+            details: self.details(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// 错误码之后的附加信息（`CODE:{detail}`），没有或为空时为 None
+fn code_detail(message: &str, code: &str) -> Option<String> {
+    message
+        .strip_prefix(code)
+        .and_then(|rest| rest.strip_prefix(':'))
+        .map(str::trim)
+        .filter(|detail| !detail.is_empty())
+        .map(str::to_string)
+}
+
+/// 识别 DataService 现有的错误字符串：NOT_INSTALLED、BUSY:{任务}、
+/// NOT_FOUND:{目标}:{候选,...}（旧格式 NOT_FOUND:{候选,...} 不带目标）、INVALID_PATH:{路径}、
+/// INVALID_LINK:{链接}、CORRUPT_ARCHIVE:{详情}、CANCELLED 与「解压已中止」，其余归为 Other
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        if message == "NOT_INSTALLED" {
+            return AppError::NotInstalled;
+        }
+        if let Some(task) = message.strip_prefix("BUSY:") {
+            return AppError::Busy {
+                task: task.to_string(),
+            };
+        }
+        if let Some(rest) = message.strip_prefix("NOT_FOUND:") {
+            // 候选 id 中不含冒号，最后一个冒号之前的部分是目标描述
+            let (what, candidates) = rest.rsplit_once(':').unwrap_or(("匹配的结果", rest));
+            return AppError::NotFound {
+                what: what.to_string(),
+                candidates: candidates
+                    .split(',')
+                    .filter(|id| !id.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            };
+        }
+        if message.starts_with("INVALID_PATH") {
+            return AppError::InvalidPath {
+                path: code_detail(&message, "INVALID_PATH"),
+            };
+        }
+        if let Some(url) = message.strip_prefix("INVALID_LINK:") {
+            return AppError::InvalidLink {
+                url: url.to_string(),
+            };
+        }
+        if message.starts_with("CORRUPT_ARCHIVE") {
+            return AppError::CorruptArchive {
+                detail: code_detail(&message, "CORRUPT_ARCHIVE"),
+            };
+        }
+        if message == "CANCELLED" || message.contains("解压已中止") {
+            return AppError::Cancelled;
+        }
+        AppError::Other { message }
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::from(message.to_string())
+    }
+}
+
+/// 兼容仍以 String 作为错误类型的调用方：错误码类错误还原为原来的字符串形式
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        match error {
+            AppError::NotInstalled
+            | AppError::InvalidPath { path: None }
+            | AppError::CorruptArchive { detail: None }
+            | AppError::Cancelled => error.code().to_string(),
+            AppError::InvalidPath { path: Some(path) } => format!("INVALID_PATH:{}", path),
+            AppError::CorruptArchive {
+                detail: Some(detail),
+            } => format!("CORRUPT_ARCHIVE:{}", detail),
+            AppError::Busy { task } => format!("BUSY:{}", task),
+            AppError::NotFound { what, candidates } => {
+                format!("NOT_FOUND:{}:{}", what, candidates.join(","))
+            }
+            AppError::InvalidLink { url } => format!("INVALID_LINK:{}", url),
+            other => other.to_string(),
+        }
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(error: std::io::Error) -> Self {
+        AppError::Io {
+            path: None,
+            source: error.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(error: serde_json::Error) -> Self {
+        AppError::Parse {
+            table: None,
+            source: error.to_string(),
+        }
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(error: reqwest::Error) -> Self {
+        AppError::Network {
+            source: error.to_string(),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(error: rusqlite::Error) -> Self {
+        AppError::Database {
+            source: error.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_code_message_and_details() {
+        assert_eq!(
+            serde_json::to_value(AppError::NotInstalled).unwrap(),
+            serde_json::json!({
+                "code": "NOT_INSTALLED",
+                "message": "数据未安装，请先同步数据",
+                "details": null
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(AppError::from("BUSY:同步数据".to_string())).unwrap(),
+            serde_json::json!({
+                "code": "BUSY",
+                "message": "正在执行「同步数据」，请稍后再试",
+                "details": { "task": "同步数据" }
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(AppError::Parse {
+                table: Some("story_review_table.json".to_string()),
+                source: "expected value".to_string(),
+            })
+            .unwrap(),
+            serde_json::json!({
+                "code": "PARSE",
+                "message": "解析 story_review_table.json 失败：expected value",
+                "details": { "table": "story_review_table.json" }
+            })
+        );
+        let not_found = serde_json::to_value(AppError::from(
+            "NOT_FOUND:干员「万」:char_4119_wanqin,char_4120_wanqiu".to_string(),
+        ))
+        .unwrap();
+        assert_eq!(not_found["code"], "NOT_FOUND");
+        assert_eq!(not_found["message"], "未找到干员「万」");
+        assert_eq!(not_found["details"]["what"], "干员「万」");
+        assert_eq!(
+            not_found["details"]["candidates"],
+            serde_json::json!(["char_4119_wanqin", "char_4120_wanqiu"])
+        );
+        assert_eq!(
+            serde_json::to_value(AppError::from("Failed to read x".to_string())).unwrap()["code"],
+            "UNKNOWN"
+        );
+    }
+
+    #[test]
+    fn legacy_strings_round_trip() {
+        for legacy in [
+            "NOT_INSTALLED",
+            "BUSY:建立索引",
+            "NOT_FOUND:剧情 main_00_01:",
+            "NOT_FOUND:干员「阿米娅」:char_002_amiya",
+            "INVALID_PATH",
+            "INVALID_PATH:../secret.txt",
+            "CORRUPT_ARCHIVE",
+            "INVALID_LINK:arkstory://stage/main_00-01",
+            "CANCELLED",
+        ] {
+            assert_eq!(String::from(AppError::from(legacy)), legacy);
+        }
+        assert_eq!(
+            AppError::from("CORRUPT_ARCHIVE: crc mismatch".to_string()),
+            AppError::CorruptArchive {
+                detail: Some("crc mismatch".to_string())
+            }
+        );
+        assert_eq!(
+            AppError::from("NOT_FOUND:char_002_amiya".to_string()),
+            AppError::NotFound {
+                what: "匹配的结果".to_string(),
+                candidates: vec!["char_002_amiya".to_string()],
+            }
+        );
+        assert_eq!(
+            AppError::from("解压已中止".to_string()),
+            AppError::Cancelled
+        );
+    }
+}
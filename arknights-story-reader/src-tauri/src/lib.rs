@@ -2,19 +2,36 @@
 mod apk_updater;
 mod commands;
 mod data_service;
+mod deep_link;
+mod error;
+mod logging;
 mod models;
 mod parser;
+mod settings;
+mod tasks;
 
 use commands::AppState;
 use data_service::DataService;
-use std::sync::Arc;
-use std::sync::Mutex;
-use tauri::Manager;
+use deep_link::{PendingDeepLink, DEEP_LINK_EVENT};
+use tauri::{Emitter, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let mut builder = tauri::Builder::default();
 
+    // 桌面端点击链接会启动新进程，single-instance 把链接转交给已运行的实例（经 deep-link 的
+    // on_open_url）；它必须最先注册
+    #[cfg(desktop)]
+    {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.set_focus();
+            }
+        }));
+    }
+    builder = builder.plugin(tauri_plugin_deep_link::init());
+
     #[cfg(not(target_os = "android"))]
     {
         builder = builder.plugin(tauri_plugin_opener::init());
@@ -37,32 +54,165 @@ pub fn run() {
 
             std::fs::create_dir_all(&app_data_dir).expect("Failed to create app data directory");
 
-            let data_service = DataService::new(app_data_dir);
+            let data_service = DataService::new(app_data_dir.clone());
+            logging::init(&app_data_dir, &data_service.log_level());
+            let prewarm_service = data_service.clone();
+            let update_service = data_service.clone();
+
+            let handle = app.handle().clone();
+            let settings = data_service.settings().clone();
+            settings.set_listener(move |updated| {
+                let _ = handle.emit("settings-changed", updated);
+            });
+
+            // Windows/Linux 上未安装（开发、便携版）时需要在运行时注册 arkstory://
+            #[cfg(any(windows, target_os = "linux"))]
+            if let Err(err) = app.deep_link().register_all() {
+                tracing::warn!("Failed to register deep link scheme: {}", err);
+            }
+            let pending_deep_link = PendingDeepLink::default();
+            if let Ok(Some(urls)) = app.deep_link().get_current() {
+                if let Some(url) = urls.first() {
+                    pending_deep_link.set(url.to_string());
+                }
+            }
+            let handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                for url in event.urls() {
+                    let _ = handle.emit(DEEP_LINK_EVENT, url.to_string());
+                }
+            });
 
+            // DataService 内部自行加锁，命令各自持有克隆，长耗时任务不会阻塞其他命令
             app.manage(AppState {
-                data_service: Arc::new(Mutex::new(data_service)),
+                data_service,
+                settings,
+                pending_deep_link,
+            });
+
+            // 后台预热数据表缓存，缩短首次打开剧情列表的等待
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn_blocking(move || prewarm_service.prewarm(&handle));
+
+            // 按设置在启动时检查数据更新
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                update_service.check_update_on_startup(&handle)
             });
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::sync_data,
+            commands::get_sync_report,
+            commands::get_new_stories,
+            commands::get_archive_format,
+            commands::set_archive_format,
+            commands::export_data_bundle,
+            commands::get_busy_state,
             commands::get_current_version,
             commands::get_remote_version,
             commands::check_update,
+            commands::check_update_detailed,
+            commands::list_data_sources,
+            commands::get_data_source,
+            commands::set_data_source,
             commands::is_installed,
             commands::get_main_stories_grouped,
             commands::get_activity_stories_grouped,
             commands::get_sidestory_stories_grouped,
             commands::get_roguelike_stories_grouped,
+            commands::get_rune_stories_grouped,
+            commands::get_record_stories_grouped,
             commands::get_memory_stories,
+            commands::get_memory_stories_grouped,
+            commands::get_story_tree,
+            commands::get_story_tree_version,
             commands::import_from_zip,
             commands::import_from_zip_bytes,
+            commands::export_app_data,
+            commands::import_app_data,
             commands::get_chapters,
+            commands::get_data_compat_report,
+            commands::get_activities,
             commands::get_story_categories,
             commands::get_story_content,
             commands::get_story_info,
             commands::get_story_entry,
+            commands::get_story_neighbors,
+            commands::set_reading_progress,
+            commands::get_reading_progress,
+            commands::get_all_reading_progress,
+            commands::add_bookmark,
+            commands::list_bookmarks,
+            commands::update_bookmark_note,
+            commands::delete_bookmark,
+            commands::toggle_favorite,
+            commands::is_favorite,
+            commands::list_favorites,
+            commands::report_read_session,
+            commands::get_recent_stories,
+            commands::get_continue_reading,
+            commands::set_story_read,
+            commands::get_group_read_counts,
+            commands::mark_group_read,
+            commands::get_story_stats,
+            commands::get_group_stats,
+            commands::get_stories_chronological,
+            commands::get_main_stories_grouped_v2,
+            commands::get_activity_stories_grouped_v2,
+            commands::get_sidestory_stories_grouped_v2,
+            commands::get_roguelike_stories_grouped_v2,
+            commands::get_rune_stories_grouped_v2,
+            commands::get_record_stories_grouped_v2,
+            commands::get_story_detail,
+            commands::get_include_hidden_in_search,
+            commands::set_include_hidden_in_search,
+            commands::get_story_characters,
+            commands::get_stories_for_character,
+            commands::get_stories_by_terra_date,
+            commands::get_random_story,
+            commands::get_related_stories,
+            commands::resolve_required_stages,
+            commands::get_chapter_reading_order,
+            commands::get_story_dependencies,
+            commands::get_month_chat_parts,
+            commands::get_characters_list,
+            commands::get_character_voice_actors,
+            commands::get_character_profile,
+            commands::search_characters,
+            commands::get_character_favor,
+            commands::get_character_attributes,
+            commands::get_range,
+            commands::get_character_ranges,
+            commands::get_character_module_details,
+            commands::get_character_upgrade_costs,
+            commands::get_character_skins,
+            commands::get_character_memories,
+            commands::get_character_voice_assets,
+            commands::get_character_tokens,
+            commands::get_character_forms,
+            commands::get_all_factions,
+            commands::get_all_sub_professions,
+            commands::get_roster_stats,
+            commands::get_character_by_name,
+            commands::get_character_credits,
+            commands::get_operators_by_artist,
+            commands::get_character_obtain_info,
+            commands::get_character_localized_names,
+            commands::list_tasks,
+            commands::cancel_task,
+            commands::get_recent_logs,
+            commands::set_log_level,
+            commands::get_settings,
+            commands::update_settings,
+            commands::export_diagnostics,
+            commands::get_disk_usage,
+            commands::get_story_cache_stats,
+            commands::prefetch_story_contents,
+            commands::get_app_status,
+            commands::resolve_deep_link,
+            commands::take_pending_deep_link,
             commands::get_story_index_status,
             commands::build_story_index,
             commands::search_stories,
@@ -72,3 +222,106 @@ pub fn run() {
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    /// commands.rs 中所有 #[tauri::command] 函数名
+    fn defined_commands() -> BTreeSet<String> {
+        let source = include_str!("commands.rs");
+        let mut names = BTreeSet::new();
+        let mut lines = source.lines();
+        while let Some(line) = lines.next() {
+            if line.trim() != "#[tauri::command]" {
+                continue;
+            }
+            let signature = lines.next().unwrap_or("").trim();
+            let name = signature
+                .strip_prefix("pub async fn ")
+                .or_else(|| signature.strip_prefix("pub fn "))
+                .and_then(|rest| rest.split(['(', '<']).next())
+                .unwrap_or_else(|| panic!("无法识别的命令签名：{}", signature));
+            names.insert(name.to_string());
+        }
+        names
+    }
+
+    /// generate_handler! 中注册的命令名
+    fn registered_commands() -> BTreeSet<String> {
+        let source = include_str!("lib.rs");
+        let marker = "generate_handler![";
+        let start = source.find(marker).expect("generate_handler! not found") + marker.len();
+        let end = start
+            + source[start..]
+                .find(']')
+                .expect("unterminated handler list");
+        source[start..end]
+            .split(',')
+            .filter_map(|item| item.trim().strip_prefix("commands::"))
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn every_command_is_registered() {
+        let defined = defined_commands();
+        let registered = registered_commands();
+        assert!(!defined.is_empty());
+        let unregistered: Vec<_> = defined.difference(&registered).collect();
+        let missing: Vec<_> = registered.difference(&defined).collect();
+        assert!(unregistered.is_empty(), "未注册的命令：{:?}", unregistered);
+        assert!(missing.is_empty(), "注册了不存在的命令：{:?}", missing);
+    }
+
+    /// 直接在异步运行时上执行的命令：只读取内存中的状态，或转调其他使用 spawn_blocking 的命令
+    const INLINE_COMMANDS: &[&str] = &[
+        "check_update",
+        "get_story_cache_stats",
+        "list_data_sources",
+        "get_data_source",
+        "get_busy_state",
+        "list_tasks",
+        "cancel_task",
+        "get_settings",
+        "take_pending_deep_link",
+    ];
+
+    /// 每个命令的函数名与函数体
+    fn command_bodies() -> Vec<(String, String)> {
+        let source = include_str!("commands.rs");
+        let mut bodies = Vec::new();
+        for chunk in source.split("#[tauri::command]\n").skip(1) {
+            let name = chunk
+                .strip_prefix("pub async fn ")
+                .or_else(|| chunk.strip_prefix("pub fn "))
+                .and_then(|rest| rest.split(['(', '<']).next())
+                .unwrap_or_default();
+            let end = chunk.find("\n}\n").map_or(chunk.len(), |end| end + 2);
+            bodies.push((name.to_string(), chunk[..end].to_string()));
+        }
+        bodies
+    }
+
+    /// 文件与数据库 IO 会阻塞整个 Tauri 异步运行时，必须放到 spawn_blocking 中执行
+    #[test]
+    fn commands_do_blocking_io_off_the_async_runtime() {
+        let bodies = command_bodies();
+        assert_eq!(bodies.len(), defined_commands().len());
+        for (name, body) in bodies {
+            if body.contains("spawn_blocking") {
+                continue;
+            }
+            assert!(
+                INLINE_COMMANDS.contains(&name.as_str()),
+                "命令 {} 未使用 spawn_blocking，若只读取内存状态请加入 INLINE_COMMANDS",
+                name
+            );
+            assert!(
+                !body.contains("fs::") && !body.contains(".lock()"),
+                "命令 {} 在异步运行时上读写文件或持有锁",
+                name
+            );
+        }
+    }
+}
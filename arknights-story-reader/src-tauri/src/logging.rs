@@ -0,0 +1,332 @@
+use std::borrow::Cow;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use regex::Regex;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+/// 日志目录（相对 app_data_dir）
+pub const LOG_DIR: &str = "logs";
+const LOG_FILE: &str = "story-teller.log";
+/// 单个日志文件的大小上限，超过后滚动到 story-teller.log.1
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+/// 保留的日志文件数（含正在写入的文件）
+const MAX_LOG_FILES: usize = 3;
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+
+lazy_static::lazy_static! {
+    /// GitHub 个人令牌与 Authorization/token 等字段的值
+    static ref SECRET_PATTERNS: Vec<(Regex, &'static str)> = vec![
+        (
+            Regex::new(r"\b(?:gh[pousr]_[A-Za-z0-9]{20,}|github_pat_[A-Za-z0-9_]{20,})").unwrap(),
+            "[REDACTED]",
+        ),
+        (
+            Regex::new(
+                r#"(?i)\b(authorization|access_token|token)(["']?\s*[:=]\s*["']?)((?:bearer|token)\s+)?[^\s"'&,]+"#,
+            )
+            .unwrap(),
+            "$1$2$3[REDACTED]",
+        ),
+    ];
+}
+
+struct LoggingState {
+    dir: PathBuf,
+    level: reload::Handle<LevelFilter, Registry>,
+}
+
+static LOGGING: OnceLock<LoggingState> = OnceLock::new();
+
+/// 安装全局 tracing 订阅者：事件同时写入 stderr 与 app_data_dir/logs 下按大小滚动的日志文件
+///
+/// Android 上 stderr 不可见，日志文件是唯一的排查途径；重复调用时保持第一次的配置
+pub fn init(app_data_dir: &Path, level: &str) {
+    let dir = app_data_dir.join(LOG_DIR);
+    let (filter, handle) = reload::Layer::new(parse_level(level).unwrap_or(LevelFilter::INFO));
+    let file_layer = match RollingFileWriter::new(&dir, MAX_LOG_BYTES, MAX_LOG_FILES) {
+        Ok(writer) => Some(fmt::layer().with_writer(move || Redacted(writer.clone()))),
+        Err(err) => {
+            eprintln!("[LOG] Failed to open log directory {:?}: {}", dir, err);
+            None
+        }
+    };
+    let installed = tracing_subscriber::registry()
+        .with(filter)
+        .with(file_layer)
+        .with(fmt::layer().with_writer(|| Redacted(io::stderr())))
+        .try_init();
+    if installed.is_ok() {
+        let _ = LOGGING.set(LoggingState { dir, level: handle });
+    }
+}
+
+/// 解析日志级别：error、warn、info、debug、trace 或 off（不区分大小写）
+pub fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    level
+        .trim()
+        .parse::<LevelFilter>()
+        .map_err(|_| format!("无效的日志级别: {}", level))
+}
+
+/// 运行时调整日志级别；日志尚未初始化时只校验级别
+pub fn set_level(level: &str) -> Result<(), String> {
+    let filter = parse_level(level)?;
+    if let Some(state) = LOGGING.get() {
+        state
+            .level
+            .reload(filter)
+            .map_err(|e| format!("Failed to change log level: {}", e))?;
+    }
+    Ok(())
+}
+
+/// 最近的 lines 行日志（按时间先后），供用户粘贴到问题反馈中
+pub fn recent_logs(lines: usize) -> Result<Vec<String>, String> {
+    match LOGGING.get() {
+        Some(state) => read_recent_lines(&state.dir, MAX_LOG_FILES, lines),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn log_file_path(dir: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        dir.join(LOG_FILE)
+    } else {
+        dir.join(format!("{}.{}", LOG_FILE, index))
+    }
+}
+
+/// 从最旧的滚动文件读到当前文件，返回最后 lines 行
+fn read_recent_lines(dir: &Path, max_files: usize, lines: usize) -> Result<Vec<String>, String> {
+    let mut collected = Vec::new();
+    for index in (0..max_files).rev() {
+        let path = log_file_path(dir, index);
+        match fs::read(&path) {
+            Ok(bytes) => {
+                collected.extend(String::from_utf8_lossy(&bytes).lines().map(str::to_string))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(format!("Failed to read log {:?}: {}", path, err)),
+        }
+    }
+    let skip = collected.len().saturating_sub(lines);
+    Ok(collected.split_off(skip))
+}
+
+/// 把日志中的 GitHub 令牌等敏感信息替换为 [REDACTED]
+pub fn redact_secrets(text: &str) -> Cow<'_, str> {
+    let mut redacted = Cow::Borrowed(text);
+    for (pattern, replacement) in SECRET_PATTERNS.iter() {
+        if let Cow::Owned(replaced) = pattern.replace_all(&redacted, *replacement) {
+            redacted = Cow::Owned(replaced);
+        }
+    }
+    redacted
+}
+
+/// 写入前脱敏；fmt 层每个事件只调用一次 write_all，因此按事件整体匹配
+struct Redacted<W: Write>(W);
+
+impl<W: Write> Write for Redacted<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        self.0.write_all(redact_secrets(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+struct RollingFile {
+    dir: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: Option<File>,
+    size: u64,
+}
+
+impl RollingFile {
+    fn open(&mut self) -> io::Result<&mut File> {
+        if self.file.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(log_file_path(&self.dir, 0))?;
+            self.size = file.metadata()?.len();
+            self.file = Some(file);
+        }
+        Ok(self.file.as_mut().expect("log file just opened"))
+    }
+
+    /// story-teller.log → .1 → .2 …，超出 max_files 的最旧文件被覆盖
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        for index in (1..self.max_files).rev() {
+            let from = log_file_path(&self.dir, index - 1);
+            if from.exists() {
+                let to = log_file_path(&self.dir, index);
+                // Windows 上 rename 不会覆盖已存在的文件
+                let _ = fs::remove_file(&to);
+                fs::rename(&from, &to)?;
+            }
+        }
+        self.size = 0;
+        Ok(())
+    }
+
+    fn write_record(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.open()?;
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.open()?.write_all(buf)?;
+        self.size += buf.len() as u64;
+        Ok(())
+    }
+}
+
+/// 按大小滚动的日志文件，克隆共享同一个文件句柄
+#[derive(Clone)]
+pub struct RollingFileWriter {
+    inner: Arc<Mutex<RollingFile>>,
+}
+
+impl RollingFileWriter {
+    pub fn new(dir: &Path, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let mut file = RollingFile {
+            dir: dir.to_path_buf(),
+            max_bytes,
+            max_files: max_files.max(1),
+            file: None,
+            size: 0,
+        };
+        file.open()?;
+        Ok(Self {
+            inner: Arc::new(Mutex::new(file)),
+        })
+    }
+}
+
+impl Write for RollingFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .write_record(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut file = self
+            .inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match file.file.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "story_teller_logging_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn rolling_writer_rotates_and_keeps_three_files() {
+        let dir = temp_dir("rotate");
+        let mut writer = RollingFileWriter::new(&dir, 64, 3).unwrap();
+        for index in 0..10 {
+            // 与 fmt 层一致：每条记录一次 write_all
+            let record = format!("record {:02} {}\n", index, "x".repeat(20));
+            writer.write_all(record.as_bytes()).unwrap();
+        }
+        writer.flush().unwrap();
+
+        let mut files: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                "story-teller.log",
+                "story-teller.log.1",
+                "story-teller.log.2"
+            ]
+        );
+        for index in 0..3 {
+            let len = fs::metadata(log_file_path(&dir, index)).unwrap().len();
+            assert!(len <= 64, "file {} has {} bytes", index, len);
+        }
+
+        // 最旧的记录已被丢弃，剩余记录按时间先后返回
+        let lines = read_recent_lines(&dir, 3, 100).unwrap();
+        assert_eq!(
+            lines.last().unwrap(),
+            &format!("record 09 {}", "x".repeat(20))
+        );
+        assert!(!lines.iter().any(|line| line.starts_with("record 00")));
+        assert_eq!(read_recent_lines(&dir, 3, 2).unwrap().len(), 2);
+
+        // 重新打开时在已有文件末尾继续写入
+        drop(writer);
+        let mut reopened = RollingFileWriter::new(&dir, 64, 3).unwrap();
+        reopened.write_all(b"after restart\n").unwrap();
+        assert_eq!(
+            read_recent_lines(&dir, 3, 1).unwrap(),
+            vec!["after restart".to_string()]
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn secrets_are_redacted() {
+        let token = format!("ghp_{}", "A1b2C3d4".repeat(5));
+        let line = format!(
+            "request failed token={} Authorization: Bearer abc.def header {}",
+            token, token
+        );
+        let redacted = redact_secrets(&line);
+        assert!(!redacted.contains(&token), "{}", redacted);
+        assert!(!redacted.contains("abc.def"), "{}", redacted);
+        assert!(redacted.contains("Authorization: Bearer [REDACTED]"));
+
+        let mut output = Redacted(Vec::new());
+        output
+            .write_all(format!("clone {}", token).as_bytes())
+            .unwrap();
+        assert_eq!(String::from_utf8(output.0).unwrap(), "clone [REDACTED]");
+
+        let plain = "sync finished: 120 stories";
+        assert!(matches!(redact_secrets(plain), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn parses_levels_case_insensitively() {
+        assert_eq!(parse_level("DEBUG").unwrap(), LevelFilter::DEBUG);
+        assert_eq!(parse_level(" off ").unwrap(), LevelFilter::OFF);
+        assert!(parse_level("verbose").is_err());
+    }
+}
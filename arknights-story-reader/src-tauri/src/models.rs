@@ -8,19 +8,26 @@ pub struct StoryEntry {
     pub story_name: String,
     #[serde(rename = "storyCode")]
     pub story_code: Option<String>,
-    #[serde(rename = "storyGroup")]
+    #[serde(rename = "storyGroup", default)]
     pub story_group: String,
-    #[serde(rename = "storySort")]
+    #[serde(rename = "storySort", default)]
     pub story_sort: i32,
     #[serde(rename = "avgTag")]
     pub avg_tag: Option<String>,
+    /// avgTag 归一化后的类型（行动前/行动后/幕间/其他），原始字符串保留在 avg_tag
+    #[serde(
+        rename = "avgTagKind",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub avg_tag_kind: Option<AvgTagKind>,
     #[serde(rename = "storyTxt")]
     pub story_txt: String,
     #[serde(rename = "storyInfo")]
     pub story_info: Option<String>,
-    #[serde(rename = "storyReviewType")]
+    #[serde(rename = "storyReviewType", default)]
     pub story_review_type: String,
-    #[serde(rename = "unLockType")]
+    #[serde(rename = "unLockType", default)]
     pub unlock_type: String,
     // 额外元数据
     #[serde(rename = "storyDependence")]
@@ -29,6 +36,9 @@ pub struct StoryEntry {
     pub story_can_show: Option<i32>,
     #[serde(rename = "storyCanEnter")]
     pub story_can_enter: Option<i32>,
+    /// 由 storyCanShow/storyCanEnter 推导的可见性
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<StoryVisibility>,
     #[serde(rename = "stageCount")]
     pub stage_count: Option<i32>,
     #[serde(rename = "requiredStages")]
@@ -39,6 +49,115 @@ pub struct StoryEntry {
     pub cost_item_id: Option<String>,
     #[serde(rename = "costItemCount")]
     pub cost_item_count: Option<i32>,
+    /// 可读的解锁条件描述，例如「解锁需要信赖值50，消耗初级近卫芯片×1」
+    #[serde(
+        rename = "unlockDesc",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub unlock_desc: Option<String>,
+    /// 仅分组列表接口填充：剧情本身是否已收藏
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub favorite: Option<bool>,
+    /// 仅分组列表接口填充：所在分组（storyGroup）是否已收藏
+    #[serde(
+        rename = "groupFavorite",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub group_favorite: Option<bool>,
+    /// 仅在请求统计时填充：来自索引中缓存的剧情统计，索引未建立时为 None
+    #[serde(rename = "wordCount", default, skip_serializing_if = "Option::is_none")]
+    pub word_count: Option<usize>,
+    #[serde(
+        rename = "estimatedMinutes",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub estimated_minutes: Option<usize>,
+    /// 仅干员密录填充：收录该密录的活动名称（story_review_meta_table.actArchiveData）
+    #[serde(
+        rename = "sourceActName",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub source_act_name: Option<String>,
+    /// 仅干员密录填充：活动档案中关联关卡的显示编号，stage_table 缺失时为关卡 id
+    #[serde(
+        rename = "sourceStageCode",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub source_stage_code: Option<String>,
+}
+
+/// 剧情分组：各分组列表接口的统一返回结构
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoryGroup {
+    /// 分组键（story_review_table 条目 id、赛季、主题或区域 id）
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "entryType")]
+    pub entry_type: String,
+    pub stories: Vec<StoryEntry>,
+    /// 开放时间（Unix 秒），数据中缺失时为 None
+    #[serde(rename = "startTime", default, skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<i64>,
+    /// 活动类分组的 actType（ACTIVITY_STORY / MINI_STORY 等），便于前端自行合并支线与活动
+    #[serde(rename = "actType", default, skip_serializing_if = "Option::is_none")]
+    pub act_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra: Option<StoryGroupExtra>,
+}
+
+impl StoryGroup {
+    /// 转为旧接口使用的 (名称, 剧情列表) 元组
+    pub fn into_pair(self) -> (String, Vec<StoryEntry>) {
+        (self.name, self.stories)
+    }
+}
+
+/// 分组的附加信息，目前仅活动与支线分组填充
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoryGroupExtra {
+    /// 是否合并了复刻活动
+    #[serde(rename = "hasRerun")]
+    pub has_rerun: bool,
+    /// activity_table 中对应的活动 id；旧活动可能不在表中
+    #[serde(rename = "actId", default, skip_serializing_if = "Option::is_none")]
+    pub act_id: Option<String>,
+    /// 活动代号（如 CB），取自关卡剧情编号的前缀
+    #[serde(rename = "actCode", default, skip_serializing_if = "Option::is_none")]
+    pub act_code: Option<String>,
+    /// 横幅图片 id
+    #[serde(rename = "bannerId", default, skip_serializing_if = "Option::is_none")]
+    pub banner_id: Option<String>,
+    /// 活动主题色（homeActConfig.actTopBarColor）
+    #[serde(
+        rename = "themeColor",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub theme_color: Option<String>,
+}
+
+/// 剧情可见性：hidden 为剧透隐藏（storyCanShow = 0），locked 为可见但不可进入
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StoryVisibility {
+    Normal,
+    Hidden,
+    Locked,
+}
+
+/// 剧情标签类型，用于筛选幕间或隐藏行动前/后剧情
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AvgTagKind {
+    Before,
+    After,
+    Interlude,
+    Other,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,23 +170,37 @@ pub struct RequiredStage {
     pub max_state: String,
 }
 
+/// 解析为可读编号与名称的解锁关卡；stage_table 中没有的关卡 code 回退为关卡 id、name 为 None
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResolvedStage {
+    #[serde(rename = "stageId")]
+    pub stage_id: String,
+    pub code: String,
+    pub name: Option<String>,
+    #[serde(rename = "minState")]
+    pub min_state: String,
+    #[serde(rename = "maxState")]
+    pub max_state: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Chapter {
     #[serde(rename = "chapterId")]
     pub chapter_id: String,
     #[serde(rename = "chapterName")]
     pub chapter_name: String,
-    #[serde(rename = "chapterName2")]
+    // 以下字段非必需，游戏更新中缺失或改名时取默认值，避免整张表解析失败
+    #[serde(rename = "chapterName2", default)]
     pub chapter_name2: String,
-    #[serde(rename = "chapterIndex")]
+    #[serde(rename = "chapterIndex", default)]
     pub chapter_index: i32,
-    #[serde(rename = "preposedChapterId")]
+    #[serde(rename = "preposedChapterId", default)]
     pub preposed_chapter_id: Option<String>,
-    #[serde(rename = "startZoneId")]
+    #[serde(rename = "startZoneId", default)]
     pub start_zone_id: String,
-    #[serde(rename = "endZoneId")]
+    #[serde(rename = "endZoneId", default)]
     pub end_zone_id: String,
-    #[serde(rename = "chapterEndStageId")]
+    #[serde(rename = "chapterEndStageId", default)]
     pub chapter_end_stage_id: String,
 }
 
@@ -81,8 +214,12 @@ pub struct Activity {
     pub act_type: String,
     #[serde(rename = "startTime")]
     pub start_time: i64,
-    #[serde(rename = "endTime")]
-    pub end_time: i64,
+    /// 结束时间；原始数据中 0 或负数表示常驻/未知，统一为 None
+    #[serde(rename = "endTime", default)]
+    pub end_time: Option<i64>,
+    /// 是否为复刻活动（由名称后缀或 actType 推断）
+    #[serde(rename = "isRerun", default)]
+    pub is_rerun: bool,
     #[serde(rename = "infoUnlockDatas")]
     pub info_unlock_datas: Vec<StoryEntry>,
 }
@@ -165,3 +302,1213 @@ pub struct StoryIndexStatus {
     #[serde(rename = "lastBuiltAt")]
     pub last_built_at: Option<i64>,
 }
+
+/// 数据更新检查结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum UpdateStatus {
+    UpToDate,
+    UpdateAvailable {
+        #[serde(rename = "remoteShort")]
+        remote_short: String,
+    },
+    /// 无法确认（如离线），前端不应提示更新
+    Unknown {
+        reason: String,
+    },
+}
+
+/// 数据源配置（仓库、分支与语言目录）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataSourceProfile {
+    pub id: String,
+    pub name: String,
+    /// GitHub 仓库，形如 "Kengxxiao/ArknightsGameData"
+    pub repo: String,
+    pub branch: String,
+    /// 数据包中的语言目录，如 "zh_CN"、"en_US"
+    pub language: String,
+}
+
+/// 数据表兼容性报告：记录需要宽松解析或被跳过的剧情条目
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DataCompatReport {
+    #[serde(rename = "totalEntries")]
+    pub total_entries: usize,
+    #[serde(rename = "strictEntries")]
+    pub strict_entries: usize,
+    #[serde(rename = "lenientEntries")]
+    pub lenient_entries: usize,
+    #[serde(rename = "failedEntries")]
+    pub failed_entries: usize,
+    #[serde(rename = "lenientIds")]
+    pub lenient_ids: Vec<String>,
+    /// 形如 "entryId/storyId"
+    #[serde(rename = "failedIds")]
+    pub failed_ids: Vec<String>,
+    /// chapter_table 中无法解析而被跳过的章节
+    #[serde(rename = "skippedChapters", default)]
+    pub skipped_chapters: usize,
+    #[serde(rename = "skippedChapterIds", default)]
+    pub skipped_chapter_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncCategoryCount {
+    pub category: String,
+    pub count: usize,
+}
+
+/// 同步完成后的摘要
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncReport {
+    #[serde(rename = "totalStories")]
+    pub total_stories: usize,
+    #[serde(rename = "categoryCounts")]
+    pub category_counts: Vec<SyncCategoryCount>,
+    /// 首次安装时没有可对比的旧数据，newStoryCount 恒为 0
+    #[serde(rename = "firstInstall")]
+    pub first_install: bool,
+    #[serde(rename = "newStoryCount")]
+    pub new_story_count: usize,
+    /// 新增剧情名称（最多 50 条）
+    #[serde(rename = "newStoryNames")]
+    pub new_story_names: Vec<String>,
+    #[serde(rename = "totalTextBytes")]
+    pub total_text_bytes: u64,
+    #[serde(rename = "previousVersion")]
+    pub previous_version: Option<String>,
+    #[serde(rename = "currentVersion")]
+    pub current_version: Option<String>,
+    #[serde(rename = "generatedAt")]
+    pub generated_at: i64,
+}
+
+/// 新增剧情中的一个分类
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewStoryGroup {
+    pub category: String,
+    pub stories: Vec<StoryEntry>,
+}
+
+/// 与上一次安装的数据相比新增的剧情
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewStories {
+    /// 没有可对比的旧数据（首次安装或尚未记录快照）时为 true，此时 groups 为空
+    #[serde(rename = "firstInstall")]
+    pub first_install: bool,
+    #[serde(rename = "previousVersion")]
+    pub previous_version: Option<String>,
+    #[serde(rename = "currentVersion")]
+    pub current_version: Option<String>,
+    pub groups: Vec<NewStoryGroup>,
+}
+
+/// 同步时下载的归档格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ArchiveFormat {
+    Zip,
+    /// tar.gz 体积更小，且可以边下载边解压
+    #[default]
+    TarGz,
+}
+
+/// 当前是否有同步、导入等独占任务正在执行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BusyState {
+    pub busy: bool,
+    pub task: Option<String>,
+}
+
+/// 深度链接解析后前端执行的跳转
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum DeepLinkNavigation {
+    Story {
+        story: Box<StoryEntry>,
+    },
+    Character {
+        #[serde(rename = "charId")]
+        char_id: String,
+        name: String,
+    },
+    Search {
+        query: String,
+    },
+}
+
+/// 统一的任务进度事件（task-progress）。每个任务以一个终止事件结束：
+/// 成功时 done 为 true，失败或取消时 error 非空
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressEvent {
+    #[serde(rename = "taskId")]
+    pub task_id: u64,
+    #[serde(rename = "taskKind")]
+    pub task_kind: String,
+    pub phase: String,
+    pub current: usize,
+    pub total: usize,
+    pub message: String,
+    pub done: bool,
+    pub error: Option<String>,
+}
+
+/// 正在执行的长耗时任务，进度事件中的 taskId 与这里的 id 对应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskInfo {
+    pub id: u64,
+    /// 任务类型：sync、index、import、search 等
+    pub kind: String,
+    /// 最近一次上报的阶段，尚未上报时为 None
+    pub phase: Option<String>,
+    pub current: usize,
+    pub total: usize,
+    /// 独占任务同一时间只能有一个
+    pub exclusive: bool,
+    /// 已请求取消，等待任务在下一个检查点退出
+    pub cancelled: bool,
+    #[serde(rename = "startedAt")]
+    pub started_at: i64,
+}
+
+/// 已安装数据的版本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledVersion {
+    pub commit: String,
+    #[serde(rename = "commitShort")]
+    pub commit_short: String,
+    #[serde(rename = "fetchedAt")]
+    pub fetched_at: i64,
+    /// 数据来源 id；旧版本写入的 version.json 中没有来源
+    #[serde(rename = "sourceId")]
+    pub source_id: Option<String>,
+}
+
+/// 全文索引状态，building 为 true 时正在重建
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexSummary {
+    pub ready: bool,
+    pub total: usize,
+    #[serde(rename = "lastBuiltAt")]
+    pub last_built_at: Option<i64>,
+    pub building: bool,
+}
+
+/// 最近一次数据更新检查的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateCheckResult {
+    pub status: UpdateStatus,
+    #[serde(rename = "checkedAt")]
+    pub checked_at: i64,
+}
+
+/// 设置页一次取回的应用状态；各项独立获取，失败的项为 None
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppStatus {
+    pub installed: Option<bool>,
+    pub version: Option<InstalledVersion>,
+    pub language: Option<String>,
+    pub index: Option<IndexSummary>,
+    #[serde(rename = "diskUsage")]
+    pub disk_usage: Option<DiskUsage>,
+    pub tasks: Option<Vec<TaskInfo>>,
+    /// 未检查过更新时为 None；获取状态不会发起网络请求
+    #[serde(rename = "lastUpdateCheck")]
+    pub last_update_check: Option<UpdateCheckResult>,
+    #[serde(rename = "storyCache")]
+    pub story_cache: Option<StoryCacheStats>,
+}
+
+/// 已解析剧情缓存的命中统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoryCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+/// 各类数据占用的磁盘空间（字节）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskUsage {
+    #[serde(rename = "gameDataBytes")]
+    pub game_data_bytes: u64,
+    #[serde(rename = "indexBytes")]
+    pub index_bytes: u64,
+    #[serde(rename = "userDataBytes")]
+    pub user_data_bytes: u64,
+    #[serde(rename = "logBytes")]
+    pub log_bytes: u64,
+    #[serde(rename = "totalBytes")]
+    pub total_bytes: u64,
+}
+
+/// export_diagnostics 写出的诊断包
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticsExport {
+    pub path: String,
+    pub size: u64,
+}
+
+/// 启动后后台预热表缓存的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrewarmReport {
+    #[serde(rename = "durationMs")]
+    pub duration_ms: u64,
+    pub tables: usize,
+    pub stories: usize,
+}
+
+/// 按干员分组的干员密录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryGroup {
+    #[serde(rename = "charId")]
+    pub char_id: String,
+    #[serde(rename = "charName")]
+    pub char_name: String,
+    /// 星级（1-6），无法解析干员时为 0
+    pub rarity: u8,
+    pub stories: Vec<StoryEntry>,
+}
+
+/// 剧情中出场的说话人；无法匹配 character_table 的 NPC 仅有名称
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoryCharacter {
+    pub name: String,
+    #[serde(rename = "charId")]
+    pub char_id: Option<String>,
+    /// 星级（1-6），NPC 为 None
+    pub rarity: Option<u8>,
+    /// 头像资源 id（与干员 id 一致）
+    #[serde(rename = "avatarId")]
+    pub avatar_id: Option<String>,
+    /// 该角色的台词条数
+    #[serde(rename = "lineCount")]
+    pub line_count: usize,
+}
+
+/// 干员某一语言的配音信息（charword_table.voiceLangDict）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VoiceActorLang {
+    /// 语言类型，如 JP / CN_MANDARIN / CN_TOPOLECT / EN / KR
+    #[serde(rename = "langType")]
+    pub lang_type: String,
+    /// voiceLangTypeDict 中的语言名称，缺失时与 lang_type 相同
+    #[serde(rename = "langName")]
+    pub lang_name: String,
+    #[serde(rename = "cvNames")]
+    pub cv_names: Vec<String>,
+    /// 语音资源路径前缀；为 None 时资源按 word_key 命名
+    #[serde(rename = "voicePath")]
+    pub voice_path: Option<String>,
+}
+
+/// 一套语音：默认语音或皮肤专属语音
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VoiceSet {
+    #[serde(rename = "wordKey")]
+    pub word_key: String,
+    /// word_key 与干员 id 不同（皮肤语音等）时为 true
+    #[serde(rename = "isAlternate")]
+    pub is_alternate: bool,
+    /// 含方言（CN_TOPOLECT）配音
+    #[serde(rename = "hasTopolect")]
+    pub has_topolect: bool,
+    pub languages: Vec<VoiceActorLang>,
+}
+
+/// 单条语音的资源路径。路径相对于游戏音频根目录，形如
+/// `{语言目录}/{wordKey 目录}/{语音名}.mp3`，全部小写：语言目录 JP 为 voice，
+/// CN_MANDARIN / EN / KR 为 voice_cn / voice_en / voice_kr，CN_TOPOLECT 为 voice_custom，
+/// 其余语言为 voice_{语言小写}；wordKey 目录与语音名取自 voiceAsset（如 char_002_amiya/CN_001），
+/// voiceLangDict 声明了 voicePath 时以其替换 wordKey 目录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VoiceAsset {
+    #[serde(rename = "charWordId")]
+    pub char_word_id: String,
+    #[serde(rename = "wordKey")]
+    pub word_key: String,
+    #[serde(rename = "voiceId")]
+    pub voice_id: String,
+    #[serde(rename = "voiceTitle")]
+    pub voice_title: Option<String>,
+    #[serde(rename = "voiceText")]
+    pub voice_text: Option<String>,
+    /// 例如 voice_cn/char_002_amiya/cn_001.mp3
+    #[serde(rename = "assetPath")]
+    pub asset_path: String,
+}
+
+/// 干员某一语言的全部语音资源
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterVoiceAssets {
+    #[serde(rename = "charId")]
+    pub char_id: String,
+    pub lang: String,
+    /// 存在皮肤等非默认语音套组
+    #[serde(rename = "hasAlternateSets")]
+    pub has_alternate_sets: bool,
+    pub lines: Vec<VoiceAsset>,
+}
+
+/// 干员的全部配音信息，默认语音在前
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterVoiceActors {
+    #[serde(rename = "charId")]
+    pub char_id: String,
+    #[serde(rename = "voiceSets")]
+    pub voice_sets: Vec<VoiceSet>,
+}
+
+/// 干员列表中的一项
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterSummary {
+    #[serde(rename = "charId")]
+    pub char_id: String,
+    pub name: String,
+    pub appellation: Option<String>,
+    /// 星级（1-6）
+    pub rarity: u8,
+    pub profession: Option<String>,
+    #[serde(rename = "subProfessionId")]
+    pub sub_profession_id: Option<String>,
+    /// uniequip_table.subProfDict 中的分支名称（如「驭法铁卫」），表中缺失时为 None
+    #[serde(rename = "subProfessionName")]
+    pub sub_profession_name: Option<String>,
+    /// 所属国家/组织/队伍的 handbook_team_table 代号
+    #[serde(rename = "nationId")]
+    pub nation_id: Option<String>,
+    #[serde(rename = "groupId")]
+    pub group_id: Option<String>,
+    #[serde(rename = "teamId")]
+    pub team_id: Option<String>,
+    pub tags: Vec<String>,
+    /// 是否存在其他形态（char_patch_table 中同组的干员，如阿米娅的近卫/医疗形态）
+    #[serde(rename = "hasAlternateForms")]
+    pub has_alternate_forms: bool,
+    /// 其他形态所属的原版干员 id，原版干员为 None
+    #[serde(rename = "baseCharId")]
+    pub base_char_id: Option<String>,
+    /// character_table.isNotObtainable（联动等无法获取的干员）
+    #[serde(rename = "isNotObtainable")]
+    pub is_not_obtainable: bool,
+    /// 来自 char_patch_table.patchChars 的其他形态（升变/异格）
+    #[serde(rename = "isPatch")]
+    pub is_patch: bool,
+}
+
+/// 干员列表的排序方式；缺省时按干员 id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CharacterSort {
+    /// 星级降序
+    Rarity,
+    Name,
+}
+
+/// get_characters_list 的筛选与分页参数，全部缺省时返回完整列表。
+/// 同一字段内的多个值为「或」，不同字段之间为「且」
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CharacterListFilter {
+    #[serde(default)]
+    pub profession: Option<Vec<String>>,
+    #[serde(default)]
+    pub rarity: Option<Vec<u8>>,
+    #[serde(default)]
+    pub nation: Option<String>,
+    #[serde(rename = "subProfession", default)]
+    pub sub_profession: Option<String>,
+    #[serde(default)]
+    pub sort: Option<CharacterSort>,
+    #[serde(default)]
+    pub offset: Option<usize>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// 同时列出召唤物与装置
+    #[serde(rename = "includeNonplayable", default)]
+    pub include_nonplayable: bool,
+}
+
+/// 筛选后的一页干员；total 为分页前的总数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterPage {
+    pub total: usize,
+    pub items: Vec<CharacterSummary>,
+}
+
+/// 档案中的一条【键】值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileField {
+    pub key: String,
+    pub value: String,
+}
+
+/// handbook_info_table 中的一个档案章节（原文不做处理）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HandbookSection {
+    pub title: String,
+    pub text: String,
+    /// 原始解锁类型（DIRECT / FAVOR / AWAKE 等）
+    #[serde(rename = "unlockType")]
+    pub unlock_type: Option<String>,
+    /// 原始解锁参数：FAVOR 为信赖等级，AWAKE 为精英阶段
+    #[serde(rename = "unlockParam")]
+    pub unlock_param: Option<String>,
+    /// 由解锁类型与参数生成的说明，如「信赖达到50%解锁」
+    #[serde(rename = "unlockDesc")]
+    pub unlock_desc: String,
+}
+
+/// 干员档案：基础档案按原顺序拆成键值，体检医师报告等叙述段落单独保留
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterProfile {
+    #[serde(rename = "charId")]
+    pub char_id: String,
+    pub profile: Vec<ProfileField>,
+    #[serde(rename = "medicalReport")]
+    pub medical_report: Option<String>,
+    pub sections: Vec<HandbookSection>,
+}
+
+/// 干员搜索的命中方式，按匹配质量从高到低排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CharacterMatchKind {
+    /// 名称或代号完全一致
+    Exact,
+    Prefix,
+    Substring,
+    /// 仅标签（tagList）命中
+    Tag,
+}
+
+/// 干员搜索结果：干员列表项加上命中方式
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterMatch {
+    #[serde(flatten)]
+    pub summary: CharacterSummary,
+    #[serde(rename = "matchKind")]
+    pub match_kind: CharacterMatchKind,
+}
+
+/// 干员的获取方式与上线时间
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterObtainInfo {
+    #[serde(rename = "charId")]
+    pub char_id: String,
+    /// itemObtainApproach，如「招募寻访」「活动获得」
+    #[serde(rename = "obtainApproach")]
+    pub obtain_approach: Option<String>,
+    #[serde(rename = "isNotObtainable")]
+    pub is_not_obtainable: bool,
+    /// 出现在寻访中时，是否只出现在限定/联动寻访；没有寻访数据或未出现时为 None
+    #[serde(rename = "isLimited")]
+    pub is_limited: Option<bool>,
+    /// 最早一次出现在寻访中的开放时间（Unix 秒）
+    #[serde(rename = "releaseTs")]
+    pub release_ts: Option<i64>,
+}
+
+/// 信赖带来的单项属性加成
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributeBonus {
+    /// character_table 中的属性键，如 maxHp / atk / def
+    pub attribute: String,
+    /// 属性的中文名称，未知属性沿用属性键
+    pub label: String,
+    pub value: f64,
+}
+
+/// favorKeyFrames 中的一个关键帧
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FavorKeyFrame {
+    pub level: i32,
+    /// 对应的信赖百分比（favor_table 缺失时按 level × 2 估算）
+    pub percent: i32,
+    /// 仅包含非零的属性加成
+    pub bonuses: Vec<AttributeBonus>,
+}
+
+/// 干员的信赖加成
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterFavor {
+    #[serde(rename = "charId")]
+    pub char_id: String,
+    #[serde(rename = "keyFrames")]
+    pub key_frames: Vec<FavorKeyFrame>,
+    /// 最高关键帧的加成
+    #[serde(rename = "maxBonuses")]
+    pub max_bonuses: Vec<AttributeBonus>,
+    /// 例如「满信赖 +300 生命 +30 攻击」
+    pub summary: String,
+}
+
+/// attributesKeyFrames 中一个关键帧的属性；字段名与 character_table 一致，缺失时为 0
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AttributeBlock {
+    #[serde(rename = "maxHp")]
+    pub max_hp: i32,
+    pub atk: i32,
+    pub def: i32,
+    #[serde(rename = "magicResistance")]
+    pub magic_resistance: f64,
+    pub cost: i32,
+    #[serde(rename = "blockCnt")]
+    pub block_cnt: i32,
+    #[serde(rename = "respawnTime")]
+    pub respawn_time: i32,
+    #[serde(rename = "attackSpeed")]
+    pub attack_speed: f64,
+}
+
+/// 一个精英阶段的等级范围与首尾关键帧属性
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhaseAttributes {
+    /// 精英阶段，0 起
+    pub phase: usize,
+    #[serde(rename = "minLevel")]
+    pub min_level: i32,
+    #[serde(rename = "maxLevel")]
+    pub max_level: i32,
+    pub start: AttributeBlock,
+    pub end: AttributeBlock,
+}
+
+/// 干员各精英阶段的属性成长
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterAttributes {
+    #[serde(rename = "charId")]
+    pub char_id: String,
+    pub phases: Vec<PhaseAttributes>,
+}
+
+/// range_table 中的攻击范围；cells 为相对干员所在格的 (row, col) 偏移
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RangeGrid {
+    pub id: String,
+    pub cells: Vec<(i32, i32)>,
+}
+
+/// 某一精英阶段的默认攻击范围；rangeId 缺失或无法解析时为 None
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhaseRange {
+    pub phase: usize,
+    pub range: Option<RangeGrid>,
+}
+
+/// 技能改写的攻击范围，从 from_level（1 起）开始生效
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkillRange {
+    #[serde(rename = "skillId")]
+    pub skill_id: String,
+    #[serde(rename = "skillName")]
+    pub skill_name: Option<String>,
+    #[serde(rename = "fromLevel")]
+    pub from_level: usize,
+    pub range: RangeGrid,
+}
+
+/// 干员的默认攻击范围与技能范围
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterRanges {
+    #[serde(rename = "charId")]
+    pub char_id: String,
+    pub phases: Vec<PhaseRange>,
+    pub skills: Vec<SkillRange>,
+}
+
+/// blackboard 中的一项数值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlackboardValue {
+    pub key: String,
+    pub value: f64,
+    #[serde(rename = "valueStr", default, skip_serializing_if = "Option::is_none")]
+    pub value_str: Option<String>,
+}
+
+/// 模组升级的天赋
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleTalent {
+    #[serde(rename = "talentIndex")]
+    pub talent_index: i32,
+    pub name: Option<String>,
+    /// upgradeDescription 优先，缺失时为 description
+    pub description: Option<String>,
+    #[serde(rename = "requiredPotentialRank")]
+    pub required_potential_rank: i32,
+    pub blackboard: Vec<BlackboardValue>,
+}
+
+/// 模组的一个等级（1-3）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleStage {
+    pub level: i32,
+    pub attributes: Vec<BlackboardValue>,
+    /// 特性追加或覆盖的描述
+    #[serde(rename = "traitDescriptions")]
+    pub trait_descriptions: Vec<String>,
+    pub talents: Vec<ModuleTalent>,
+}
+
+/// 干员的一个模组；原始模组（ORIGINAL）没有战斗数据，stages 为空
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterModule {
+    #[serde(rename = "moduleId")]
+    pub module_id: String,
+    pub name: String,
+    /// 如 GUA-X / GUA-Y，原始模组为 ORIGINAL
+    #[serde(rename = "typeName")]
+    pub type_name: String,
+    #[serde(rename = "isOriginal")]
+    pub is_original: bool,
+    pub stages: Vec<ModuleStage>,
+    /// 解锁条件；原始模组为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unlock: Option<ModuleUnlock>,
+}
+
+/// 模组解锁任务
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleMission {
+    #[serde(rename = "missionId")]
+    pub mission_id: String,
+    /// missions 中的任务描述，缺失时为 None
+    pub description: Option<String>,
+}
+
+/// 模组的解锁条件与各等级的材料消耗
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleUnlock {
+    /// 所需精英阶段（0-2）
+    #[serde(rename = "evolvePhase")]
+    pub evolve_phase: i32,
+    pub level: i32,
+    /// 所需信赖（百分比），未要求时为 None
+    pub favor: Option<i32>,
+    pub missions: Vec<ModuleMission>,
+    /// level 为模组等级
+    #[serde(rename = "itemCosts")]
+    pub item_costs: Vec<LevelCost>,
+    /// 例如「精二60级、信赖100、完成2个任务」
+    pub summary: String,
+}
+
+/// 一项材料消耗；item_table 中没有该物品时名称为物品 id
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemCost {
+    #[serde(rename = "itemId")]
+    pub item_id: String,
+    #[serde(rename = "itemName")]
+    pub item_name: String,
+    pub count: i32,
+}
+
+/// 升到某一等级所需的材料（精英阶段、技能等级或专精等级）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelCost {
+    pub level: i32,
+    pub costs: Vec<ItemCost>,
+}
+
+/// 单个技能的专精 1-3 消耗
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkillMasteryCost {
+    #[serde(rename = "skillId")]
+    pub skill_id: String,
+    #[serde(rename = "skillName")]
+    pub skill_name: Option<String>,
+    pub masteries: Vec<LevelCost>,
+}
+
+/// 干员的精英化、技能升级与专精消耗
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterUpgradeCosts {
+    #[serde(rename = "charId")]
+    pub char_id: String,
+    /// level 为目标精英阶段（1、2）
+    pub elite: Vec<LevelCost>,
+    /// level 为目标技能等级（2-7）
+    #[serde(rename = "skillLevels")]
+    pub skill_levels: Vec<LevelCost>,
+    pub masteries: Vec<SkillMasteryCost>,
+}
+
+/// skin_table 中干员的一套立绘（含精英阶段立绘与商店时装）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkinInfo {
+    #[serde(rename = "skinId")]
+    pub skin_id: String,
+    #[serde(rename = "skinName")]
+    pub skin_name: Option<String>,
+    /// 如 ILLUST_0 / ILLUST_2 为精英阶段立绘，其余为时装系列
+    #[serde(rename = "skinGroupId")]
+    pub skin_group_id: Option<String>,
+    #[serde(rename = "skinGroupName")]
+    pub skin_group_name: Option<String>,
+    #[serde(rename = "avatarId")]
+    pub avatar_id: Option<String>,
+    #[serde(rename = "portraitId")]
+    pub portrait_id: Option<String>,
+    #[serde(rename = "illustId")]
+    pub illust_id: Option<String>,
+    #[serde(rename = "sortId")]
+    pub sort_id: Option<i32>,
+    /// 上架时间（Unix 秒），精英阶段立绘通常为空
+    #[serde(rename = "getTime")]
+    pub get_time: Option<i64>,
+    /// 画师（displaySkin.drawerList）
+    pub drawers: Vec<String>,
+}
+
+/// 画师及其为该干员绘制的立绘/皮肤
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArtistCredit {
+    pub name: String,
+    #[serde(rename = "skinIds")]
+    pub skin_ids: Vec<String>,
+}
+
+/// 某一语言的全部配音演员（合并默认语音与皮肤语音）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CvCredit {
+    #[serde(rename = "langType")]
+    pub lang_type: String,
+    #[serde(rename = "langName")]
+    pub lang_name: String,
+    #[serde(rename = "cvNames")]
+    pub cv_names: Vec<String>,
+}
+
+/// 干员的画师与配音汇总，均按首次出现顺序去重
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterCredits {
+    #[serde(rename = "charId")]
+    pub char_id: String,
+    pub artists: Vec<ArtistCredit>,
+    #[serde(rename = "voiceActors")]
+    pub voice_actors: Vec<CvCredit>,
+}
+
+/// 画师参与绘制的一名干员
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArtistWork {
+    #[serde(rename = "charId")]
+    pub char_id: String,
+    /// character_table 中的名称，缺失时为 None
+    #[serde(rename = "charName")]
+    pub char_name: Option<String>,
+    #[serde(rename = "skinIds")]
+    pub skin_ids: Vec<String>,
+}
+
+/// 天赋（取最高潜能/精英阶段的候选）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TalentInfo {
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+/// 干员的召唤物（displayTokenDict 等引用的 token 条目）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterToken {
+    #[serde(rename = "tokenId")]
+    pub token_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// 特性描述：trait 覆盖描述优先，否则为 description
+    #[serde(rename = "traitDescription")]
+    pub trait_description: Option<String>,
+    pub talents: Vec<TalentInfo>,
+    pub phases: Vec<PhaseAttributes>,
+}
+
+/// 技能 id 与名称（skill_table 首级的 name）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkillInfo {
+    #[serde(rename = "skillId")]
+    pub skill_id: String,
+    #[serde(rename = "skillName")]
+    pub skill_name: Option<String>,
+}
+
+/// 干员的一种形态（原版或 char_patch_table 中的其他形态），各自带有技能与天赋
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterForm {
+    #[serde(rename = "charId")]
+    pub char_id: String,
+    pub name: String,
+    pub profession: Option<String>,
+    #[serde(rename = "isBase")]
+    pub is_base: bool,
+    pub skills: Vec<SkillInfo>,
+    pub talents: Vec<TalentInfo>,
+}
+
+/// handbook_team_table 中的势力（国家、组织、小队），附带所属干员数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TeamPowerInfo {
+    #[serde(rename = "powerId")]
+    pub power_id: String,
+    #[serde(rename = "powerName")]
+    pub power_name: String,
+    #[serde(rename = "powerCode")]
+    pub power_code: Option<String>,
+    /// 0 为国家，1 为组织，2 为小队
+    #[serde(rename = "powerLevel")]
+    pub power_level: i64,
+    pub color: Option<String>,
+    #[serde(rename = "orderNum")]
+    pub order_num: i64,
+    #[serde(rename = "isLimited")]
+    pub is_limited: bool,
+    /// nationId / groupId / teamId 任一指向该势力的干员数
+    #[serde(rename = "operatorCount")]
+    pub operator_count: usize,
+}
+
+/// uniequip_table.subProfDict 中的职业分支，附带所属干员数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubProfessionInfo {
+    #[serde(rename = "subProfessionId")]
+    pub sub_profession_id: String,
+    #[serde(rename = "subProfessionName")]
+    pub sub_profession_name: String,
+    /// subProfessionCatagory，对应所属职业的序号
+    pub category: Option<i64>,
+    #[serde(rename = "operatorCount")]
+    pub operator_count: usize,
+}
+
+/// 干员总览中的一项计数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RosterCount {
+    pub key: String,
+    pub count: usize,
+}
+
+/// 干员总览统计；依赖的表缺失时对应的关联统计为 None
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RosterStats {
+    pub total: usize,
+    /// 星级从高到低
+    #[serde(rename = "byRarity")]
+    pub by_rarity: Vec<RosterCount>,
+    /// 以下均按数量降序
+    #[serde(rename = "byProfession")]
+    pub by_profession: Vec<RosterCount>,
+    #[serde(rename = "bySubProfession")]
+    pub by_sub_profession: Vec<RosterCount>,
+    #[serde(rename = "byNation")]
+    pub by_nation: Vec<RosterCount>,
+    /// 拥有非原始模组的干员数（uniequip_table）
+    #[serde(rename = "withModules")]
+    pub with_modules: Option<usize>,
+    /// 拥有干员密录的干员数（handbook_info_table）
+    #[serde(rename = "withMemories")]
+    pub with_memories: Option<usize>,
+    /// 拥有默认立绘以外皮肤的干员数（skin_table）
+    #[serde(rename = "withExtraSkins")]
+    pub with_extra_skins: Option<usize>,
+}
+
+/// 干员在某一语言数据中的名称
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocalizedName {
+    /// 语言目录，如 zh_CN / en_US / ja_JP
+    pub language: String,
+    pub name: String,
+    pub appellation: Option<String>,
+}
+
+/// 阅读器的上一篇/下一篇信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoryNeighbors {
+    pub prev: Option<StoryEntry>,
+    pub next: Option<StoryEntry>,
+    #[serde(rename = "groupName")]
+    pub group_name: String,
+    /// 当前剧情在分组中的位置（从 1 开始）
+    pub position: usize,
+    pub total: usize,
+}
+
+/// 剧情树中的一个分组（章节、活动、干员等）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoryTreeGroup {
+    pub name: String,
+    pub count: usize,
+    pub stories: Vec<StoryEntry>,
+}
+
+/// 剧情树的顶层分类节点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoryTreeNode {
+    pub id: String,
+    pub name: String,
+    pub count: usize,
+    pub groups: Vec<StoryTreeGroup>,
+}
+
+/// 所有分类的完整剧情树，treeVersion 不变时前端可直接使用缓存
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoryTree {
+    #[serde(rename = "treeVersion")]
+    pub tree_version: String,
+    pub total: usize,
+    pub nodes: Vec<StoryTreeNode>,
+}
+
+/// 单篇剧情的阅读进度
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadingProgress {
+    #[serde(rename = "storyId")]
+    pub story_id: String,
+    #[serde(rename = "segmentIndex")]
+    pub segment_index: i64,
+    #[serde(rename = "charOffset")]
+    pub char_offset: i64,
+    /// 0-100
+    pub percent: f64,
+    /// 毫秒时间戳
+    #[serde(rename = "updatedAt")]
+    pub updated_at: i64,
+}
+
+/// 剧情片段书签
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub id: i64,
+    #[serde(rename = "storyId")]
+    pub story_id: String,
+    #[serde(rename = "segmentIndex")]
+    pub segment_index: i64,
+    pub excerpt: String,
+    pub note: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: i64,
+    #[serde(rename = "storyName")]
+    pub story_name: Option<String>,
+    pub category: Option<String>,
+}
+
+/// 收藏类型：单篇剧情或整个分组
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FavoriteKind {
+    Story,
+    Group,
+}
+
+impl FavoriteKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FavoriteKind::Story => "story",
+            FavoriteKind::Group => "group",
+        }
+    }
+}
+
+/// 收藏列表项：剧情收藏附带 StoryEntry，分组收藏附带分组名称与剧情数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FavoriteItem {
+    pub kind: FavoriteKind,
+    pub key: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+    pub story: Option<StoryEntry>,
+    #[serde(rename = "groupName")]
+    pub group_name: Option<String>,
+    #[serde(rename = "storyCount")]
+    pub story_count: Option<usize>,
+}
+
+/// 最近阅读的剧情
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentStory {
+    pub story: StoryEntry,
+    #[serde(rename = "lastOpenedAt")]
+    pub last_opened_at: i64,
+    /// 累计阅读时长（秒），前端未上报时为 0
+    #[serde(rename = "readSeconds")]
+    pub read_seconds: i64,
+}
+
+/// 启动页「继续阅读」卡片
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContinueReading {
+    pub story: StoryEntry,
+    pub progress: ReadingProgress,
+    /// 所在分组与前后篇；不在导航分组中的剧情为 None
+    pub neighbors: Option<StoryNeighbors>,
+}
+
+/// 解锁前置剧情链中的一环；story 为 None 表示该 id 在当前数据中不存在
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoryDependency {
+    #[serde(rename = "storyId")]
+    pub story_id: String,
+    pub story: Option<StoryEntry>,
+    /// 分类标签，例如「主线 | 黑暗时代·上」
+    pub category: Option<String>,
+}
+
+/// 阅读顺序指引中的条目类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadingOrderKind {
+    Story,
+    /// 打关提示，位于行动前与行动后之间
+    StageMarker,
+}
+
+/// 主线章节的阅读顺序：行动前 → 打关 → 行动后，幕间按 story_sort 穿插
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadingOrderItem {
+    pub kind: ReadingOrderKind,
+    pub story: Option<StoryEntry>,
+    #[serde(rename = "stageCode")]
+    pub stage_code: Option<String>,
+}
+
+/// 「接下来读什么」推荐：与当前剧情有共同说话人的其他剧情
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelatedStory {
+    pub story: StoryEntry,
+    /// 共同说话人按稀有度（逆文档频率）加权求和
+    pub score: f64,
+    /// 共同说话人（规范化名称），越稀有越靠前
+    #[serde(rename = "sharedSpeakers")]
+    pub shared_speakers: Vec<String>,
+}
+
+/// 「手气不错」的筛选条件，均为可选
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RandomStoryFilter {
+    /// 索引分类类型（MAINLINE、SIDESTORY、STORY_COLLECTION、ACTIVITY 等），为空时不限；
+    /// 旧值 MINI_ACTIVITY 等同于 STORY_COLLECTION 与 ACTIVITY
+    #[serde(rename = "entryTypes", default)]
+    pub entry_types: Option<Vec<String>>,
+    /// 仅抽取未读剧情
+    #[serde(rename = "unreadOnly", default)]
+    pub unread_only: bool,
+    /// 最少字数，依赖索引中缓存的统计
+    #[serde(rename = "minWordCount", default)]
+    pub min_word_count: Option<usize>,
+}
+
+/// 随机抽取的剧情及其所在分组
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RandomStory {
+    pub story: StoryEntry,
+    /// 分类标签，例如「主线 | 黑暗时代·上」
+    pub category: String,
+    /// 所在分组与前后篇；不在导航分组中的剧情为 None
+    pub neighbors: Option<StoryNeighbors>,
+}
+
+/// 分组阅读统计，用于列表中的「12/15 已读」
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupReadCount {
+    #[serde(rename = "groupKey")]
+    pub group_key: String,
+    pub read: usize,
+    pub total: usize,
+}
+
+/// 单篇剧情统计；字数按非空白字符计
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoryStats {
+    #[serde(rename = "storyId")]
+    pub story_id: String,
+    #[serde(rename = "wordCount")]
+    pub word_count: usize,
+    #[serde(rename = "dialogueCount")]
+    pub dialogue_count: usize,
+    #[serde(rename = "narrationCount")]
+    pub narration_count: usize,
+    #[serde(rename = "decisionCount")]
+    pub decision_count: usize,
+    #[serde(rename = "speakerCount")]
+    pub speaker_count: usize,
+    #[serde(rename = "estimatedMinutes")]
+    pub estimated_minutes: usize,
+    /// 开场字幕/动画文字中首个泰拉历日期
+    #[serde(rename = "terraDate", default, skip_serializing_if = "Option::is_none")]
+    pub terra_date: Option<TerraDate>,
+}
+
+/// 泰拉历日期，允许只有年份或年月
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TerraDate {
+    pub year: i32,
+    pub month: Option<u8>,
+    pub day: Option<u8>,
+}
+
+/// 带泰拉历日期的剧情
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerraDatedStory {
+    pub story: StoryEntry,
+    pub date: TerraDate,
+}
+
+/// 按泰拉历排列的剧情时间线，未识别出日期的剧情单独列出
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerraTimeline {
+    pub dated: Vec<TerraDatedStory>,
+    pub undated: Vec<StoryEntry>,
+}
+
+/// 分组统计汇总
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupStats {
+    #[serde(rename = "groupKey")]
+    pub group_key: String,
+    #[serde(rename = "storyCount")]
+    pub story_count: usize,
+    #[serde(rename = "wordCount")]
+    pub word_count: usize,
+    #[serde(rename = "dialogueCount")]
+    pub dialogue_count: usize,
+    #[serde(rename = "narrationCount")]
+    pub narration_count: usize,
+    #[serde(rename = "decisionCount")]
+    pub decision_count: usize,
+    #[serde(rename = "estimatedMinutes")]
+    pub estimated_minutes: usize,
+}
+
+/// 时间线中的一个年份；year 为 None 表示开放时间未知
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChronologyYear {
+    pub year: Option<i32>,
+    pub entries: Vec<Activity>,
+}
+
+/// 打开剧情时一次性返回的详情；可选部分读取失败时为 None
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoryDetail {
+    pub entry: StoryEntry,
+    pub synopsis: Option<String>,
+    pub stats: Option<StoryStats>,
+    pub progress: Option<ReadingProgress>,
+    pub neighbors: Option<StoryNeighbors>,
+    /// 直接前置剧情（storyDependence）的名称；无前置或无法解析时为 None
+    #[serde(
+        rename = "dependenceName",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub dependence_name: Option<String>,
+    /// 解锁所需关卡（已解析编号与名称），没有前置关卡时为 None
+    #[serde(
+        rename = "requiredStages",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub required_stages: Option<Vec<ResolvedStage>>,
+}
@@ -15,6 +15,11 @@ lazy_static! {
         Regex::new(r"(?i)<p[^>]*>").expect("invalid paragraph tag regex");
 }
 
+/// 剧情文本中代表玩家称呼的占位符
+const NICKNAME_PLACEHOLDER: &str = "{@nickname}";
+/// 未设置称呼时的默认替换
+pub const DEFAULT_NICKNAME: &str = "博士";
+
 pub fn parse_story_text(content: &str) -> ParsedStoryContent {
     let mut segments = Vec::new();
 
@@ -40,6 +45,11 @@ pub fn parse_story_text(content: &str) -> ParsedStoryContent {
     ParsedStoryContent { segments }
 }
 
+/// 解析剧情文本，并用自定义称呼替换 `{@nickname}`
+pub fn parse_story_text_with_nickname(content: &str, nickname: &str) -> ParsedStoryContent {
+    parse_story_text(&content.replace(NICKNAME_PLACEHOLDER, nickname))
+}
+
 fn parse_command_line(line: &str) -> Option<StorySegment> {
     let end = line.find(']')?;
     let inside = &line[1..end];
@@ -289,7 +299,7 @@ fn clean_text(text: &str) -> String {
         .replace('\u{00A0}', " ");
     cleaned = PARAGRAPH_TAG_RE.replace_all(&cleaned, "\n").to_string();
     cleaned = GENERIC_TAG_RE.replace_all(&cleaned, "").to_string();
-    cleaned = cleaned.replace("{@nickname}", "博士");
+    cleaned = cleaned.replace(NICKNAME_PLACEHOLDER, DEFAULT_NICKNAME);
     cleaned = cleaned.trim().to_string();
 
     if cleaned.contains('\n') {
@@ -605,4 +615,28 @@ mod tests {
             _ => panic!("Expected narration segment"),
         }
     }
+
+    #[test]
+    fn test_parse_with_nickname() {
+        let content = r#"[name="凯尔希"]  {@nickname}，你醒了。
+[Decision(options="{@nickname}在这里;离开", values="1;2")]"#;
+
+        let result = parse_story_text(content);
+        match &result.segments[0] {
+            StorySegment::Dialogue { text, .. } => assert_eq!(text, "博士，你醒了。"),
+            _ => panic!("Expected dialogue segment"),
+        }
+
+        let result = parse_story_text_with_nickname(content, "阿米娅的朋友");
+        match &result.segments[0] {
+            StorySegment::Dialogue { text, .. } => assert_eq!(text, "阿米娅的朋友，你醒了。"),
+            _ => panic!("Expected dialogue segment"),
+        }
+        match &result.segments[1] {
+            StorySegment::Decision { options, .. } => {
+                assert_eq!(options[0], "阿米娅的朋友在这里");
+            }
+            _ => panic!("Expected decision segment"),
+        }
+    }
 }
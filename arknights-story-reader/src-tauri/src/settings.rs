@@ -0,0 +1,501 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::logging::{parse_level, DEFAULT_LOG_LEVEL};
+use crate::models::{ArchiveFormat, DataSourceProfile};
+
+/// 内置的数据源：国服使用 Kengxxiao/ArknightsGameData，海外服使用 YoStar 数据仓库
+pub fn builtin_data_sources() -> Vec<DataSourceProfile> {
+    let profile =
+        |id: &str, name: &str, repo: &str, branch: &str, language: &str| DataSourceProfile {
+            id: id.to_string(),
+            name: name.to_string(),
+            repo: repo.to_string(),
+            branch: branch.to_string(),
+            language: language.to_string(),
+        };
+    vec![
+        profile(
+            "cn",
+            "国服（简体中文）",
+            "Kengxxiao/ArknightsGameData",
+            "master",
+            "zh_CN",
+        ),
+        profile(
+            "en",
+            "Global (English)",
+            "Kengxxiao/ArknightsGameData_YoStar",
+            "main",
+            "en_US",
+        ),
+        profile(
+            "jp",
+            "日本版（日本語）",
+            "Kengxxiao/ArknightsGameData_YoStar",
+            "main",
+            "ja_JP",
+        ),
+        profile(
+            "kr",
+            "한국 서버 (한국어)",
+            "Kengxxiao/ArknightsGameData_YoStar",
+            "main",
+            "ko_KR",
+        ),
+    ]
+}
+
+impl Default for DataSourceProfile {
+    fn default() -> Self {
+        builtin_data_sources()
+            .into_iter()
+            .next()
+            .expect("builtin data sources must not be empty")
+    }
+}
+
+/// 持久化在 app_data_dir/settings.json 中的应用设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppSettings {
+    #[serde(rename = "dataSource", default)]
+    pub data_source: DataSourceProfile,
+    #[serde(rename = "archiveFormat", default)]
+    pub archive_format: ArchiveFormat,
+    /// 下载时超过该秒数没有收到任何数据即判定为停滞
+    #[serde(rename = "stallTimeoutSecs", default = "default_stall_timeout_secs")]
+    pub stall_timeout_secs: u64,
+    /// 多线程解压 ZIP；遇到问题时可关闭以退回单线程解压
+    #[serde(rename = "parallelExtraction", default = "default_true")]
+    pub parallel_extraction: bool,
+    /// 启动后在后台预先加载常用数据表；低内存设备可关闭
+    #[serde(rename = "prewarmCache", default = "default_true")]
+    pub prewarm_cache: bool,
+    /// 全文索引包含被隐藏（防剧透）的剧情；修改后需重建索引
+    #[serde(rename = "includeHiddenInSearch", default)]
+    pub include_hidden_in_search: bool,
+    /// 日志级别：error、warn、info、debug、trace 或 off
+    #[serde(rename = "logLevel", default = "default_log_level")]
+    pub log_level: String,
+    /// 阅读界面中替换「博士」的称呼，None 时保持原文
+    #[serde(default)]
+    pub nickname: Option<String>,
+    /// 启动时自动检查数据更新
+    #[serde(rename = "autoCheckUpdate", default = "default_true")]
+    pub auto_check_update: bool,
+    /// 单次搜索最多返回的结果数
+    #[serde(rename = "searchResultLimit", default = "default_search_result_limit")]
+    pub search_result_limit: usize,
+    /// 同步或导入完成后自动重建全文索引
+    #[serde(rename = "autoIndex", default)]
+    pub auto_index: bool,
+    /// 下载数据包使用的镜像地址（替换 codeload.github.com），None 时直连
+    #[serde(rename = "downloadMirror", default)]
+    pub download_mirror: Option<String>,
+}
+
+/// update_settings 的部分更新：为 None 的字段保持不变；
+/// nickname 与 downloadMirror 传空字符串表示清除。数据源通过 set_data_source 切换
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SettingsPatch {
+    #[serde(rename = "archiveFormat", default)]
+    pub archive_format: Option<ArchiveFormat>,
+    #[serde(rename = "stallTimeoutSecs", default)]
+    pub stall_timeout_secs: Option<u64>,
+    #[serde(rename = "parallelExtraction", default)]
+    pub parallel_extraction: Option<bool>,
+    #[serde(rename = "prewarmCache", default)]
+    pub prewarm_cache: Option<bool>,
+    #[serde(rename = "includeHiddenInSearch", default)]
+    pub include_hidden_in_search: Option<bool>,
+    #[serde(rename = "logLevel", default)]
+    pub log_level: Option<String>,
+    #[serde(default)]
+    pub nickname: Option<String>,
+    #[serde(rename = "autoCheckUpdate", default)]
+    pub auto_check_update: Option<bool>,
+    #[serde(rename = "searchResultLimit", default)]
+    pub search_result_limit: Option<usize>,
+    #[serde(rename = "autoIndex", default)]
+    pub auto_index: Option<bool>,
+    #[serde(rename = "downloadMirror", default)]
+    pub download_mirror: Option<String>,
+}
+
+fn non_empty(value: String) -> Option<String> {
+    let trimmed = value.trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+impl SettingsPatch {
+    /// 校验并写入 settings；任一字段无效时返回错误且不做任何修改
+    pub fn apply_to(self, settings: &mut AppSettings) -> Result<(), String> {
+        if self.stall_timeout_secs == Some(0) {
+            return Err("停滞超时必须大于 0 秒".to_string());
+        }
+        if let Some(limit) = self.search_result_limit {
+            if !(1..=MAX_SEARCH_RESULT_LIMIT).contains(&limit) {
+                return Err(format!(
+                    "搜索结果数应在 1 到 {} 之间",
+                    MAX_SEARCH_RESULT_LIMIT
+                ));
+            }
+        }
+        let log_level = match self.log_level {
+            Some(level) => {
+                parse_level(&level)?;
+                Some(level.trim().to_ascii_lowercase())
+            }
+            None => None,
+        };
+        // 称呼会写回剧情指令中再解析，不能包含指令与标签使用的符号
+        if let Some(nickname) = &self.nickname {
+            if nickname
+                .chars()
+                .any(|c| NICKNAME_FORBIDDEN_CHARS.contains(&c))
+            {
+                return Err(format!(
+                    "称呼不能包含以下字符: {}",
+                    NICKNAME_FORBIDDEN_CHARS.iter().collect::<String>()
+                ));
+            }
+        }
+        let download_mirror = match self.download_mirror.map(non_empty) {
+            Some(Some(mirror)) if !mirror.starts_with("https://") => {
+                return Err(format!("镜像地址必须以 https:// 开头: {}", mirror));
+            }
+            other => other,
+        };
+
+        if let Some(format) = self.archive_format {
+            settings.archive_format = format;
+        }
+        if let Some(secs) = self.stall_timeout_secs {
+            settings.stall_timeout_secs = secs;
+        }
+        if let Some(parallel) = self.parallel_extraction {
+            settings.parallel_extraction = parallel;
+        }
+        if let Some(prewarm) = self.prewarm_cache {
+            settings.prewarm_cache = prewarm;
+        }
+        if let Some(include) = self.include_hidden_in_search {
+            settings.include_hidden_in_search = include;
+        }
+        if let Some(level) = log_level {
+            settings.log_level = level;
+        }
+        if let Some(nickname) = self.nickname {
+            settings.nickname = non_empty(nickname);
+        }
+        if let Some(auto_check) = self.auto_check_update {
+            settings.auto_check_update = auto_check;
+        }
+        if let Some(limit) = self.search_result_limit {
+            settings.search_result_limit = limit;
+        }
+        if let Some(auto_index) = self.auto_index {
+            settings.auto_index = auto_index;
+        }
+        if let Some(mirror) = download_mirror {
+            settings.download_mirror = mirror;
+        }
+        Ok(())
+    }
+}
+
+fn default_stall_timeout_secs() -> u64 {
+    60
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_log_level() -> String {
+    DEFAULT_LOG_LEVEL.to_string()
+}
+
+fn default_search_result_limit() -> usize {
+    500
+}
+
+const MAX_SEARCH_RESULT_LIMIT: usize = 5000;
+
+const NICKNAME_FORBIDDEN_CHARS: [char; 7] = ['[', ']', '"', '<', '>', '{', '}'];
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            data_source: DataSourceProfile::default(),
+            archive_format: ArchiveFormat::default(),
+            stall_timeout_secs: default_stall_timeout_secs(),
+            parallel_extraction: true,
+            prewarm_cache: true,
+            include_hidden_in_search: false,
+            log_level: default_log_level(),
+            nickname: None,
+            auto_check_update: true,
+            search_result_limit: default_search_result_limit(),
+            auto_index: false,
+            download_mirror: None,
+        }
+    }
+}
+
+impl AppSettings {
+    /// 读取设置；文件不存在或损坏时使用默认值
+    pub fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|err| {
+                tracing::warn!("Failed to parse settings, using defaults: {}", err);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// 先写临时文件再重命名，写入中途崩溃也不会留下半个 settings.json
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, content).map_err(|e| format!("Failed to write settings: {}", e))?;
+        fs::rename(&temp_path, path).map_err(|e| {
+            fs::remove_file(&temp_path).ok();
+            format!("Failed to replace settings: {}", e)
+        })
+    }
+}
+
+type SettingsListener = Box<dyn Fn(&AppSettings) + Send + Sync>;
+
+struct SettingsState {
+    path: PathBuf,
+    current: RwLock<AppSettings>,
+    /// 串行化「读取-修改-写入」，避免并发更新互相覆盖
+    write_lock: Mutex<()>,
+    listener: RwLock<Option<SettingsListener>>,
+}
+
+/// 内存中缓存的应用设置，克隆共享同一份状态；读取不访问磁盘，更新时原子写回 settings.json
+#[derive(Clone)]
+pub struct SettingsService {
+    state: Arc<SettingsState>,
+}
+
+impl SettingsService {
+    pub fn load(path: PathBuf) -> Self {
+        let current = AppSettings::load(&path);
+        Self {
+            state: Arc::new(SettingsState {
+                path,
+                current: RwLock::new(current),
+                write_lock: Mutex::new(()),
+                listener: RwLock::new(None),
+            }),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.state.path
+    }
+
+    /// 当前设置的快照
+    pub fn get(&self) -> AppSettings {
+        self.state
+            .current
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// 在最新设置上执行 mutate 并写回；mutate 返回错误时不做任何修改。成功后通知监听者
+    pub fn update(
+        &self,
+        mutate: impl FnOnce(&mut AppSettings) -> Result<(), String>,
+    ) -> Result<AppSettings, String> {
+        let _write = self
+            .state
+            .write_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut next = self.get();
+        mutate(&mut next)?;
+        next.save(&self.state.path)?;
+        *self
+            .state
+            .current
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = next.clone();
+        self.notify(&next);
+        Ok(next)
+    }
+
+    pub fn apply_patch(&self, patch: SettingsPatch) -> Result<AppSettings, String> {
+        self.update(|settings| patch.apply_to(settings))
+    }
+
+    /// settings.json 被外部替换（如导入应用数据）后重新读取
+    pub fn reload(&self) -> AppSettings {
+        let _write = self
+            .state
+            .write_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let next = AppSettings::load(&self.state.path);
+        *self
+            .state
+            .current
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = next.clone();
+        self.notify(&next);
+        next
+    }
+
+    /// 设置变更时的回调（lib.rs 中用于向前端发送 settings-changed 事件）
+    pub fn set_listener(&self, listener: impl Fn(&AppSettings) + Send + Sync + 'static) {
+        *self
+            .state
+            .listener
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Box::new(listener));
+    }
+
+    fn notify(&self, settings: &AppSettings) {
+        let listener = self
+            .state
+            .listener
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(listener) = listener.as_ref() {
+            listener(settings);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_settings_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "story_teller_settings_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("settings.json")
+    }
+
+    #[test]
+    fn missing_fields_are_filled_with_defaults() {
+        let path = temp_settings_path("defaults");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        // 旧版本写入的设置文件只有部分字段
+        fs::write(&path, r#"{"archiveFormat":"zip","prewarmCache":false}"#).unwrap();
+
+        let settings = SettingsService::load(path.clone()).get();
+        assert_eq!(settings.archive_format, ArchiveFormat::Zip);
+        assert!(!settings.prewarm_cache);
+        assert_eq!(settings.data_source.id, "cn");
+        assert_eq!(settings.stall_timeout_secs, 60);
+        assert_eq!(settings.log_level, "info");
+        assert!(settings.auto_check_update);
+        assert_eq!(settings.search_result_limit, 500);
+        assert_eq!(settings.nickname, None);
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn patch_round_trips_through_disk() {
+        let path = temp_settings_path("round_trip");
+        let service = SettingsService::load(path.clone());
+        let notified = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&notified);
+        service.set_listener(move |settings| {
+            sink.lock().unwrap().push(settings.nickname.clone());
+        });
+
+        let patch: SettingsPatch = serde_json::from_str(
+            r#"{"nickname":"  刀客塔 ","autoIndex":true,"logLevel":"DEBUG","downloadMirror":"https://mirror.example"}"#,
+        )
+        .unwrap();
+        let updated = service.apply_patch(patch).unwrap();
+        assert_eq!(updated.nickname.as_deref(), Some("刀客塔"));
+        assert!(updated.auto_index);
+        assert_eq!(updated.log_level, "debug");
+        // 未出现在补丁中的字段保持不变
+        assert!(updated.prewarm_cache);
+        assert!(!path.with_extension("json.tmp").exists());
+
+        let reloaded = SettingsService::load(path.clone()).get();
+        assert_eq!(
+            serde_json::to_value(&reloaded).unwrap(),
+            serde_json::to_value(&updated).unwrap()
+        );
+
+        // 空字符串清除可选字段；无效值整体拒绝
+        let cleared = service
+            .apply_patch(serde_json::from_str(r#"{"nickname":""}"#).unwrap())
+            .unwrap();
+        assert_eq!(cleared.nickname, None);
+        let rejected =
+            serde_json::from_str(r#"{"autoIndex":false,"searchResultLimit":0}"#).unwrap();
+        assert!(service.apply_patch(rejected).is_err());
+        assert!(service.get().auto_index);
+        assert!(service
+            .apply_patch(serde_json::from_str(r#"{"logLevel":"loud"}"#).unwrap())
+            .is_err());
+        assert!(service
+            .apply_patch(serde_json::from_str(r#"{"nickname":"[博士]"}"#).unwrap())
+            .is_err());
+
+        assert_eq!(
+            *notified.lock().unwrap(),
+            vec![Some("刀客塔".to_string()), None]
+        );
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn concurrent_updates_are_not_lost() {
+        let path = temp_settings_path("concurrent");
+        let service = SettingsService::load(path.clone());
+        let workers: Vec<_> = (0..8)
+            .map(|index| {
+                let service = service.clone();
+                std::thread::spawn(move || {
+                    service
+                        .update(|settings| {
+                            settings.stall_timeout_secs += 1;
+                            if index == 3 {
+                                settings.auto_index = true;
+                            }
+                            Ok(())
+                        })
+                        .unwrap();
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let on_disk = AppSettings::load(&path);
+        assert_eq!(on_disk.stall_timeout_secs, 68);
+        assert!(on_disk.auto_index);
+        assert_eq!(service.get().stall_timeout_secs, 68);
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,514 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::models::{ProgressEvent, TaskInfo};
+
+/// 任务被用户取消时返回的错误码
+pub const TASK_CANCELLED: &str = "CANCELLED";
+/// 所有任务共用的进度事件名，载荷为 ProgressEvent
+pub const TASK_PROGRESS_EVENT: &str = "task-progress";
+/// 任务未报告结果就结束（如线程 panic）时终止事件中的错误
+const TASK_ABORTED: &str = "任务意外中止";
+const PROGRESS_THROTTLE_INTERVAL: Duration = Duration::from_millis(80);
+
+/// 进度事件的接收方，通常把事件转发到前端
+pub type ProgressSink = Arc<dyn Fn(&ProgressEvent) + Send + Sync>;
+
+/// 任务的取消标记，克隆共享同一个标记；长循环在每次迭代时检查
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// 已取消时返回 TASK_CANCELLED，便于在循环中直接 `?`
+    pub fn check(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            return Err(TASK_CANCELLED.to_string());
+        }
+        Ok(())
+    }
+}
+
+struct TaskEntry {
+    info: TaskInfo,
+    token: CancelToken,
+}
+
+/// 正在执行的长耗时任务（同步、建索引、导入、带进度的搜索等），在 DataService 的所有克隆间共享
+///
+/// 独占任务（同步、导入、建索引等）同一时间只能有一个；非独占任务（搜索）可与任何任务并行
+#[derive(Default)]
+pub struct TaskRegistry {
+    next_id: AtomicU64,
+    tasks: Mutex<BTreeMap<u64, TaskEntry>>,
+}
+
+impl TaskRegistry {
+    fn tasks(&self) -> MutexGuard<'_, BTreeMap<u64, TaskEntry>> {
+        self.tasks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// 登记一个任务并分配 id；已有独占任务时，新的独占任务返回 `BUSY:{正在执行的任务}`
+    pub fn start(self: &Arc<Self>, kind: &str, exclusive: bool) -> Result<TaskHandle, String> {
+        let mut tasks = self.tasks();
+        if exclusive {
+            if let Some(running) = tasks.values().find(|entry| entry.info.exclusive) {
+                return Err(format!("BUSY:{}", running.info.kind));
+            }
+        }
+        // id 从 1 开始单调递增，不会复用，前端可据此区分先后两次同类任务的进度事件
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let token = CancelToken::default();
+        let handle_kind = kind.to_string();
+        tasks.insert(
+            id,
+            TaskEntry {
+                info: TaskInfo {
+                    id,
+                    kind: kind.to_string(),
+                    phase: None,
+                    current: 0,
+                    total: 0,
+                    exclusive,
+                    cancelled: false,
+                    started_at,
+                },
+                token: token.clone(),
+            },
+        );
+        Ok(TaskHandle {
+            id,
+            kind: handle_kind,
+            token,
+            registry: Arc::clone(self),
+            progress: Mutex::new(TaskProgress::default()),
+        })
+    }
+
+    /// 按启动顺序列出正在执行的任务
+    pub fn list(&self) -> Vec<TaskInfo> {
+        self.tasks()
+            .values()
+            .map(|entry| entry.info.clone())
+            .collect()
+    }
+
+    /// 请求取消任务；任务已结束或 id 不存在时返回 false
+    pub fn cancel(&self, id: u64) -> bool {
+        let mut tasks = self.tasks();
+        let Some(entry) = tasks.get_mut(&id) else {
+            return false;
+        };
+        entry.token.cancel();
+        entry.info.cancelled = true;
+        true
+    }
+
+    /// 取消指定类型的全部任务，返回被取消的数量
+    pub fn cancel_kind(&self, kind: &str) -> usize {
+        let mut tasks = self.tasks();
+        let mut cancelled = 0;
+        for entry in tasks.values_mut().filter(|entry| entry.info.kind == kind) {
+            entry.token.cancel();
+            entry.info.cancelled = true;
+            cancelled += 1;
+        }
+        cancelled
+    }
+
+    /// 正在执行的独占任务类型
+    pub fn exclusive_task(&self) -> Option<String> {
+        self.tasks()
+            .values()
+            .find(|entry| entry.info.exclusive)
+            .map(|entry| entry.info.kind.clone())
+    }
+
+    fn update(&self, id: u64, phase: &str, current: usize, total: usize) {
+        if let Some(entry) = self.tasks().get_mut(&id) {
+            entry.info.phase = Some(phase.to_string());
+            entry.info.current = current;
+            entry.info.total = total;
+        }
+    }
+}
+
+/// 进度事件节流：同一阶段内最多每隔 min_interval 发送一次，阶段切换或到达 total 时立即发送
+///
+/// 解压、扫描等循环每次迭代都会产生进度，直接发送会在 Android 上造成大量 IPC 消息拖慢 WebView
+struct ProgressThrottle {
+    min_interval: Duration,
+    last_emit: Option<Instant>,
+    last_phase: String,
+}
+
+impl Default for ProgressThrottle {
+    fn default() -> Self {
+        Self::with_interval(PROGRESS_THROTTLE_INTERVAL)
+    }
+}
+
+impl ProgressThrottle {
+    fn with_interval(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            last_emit: None,
+            last_phase: String::new(),
+        }
+    }
+
+    fn should_emit(&mut self, phase: &str, current: usize, total: usize) -> bool {
+        self.should_emit_at(Instant::now(), phase, current, total)
+    }
+
+    fn should_emit_at(&mut self, now: Instant, phase: &str, current: usize, total: usize) -> bool {
+        let finished = current >= total;
+        let phase_changed = self.last_phase != phase;
+        let due = match self.last_emit {
+            Some(last) => now.duration_since(last) >= self.min_interval,
+            None => true,
+        };
+        if !(finished || phase_changed || due) {
+            return false;
+        }
+        self.last_emit = Some(now);
+        if phase_changed {
+            self.last_phase = phase.to_string();
+        }
+        true
+    }
+}
+
+/// 任务的进度事件状态：最近的阶段用于终止事件，finished 保证终止事件只发送一次
+#[derive(Default)]
+struct TaskProgress {
+    sink: Option<ProgressSink>,
+    throttle: ProgressThrottle,
+    phase: String,
+    current: usize,
+    total: usize,
+    finished: bool,
+}
+
+/// 任务的占用凭证，drop 时从注册表中移除
+///
+/// 设置了进度接收方的任务总会以一个终止事件（done 或 error）结束：
+/// 通过 run 执行时按结果发送，否则在 drop 时补发错误事件
+pub struct TaskHandle {
+    id: u64,
+    kind: String,
+    token: CancelToken,
+    registry: Arc<TaskRegistry>,
+    progress: Mutex<TaskProgress>,
+}
+
+impl TaskHandle {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn token(&self) -> &CancelToken {
+        &self.token
+    }
+
+    pub fn check_cancelled(&self) -> Result<(), String> {
+        self.token.check()
+    }
+
+    /// 记录最新进度，供 list_tasks 查询
+    pub fn report(&self, phase: &str, current: usize, total: usize) {
+        self.registry.update(self.id, phase, current, total);
+    }
+
+    /// 把进度事件发送到 sink（统一的 task-progress 事件）
+    pub fn with_progress(self, sink: ProgressSink) -> Self {
+        self.progress_state().sink = Some(sink);
+        self
+    }
+
+    /// 执行任务主体，并按结果发送终止事件；主体内已调用 finish 时不再重复发送
+    pub fn run<T>(&self, body: impl FnOnce(&TaskHandle) -> Result<T, String>) -> Result<T, String> {
+        let result = body(self);
+        match &result {
+            Ok(_) => self.finish("完成", 1, 1, "完成"),
+            Err(err) => self.fail(err),
+        }
+        result
+    }
+
+    /// 记录并发送进度，经过节流；返回事件是否被发送，供旧事件的适配层保持同样的节奏
+    pub fn progress(&self, phase: &str, current: usize, total: usize, message: &str) -> bool {
+        self.report(phase, current, total);
+        let mut state = self.progress_state();
+        if state.finished || !state.throttle.should_emit(phase, current, total) {
+            return false;
+        }
+        state.phase = phase.to_string();
+        state.current = current;
+        state.total = total;
+        self.send(&mut state, message, true, None);
+        true
+    }
+
+    /// 发送成功的终止事件（done = true）；已结束的任务忽略
+    pub fn finish(&self, phase: &str, current: usize, total: usize, message: &str) {
+        self.report(phase, current, total);
+        let mut state = self.progress_state();
+        if state.finished {
+            return;
+        }
+        state.phase = phase.to_string();
+        state.current = current;
+        state.total = total;
+        self.send(&mut state, message, false, None);
+    }
+
+    /// 发送失败的终止事件，phase 与进度沿用最后一次上报的值；已结束的任务忽略
+    pub fn fail(&self, error: &str) {
+        let mut state = self.progress_state();
+        if state.finished {
+            return;
+        }
+        self.send(&mut state, error, false, Some(error.to_string()));
+    }
+
+    fn send(&self, state: &mut TaskProgress, message: &str, running: bool, error: Option<String>) {
+        if !running {
+            state.finished = true;
+        }
+        let Some(sink) = state.sink.as_ref() else {
+            return;
+        };
+        sink(&ProgressEvent {
+            task_id: self.id,
+            task_kind: self.kind.clone(),
+            phase: state.phase.clone(),
+            current: state.current,
+            total: state.total,
+            message: message.to_string(),
+            done: !running && error.is_none(),
+            error,
+        });
+    }
+
+    fn progress_state(&self) -> MutexGuard<'_, TaskProgress> {
+        self.progress
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Drop for TaskHandle {
+    fn drop(&mut self) {
+        let error = if self.token.is_cancelled() {
+            TASK_CANCELLED
+        } else {
+            TASK_ABORTED
+        };
+        self.fail(error);
+        self.registry.tasks().remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocates_unique_increasing_ids() {
+        let registry = Arc::new(TaskRegistry::default());
+        let first = registry.start("search", false).unwrap();
+        let second = registry.start("search", false).unwrap();
+        assert_eq!(first.id(), 1);
+        assert_eq!(second.id(), 2);
+
+        // 结束的任务 id 不会被复用
+        drop(first);
+        drop(second);
+        assert_eq!(registry.start("search", false).unwrap().id(), 3);
+    }
+
+    #[test]
+    fn lists_running_tasks_with_progress() {
+        let registry = Arc::new(TaskRegistry::default());
+        let sync = registry.start("sync", true).unwrap();
+        let search = registry.start("search", false).unwrap();
+        sync.report("下载", 40, 100);
+
+        let tasks = registry.list();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].id, sync.id());
+        assert_eq!(tasks[0].kind, "sync");
+        assert_eq!(tasks[0].phase.as_deref(), Some("下载"));
+        assert_eq!((tasks[0].current, tasks[0].total), (40, 100));
+        assert!(tasks[0].exclusive);
+        assert_eq!(tasks[1].kind, "search");
+        assert_eq!(tasks[1].phase, None);
+
+        drop(search);
+        let tasks = registry.list();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].kind, "sync");
+    }
+
+    #[test]
+    fn cancels_all_tasks_of_a_kind() {
+        let registry = Arc::new(TaskRegistry::default());
+        let first = registry.start("prewarm", false).unwrap();
+        let second = registry.start("prewarm", false).unwrap();
+        let search = registry.start("search", false).unwrap();
+
+        assert_eq!(registry.cancel_kind("prewarm"), 2);
+        assert!(first.token().is_cancelled());
+        assert!(second.token().is_cancelled());
+        assert!(!search.token().is_cancelled());
+        assert_eq!(registry.cancel_kind("index"), 0);
+    }
+
+    #[test]
+    fn enforces_one_exclusive_task_at_a_time() {
+        let registry = Arc::new(TaskRegistry::default());
+        let sync = registry.start("sync", true).unwrap();
+        assert_eq!(registry.exclusive_task().as_deref(), Some("sync"));
+        assert_eq!(registry.start("index", true).err().unwrap(), "BUSY:sync");
+        assert!(registry.start("search", false).is_ok());
+
+        drop(sync);
+        assert_eq!(registry.exclusive_task(), None);
+        assert!(registry.start("index", true).is_ok());
+    }
+
+    #[test]
+    fn cancel_signals_only_the_target_task() {
+        let registry = Arc::new(TaskRegistry::default());
+        let sync = registry.start("sync", true).unwrap();
+        let search = registry.start("search", false).unwrap();
+        let token = sync.token().clone();
+
+        assert!(registry.cancel(sync.id()));
+        assert!(token.is_cancelled());
+        assert_eq!(sync.check_cancelled().unwrap_err(), TASK_CANCELLED);
+        assert!(registry.list()[0].cancelled);
+        assert!(search.check_cancelled().is_ok());
+
+        let id = sync.id();
+        drop(sync);
+        assert!(!registry.cancel(id));
+        assert!(!registry.cancel(999));
+    }
+
+    fn collecting_sink() -> (ProgressSink, Arc<Mutex<Vec<ProgressEvent>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let collected = Arc::clone(&events);
+        let sink: ProgressSink = Arc::new(move |event: &ProgressEvent| {
+            collected.lock().unwrap().push(event.clone());
+        });
+        (sink, events)
+    }
+
+    fn terminal_events(events: &[ProgressEvent]) -> Vec<&ProgressEvent> {
+        events
+            .iter()
+            .filter(|event| event.done || event.error.is_some())
+            .collect()
+    }
+
+    #[test]
+    fn every_task_ends_with_exactly_one_terminal_event() {
+        let registry = Arc::new(TaskRegistry::default());
+
+        // 成功：主体内已发送完成事件，run 不再重复
+        let (sink, events) = collecting_sink();
+        let task = registry.start("sync", true).unwrap().with_progress(sink);
+        task.run(|task| {
+            for percent in 0..=100 {
+                task.progress("下载", percent, 100, "下载中");
+            }
+            task.finish("完成", 1, 1, "同步完成");
+            Ok(())
+        })
+        .unwrap();
+        drop(task);
+        let events = events.lock().unwrap();
+        let terminal = terminal_events(&events);
+        assert_eq!(terminal.len(), 1);
+        assert_eq!(terminal[0].message, "同步完成");
+        assert!(std::ptr::eq(terminal[0], events.last().unwrap()));
+        // 节流后远少于 101 条，但最后的 100% 一定发出
+        assert!(events.len() < 20, "{} events", events.len());
+        assert!(events
+            .iter()
+            .any(|event| event.phase == "下载" && event.current == 100));
+
+        // 失败：错误事件沿用最后的阶段
+        let (sink, events) = collecting_sink();
+        let task = registry.start("import", true).unwrap().with_progress(sink);
+        let result: Result<(), String> = task.run(|task| {
+            task.progress("解压", 30, 100, "解压中");
+            Err("CORRUPT_ARCHIVE".to_string())
+        });
+        assert!(result.is_err());
+        task.progress("解压", 40, 100, "结束后的进度被忽略");
+        drop(task);
+        let events = events.lock().unwrap();
+        let terminal = terminal_events(&events);
+        assert_eq!(terminal.len(), 1);
+        assert_eq!(terminal[0].error.as_deref(), Some("CORRUPT_ARCHIVE"));
+        assert_eq!(
+            (terminal[0].phase.as_str(), terminal[0].current),
+            ("解压", 30)
+        );
+        assert!(!terminal[0].done);
+        assert_eq!(events.len(), 2);
+
+        // 未经 run 结束（提前返回、取消）时 drop 补发错误事件
+        let (sink, events) = collecting_sink();
+        let task = registry.start("search", false).unwrap().with_progress(sink);
+        task.progress("线性扫描", 1, 10, "扫描中");
+        registry.cancel(task.id());
+        drop(task);
+        let events = events.lock().unwrap();
+        let terminal = terminal_events(&events);
+        assert_eq!(terminal.len(), 1);
+        assert_eq!(terminal[0].error.as_deref(), Some(TASK_CANCELLED));
+        assert_eq!(terminal[0].task_kind, "search");
+    }
+
+    #[test]
+    fn progress_throttle_limits_rate_but_keeps_phase_changes_and_completion() {
+        let mut throttle = ProgressThrottle::with_interval(Duration::from_millis(80));
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        assert!(throttle.should_emit_at(at(0), "解压", 1, 100));
+        assert!(!throttle.should_emit_at(at(10), "解压", 2, 100));
+        assert!(!throttle.should_emit_at(at(79), "解压", 3, 100));
+        assert!(throttle.should_emit_at(at(80), "解压", 4, 100));
+        assert!(!throttle.should_emit_at(at(100), "解压", 5, 100));
+        // 完成事件不受节流影响
+        assert!(throttle.should_emit_at(at(101), "解压", 100, 100));
+        // 阶段切换立即发送
+        assert!(throttle.should_emit_at(at(102), "索引", 0, 10));
+        assert!(!throttle.should_emit_at(at(103), "索引", 1, 10));
+
+        let emitted = (0..10_000u64)
+            .filter(|i| throttle.should_emit_at(at(200 + i / 10), "扫描", *i as usize, 10_000))
+            .count();
+        assert!(emitted <= 14, "emitted {}", emitted);
+    }
+}
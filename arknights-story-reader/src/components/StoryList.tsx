@@ -1,5 +1,5 @@
 import { useCallback, useEffect, useMemo, useState } from "react";
-import { api } from "@/services/api";
+import { api, appErrorCode } from "@/services/api";
 import type { StoryEntry } from "@/types/story";
 import { Button } from "@/components/ui/button";
 import { BookOpen, RefreshCw, Star, FileText } from "lucide-react";
@@ -372,7 +372,7 @@ export function StoryList({ onSelectStory }: StoryListProps) {
       console.error("[StoryList] 加载主线剧情失败:", errorMsg, err);
       // 未安装或超时，提示同步
       if (
-        errorMsg.includes("NOT_INSTALLED") ||
+        appErrorCode(err) === "NOT_INSTALLED" ||
         errorMsg.includes("No such file") ||
         errorMsg === "TIMEOUT"
       ) {
@@ -411,7 +411,7 @@ export function StoryList({ onSelectStory }: StoryListProps) {
     } catch (err) {
       const errorMsg = err instanceof Error ? err.message : "加载失败";
       console.error("[StoryList] 加载活动剧情失败:", errorMsg, err);
-      if (errorMsg.includes("NOT_INSTALLED") || errorMsg.includes("No such file") || errorMsg === "TIMEOUT") {
+      if (appErrorCode(err) === "NOT_INSTALLED" || errorMsg.includes("No such file") || errorMsg === "TIMEOUT") {
         setError("未安装或网络缓慢，请先同步数据");
         setSyncDialogOpen(true);
       } else {
@@ -454,7 +454,7 @@ export function StoryList({ onSelectStory }: StoryListProps) {
     } catch (err) {
       const errorMsg = err instanceof Error ? err.message : "加载失败";
       console.error("[StoryList] 加载支线剧情失败:", errorMsg, err);
-      if (errorMsg.includes("NOT_INSTALLED") || errorMsg.includes("No such file") || errorMsg === "TIMEOUT") {
+      if (appErrorCode(err) === "NOT_INSTALLED" || errorMsg.includes("No such file") || errorMsg === "TIMEOUT") {
         setError("未安装或网络缓慢，请先同步数据");
         setSyncDialogOpen(true);
       } else {
@@ -484,7 +484,7 @@ export function StoryList({ onSelectStory }: StoryListProps) {
     } catch (err) {
       const errorMsg = err instanceof Error ? err.message : "加载失败";
       console.error("[StoryList] 加载肉鸽剧情失败:", errorMsg, err);
-      if (errorMsg.includes("NOT_INSTALLED") || errorMsg.includes("No such file") || errorMsg === "TIMEOUT") {
+      if (appErrorCode(err) === "NOT_INSTALLED" || errorMsg.includes("No such file") || errorMsg === "TIMEOUT") {
         setError("未安装或网络缓慢，请先同步数据");
         setSyncDialogOpen(true);
       } else {
@@ -516,7 +516,7 @@ export function StoryList({ onSelectStory }: StoryListProps) {
     } catch (err) {
       const errorMsg = err instanceof Error ? err.message : "加载失败";
       console.error("[StoryList] 加载干员密录失败:", errorMsg, err);
-      if (errorMsg.includes("NOT_INSTALLED") || errorMsg.includes("No such file") || errorMsg === "TIMEOUT") {
+      if (appErrorCode(err) === "NOT_INSTALLED" || errorMsg.includes("No such file") || errorMsg === "TIMEOUT") {
         setError("未安装或网络缓慢，请先同步数据");
         setSyncDialogOpen(true);
       } else {
@@ -1,5 +1,5 @@
 import { useCallback, useEffect, useMemo, useState } from "react";
-import { api, type SyncProgress } from "@/services/api";
+import { api, describeError, type SyncProgress } from "@/services/api";
 
 interface UseDataSyncManagerOptions {
   active: boolean;
@@ -29,7 +29,7 @@ export function useDataSyncManager({ active, onSuccess }: UseDataSyncManagerOpti
       setHasUpdate(needUpdate);
     } catch (err) {
       console.error("[useDataSyncManager] 加载版本信息失败:", err);
-      setError((err instanceof Error ? err.message : "加载版本信息失败") ?? "加载版本信息失败");
+      setError(describeError(err, "加载版本信息失败"));
     } finally {
       setLoadingInfo(false);
     }
@@ -61,7 +61,7 @@ export function useDataSyncManager({ active, onSuccess }: UseDataSyncManagerOpti
       onSuccess?.();
       await loadVersionInfo();
     } catch (err) {
-      const message = err instanceof Error ? err.message : "同步失败";
+      const message = describeError(err, "同步失败");
       console.error("[useDataSyncManager] 同步失败:", message, err);
       setError(message);
     } finally {
@@ -104,7 +104,7 @@ export function useDataSyncManager({ active, onSuccess }: UseDataSyncManagerOpti
         onSuccess?.();
         await loadVersionInfo();
       } catch (err) {
-        const message = err instanceof Error ? err.message : "导入失败";
+        const message = describeError(err, "导入失败");
         console.error("[useDataSyncManager] 导入失败:", message, err);
         setError(message);
       } finally {
@@ -1,6 +1,62 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import type {
+  Activity,
+  Bookmark,
+  FavoriteItem,
+  FavoriteKind,
+  MemoryGroup,
+  ReadingProgress,
+  RecentStory,
+  ContinueReading,
+  GroupReadCount,
+  StoryStats,
+  StoryCharacter,
+  CharacterListFilter,
+  CharacterPage,
+  CharacterVoiceActors,
+  CharacterVoiceAssets,
+  CharacterProfile,
+  CharacterMatch,
+  CharacterFavor,
+  CharacterAttributes,
+  CharacterToken,
+  CharacterForm,
+  TeamPowerInfo,
+  SubProfessionInfo,
+  RosterStats,
+  CharacterCredits,
+  ArtistWork,
+  CharacterObtainInfo,
+  LocalizedName,
+  TaskInfo,
+  ProgressEvent,
+  DeepLinkNavigation,
+  AppSettings,
+  SettingsPatch,
+  DiskUsage,
+  DiagnosticsExport,
+  StoryCacheStats,
+  AppStatus,
+  CharacterRanges,
+  CharacterModule,
+  CharacterUpgradeCosts,
+  SkinInfo,
+  RangeGrid,
+  TerraTimeline,
+  RandomStory,
+  RandomStoryFilter,
+  RelatedStory,
+  ResolvedStage,
+  ReadingOrderItem,
+  StoryDependency,
+  GroupStats,
+  ChronologyYear,
+  AvgTagKind,
+  StoryGroup,
+  StoryDetail,
+  StoryNeighbors,
+  StoryTree,
   StoryCategory,
   Chapter,
   ParsedStoryContent,
@@ -8,9 +64,38 @@ import type {
   StoryEntry,
   StoryIndexStatus,
   SearchDebugResponse,
+  UpdateStatus,
 } from "@/types/story";
 
+/** 后端命令返回的结构化错误 */
+export interface AppError {
+  /** 稳定的错误码，如 NOT_INSTALLED / BUSY / NOT_FOUND / NETWORK */
+  code: string;
+  /** 可直接展示的说明 */
+  message: string;
+  details: Record<string, unknown> | null;
+}
+
+export const isAppError = (err: unknown): err is AppError =>
+  typeof err === "object" &&
+  err !== null &&
+  typeof (err as AppError).code === "string" &&
+  typeof (err as AppError).message === "string";
+
+// 取错误码；非后端错误（如前端超时）返回 null
+export const appErrorCode = (err: unknown): string | null =>
+  isAppError(err) ? err.code : null;
+
+// 转换为可展示的错误信息
+export const describeError = (err: unknown, fallback: string): string => {
+  if (isAppError(err)) return err.message;
+  if (err instanceof Error) return err.message;
+  if (typeof err === "string" && err) return err;
+  return fallback;
+};
+
 export interface SyncProgress {
+  taskId: number;
   phase: string;
   current: number;
   total: number;
@@ -81,6 +166,13 @@ export const api = {
     }
   },
 
+  // 监听启动时的自动更新检查结果（仅在有更新时触发，可在设置中关闭）
+  onUpdateAvailable: (callback: (status: UpdateStatus) => void) => {
+    return listen<UpdateStatus>("update-available", (event) => {
+      callback(event.payload);
+    });
+  },
+
   // 手动导入ZIP（字节流）
   importZipFromBytes: async (bytes: Uint8Array): Promise<void> => {
     console.log("[API] 调用 import_from_zip_bytes, 大小:", bytes.byteLength);
@@ -96,14 +188,43 @@ export const api = {
     });
   },
 
+  // 监听所有任务（同步、导入、导出、搜索）的统一进度事件
+  onTaskProgress: (callback: (event: ProgressEvent) => void) => {
+    return listen<ProgressEvent>("task-progress", (event) => {
+      callback(event.payload);
+    });
+  },
+
+  // 解析 arkstory:// 链接，目标不存在时返回 NOT_FOUND 错误
+  resolveDeepLink: async (url: string): Promise<DeepLinkNavigation> => {
+    return invoke("resolve_deep_link", { url });
+  },
+
+  // 取走冷启动时携带的链接（只返回一次）
+  takePendingDeepLink: async (): Promise<string | null> => {
+    return invoke("take_pending_deep_link");
+  },
+
+  // 监听应用运行期间收到的链接
+  onDeepLink: (callback: (url: string) => void) => {
+    return listen<string>("deep-link", (event) => {
+      callback(event.payload);
+    });
+  },
+
   // 获取章节列表
   getChapters: async (): Promise<Chapter[]> => {
     return invoke("get_chapters");
   },
 
+  // 获取活动列表（按开始时间排序）
+  getActivities: async (): Promise<Activity[]> => {
+    return invoke("get_activities");
+  },
+
   // 获取剧情分类
-  getStoryCategories: async (): Promise<StoryCategory[]> => {
-    return invoke("get_story_categories");
+  getStoryCategories: async (include?: string[]): Promise<StoryCategory[]> => {
+    return invoke("get_story_categories", { include: include ?? null });
   },
 
   // 获取剧情内容
@@ -121,6 +242,423 @@ export const api = {
     return invoke("get_story_entry", { storyId });
   },
 
+  // 获取上一篇/下一篇（crossGroups 为 true 时在分组边界滚动到相邻分组）
+  getStoryNeighbors: async (storyId: string, crossGroups = false): Promise<StoryNeighbors> => {
+    return invoke("get_story_neighbors", { storyId, crossGroups });
+  },
+
+  // 阅读进度
+  setReadingProgress: async (
+    storyId: string,
+    segmentIndex: number,
+    percent: number,
+    charOffset?: number
+  ): Promise<void> => {
+    return invoke("set_reading_progress", { storyId, segmentIndex, percent, charOffset });
+  },
+
+  getReadingProgress: async (storyId: string): Promise<ReadingProgress | null> => {
+    return invoke("get_reading_progress", { storyId });
+  },
+
+  getAllReadingProgress: async (): Promise<ReadingProgress[]> => {
+    return invoke("get_all_reading_progress");
+  },
+
+  // 书签
+  addBookmark: async (
+    storyId: string,
+    segmentIndex: number,
+    excerpt: string,
+    note?: string
+  ): Promise<Bookmark> => {
+    return invoke("add_bookmark", { storyId, segmentIndex, excerpt, note });
+  },
+
+  listBookmarks: async (storyId?: string): Promise<Bookmark[]> => {
+    return invoke("list_bookmarks", { storyId: storyId ?? null });
+  },
+
+  updateBookmarkNote: async (id: number, note: string): Promise<void> => {
+    return invoke("update_bookmark_note", { id, note });
+  },
+
+  deleteBookmark: async (id: number): Promise<void> => {
+    return invoke("delete_bookmark", { id });
+  },
+
+  // 收藏（剧情或分组）
+  toggleFavorite: async (kind: FavoriteKind, key: string): Promise<boolean> => {
+    return invoke("toggle_favorite", { kind, key });
+  },
+
+  isFavorite: async (kind: FavoriteKind, key: string): Promise<boolean> => {
+    return invoke("is_favorite", { kind, key });
+  },
+
+  listFavorites: async (): Promise<FavoriteItem[]> => {
+    return invoke("list_favorites");
+  },
+
+  // 最近阅读
+  reportReadSession: async (storyId: string, seconds: number): Promise<void> => {
+    return invoke("report_read_session", { storyId, seconds });
+  },
+
+  getRecentStories: async (limit?: number): Promise<RecentStory[]> => {
+    return invoke("get_recent_stories", { limit: limit ?? null });
+  },
+
+  // 获取「继续阅读」的剧情，没有未读完的剧情时返回 null
+  getContinueReading: async (): Promise<ContinueReading | null> => {
+    return invoke("get_continue_reading");
+  },
+
+  // 手动标记剧情已读/未读
+  setStoryRead: async (storyId: string, read: boolean): Promise<void> => {
+    return invoke("set_story_read", { storyId, read });
+  },
+
+  // 获取各分组的已读数量
+  getGroupReadCounts: async (): Promise<GroupReadCount[]> => {
+    return invoke("get_group_read_counts");
+  },
+
+  // 将整个分组标记为已读，返回剧情数
+  markGroupRead: async (groupKey: string): Promise<number> => {
+    return invoke("mark_group_read", { groupKey });
+  },
+
+  // 全文索引是否包含被隐藏的剧情（修改后需重建索引）
+  getIncludeHiddenInSearch: async (): Promise<boolean> => {
+    return invoke("get_include_hidden_in_search");
+  },
+
+  setIncludeHiddenInSearch: async (include: boolean): Promise<void> => {
+    return invoke("set_include_hidden_in_search", { include });
+  },
+
+  // 打开剧情所需的条目、简介、统计、进度与前后篇
+  getStoryDetail: async (storyId: string): Promise<StoryDetail> => {
+    return invoke("get_story_detail", { storyId });
+  },
+
+  // 获取单篇剧情的字数、对话数等统计
+  getStoryStats: async (storyId: string): Promise<StoryStats> => {
+    return invoke("get_story_stats", { storyId });
+  },
+
+  // 获取剧情的出场人物
+  getStoryCharacters: async (storyPath: string): Promise<StoryCharacter[]> => {
+    return invoke("get_story_characters", { storyPath });
+  },
+
+  // 获取干员作为说话人出现过的剧情（按类型分组）
+  getStoriesForCharacter: async (charId: string): Promise<StoryGroup[]> => {
+    return invoke("get_stories_for_character", { charId });
+  },
+
+  // 获取按泰拉历日期排列的剧情时间线
+  getStoriesByTerraDate: async (): Promise<TerraTimeline> => {
+    return invoke("get_stories_by_terra_date");
+  },
+
+  // 手气不错：按条件随机抽取一篇剧情，无符合条件的剧情时报错 NO_MATCH
+  getRandomStory: async (filter?: RandomStoryFilter): Promise<RandomStory> => {
+    return invoke("get_random_story", { filter: filter ?? null });
+  },
+
+  // 接下来读什么：与当前剧情共享说话人的其他剧情（需先建立索引）
+  getRelatedStories: async (storyId: string, limit?: number): Promise<RelatedStory[]> => {
+    return invoke("get_related_stories", { storyId, limit: limit ?? null });
+  },
+
+  // 将剧情的解锁关卡解析为关卡编号与名称
+  resolveRequiredStages: async (storyId: string): Promise<ResolvedStage[]> => {
+    return invoke("resolve_required_stages", { storyId });
+  },
+
+  // 主线章节的阅读顺序指引（行动前 → 打关 → 行动后）
+  getChapterReadingOrder: async (groupKey: string): Promise<ReadingOrderItem[]> => {
+    return invoke("get_chapter_reading_order", { groupKey });
+  },
+
+  // 获取解锁前置剧情链（最早的前置在前）
+  getStoryDependencies: async (storyId: string): Promise<StoryDependency[]> => {
+    return invoke("get_story_dependencies", { storyId });
+  },
+
+  // 获取月度小队记闻的各部分（每部分可单独阅读）
+  getMonthChatParts: async (storyId: string): Promise<StoryEntry[]> => {
+    return invoke("get_month_chat_parts", { storyId });
+  },
+
+  // 获取干员列表（含职业分支名称）；可按职业、星级、国家、分支筛选并分页
+  getCharactersList: async (filter?: CharacterListFilter): Promise<CharacterPage> => {
+    return invoke("get_characters_list", { filter: filter ?? null });
+  },
+
+  // 获取干员配音演员（默认语音在前，其后为皮肤等语音套组）
+  getCharacterVoiceActors: async (charId: string): Promise<CharacterVoiceActors> => {
+    return invoke("get_character_voice_actors", { charId });
+  },
+
+  // 获取干员档案（基础档案键值与各章节原文）
+  getCharacterProfile: async (charId: string): Promise<CharacterProfile> => {
+    return invoke("get_character_profile", { charId });
+  },
+
+  // 按名称、代号或标签搜索干员
+  searchCharacters: async (
+    query: string,
+    includeNonplayable?: boolean
+  ): Promise<CharacterMatch[]> => {
+    return invoke("search_characters", {
+      query,
+      includeNonplayable: includeNonplayable ?? null,
+    });
+  },
+
+  // 获取干员信赖加成
+  getCharacterFavor: async (charId: string): Promise<CharacterFavor> => {
+    return invoke("get_character_favor", { charId });
+  },
+
+  // 获取干员各精英阶段的属性
+  getCharacterAttributes: async (charId: string): Promise<CharacterAttributes> => {
+    return invoke("get_character_attributes", { charId });
+  },
+
+  // 获取攻击范围格子
+  getRange: async (rangeId: string): Promise<RangeGrid> => {
+    return invoke("get_range", { rangeId });
+  },
+
+  // 获取干员各阶段默认攻击范围与技能范围
+  getCharacterRanges: async (charId: string): Promise<CharacterRanges> => {
+    return invoke("get_character_ranges", { charId });
+  },
+
+  // 获取干员模组各等级的属性、特性与天赋变化
+  getCharacterModuleDetails: async (charId: string): Promise<CharacterModule[]> => {
+    return invoke("get_character_module_details", { charId });
+  },
+
+  // 获取干员精英化、技能升级与专精消耗
+  getCharacterUpgradeCosts: async (charId: string): Promise<CharacterUpgradeCosts> => {
+    return invoke("get_character_upgrade_costs", { charId });
+  },
+
+  // 获取干员立绘与时装（按游戏内展示顺序）
+  getCharacterSkins: async (charId: string): Promise<SkinInfo[]> => {
+    return invoke("get_character_skins", { charId });
+  },
+
+  // 获取干员的密录（含解锁条件）
+  getCharacterMemories: async (charId: string): Promise<StoryEntry[]> => {
+    return invoke("get_character_memories", { charId });
+  },
+
+  // 获取干员指定语言的语音资源路径
+  getCharacterVoiceAssets: async (
+    charId: string,
+    lang: string
+  ): Promise<CharacterVoiceAssets> => {
+    return invoke("get_character_voice_assets", { charId, lang });
+  },
+
+  // 获取干员的召唤物
+  getCharacterTokens: async (charId: string): Promise<CharacterToken[]> => {
+    return invoke("get_character_tokens", { charId });
+  },
+
+  // 获取干员的全部形态（原版在前）
+  getCharacterForms: async (charId: string): Promise<CharacterForm[]> => {
+    return invoke("get_character_forms", { charId });
+  },
+
+  // 获取全部势力（附所属干员数）
+  getAllFactions: async (): Promise<TeamPowerInfo[]> => {
+    return invoke("get_all_factions");
+  },
+
+  // 获取全部职业分支（附所属干员数）
+  getAllSubProfessions: async (): Promise<SubProfessionInfo[]> => {
+    return invoke("get_all_sub_professions");
+  },
+
+  // 获取干员总览统计
+  getRosterStats: async (): Promise<RosterStats> => {
+    return invoke("get_roster_stats");
+  },
+
+  // 按名称精确定位干员；找不到时错误码为 NOT_FOUND，details.candidates 为候选干员 id
+  getCharacterByName: async (name: string): Promise<CharacterMatch> => {
+    return invoke("get_character_by_name", { name });
+  },
+
+  // 获取干员的画师与配音汇总
+  getCharacterCredits: async (charId: string): Promise<CharacterCredits> => {
+    return invoke("get_character_credits", { charId });
+  },
+
+  // 按画师查找干员
+  getOperatorsByArtist: async (name: string): Promise<ArtistWork[]> => {
+    return invoke("get_operators_by_artist", { name });
+  },
+
+  // 获取干员的获取方式与上线时间
+  getCharacterObtainInfo: async (charId: string): Promise<CharacterObtainInfo> => {
+    return invoke("get_character_obtain_info", { charId });
+  },
+
+  // 获取干员在各已安装语言中的名称
+  getCharacterLocalizedNames: async (charId: string): Promise<LocalizedName[]> => {
+    return invoke("get_character_localized_names", { charId });
+  },
+
+  // 正在执行的长耗时任务
+  listTasks: async (): Promise<TaskInfo[]> => {
+    return invoke("list_tasks");
+  },
+
+  // 取消任务，返回 false 表示任务已结束
+  cancelTask: async (taskId: number): Promise<boolean> => {
+    return invoke("cancel_task", { taskId });
+  },
+
+  // 最近的日志，用于问题反馈
+  getRecentLogs: async (lines?: number): Promise<string[]> => {
+    return invoke("get_recent_logs", { lines: lines ?? null });
+  },
+
+  // 设置日志级别：error、warn、info、debug、trace 或 off
+  setLogLevel: async (level: string): Promise<void> => {
+    return invoke("set_log_level", { level });
+  },
+
+  // 读取应用设置
+  getSettings: async (): Promise<AppSettings> => {
+    return invoke("get_settings");
+  },
+
+  // 部分更新应用设置，返回更新后的完整设置
+  updateSettings: async (patch: SettingsPatch): Promise<AppSettings> => {
+    return invoke("update_settings", { patch });
+  },
+
+  // 监听设置变更
+  onSettingsChanged: (callback: (settings: AppSettings) => void) => {
+    return listen<AppSettings>("settings-changed", (event) => {
+      callback(event.payload);
+    });
+  },
+
+  // 导出问题反馈用的诊断包；默认不含阅读记录
+  exportDiagnostics: async (
+    destPath: string,
+    includeReadingProgress = false
+  ): Promise<DiagnosticsExport> => {
+    return invoke("export_diagnostics", { destPath, includeReadingProgress });
+  },
+
+  // 获取各类数据的磁盘占用
+  getDiskUsage: async (): Promise<DiskUsage> => {
+    return invoke("get_disk_usage");
+  },
+
+  // 预先解析相邻剧情，返回每个路径是否已缓存（每次最多 4 个）
+  prefetchStoryContents: async (storyPaths: string[]): Promise<boolean[]> => {
+    return invoke("prefetch_story_contents", { storyPaths });
+  },
+
+  // 一次获取设置页所需的应用状态（不发起网络请求）
+  getAppStatus: async (): Promise<AppStatus> => {
+    return invoke("get_app_status");
+  },
+
+  // 获取剧情缓存的命中统计
+  getStoryCacheStats: async (): Promise<StoryCacheStats> => {
+    return invoke("get_story_cache_stats");
+  },
+
+  // 获取分组统计汇总
+  getGroupStats: async (groupKey: string): Promise<GroupStats> => {
+    return invoke("get_group_stats", { groupKey });
+  },
+
+  // 分组接口 v2：附带分组 id、类型、开放时间等信息
+  // excludeHidden 为 true 时不返回被隐藏（防剧透）的剧情
+  // includeStats 为 true 时附带字数与预计阅读时长（索引未建立时缺省）
+  getMainStoriesGroupedV2: async (
+    tagFilter?: AvgTagKind[],
+    excludeHidden?: boolean,
+    includeStats?: boolean
+  ): Promise<StoryGroup[]> => {
+    return invoke("get_main_stories_grouped_v2", {
+      tagFilter: tagFilter ?? null,
+      excludeHidden: excludeHidden ?? null,
+      includeStats: includeStats ?? null,
+    });
+  },
+
+  getActivityStoriesGroupedV2: async (
+    tagFilter?: AvgTagKind[],
+    excludeHidden?: boolean,
+    includeStats?: boolean
+  ): Promise<StoryGroup[]> => {
+    return invoke("get_activity_stories_grouped_v2", {
+      tagFilter: tagFilter ?? null,
+      excludeHidden: excludeHidden ?? null,
+      includeStats: includeStats ?? null,
+    });
+  },
+
+  getSidestoryStoriesGroupedV2: async (
+    excludeHidden?: boolean,
+    includeStats?: boolean
+  ): Promise<StoryGroup[]> => {
+    return invoke("get_sidestory_stories_grouped_v2", {
+      excludeHidden: excludeHidden ?? null,
+      includeStats: includeStats ?? null,
+    });
+  },
+
+  getRoguelikeStoriesGroupedV2: async (
+    excludeHidden?: boolean,
+    includeStats?: boolean
+  ): Promise<StoryGroup[]> => {
+    return invoke("get_roguelike_stories_grouped_v2", {
+      excludeHidden: excludeHidden ?? null,
+      includeStats: includeStats ?? null,
+    });
+  },
+
+  getRuneStoriesGroupedV2: async (
+    excludeHidden?: boolean,
+    includeStats?: boolean
+  ): Promise<StoryGroup[]> => {
+    return invoke("get_rune_stories_grouped_v2", {
+      excludeHidden: excludeHidden ?? null,
+      includeStats: includeStats ?? null,
+    });
+  },
+
+  getRecordStoriesGroupedV2: async (
+    excludeHidden?: boolean,
+    includeStats?: boolean
+  ): Promise<StoryGroup[]> => {
+    return invoke("get_record_stories_grouped_v2", {
+      excludeHidden: excludeHidden ?? null,
+      includeStats: includeStats ?? null,
+    });
+  },
+
+  // 按开放时间混排的剧情时间线（按年份分组）
+  getStoriesChronological: async (): Promise<ChronologyYear[]> => {
+    return invoke("get_stories_chronological");
+  },
+
   // 获取全文索引状态
   getStoryIndexStatus: async (): Promise<StoryIndexStatus> => {
     return invoke("get_story_index_status");
@@ -142,7 +680,15 @@ export const api = {
   },
 
   // 监听搜索进度
-  onSearchProgress: (callback: (progress: { phase: string; current: number; total: number; message: string }) => void) => {
+  onSearchProgress: (
+    callback: (progress: {
+      taskId: number;
+      phase: string;
+      current: number;
+      total: number;
+      message: string;
+    }) => void
+  ) => {
     return listen("search-progress", (event) => {
       // @ts-expect-error payload shape from Rust
       callback(event.payload);
@@ -155,15 +701,19 @@ export const api = {
   },
 
   // 获取主线剧情（按章节分组）
-  getMainStoriesGrouped: async (): Promise<Array<[string, StoryEntry[]]>> => {
+  getMainStoriesGrouped: async (
+    tagFilter?: AvgTagKind[]
+  ): Promise<Array<[string, StoryEntry[]]>> => {
     console.log("[API] 调用 get_main_stories_grouped");
-    return invoke("get_main_stories_grouped");
+    return invoke("get_main_stories_grouped", { tagFilter: tagFilter ?? null });
   },
 
   // 获取活动剧情（按活动分组）
-  getActivityStoriesGrouped: async (): Promise<Array<[string, StoryEntry[]]>> => {
+  getActivityStoriesGrouped: async (
+    tagFilter?: AvgTagKind[]
+  ): Promise<Array<[string, StoryEntry[]]>> => {
     console.log("[API] 调用 get_activity_stories_grouped");
-    return invoke("get_activity_stories_grouped");
+    return invoke("get_activity_stories_grouped", { tagFilter: tagFilter ?? null });
   },
 
   // 获取支线剧情（按项目分组）
@@ -178,9 +728,34 @@ export const api = {
     return invoke("get_roguelike_stories_grouped");
   },
 
+  // 获取按赛季分组的危机合约剧情
+  getRuneStoriesGrouped: async (): Promise<Array<[string, StoryEntry[]]>> => {
+    return invoke("get_rune_stories_grouped");
+  },
+
+  // 获取按区域分组的主线笔记
+  getRecordStoriesGrouped: async (): Promise<Array<[string, StoryEntry[]]>> => {
+    return invoke("get_record_stories_grouped");
+  },
+
   // 获取干员密录（原追忆集）
   getMemoryStories: async (): Promise<StoryEntry[]> => {
     console.log("[API] 调用 get_memory_stories");
     return invoke("get_memory_stories");
   },
+
+  // 获取按干员分组的干员密录
+  getMemoryStoriesGrouped: async (): Promise<MemoryGroup[]> => {
+    return invoke("get_memory_stories_grouped");
+  },
+
+  // 一次性获取全部分类的剧情树
+  getStoryTree: async (): Promise<StoryTree> => {
+    return invoke("get_story_tree");
+  },
+
+  // 剧情树版本号，未变化时可直接使用前端缓存
+  getStoryTreeVersion: async (): Promise<string> => {
+    return invoke("get_story_tree_version");
+  },
 };
@@ -1,3 +1,6 @@
+// 剧情标签归一化类型
+export type AvgTagKind = "before" | "after" | "interlude" | "other";
+
 // 剧情条目
 export interface StoryEntry {
   storyId: string;
@@ -6,6 +9,8 @@ export interface StoryEntry {
   storyGroup: string;
   storySort: number;
   avgTag?: string; // 行动前/行动后
+  avgTagKind?: AvgTagKind;
+  visibility?: "normal" | "hidden" | "locked"; // hidden 为防剧透隐藏
   storyTxt: string; // 剧情文本文件路径
   storyInfo?: string; // 剧情简介文件路径
   storyReviewType: string;
@@ -23,6 +28,15 @@ export interface StoryEntry {
   costItemType?: string | null;
   costItemId?: string | null;
   costItemCount?: number | null;
+  unlockDesc?: string;
+  favorite?: boolean;
+  groupFavorite?: boolean;
+  /** 分组接口 includeStats 时填充 */
+  wordCount?: number;
+  estimatedMinutes?: number;
+  /** 干员密录的来源活动与关联关卡 */
+  sourceActName?: string;
+  sourceStageCode?: string;
 }
 
 // 章节
@@ -44,10 +58,671 @@ export interface Activity {
   entryType: string;
   actType: string;
   startTime: number;
-  endTime: number;
+  endTime: number | null;
+  isRerun: boolean;
   infoUnlockDatas: StoryEntry[];
 }
 
+// 按干员分组的干员密录
+export interface MemoryGroup {
+  charId: string;
+  charName: string;
+  rarity: number;
+  stories: StoryEntry[];
+}
+
+// 阅读器的上一篇/下一篇
+export interface StoryNeighbors {
+  prev: StoryEntry | null;
+  next: StoryEntry | null;
+  groupName: string;
+  position: number;
+  total: number;
+}
+
+// 剧情树
+export interface StoryTreeGroup {
+  name: string;
+  count: number;
+  stories: StoryEntry[];
+}
+
+export interface StoryTreeNode {
+  id: string;
+  name: string;
+  count: number;
+  groups: StoryTreeGroup[];
+}
+
+export interface StoryTree {
+  treeVersion: string;
+  total: number;
+  nodes: StoryTreeNode[];
+}
+
+// 阅读进度
+export interface ReadingProgress {
+  storyId: string;
+  segmentIndex: number;
+  charOffset: number;
+  percent: number;
+  updatedAt: number;
+}
+
+// 书签
+export interface Bookmark {
+  id: number;
+  storyId: string;
+  segmentIndex: number;
+  excerpt: string;
+  note: string;
+  createdAt: number;
+  updatedAt: number;
+  storyName: string | null;
+  category: string | null;
+}
+
+// 收藏
+export type FavoriteKind = "story" | "group";
+
+export interface FavoriteItem {
+  kind: FavoriteKind;
+  key: string;
+  createdAt: number;
+  story: StoryEntry | null;
+  groupName: string | null;
+  storyCount: number | null;
+}
+
+// 最近阅读
+export interface RecentStory {
+  story: StoryEntry;
+  lastOpenedAt: number;
+  readSeconds: number;
+}
+
+export interface ContinueReading {
+  story: StoryEntry;
+  progress: ReadingProgress;
+  neighbors: StoryNeighbors | null;
+}
+
+export interface GroupReadCount {
+  groupKey: string;
+  read: number;
+  total: number;
+}
+
+export interface StoryStats {
+  storyId: string;
+  wordCount: number;
+  dialogueCount: number;
+  narrationCount: number;
+  decisionCount: number;
+  speakerCount: number;
+  estimatedMinutes: number;
+  /** 开场字幕中首个泰拉历日期 */
+  terraDate?: TerraDate;
+}
+
+export interface TerraDate {
+  year: number;
+  month: number | null;
+  day: number | null;
+}
+
+export interface TerraDatedStory {
+  story: StoryEntry;
+  date: TerraDate;
+}
+
+export interface TerraTimeline {
+  dated: TerraDatedStory[];
+  undated: StoryEntry[];
+}
+
+export interface StoryCharacter {
+  name: string;
+  /** 匹配不到干员（NPC）时为 null */
+  charId: string | null;
+  rarity: number | null;
+  avatarId: string | null;
+  lineCount: number;
+}
+
+export interface VoiceActorLang {
+  /** JP / CN_MANDARIN / CN_TOPOLECT / EN / KR 等 */
+  langType: string;
+  langName: string;
+  cvNames: string[];
+  voicePath: string | null;
+}
+
+export interface VoiceSet {
+  wordKey: string;
+  /** 皮肤等非默认语音 */
+  isAlternate: boolean;
+  hasTopolect: boolean;
+  languages: VoiceActorLang[];
+}
+
+export interface CharacterSummary {
+  charId: string;
+  name: string;
+  appellation: string | null;
+  rarity: number;
+  profession: string | null;
+  subProfessionId: string | null;
+  /** 职业分支名称，如「驭法铁卫」 */
+  subProfessionName: string | null;
+  nationId: string | null;
+  groupId: string | null;
+  teamId: string | null;
+  tags: string[];
+  /** 是否存在其他形态（如阿米娅的近卫/医疗形态） */
+  hasAlternateForms: boolean;
+  /** 其他形态所属的原版干员 id，原版为 null */
+  baseCharId: string | null;
+  /** 联动等无法获取的干员 */
+  isNotObtainable: boolean;
+  /** 来自 char_patch_table 的其他形态 */
+  isPatch: boolean;
+}
+
+export type CharacterSort = "rarity" | "name";
+
+// 省略的条件不参与筛选；同一条件内的多个值为「或」
+export interface CharacterListFilter {
+  profession?: string[];
+  rarity?: number[];
+  nation?: string;
+  subProfession?: string;
+  sort?: CharacterSort;
+  offset?: number;
+  limit?: number;
+  includeNonplayable?: boolean;
+}
+
+export interface CharacterPage {
+  /** 分页前的总数 */
+  total: number;
+  items: CharacterSummary[];
+}
+
+export interface CharacterVoiceActors {
+  charId: string;
+  voiceSets: VoiceSet[];
+}
+
+export interface ProfileField {
+  key: string;
+  value: string;
+}
+
+export interface HandbookSection {
+  title: string;
+  text: string;
+  /** 原始解锁类型（DIRECT / FAVOR / AWAKE 等） */
+  unlockType: string | null;
+  unlockParam: string | null;
+  /** 解锁说明，如「信赖达到50%解锁」 */
+  unlockDesc: string;
+}
+
+export interface CharacterProfile {
+  charId: string;
+  /** 基础档案中的【键】值，保持原顺序 */
+  profile: ProfileField[];
+  medicalReport: string | null;
+  sections: HandbookSection[];
+}
+
+export type CharacterMatchKind = "exact" | "prefix" | "substring" | "tag";
+
+export type CharacterMatch = CharacterSummary & {
+  matchKind: CharacterMatchKind;
+};
+
+export interface AttributeBonus {
+  attribute: string;
+  label: string;
+  value: number;
+}
+
+export interface FavorKeyFrame {
+  level: number;
+  percent: number;
+  bonuses: AttributeBonus[];
+}
+
+export interface CharacterFavor {
+  charId: string;
+  keyFrames: FavorKeyFrame[];
+  maxBonuses: AttributeBonus[];
+  /** 例如「满信赖 +300 生命 +30 攻击」 */
+  summary: string;
+}
+
+export interface AttributeBlock {
+  maxHp: number;
+  atk: number;
+  def: number;
+  magicResistance: number;
+  cost: number;
+  blockCnt: number;
+  respawnTime: number;
+  attackSpeed: number;
+}
+
+export interface PhaseAttributes {
+  /** 精英阶段，0 起 */
+  phase: number;
+  minLevel: number;
+  maxLevel: number;
+  start: AttributeBlock;
+  end: AttributeBlock;
+}
+
+export interface CharacterAttributes {
+  charId: string;
+  phases: PhaseAttributes[];
+}
+
+export interface RangeGrid {
+  id: string;
+  /** 相对干员所在格的 [row, col] 偏移 */
+  cells: Array<[number, number]>;
+}
+
+export interface PhaseRange {
+  phase: number;
+  range: RangeGrid | null;
+}
+
+export interface SkillRange {
+  skillId: string;
+  skillName: string | null;
+  fromLevel: number;
+  range: RangeGrid;
+}
+
+export interface CharacterRanges {
+  charId: string;
+  phases: PhaseRange[];
+  skills: SkillRange[];
+}
+
+export interface BlackboardValue {
+  key: string;
+  value: number;
+  valueStr?: string;
+}
+
+export interface ModuleTalent {
+  talentIndex: number;
+  name: string | null;
+  description: string | null;
+  requiredPotentialRank: number;
+  blackboard: BlackboardValue[];
+}
+
+export interface ModuleStage {
+  level: number;
+  attributes: BlackboardValue[];
+  traitDescriptions: string[];
+  talents: ModuleTalent[];
+}
+
+export interface CharacterModule {
+  moduleId: string;
+  name: string;
+  /** 如 GUA-X，原始模组为 ORIGINAL */
+  typeName: string;
+  isOriginal: boolean;
+  stages: ModuleStage[];
+  /** 原始模组没有解锁条件 */
+  unlock?: ModuleUnlock;
+}
+
+export interface ModuleMission {
+  missionId: string;
+  description: string | null;
+}
+
+export interface ModuleUnlock {
+  evolvePhase: number;
+  level: number;
+  favor: number | null;
+  missions: ModuleMission[];
+  itemCosts: LevelCost[];
+  /** 例如「精二60级、信赖100、完成2个任务」 */
+  summary: string;
+}
+
+export interface ItemCost {
+  itemId: string;
+  itemName: string;
+  count: number;
+}
+
+export interface LevelCost {
+  level: number;
+  costs: ItemCost[];
+}
+
+export interface SkillMasteryCost {
+  skillId: string;
+  skillName: string | null;
+  masteries: LevelCost[];
+}
+
+export interface CharacterUpgradeCosts {
+  charId: string;
+  /** level 为目标精英阶段 */
+  elite: LevelCost[];
+  /** level 为目标技能等级（2-7） */
+  skillLevels: LevelCost[];
+  masteries: SkillMasteryCost[];
+}
+
+export interface SkinInfo {
+  skinId: string;
+  skinName: string | null;
+  /** ILLUST_0 / ILLUST_2 等为精英阶段立绘 */
+  skinGroupId: string | null;
+  skinGroupName: string | null;
+  avatarId: string | null;
+  portraitId: string | null;
+  illustId: string | null;
+  sortId: number | null;
+  /** 上架时间（Unix 秒） */
+  getTime: number | null;
+  /** 画师 */
+  drawers: string[];
+}
+
+export interface VoiceAsset {
+  charWordId: string;
+  wordKey: string;
+  voiceId: string;
+  voiceTitle: string | null;
+  voiceText: string | null;
+  /** 相对音频根目录，如 voice_cn/char_002_amiya/cn_001.mp3 */
+  assetPath: string;
+}
+
+export interface CharacterVoiceAssets {
+  charId: string;
+  lang: string;
+  hasAlternateSets: boolean;
+  lines: VoiceAsset[];
+}
+
+export interface TalentInfo {
+  name: string | null;
+  description: string | null;
+}
+
+export interface CharacterToken {
+  tokenId: string;
+  name: string;
+  description: string | null;
+  traitDescription: string | null;
+  talents: TalentInfo[];
+  phases: PhaseAttributes[];
+}
+
+export interface SkillInfo {
+  skillId: string;
+  skillName: string | null;
+}
+
+export interface CharacterForm {
+  charId: string;
+  name: string;
+  profession: string | null;
+  isBase: boolean;
+  skills: SkillInfo[];
+  talents: TalentInfo[];
+}
+
+export interface TeamPowerInfo {
+  powerId: string;
+  powerName: string;
+  powerCode: string | null;
+  /** 0 为国家，1 为组织，2 为小队 */
+  powerLevel: number;
+  color: string | null;
+  orderNum: number;
+  isLimited: boolean;
+  operatorCount: number;
+}
+
+export interface SubProfessionInfo {
+  subProfessionId: string;
+  subProfessionName: string;
+  category: number | null;
+  operatorCount: number;
+}
+
+export interface RosterCount {
+  key: string;
+  count: number;
+}
+
+export interface RosterStats {
+  total: number;
+  byRarity: RosterCount[];
+  byProfession: RosterCount[];
+  bySubProfession: RosterCount[];
+  byNation: RosterCount[];
+  /** 依赖的表缺失时为 null */
+  withModules: number | null;
+  withMemories: number | null;
+  withExtraSkins: number | null;
+}
+
+export interface ArtistCredit {
+  name: string;
+  skinIds: string[];
+}
+
+export interface CvCredit {
+  langType: string;
+  langName: string;
+  cvNames: string[];
+}
+
+export interface CharacterCredits {
+  charId: string;
+  artists: ArtistCredit[];
+  voiceActors: CvCredit[];
+}
+
+export interface ArtistWork {
+  charId: string;
+  charName: string | null;
+  skinIds: string[];
+}
+
+export interface CharacterObtainInfo {
+  charId: string;
+  obtainApproach: string | null;
+  isNotObtainable: boolean;
+  /** 只出现在限定/联动寻访中；无寻访数据时为 null */
+  isLimited: boolean | null;
+  /** 首次出现在寻访中的时间（Unix 秒） */
+  releaseTs: number | null;
+}
+
+export interface LocalizedName {
+  /** 语言目录，如 zh_CN / en_US / ja_JP */
+  language: string;
+  name: string;
+  appellation: string | null;
+}
+
+// 正在执行的长耗时任务，进度事件中的 taskId 与 id 对应
+/** arkstory:// 链接解析后的跳转 */
+export type DeepLinkNavigation =
+  | { kind: "story"; story: StoryEntry }
+  | { kind: "character"; charId: string; name: string }
+  | { kind: "search"; query: string };
+
+/** 统一的任务进度事件；成功结束时 done 为 true，失败或取消时 error 非空 */
+export interface ProgressEvent {
+  taskId: number;
+  taskKind: string;
+  phase: string;
+  current: number;
+  total: number;
+  message: string;
+  done: boolean;
+  error: string | null;
+}
+
+export interface TaskInfo {
+  id: number;
+  kind: string;
+  phase: string | null;
+  current: number;
+  total: number;
+  exclusive: boolean;
+  cancelled: boolean;
+  startedAt: number;
+}
+
+export interface DataSourceProfile {
+  id: string;
+  name: string;
+  repo: string;
+  branch: string;
+  language: string;
+}
+
+// 持久化在 settings.json 中的应用设置
+export interface AppSettings {
+  dataSource: DataSourceProfile;
+  archiveFormat: "zip" | "tarGz";
+  stallTimeoutSecs: number;
+  parallelExtraction: boolean;
+  prewarmCache: boolean;
+  includeHiddenInSearch: boolean;
+  logLevel: string;
+  nickname: string | null;
+  autoCheckUpdate: boolean;
+  searchResultLimit: number;
+  autoIndex: boolean;
+  downloadMirror: string | null;
+}
+
+// 部分更新：省略的字段保持不变，nickname / downloadMirror 传空字符串清除；数据源通过单独的命令切换
+export type SettingsPatch = Partial<
+  Omit<AppSettings, "dataSource" | "nickname" | "downloadMirror">
+> & {
+  nickname?: string;
+  downloadMirror?: string;
+};
+
+export type UpdateStatus =
+  | { status: "upToDate" }
+  | { status: "updateAvailable"; remoteShort: string }
+  | { status: "unknown"; reason: string };
+
+export interface InstalledVersion {
+  commit: string;
+  commitShort: string;
+  fetchedAt: number;
+  sourceId: string | null;
+}
+
+export interface IndexSummary {
+  ready: boolean;
+  total: number;
+  lastBuiltAt: number | null;
+  building: boolean;
+}
+
+export interface UpdateCheckResult {
+  status: UpdateStatus;
+  checkedAt: number;
+}
+
+export interface AppStatus {
+  installed: boolean | null;
+  version: InstalledVersion | null;
+  language: string | null;
+  index: IndexSummary | null;
+  diskUsage: DiskUsage | null;
+  tasks: TaskInfo[] | null;
+  lastUpdateCheck: UpdateCheckResult | null;
+  storyCache: StoryCacheStats | null;
+}
+
+export interface StoryCacheStats {
+  hits: number;
+  misses: number;
+  entries: number;
+  capacity: number;
+}
+
+export interface DiskUsage {
+  gameDataBytes: number;
+  indexBytes: number;
+  userDataBytes: number;
+  logBytes: number;
+  totalBytes: number;
+}
+
+export interface DiagnosticsExport {
+  path: string;
+  size: number;
+}
+
+export interface StoryDependency {
+  storyId: string;
+  /** 当前数据中不存在该剧情时为 null */
+  story: StoryEntry | null;
+  category: string | null;
+}
+
+export interface ReadingOrderItem {
+  kind: "story" | "stage_marker";
+  story: StoryEntry | null;
+  stageCode: string | null;
+}
+
+export interface RelatedStory {
+  story: StoryEntry;
+  score: number;
+  /** 共同说话人，越稀有越靠前 */
+  sharedSpeakers: string[];
+}
+
+export interface RandomStoryFilter {
+  /** 分类类型，如 MAINLINE、SIDESTORY、STORY_COLLECTION、ACTIVITY */
+  entryTypes?: string[];
+  unreadOnly?: boolean;
+  /** 依赖已建立的索引 */
+  minWordCount?: number;
+}
+
+export interface RandomStory {
+  story: StoryEntry;
+  category: string;
+  neighbors: StoryNeighbors | null;
+}
+
+export interface GroupStats {
+  groupKey: string;
+  storyCount: number;
+  wordCount: number;
+  dialogueCount: number;
+  narrationCount: number;
+  decisionCount: number;
+  estimatedMinutes: number;
+}
+
 // 解析后的剧情内容
 export interface ParsedStoryContent {
   segments: StorySegment[];
@@ -133,3 +808,46 @@ export interface StoryIndexStatus {
   total: number;
   lastBuiltAt?: number | null;
 }
+
+export interface ChronologyYear {
+  year: number | null;
+  entries: Activity[];
+}
+
+// 剧情分组（*_grouped_v2 接口）
+export interface StoryGroup {
+  id: string;
+  name: string;
+  entryType: string;
+  stories: StoryEntry[];
+  startTime?: number;
+  /** 活动类分组的 actType（ACTIVITY_STORY 为支线，MINI_STORY 为故事集） */
+  actType?: string;
+  extra?: {
+    hasRerun: boolean;
+    /** activity_table 中的活动 id，旧活动可能缺失 */
+    actId?: string;
+    actCode?: string;
+    bannerId?: string;
+    themeColor?: string;
+  };
+}
+
+export interface StoryDetail {
+  entry: StoryEntry;
+  synopsis: string | null;
+  stats: StoryStats | null;
+  progress: ReadingProgress | null;
+  neighbors: StoryNeighbors | null;
+  dependenceName?: string;
+  requiredStages?: ResolvedStage[];
+}
+
+export interface ResolvedStage {
+  stageId: string;
+  /** 关卡编号，stage_table 中没有该关卡时为关卡 id */
+  code: string;
+  name: string | null;
+  minState: string;
+  maxState: string;
+}